@@ -10,8 +10,8 @@ use fluxemu_math::range::ContiguousRange;
 use fluxemu_runtime::{
     machine::Machine,
     memory::{MapTarget, MemoryMapCommand, Permissions},
-    scheduler::Frequency,
 };
+use num::rational::Ratio;
 
 fn produce_memory() -> [u8; 0x10000] {
     let mut memory = [0u8; 0x10000];
@@ -76,10 +76,8 @@ fn build_machine<V: Variant>() -> Arc<Machine> {
         }],
     );
 
-    let (machine, _) = machine.component(
-        "cpu",
-        Config::<V>::new(Frequency::from_num(1000000), address_space),
-    );
+    let (machine, clock) = machine.master_clock("cpu", Ratio::from_integer(1000000));
+    let (machine, _) = machine.component("cpu", Config::<V>::new(clock, address_space));
 
     machine.seal().build(())
 }
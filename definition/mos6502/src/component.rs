@@ -3,15 +3,18 @@ use core::marker::PhantomData;
 use alloc::boxed::Box;
 use fluxemu_runtime::{
     Platform,
-    component::{Component, config::ComponentConfig},
+    clock::Clock,
+    component::{Component, ResetKind, config::ComponentConfig},
     event::{Event, downcast_event},
     machine::builder::{ComponentBuilder, SchedulerParticipation},
     memory::{Address, AddressSpaceId},
-    scheduler::{Frequency, Period, SynchronizationContext},
+    scheduler::{Period, SynchronizationContext},
+    signal::{SignalChange, Wiring},
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
 
 use crate::{
-    Bus, FlagRegister, IRQ_VECTOR, Mos6502Event, NMI_VECTOR, NmiFlag, Pin, RESET_VECTOR,
+    Bus, FlagRegister, IRQ_VECTOR, Mos6502Event, NMI_VECTOR, NmiFlag, RESET_VECTOR,
     STACK_BASE_ADDRESS, State,
     cycle::{BusMode, Cycle, Flag, MoveDestination, MoveSource, Phi1, Phi2, SetAddressBusSource},
     variant::Variant,
@@ -35,13 +38,20 @@ impl<V: Variant> Component for Mos6502<V> {
             .address_space(self.config.assigned_address_space)
             .unwrap();
 
-        let mut quanta_iterator = context.quanta_allocator(self.period);
-        while let Some(timestamp) = quanta_iterator.allocate() {
+        let mut cycle_allocator = context.cycle_allocator(self.config.clock);
+        while let Some(timestamp) = cycle_allocator.allocate() {
+            // Slow memory holds the processor up for the cycles after the access
+            self.state.wait_states += address_space.take_wait_states();
+            if self.state.wait_states != 0 {
+                self.state.wait_states -= 1;
+                continue;
+            }
+
             if self.state.cycle_queue.is_empty() {
                 self.state
                     .cycle_queue
                     .push_back(Cycle::new(
-                        BusMode::Read,
+                        BusMode::Fetch,
                         Some(Phi1::SetAddressBus {
                             source: SetAddressBusSource::InstructionPointer,
                         }),
@@ -103,6 +113,15 @@ impl<V: Variant> Component for Mos6502<V> {
 
                     true
                 }
+                BusMode::Fetch => {
+                    let mut opcode = [0];
+                    self.state.bus.data = address_space
+                        .fetch(self.state.bus.address as Address, timestamp, &mut opcode)
+                        .map(|()| opcode[0])
+                        .unwrap_or_default();
+
+                    true
+                }
                 BusMode::Write => false,
             };
 
@@ -116,7 +135,7 @@ impl<V: Variant> Component for Mos6502<V> {
                 self.handle_phi2(&current_cycle);
 
                 match current_cycle.bus_mode {
-                    BusMode::Read => {}
+                    BusMode::Read | BusMode::Fetch => {}
                     BusMode::Write => {
                         address_space
                             .write_le_value(
@@ -133,12 +152,14 @@ impl<V: Variant> Component for Mos6502<V> {
                 if V::SUPPORTS_INTERRUPTS && self.state.cycle_queue.is_empty() {
                     if self.state.nmi.interrupt_required() {
                         self.handle_nmi();
-                    } else if core::mem::take(&mut self.state.irq) {
+                    } else if !self.state.irq && !self.state.flags.interrupt_disable {
                         self.handle_irq();
                     }
                 }
             }
         }
+
+        self.state.wait_states += address_space.take_wait_states();
     }
 
     fn needs_work(&self, _timestamp: &Period, delta: &Period) -> bool {
@@ -149,26 +170,69 @@ impl<V: Variant> Component for Mos6502<V> {
         let event = downcast_event::<Self>(event);
 
         match event {
-            Mos6502Event::FlagChange { pin: flag, value } => match flag {
-                Pin::Nmi => self.state.nmi.store(value),
-                Pin::Irq => self.state.irq = value,
-                Pin::Rdy => self.state.rdy = value,
+            Mos6502Event::Signal(SignalChange { pin, level }) => match pin.as_str() {
+                "nmi" => self.state.nmi.store(level),
+                "irq" => self.state.irq = level,
+                "rdy" => self.state.rdy = level,
+                _ => tracing::warn!("Signal change on unknown pin {}", pin),
             },
         }
     }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.state))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        self.state = snapshot.decode()?;
+
+        Ok(())
+    }
+
+    fn reset(&mut self, kind: ResetKind) {
+        if kind == ResetKind::Hard {
+            self.state = power_on_state();
+        }
+
+        self.reset_sequence(kind);
+    }
+}
+
+fn power_on_state() -> State {
+    State {
+        a: 0,
+        x: 0,
+        y: 0,
+        flags: FlagRegister::default(),
+        stack: 0xff,
+        // Will be set later
+        instruction_pointer: 0x0000,
+        cycle_queue: heapless::Deque::default(),
+        operand: 0,
+        bus: Bus {
+            address: 0x0000,
+            data: 0x00,
+        },
+        rdy: true,
+        irq: true,
+        nmi: NmiFlag::default(),
+        effective_address: heapless::Vec::default(),
+        consume_effective_address: false,
+        wait_states: 0,
+    }
 }
 
 #[derive(Debug)]
 pub struct Config<V: Variant> {
-    frequency: Frequency,
+    clock: Clock,
     assigned_address_space: AddressSpaceId,
     _phantom: PhantomData<V>,
 }
 
 impl<V: Variant> Config<V> {
-    pub fn new(frequency: Frequency, assigned_address_space: AddressSpaceId) -> Self {
+    pub fn new(clock: Clock, assigned_address_space: AddressSpaceId) -> Self {
         Self {
-            frequency,
+            clock,
             assigned_address_space,
             _phantom: PhantomData,
         }
@@ -182,36 +246,22 @@ impl<P: Platform, V: Variant> ComponentConfig<P> for Config<V> {
         self,
         component_builder: ComponentBuilder<P, Self::Component>,
     ) -> Result<Self::Component, Box<dyn core::error::Error>> {
-        component_builder.scheduler_participation(Some(SchedulerParticipation::SchedulerDriven));
+        // NMI, IRQ and RDY are all active low, so any device pulling them low wins
+        let (component_builder, _) = component_builder
+            .scheduler_participation(Some(SchedulerParticipation::SchedulerDriven))
+            .signal_input("nmi", Wiring::WiredAnd);
+        let (component_builder, _) = component_builder.signal_input("irq", Wiring::WiredAnd);
+        component_builder.signal_input("rdy", Wiring::WiredAnd);
 
         let mut component = Mos6502 {
-            state: State {
-                a: 0,
-                x: 0,
-                y: 0,
-                flags: FlagRegister::default(),
-                stack: 0xff,
-                // Will be set later
-                instruction_pointer: 0x0000,
-                cycle_queue: heapless::Deque::default(),
-                operand: 0,
-                bus: Bus {
-                    address: 0x0000,
-                    data: 0x00,
-                },
-                rdy: true,
-                irq: false,
-                nmi: NmiFlag::default(),
-                effective_address: heapless::Vec::default(),
-                consume_effective_address: false,
-            },
-            period: self.frequency.recip(),
+            state: power_on_state(),
+            period: self.clock.period(),
             config: self,
             _variant: PhantomData::<V>,
         };
 
         // Put it in the reset state for startup
-        component.reset();
+        component.reset_sequence(ResetKind::Hard);
 
         Ok(component)
     }
@@ -222,40 +272,89 @@ impl<V: Variant> Mos6502<V> {
         self.config.assigned_address_space
     }
 
-    fn reset(&mut self) {
+    /// Queue up the cycles the processor goes through while its reset line is held
+    ///
+    /// From power on the registers are given fixed values, while a reset of a running processor only walks the stack
+    /// pointer down and masks interrupts, as it goes through the motions of an interrupt with writes suppressed
+    fn reset_sequence(&mut self, kind: ResetKind) {
+        // Whatever instruction was in flight is abandoned
         self.state.cycle_queue.clear();
+        self.state.effective_address.clear();
+        self.state.consume_effective_address = false;
+
+        // Two dummy cycles
         self.state.cycle_queue.extend([
-            // Two dummy cycles
             Cycle::new(BusMode::Read, None, []),
             Cycle::new(BusMode::Read, None, []),
-            // Initialize the stack
-            Cycle::new(
-                BusMode::Read,
-                None,
-                [Phi2::Move {
-                    source: MoveSource::Constant(0xfd),
-                    destination: MoveDestination::Stack,
-                }],
-            ),
-            // Sets flags
-            Cycle::new(
-                BusMode::Read,
-                None,
-                [Phi2::Move {
-                    source: MoveSource::Constant(
-                        FlagRegister {
-                            negative: false,
-                            overflow: false,
-                            decimal: false,
-                            interrupt_disable: true,
-                            zero: false,
-                            carry: false,
-                        }
-                        .to_byte(false),
+        ]);
+
+        match kind {
+            ResetKind::Soft => {
+                self.state.cycle_queue.extend([
+                    Cycle::new(
+                        BusMode::Read,
+                        Some(Phi1::SetAddressBus {
+                            source: SetAddressBusSource::Stack,
+                        }),
+                        [Phi2::IncrementStack { subtract: true }],
                     ),
-                    destination: MoveDestination::Flags,
-                }],
-            ),
+                    Cycle::new(
+                        BusMode::Read,
+                        Some(Phi1::SetAddressBus {
+                            source: SetAddressBusSource::Stack,
+                        }),
+                        [Phi2::IncrementStack { subtract: true }],
+                    ),
+                    Cycle::new(
+                        BusMode::Read,
+                        Some(Phi1::SetAddressBus {
+                            source: SetAddressBusSource::Stack,
+                        }),
+                        [
+                            Phi2::IncrementStack { subtract: true },
+                            Phi2::SetFlag {
+                                flag: Flag::InterruptDisable,
+                                value: true,
+                            },
+                        ],
+                    ),
+                ]);
+            }
+            ResetKind::Hard => {
+                self.state.cycle_queue.extend([
+                    // Initialize the stack
+                    Cycle::new(
+                        BusMode::Read,
+                        None,
+                        [Phi2::Move {
+                            source: MoveSource::Constant(0xfd),
+                            destination: MoveDestination::Stack,
+                        }],
+                    ),
+                    // Sets flags
+                    Cycle::new(
+                        BusMode::Read,
+                        None,
+                        [Phi2::Move {
+                            source: MoveSource::Constant(
+                                FlagRegister {
+                                    negative: false,
+                                    overflow: false,
+                                    decimal: false,
+                                    interrupt_disable: true,
+                                    zero: false,
+                                    carry: false,
+                                }
+                                .to_byte(false),
+                            ),
+                            destination: MoveDestination::Flags,
+                        }],
+                    ),
+                ]);
+            }
+        }
+
+        self.state.cycle_queue.extend([
             // Load the reset vector
             Cycle::new(
                 BusMode::Read,
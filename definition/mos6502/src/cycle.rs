@@ -17,6 +17,8 @@ pub enum AddToPointerLikeRegisterSource {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BusMode {
     Read,
+    /// A read of an opcode, reported to execute watchpoints
+    Fetch,
     Write,
 }
 
@@ -4,6 +4,7 @@ extern crate alloc;
 
 use crate::cycle::Cycle;
 use core::fmt::Debug;
+use fluxemu_runtime::signal::SignalChange;
 use serde::{Deserialize, Serialize};
 
 mod component;
@@ -77,7 +78,11 @@ struct State {
     pub operand: u8,
     pub rdy: bool,
     pub nmi: NmiFlag,
+    /// Level of the IRQ pin, held low for as long as a interrupt is wanted
     pub irq: bool,
+    /// Cycles left to stall for from the last access
+    #[serde(default)]
+    pub wait_states: u32,
 }
 
 /// NMI is falling edge
@@ -108,14 +113,14 @@ impl NmiFlag {
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum Pin {
-    Nmi,
-    Irq,
-    Rdy,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Mos6502Event {
+    /// The level of the NMI, IRQ or RDY pin changed, all of them active low
+    Signal(SignalChange),
 }
 
-#[derive(Debug, Clone)]
-pub enum Mos6502Event {
-    FlagChange { pin: Pin, value: bool },
+impl From<SignalChange> for Mos6502Event {
+    fn from(change: SignalChange) -> Self {
+        Self::Signal(change)
+    }
 }
@@ -3,8 +3,9 @@ use std::{fmt::Debug, ops::RangeInclusive};
 use fluxemu_math::range::ContiguousRange;
 use fluxemu_runtime::{
     RuntimeHandle,
+    clock::Clock,
     component::{
-        Component,
+        Component, ResetKind,
         config::{ComponentConfig, LateContext},
     },
     machine::builder::{ComponentBuilder, SchedulerParticipation},
@@ -14,11 +15,15 @@ use fluxemu_runtime::{
     },
     path::ComponentPath,
     platform::Platform,
-    scheduler::{Frequency, Period, SynchronizationContext},
+    scheduler::{Period, SynchronizationContext},
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
 use serde::{Deserialize, Serialize};
 use strum::FromRepr;
 
+#[cfg(test)]
+mod tests;
+
 #[repr(usize)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, FromRepr)]
 pub enum Register {
@@ -54,7 +59,7 @@ pub struct Mos6532Riot {
     state: State,
     config: Mos6532RiotConfig,
     path: ComponentPath,
-    period: Period,
+    clock: Clock,
 }
 
 impl Mos6532Riot {
@@ -65,6 +70,41 @@ impl Mos6532Riot {
     pub fn swchb_address(&self) -> Address {
         self.config.registers_assigned_address + (Register::Swchb as Address)
     }
+
+    /// Start of the cycle `divider` cycles after the one running at `timestamp`, where the timer next counts down
+    fn timer_deadline(&self, timestamp: Period, divider: u16) -> Period {
+        self.clock
+            .cycles(self.clock.cycle_at(timestamp) + u64::from(divider))
+    }
+
+    /// Map the ports so their direction registers are reflected in who handles accesses to them
+    fn remap_ports(&self) {
+        RuntimeHandle::with_current(|runtime| {
+            let timestamp = runtime.current_timestamp(&self.path);
+
+            let commands = [
+                (self.swcha_address(), &self.config.swcha, self.state.swacnt),
+                (self.swchb_address(), &self.config.swchb, self.state.swbcnt),
+            ]
+            .into_iter()
+            .filter_map(|(address, port, output)| {
+                Some(MemoryMapCommand::Map {
+                    range: address..=address,
+                    target: MapTarget::Component(port.clone()?),
+                    permissions: if output {
+                        Permissions::WRITE
+                    } else {
+                        Permissions::READ
+                    },
+                })
+            });
+
+            runtime
+                .address_space(self.config.assigned_address_space)
+                .unwrap()
+                .remap(&timestamp, commands);
+        });
+    }
 }
 
 impl Component for Mos6532Riot {
@@ -194,28 +234,28 @@ impl Component for Mos6532Riot {
                         self.state.timer_configuration = Some(TimerConfiguration {
                             timer: *buffer_section,
                             divider: 1,
-                            next_timestamp: timestamp + self.period,
+                            next_timestamp: self.timer_deadline(timestamp, 1),
                         });
                     }
                     Register::Tim8t => {
                         self.state.timer_configuration = Some(TimerConfiguration {
                             timer: *buffer_section,
                             divider: 8,
-                            next_timestamp: timestamp + self.period * 8,
+                            next_timestamp: self.timer_deadline(timestamp, 8),
                         });
                     }
                     Register::Tim64t => {
                         self.state.timer_configuration = Some(TimerConfiguration {
                             timer: *buffer_section,
                             divider: 64,
-                            next_timestamp: timestamp + self.period * 64,
+                            next_timestamp: self.timer_deadline(timestamp, 64),
                         });
                     }
                     Register::T1024t => {
                         self.state.timer_configuration = Some(TimerConfiguration {
                             timer: *buffer_section,
                             divider: 1024,
-                            next_timestamp: timestamp + self.period * 1024,
+                            next_timestamp: self.timer_deadline(timestamp, 1024),
                         });
                     }
                     Register::Instat => todo!(),
@@ -227,21 +267,22 @@ impl Component for Mos6532Riot {
     }
 
     fn synchronize(&mut self, mut context: SynchronizationContext) {
-        let mut quanta_iterator = context.quanta_allocator(self.period);
-        while let Some(timestamp) = quanta_iterator.allocate() {
+        let mut cycle_allocator = context.cycle_allocator(self.clock);
+        while let Some(timestamp) = cycle_allocator.allocate() {
             if let Some(config) = &mut self.state.timer_configuration
-                && timestamp == &config.next_timestamp
+                && timestamp >= &config.next_timestamp
             {
                 let (new_timer, underflowed) = config.timer.overflowing_sub(1);
 
-                if !underflowed {
-                    config.next_timestamp += self.period * config.divider as u128;
-                } else {
+                if underflowed {
                     config.divider = 1;
-                    config.next_timestamp += self.period;
                     self.state.instat |= 0b1000_0000;
                 }
 
+                config.next_timestamp = self
+                    .clock
+                    .cycles(cycle_allocator.cycle() + u64::from(config.divider));
+
                 config.timer = new_timer;
             }
         }
@@ -254,6 +295,32 @@ impl Component for Mos6532Riot {
             false
         }
     }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.state))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        self.state = snapshot.decode()?;
+
+        // Port directions are expressed through the memory map, so bring it back in line
+        self.remap_ports();
+
+        Ok(())
+    }
+
+    fn reset(&mut self, kind: ResetKind) {
+        // RES turns both ports into inputs and clears the interrupt flags, but the timer keeps counting
+        self.state.swacnt = false;
+        self.state.swbcnt = false;
+        self.state.instat = 0;
+
+        if kind == ResetKind::Hard {
+            self.state.timer_configuration = None;
+        }
+
+        self.remap_ports();
+    }
 }
 
 impl<P: Platform> ComponentConfig<P> for Mos6532RiotConfig {
@@ -352,7 +419,7 @@ impl<P: Platform> ComponentConfig<P> for Mos6532RiotConfig {
                 instat: 0,
                 timer_configuration: None,
             },
-            period: self.frequency.recip(),
+            clock: self.clock,
             config: self,
             path,
         })
@@ -361,7 +428,7 @@ impl<P: Platform> ComponentConfig<P> for Mos6532RiotConfig {
 
 #[derive(Debug)]
 pub struct Mos6532RiotConfig {
-    pub frequency: Frequency,
+    pub clock: Clock,
     pub registers_assigned_address: Address,
     pub ram_assigned_address: Address,
     pub assigned_address_space: AddressSpaceId,
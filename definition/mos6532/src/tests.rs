@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use fluxemu_runtime::{
+    component::ResetKind,
+    machine::Machine,
+    memory::{Address, AddressSpaceId},
+    scheduler::Period,
+};
+use num::rational::Ratio;
+
+use crate::{Mos6532RiotConfig, Register};
+
+const REGISTERS: Address = 0x280;
+
+fn build_machine() -> (Arc<Machine>, AddressSpaceId) {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+    let (machine, clock) = machine.master_clock("cpu", Ratio::from_integer(1_000_000));
+    let (machine, _) = machine.component(
+        "riot",
+        Mos6532RiotConfig {
+            clock,
+            registers_assigned_address: REGISTERS,
+            ram_assigned_address: 0x80,
+            assigned_address_space: address_space,
+            swcha: None,
+            swchb: None,
+        },
+    );
+
+    (machine.seal().build(()), address_space)
+}
+
+#[test]
+fn reset_returns_registers_to_power_on_values() {
+    let (machine, address_space) = build_machine();
+    let runtime_guard = machine.enter_runtime();
+    let mut memory = runtime_guard.address_space(address_space).unwrap();
+
+    let swacnt = REGISTERS + Register::Swacnt as Address;
+    let intim = REGISTERS + Register::Intim as Address;
+    let tim64t = REGISTERS + Register::Tim64t as Address;
+
+    memory.write_le_value(swacnt, &Period::ZERO, 1u8).unwrap();
+    memory
+        .write_le_value(tim64t, &Period::ZERO, 0x40u8)
+        .unwrap();
+    runtime_guard.run(Period::lit("0.001"));
+
+    let timestamp = runtime_guard.safe_advance_timestamp();
+    assert_eq!(
+        memory
+            .read_le_value::<u8, false>(swacnt, &timestamp)
+            .unwrap(),
+        1
+    );
+
+    // The timer survives the reset button, the port direction does not
+    runtime_guard.reset(ResetKind::Soft);
+    assert_eq!(
+        memory
+            .read_le_value::<u8, false>(swacnt, &timestamp)
+            .unwrap(),
+        0
+    );
+    assert_ne!(
+        memory
+            .read_le_value::<u8, false>(intim, &timestamp)
+            .unwrap(),
+        0
+    );
+
+    runtime_guard.reset(ResetKind::Hard);
+    assert_eq!(
+        memory
+            .read_le_value::<u8, false>(intim, &Period::ZERO)
+            .unwrap(),
+        0
+    );
+}
@@ -3,10 +3,13 @@ use std::{collections::BTreeMap, num::Wrapping, ops::Deref, path::PathBuf, sync:
 use audio::AudioSettings;
 use confique::Config;
 use fluxemu_input::physical::PhysicalInputDeviceId;
+use fluxemu_program::ProgramId;
 use ron::{Options, extensions::Extensions};
 use serde::{Deserialize, Serialize};
 
-use crate::{graphics::GraphicsSettings, input::PhysicalGamepadConfiguration};
+use crate::{
+    graphics::GraphicsSettings, input::PhysicalGamepadConfiguration, rewind::RewindSettings,
+};
 
 /// Audio related config types
 pub mod audio;
@@ -14,12 +17,15 @@ pub mod audio;
 pub mod graphics;
 /// Input configuration
 pub mod input;
+/// Rewind buffer config types
+pub mod rewind;
 
 #[derive(Config, Serialize, Deserialize, Debug, Clone)]
 pub struct Environment {
     pub gamepads: BTreeMap<PhysicalInputDeviceId, PhysicalGamepadConfiguration>,
     pub graphics: GraphicsSettings,
     pub audio: AudioSettings,
+    pub rewind: RewindSettings,
     pub file_browser_home_directory: PathBuf,
     #[config(env = "FLUXEMU_LOG_LOCATION")]
     pub log_location: PathBuf,
@@ -32,6 +38,10 @@ pub struct Environment {
     #[config(env = "FLUXEMU_ROM_STORE_DIRECTORIES")]
     pub rom_store_directories: Vec<PathBuf>,
     pub active_snapshot_slot: Wrapping<u8>,
+    /// Quirks the user set for a program, in RON, taking precedence over the ones in the database
+    pub program_quirks: BTreeMap<ProgramId, String>,
+    /// Speed of every emulated clock in percent of the real hardware, taking effect on the next boot
+    pub overclock: u16,
 }
 
 pub static STORAGE_DIRECTORY: LazyLock<PathBuf> = LazyLock::new(|| {
@@ -75,6 +85,7 @@ pub fn load_environment() -> Environment {
         gamepads: BTreeMap::default(),
         graphics: GraphicsSettings::default(),
         audio: AudioSettings::default(),
+        rewind: RewindSettings::default(),
         file_browser_home_directory: std::env::home_dir().unwrap_or(STORAGE_DIRECTORY.clone()),
         log_location: STORAGE_DIRECTORY.join("log"),
         database_location: STORAGE_DIRECTORY.join("database.redb"),
@@ -82,6 +93,8 @@ pub fn load_environment() -> Environment {
         snapshot_directory: STORAGE_DIRECTORY.join("snapshot"),
         rom_store_directories: vec![STORAGE_DIRECTORY.join("roms")],
         active_snapshot_slot: Wrapping(0),
+        program_quirks: BTreeMap::default(),
+        overclock: 100,
     })
     .unwrap();
 
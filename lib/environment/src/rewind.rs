@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RewindSettings {
+    /// Whether machine state should be recorded for rewinding at all
+    pub enabled: bool,
+    /// How many frames pass between captured states
+    pub capture_interval: u16,
+    /// How many captured states are stored as deltas before a new keyframe is taken
+    pub keyframe_interval: u16,
+    /// Upper bound of memory the rewind buffer may use, in bytes
+    pub memory_budget: usize,
+}
+
+impl Default for RewindSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            capture_interval: 4,
+            keyframe_interval: 30,
+            memory_budget: 64 * 1024 * 1024,
+        }
+    }
+}
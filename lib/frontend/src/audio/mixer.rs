@@ -39,7 +39,7 @@ impl AudioMixer {
         let mut state_guard = self.state.lock().unwrap();
         let state_guard = &mut *state_guard;
 
-        for audio_stream_path in runtime_guard.audio_outputs() {
+        for audio_stream_path in &runtime_guard.audio_outputs() {
             let Some(component_path) = audio_stream_path.parent() else {
                 continue;
             };
@@ -78,6 +78,34 @@ impl AudioMixer {
         }
     }
 
+    /// Throw away all audio the machine has generated but not yet played, for when it jumps in time
+    pub fn discard_machine_samples(&self, runtime_guard: &RuntimeGuard<'_>) {
+        let mut state_guard = self.state.lock().unwrap();
+
+        for audio_stream_path in &runtime_guard.audio_outputs() {
+            let Some(component_path) = audio_stream_path.parent() else {
+                continue;
+            };
+
+            runtime_guard
+                .component_registry()
+                .interact_dyn(
+                    component_path,
+                    &runtime_guard.safe_advance_timestamp(),
+                    |component| {
+                        component
+                            .get_audio_channel(audio_stream_path.name())
+                            .audio_ring
+                            .clear();
+                    },
+                )
+                .unwrap();
+        }
+
+        state_guard.audio_ring.clear();
+        state_guard.interpolaters.clear();
+    }
+
     pub fn write_buffer<S: SampleFormat + FromSample<f32>, const CHANNELS: usize>(
         &self,
         buffer: &mut [SVector<S, CHANNELS>],
@@ -11,7 +11,7 @@ use egui::{
     Widget,
 };
 use egui_toast::ToastKind;
-use fluxemu_program::ProgramManager;
+use fluxemu_program::{ArchiveFormat, ProgramManager, RomId, archive_entries};
 use indexmap::IndexMap;
 use palette::{
     WithAlpha,
@@ -43,6 +43,7 @@ impl<P: FrontendPlatform> Widget for FileBrowser<'_, P> {
             reverse_sorting,
             show_hidden,
             current_directory,
+            inside_archive,
             current_directory_contents,
             refresh_directory_results,
             directory_to_navigate_to,
@@ -68,25 +69,28 @@ impl<P: FrontendPlatform> Widget for FileBrowser<'_, P> {
                                 native_file_picker_dialog_job.join().unwrap()
                             && !file_handles.is_empty()
                         {
-                            let program_manager = self.program_manager.clone();
-
-                            let job = std::thread::Builder::new()
-                                .name(t!("browser.thread_name_rom_id_calculator").to_string())
-                                .spawn(move || {
+                            register_roms(
+                                self.program_manager,
+                                self.machine_initialization_step,
+                                move |program_manager| {
                                     let mut rom_ids = Vec::default();
 
                                     for handle in file_handles {
-                                        rom_ids.push(
-                                            program_manager.register_external(handle.path())?,
-                                        );
+                                        // Everything in a picked archive is assumed to belong together
+                                        if ArchiveFormat::detect_file(handle.path())?.is_some() {
+                                            rom_ids.extend(
+                                                program_manager.register_archive(handle.path())?,
+                                            );
+                                        } else {
+                                            rom_ids.push(
+                                                program_manager.register_external(handle.path())?,
+                                            );
+                                        }
                                     }
 
                                     Ok(rom_ids)
-                                })
-                                .expect("Could not launch thread to process rom for its id!");
-
-                            *self.machine_initialization_step =
-                                Some(MachineInitializationStep::CalculatingRomIds { job });
+                                },
+                            );
                         }
                     }
                     None if clicked => {
@@ -196,6 +200,21 @@ impl<P: FrontendPlatform> Widget for FileBrowser<'_, P> {
                 *directory_to_navigate_to = Some(current_directory.clone());
             }
 
+            if *inside_archive
+                && ui
+                    .button(t!("browser.load_archive"))
+                    .on_hover_text(t!("browser.load_archive_hint"))
+                    .clicked()
+            {
+                let archive = current_directory.clone();
+
+                register_roms(
+                    self.program_manager,
+                    self.machine_initialization_step,
+                    move |program_manager| program_manager.register_archive(archive),
+                );
+            }
+
             if let Some(job) = refresh_directory_results.as_mut()
                 && job.is_finished()
             {
@@ -225,6 +244,7 @@ impl<P: FrontendPlatform> Widget for FileBrowser<'_, P> {
                             readable,
                             is_hidden,
                             is_directory,
+                            is_archive,
                             ..
                         },
                     ) in current_directory_contents.iter()
@@ -236,6 +256,8 @@ impl<P: FrontendPlatform> Widget for FileBrowser<'_, P> {
 
                         let label = if *is_directory {
                             format!("📁 {}", name_str)
+                        } else if *is_archive {
+                            format!("🗜️ {}", name_str)
                         } else if !*readable {
                             format!("{} 🔒", name_str)
                         } else {
@@ -251,23 +273,32 @@ impl<P: FrontendPlatform> Widget for FileBrowser<'_, P> {
                             )
                             .clicked()
                         {
-                            let path = current_directory.join(name);
-                            if *is_directory {
-                                *directory_to_navigate_to = Some(path.clone());
+                            if *inside_archive {
+                                let archive = current_directory.clone();
+                                let entry = name_str.into_owned();
+
+                                register_roms(
+                                    self.program_manager,
+                                    self.machine_initialization_step,
+                                    move |program_manager| {
+                                        Ok(vec![
+                                            program_manager
+                                                .register_archive_entry(archive, &entry)?,
+                                        ])
+                                    },
+                                );
+                            } else if *is_directory || *is_archive {
+                                *directory_to_navigate_to = Some(current_directory.join(name));
                             } else {
-                                let program_manager = self.program_manager.clone();
-
-                                let job = std::thread::Builder::new()
-                                    .name(t!("browser.thread_name_rom_id_calculator").to_string())
-                                    .spawn(move || {
-                                        let rom_id = program_manager.register_external(path)?;
-
-                                        Ok(vec![rom_id])
-                                    })
-                                    .expect("Could not launch thread to process ROM for its ID!");
-
-                                *self.machine_initialization_step =
-                                    Some(MachineInitializationStep::CalculatingRomIds { job });
+                                let path = current_directory.join(name);
+
+                                register_roms(
+                                    self.program_manager,
+                                    self.machine_initialization_step,
+                                    move |program_manager| {
+                                        Ok(vec![program_manager.register_external(path)?])
+                                    },
+                                );
                             }
                         }
                     }
@@ -280,6 +311,7 @@ impl<P: FrontendPlatform> Widget for FileBrowser<'_, P> {
 
             *pathbar_state = PathBarState::Normal(directory_to_navigate_to.clone());
             *current_directory = directory_to_navigate_to.clone();
+            *inside_archive = directory_to_navigate_to.is_file();
 
             let sorting_method = *sorting_method;
             let reverse_sorting = *reverse_sorting;
@@ -295,37 +327,82 @@ impl<P: FrontendPlatform> Widget for FileBrowser<'_, P> {
     }
 }
 
-/// Populates cwd_tracker with the contents of directory
+/// Register ROMs on a background thread, then go on to identify them
+fn register_roms<P: FrontendPlatform>(
+    program_manager: &Arc<ProgramManager>,
+    machine_initialization_step: &mut Option<MachineInitializationStep<P>>,
+    register: impl FnOnce(&ProgramManager) -> Result<Vec<RomId>, fluxemu_program::Error>
+    + Send
+    + 'static,
+) {
+    let program_manager = program_manager.clone();
+
+    let job = std::thread::Builder::new()
+        .name(t!("browser.thread_name_rom_id_calculator").to_string())
+        .spawn(move || register(&program_manager))
+        .expect("Could not launch thread to process ROM for its ID!");
+
+    *machine_initialization_step = Some(MachineInitializationStep::CalculatingRomIds { job });
+}
+
+/// Populates cwd_tracker with the contents of directory, or of the archive if it is one
 fn refresh_current_dir_task(
     directory: PathBuf,
     sorting_method: SortingMethod,
     reverse: bool,
-) -> Result<IndexMap<OsString, DirectoryEntry>, std::io::Error> {
+) -> Result<IndexMap<OsString, DirectoryEntry>, fluxemu_program::Error> {
     let mut contents = IndexMap::default();
-    let directory_reader = read_dir(&directory)?;
-
-    for entry in directory_reader {
-        let entry = entry?;
-        let path = entry.path();
-        let name = entry.file_name();
 
-        let readable = path_is_readable(&path);
-        let is_hidden = path_is_hidden(&path);
+    // Only files named like a archive are opened to check what they are
+    if ArchiveFormat::from_extension(&directory).is_some() && directory.is_file() {
+        let format = ArchiveFormat::detect_file(&directory)?
+            .ok_or_else(|| fluxemu_program::Error::NotAnArchive(directory.clone()))?;
 
-        let modified = path
+        let modified = directory
             .metadata()
             .and_then(|metadata| metadata.modified())
             .unwrap_or_else(|_| SystemTime::now());
 
-        contents.insert(
-            name,
-            DirectoryEntry {
-                readable,
-                modified,
-                is_hidden,
-                is_directory: path.is_dir(),
-            },
-        );
+        for entry in archive_entries(&directory, format)? {
+            contents.insert(
+                OsString::from(entry),
+                DirectoryEntry {
+                    readable: true,
+                    modified,
+                    is_hidden: false,
+                    is_directory: false,
+                    is_archive: false,
+                },
+            );
+        }
+    } else {
+        for entry in read_dir(&directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name();
+
+            let readable = path_is_readable(&path);
+            let is_hidden = path_is_hidden(&path);
+
+            let modified = path
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or_else(|_| SystemTime::now());
+
+            let is_directory = path.is_dir();
+            let is_archive = !is_directory && ArchiveFormat::from_extension(&path).is_some();
+
+            contents.insert(
+                name,
+                DirectoryEntry {
+                    readable,
+                    modified,
+                    is_hidden,
+                    is_directory,
+                    is_archive,
+                },
+            );
+        }
     }
 
     match sorting_method {
@@ -9,6 +9,8 @@ pub struct DirectoryEntry {
     pub modified: SystemTime,
     pub is_hidden: bool,
     pub is_directory: bool,
+    /// A zip or 7z archive, which can be browsed like a directory
+    pub is_archive: bool,
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug, EnumIter, AsRefStr)]
@@ -27,6 +29,8 @@ pub enum PathBarState {
 pub struct FileBrowserState {
     pub pathbar_state: PathBarState,
     pub current_directory: PathBuf,
+    /// The current directory is a archive and its contents are archive entries
+    pub inside_archive: bool,
     pub current_directory_contents: IndexMap<OsString, DirectoryEntry>,
     pub sorting_method: SortingMethod,
     pub reverse_sorting: bool,
@@ -34,7 +38,7 @@ pub struct FileBrowserState {
     pub directory_to_navigate_to: Option<PathBuf>,
 
     pub refresh_directory_results:
-        Option<JoinHandle<Result<IndexMap<OsString, DirectoryEntry>, std::io::Error>>>,
+        Option<JoinHandle<Result<IndexMap<OsString, DirectoryEntry>, fluxemu_program::Error>>>,
 
     #[cfg(feature = "external-file-dialog")]
     pub native_file_picker_dialog_job: Option<JoinHandle<Option<Vec<rfd::FileHandle>>>>,
@@ -45,6 +49,7 @@ impl FileBrowserState {
         Self {
             pathbar_state: PathBarState::Normal(home_directory.clone()),
             current_directory: home_directory.clone(),
+            inside_archive: false,
             sorting_method: SortingMethod::Name,
             reverse_sorting: false,
             show_hidden: false,
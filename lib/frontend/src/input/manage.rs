@@ -40,6 +40,8 @@ impl<P: FrontendPlatform> Frontend<P> {
             .insert(input_id, state);
 
         let mut was_relevant_for_hotkeys = false;
+        // Rewinding lasts as long as any of its combinations are held, rather than triggering once
+        let mut rewind_held = None;
 
         // Check for hotkeys
         for (combinations, hotkey_action) in &physical_gamepad_configuration.hotkey {
@@ -52,6 +54,10 @@ impl<P: FrontendPlatform> Frontend<P> {
                     .as_digital(None)
             });
 
+            if *hotkey_action == Hotkey::Rewind {
+                rewind_held = Some(rewind_held.unwrap_or(false) || is_activated);
+            }
+
             if is_activated {
                 was_relevant_for_hotkeys = true;
 
@@ -80,7 +86,7 @@ impl<P: FrontendPlatform> Frontend<P> {
 
                                 // Unset ALL inputs
                                 for (logical_input_device_path, logical_input_device) in
-                                    runtime_guard.input_devices()
+                                    &runtime_guard.input_devices()
                                 {
                                     let unset_inputs = logical_input_device
                                         .metadata()
@@ -101,6 +107,7 @@ impl<P: FrontendPlatform> Frontend<P> {
                         }
                     }
                     Hotkey::FastForward => {}
+                    Hotkey::Rewind => {}
                     Hotkey::LoadSnapshot => {}
                     Hotkey::StoreSnapshot => {}
                     Hotkey::IncrementSnapshotCounter => {
@@ -129,6 +136,15 @@ impl<P: FrontendPlatform> Frontend<P> {
             }
         }
 
+        if let Some(rewind_held) = rewind_held
+            && let Some(MachineContext {
+                simulation_controller,
+                ..
+            }) = &self.machine_context
+        {
+            simulation_controller.set_rewinding(rewind_held && !self.frontend_overlay_active);
+        }
+
         // Ignore if that key participated in a hotkey(s)
         if !was_relevant_for_hotkeys {
             if !self.frontend_overlay_active {
@@ -152,6 +168,7 @@ impl<P: FrontendPlatform> Frontend<P> {
                                 let logical_input_device = runtime_guard
                                     .input_devices()
                                     .get(logical_input_device_path)
+                                    .cloned()
                                     .unwrap();
 
                                 logical_input_device
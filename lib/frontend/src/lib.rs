@@ -7,7 +7,9 @@ mod file_browser;
 mod input;
 pub mod machine;
 mod platform;
+mod quirks;
 mod settings;
+mod system_choice;
 mod toast;
 
 use std::{borrow::Cow, collections::HashMap, ops::Deref, sync::Arc, thread::JoinHandle};
@@ -20,13 +22,14 @@ use egui_toast::ToastKind;
 use fluxemu_environment::{ENVIRONMENT_LOCATION, Environment};
 use fluxemu_graphics::api::GraphicsApi;
 use fluxemu_input::{InputId, InputState, physical::PhysicalInputDeviceId};
-use fluxemu_program::{ProgramManager, ProgramSpecification, RomId};
+use fluxemu_program::{ProgramManager, ProgramSpecification, RomId, SystemGuess};
 use fluxemu_runtime::{
     ResourcePath,
     machine::{Machine, builder::SealedMachineBuilder},
     platform::Platform,
 };
 use indexmap::{IndexMap, IndexSet};
+use num::rational::Ratio;
 use palette::Srgba;
 pub use platform::*;
 use ron::ser::PrettyConfig;
@@ -36,7 +39,7 @@ use crate::{
     audio::{AudioRuntime, mixer::AudioMixer},
     file_browser::{FileBrowser, state::FileBrowserState},
     input::translator::EguiInputTranslator,
-    machine::{FactoryManager, SimulationController},
+    machine::{FactoryError, FactoryManager, ProfilerView, SimulationController},
     toast::ToastManager,
 };
 
@@ -70,6 +73,7 @@ impl TabId {
 struct MachineContext {
     machine: Arc<Machine>,
     simulation_controller: SimulationController,
+    profiler: ProfilerView,
 }
 
 #[derive(Debug, Clone)]
@@ -92,9 +96,14 @@ enum MachineInitializationStep<P: Platform> {
         roms: Vec<RomId>,
         job: JoinHandle<Result<Vec<ProgramSpecification>, fluxemu_program::Error>>,
     },
+    /// Step 2b: Ask the user for the system when nothing knows of the program and guessing is inconclusive
+    ChoosingSystem {
+        rom_id: RomId,
+        guesses: Vec<SystemGuess>,
+    },
     /// Step 3: Create and seal a machine builder given the specification
     BuildingMachineBuilder {
-        job: JoinHandle<Option<SealedMachineBuilder<P>>>,
+        job: JoinHandle<Result<SealedMachineBuilder<P>, FactoryError>>,
     },
 }
 
@@ -193,10 +202,20 @@ impl<P: FrontendPlatform> Frontend<P> {
         let program_manager = self.program_manager.clone();
         let machine_factories = self.machine_factory_manager.clone();
 
-        let machine_builder = Machine::build(Some(specification), program_manager);
+        // Quirks the user set win over the ones the database knows of
+        let quirks = self
+            .environment
+            .program_quirks
+            .get(&specification.id)
+            .cloned()
+            .or_else(|| specification.info.quirks().map(str::to_owned));
+
+        let machine_builder = Machine::build(Some(specification), program_manager)
+            .save_directory(self.environment.save_directory.clone())
+            .overclock(Ratio::new(self.environment.overclock.into(), 100));
 
         let handle = std::thread::spawn(move || {
-            machine_factories.construct_machine(ron::Value::from(()), machine_builder)
+            machine_factories.construct_machine(quirks.as_deref(), machine_builder)
         });
 
         self.machine_initialization_step =
@@ -234,14 +253,18 @@ impl<P: FrontendPlatform> Frontend<P> {
             // Exit runtime
             drop(runtime_guard);
 
-            let simulation_controller =
-                SimulationController::new(machine.clone(), self.audio_mixer.clone());
+            let simulation_controller = SimulationController::new(
+                machine.clone(),
+                self.audio_mixer.clone(),
+                &self.environment.rewind,
+            );
 
             // Make sure the simulation is currently running
             simulation_controller.set_paused(false);
 
             self.machine_context = Some(MachineContext {
                 simulation_controller,
+                profiler: ProfilerView::new(machine.clone()),
                 machine,
             });
 
@@ -257,7 +280,7 @@ impl<P: FrontendPlatform> Frontend<P> {
 
         self.egui_context.clone().run_ui(external_input, |ui| {
             if let Some(machine_initialization_step) = self.machine_initialization_step.take() {
-                self.service_machine_initialization_step(machine_initialization_step);
+                self.service_machine_initialization_step(ui, machine_initialization_step);
             }
 
             self.toast_manager.show(ui);
@@ -286,7 +309,9 @@ impl<P: FrontendPlatform> Frontend<P> {
             CentralPanel::default().show(ui, |ui| {
                 ui.with_layout(Layout::top_down_justified(Align::LEFT), |ui| {
                     Frame::new().show(ui, |ui| match self.current_tab {
-                        TabId::Library => {}
+                        TabId::Library => {
+                            self.handle_program_quirks(ui);
+                        }
                         TabId::FileBrowser => {
                             ui.add(FileBrowser {
                                 state: &mut self.file_browser_state,
@@ -303,10 +328,13 @@ impl<P: FrontendPlatform> Frontend<P> {
                         TabId::Debug => {
                             if let Some(MachineContext {
                                 simulation_controller,
+                                profiler,
                                 ..
                             }) = &mut self.machine_context
                             {
                                 ui.add(simulation_controller);
+                                ui.separator();
+                                ui.add(profiler);
                             }
                         }
                         TabId::About => {}
@@ -316,7 +344,11 @@ impl<P: FrontendPlatform> Frontend<P> {
         })
     }
 
-    fn service_machine_initialization_step(&mut self, step: MachineInitializationStep<P>) {
+    fn service_machine_initialization_step(
+        &mut self,
+        ui: &mut egui::Ui,
+        step: MachineInitializationStep<P>,
+    ) {
         match step {
             MachineInitializationStep::CalculatingRomIds { job } if job.is_finished() => {
                 match job.join().unwrap() {
@@ -342,8 +374,16 @@ impl<P: FrontendPlatform> Frontend<P> {
                         let specification = if !specifications.is_empty() {
                             specifications.remove(0)
                         } else {
-                            let Ok(Some(program_specification)) =
-                                self.program_manager.auto_generate_specification(roms[0])
+                            // An empty archive leaves nothing to guess from
+                            let Some((rom_id, guesses)) = roms
+                                .first()
+                                .and_then(|rom_id| {
+                                    Some((
+                                        *rom_id,
+                                        self.program_manager.guess_systems(*rom_id).ok()?,
+                                    ))
+                                })
+                                .filter(|(_, guesses)| !guesses.is_empty())
                             else {
                                 self.toast_manager
                                     .toast(ToastKind::Error, "Could not properly identify program");
@@ -351,7 +391,18 @@ impl<P: FrontendPlatform> Frontend<P> {
                                 return;
                             };
 
-                            program_specification
+                            if SystemGuess::is_ambiguous(&guesses) {
+                                self.machine_initialization_step =
+                                    Some(MachineInitializationStep::ChoosingSystem {
+                                        rom_id,
+                                        guesses,
+                                    });
+
+                                return;
+                            }
+
+                            self.program_manager
+                                .generate_specification(rom_id, guesses[0].system)
                         };
 
                         self.build_machine_for_specification(specification);
@@ -364,12 +415,18 @@ impl<P: FrontendPlatform> Frontend<P> {
                     }
                 }
             }
+            MachineInitializationStep::ChoosingSystem { rom_id, guesses } => {
+                self.handle_system_choice(ui, rom_id, guesses);
+            }
             MachineInitializationStep::BuildingMachineBuilder { job } if job.is_finished() => {
-                if let Some(sealed) = job.join().unwrap() {
-                    self.pending_machine = Some(sealed);
-                } else {
-                    self.toast_manager
-                        .toast(ToastKind::Error, "Could not construct machine for program");
+                match job.join().unwrap() {
+                    Ok(sealed) => self.pending_machine = Some(sealed),
+                    Err(err) => {
+                        self.toast_manager.toast(
+                            ToastKind::Error,
+                            format!("Could not construct machine for program: {}", err),
+                        );
+                    }
                 }
             }
             unfinished => self.machine_initialization_step = Some(unfinished),
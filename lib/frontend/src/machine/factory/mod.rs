@@ -0,0 +1,129 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use fluxemu_graphics::api::GraphicsApi;
+use fluxemu_program::SystemId;
+use fluxemu_runtime::{
+    machine::{
+        RuntimeGuard,
+        builder::{MachineBuilder, SealedMachineBuilder},
+    },
+    platform::Platform,
+};
+use fluxemu_system::System;
+use ron::{Options, extensions::Extensions};
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+
+type MachineConstructor<P> = Box<
+    dyn Fn(Option<&str>, MachineBuilder<P>) -> Result<SealedMachineBuilder<P>, FactoryError>
+        + Send
+        + Sync,
+>;
+
+type PeripheralPlugger<P> = Box<
+    dyn Fn(
+            &RuntimeGuard<'_>,
+            &str,
+            <<P as Platform>::GraphicsApi as GraphicsApi>::InitializationData,
+        ) -> Result<(), Box<dyn std::error::Error>>
+        + Send
+        + Sync,
+>;
+
+struct Factory<P: Platform> {
+    constructor: MachineConstructor<P>,
+    peripheral_plugger: PeripheralPlugger<P>,
+}
+
+#[derive(Debug, Error)]
+pub enum FactoryError {
+    #[error("No machine factory is available for this program")]
+    MissingFactory,
+    #[error("Invalid quirks for this program: {0}")]
+    InvalidQuirks(#[from] ron::error::SpannedError),
+    #[error("Could not build the machine: {0}")]
+    Build(Box<dyn std::error::Error + Send + Sync>),
+    #[error("Could not plug in the peripheral: {0}")]
+    Peripheral(Box<dyn std::error::Error>),
+}
+
+/// Factory storage for frontend machine generation automation
+pub struct FactoryManager<P: Platform>(HashMap<SystemId, Factory<P>>);
+
+impl<P: Platform> Debug for FactoryManager<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MachineFactories").finish()
+    }
+}
+
+impl<P: Platform> FactoryManager<P> {
+    /// Register a factory
+    pub fn insert_factory<S: System<P> + Default>(&mut self) {
+        self.0.insert(
+            S::ID,
+            Factory {
+                constructor: Box::new(|quirks, machine_builder| {
+                    let factory = S::default();
+                    let quirks = match quirks {
+                        Some(quirks) => Options::default()
+                            .with_default_extension(Extensions::IMPLICIT_SOME)
+                            .from_str(quirks)?,
+                        None => S::Quirks::default(),
+                    };
+
+                    factory
+                        .build(quirks, machine_builder)
+                        .map_err(FactoryError::Build)
+                }),
+                peripheral_plugger: Box::new(
+                    |runtime_guard, peripheral, graphics_initialization_data| {
+                        S::default().plug_peripheral(
+                            runtime_guard,
+                            peripheral,
+                            graphics_initialization_data,
+                        )
+                    },
+                ),
+            },
+        );
+    }
+
+    /// Construct a machine based upon the factories
+    ///
+    /// `quirks` is the RON form of the quirks of the system the program runs on, with the system defaults used if
+    /// none are given
+    pub fn construct_machine(
+        &self,
+        quirks: Option<&str>,
+        machine_builder: MachineBuilder<P>,
+    ) -> Result<SealedMachineBuilder<P>, FactoryError> {
+        let factory = machine_builder
+            .system_id()
+            .and_then(|system| self.0.get(&system))
+            .ok_or(FactoryError::MissingFactory)?;
+
+        (factory.constructor)(quirks, machine_builder)
+    }
+
+    /// Plug a peripheral into a running machine of `system`, by the name the system gives it
+    pub fn plug_peripheral(
+        &self,
+        system: SystemId,
+        runtime_guard: &RuntimeGuard<'_>,
+        peripheral: &str,
+        graphics_initialization_data: <P::GraphicsApi as GraphicsApi>::InitializationData,
+    ) -> Result<(), FactoryError> {
+        let factory = self.0.get(&system).ok_or(FactoryError::MissingFactory)?;
+
+        (factory.peripheral_plugger)(runtime_guard, peripheral, graphics_initialization_data)
+            .map_err(FactoryError::Peripheral)
+    }
+}
+
+impl<P: Platform> Default for FactoryManager<P> {
+    fn default() -> Self {
+        Self(HashMap::default())
+    }
+}
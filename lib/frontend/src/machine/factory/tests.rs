@@ -0,0 +1,159 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use fluxemu_program::{
+    OtherSystem, ProgramId, ProgramInfo, ProgramManager, ProgramSpecification, SystemId,
+};
+use fluxemu_runtime::{
+    machine::{
+        Machine,
+        builder::{MachineBuilder, SealedMachineBuilder},
+    },
+    platform::TestPlatform,
+};
+use fluxemu_system::System;
+use redb::{Database, backends::InMemoryBackend};
+use serde::{Deserialize, Serialize};
+
+use super::{FactoryError, FactoryManager};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+struct TestQuirks {
+    fast: bool,
+    name: Option<String>,
+    refuse: bool,
+}
+
+thread_local! {
+    static BUILT_WITH: RefCell<Option<TestQuirks>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Default)]
+struct TestSystem;
+
+impl System<TestPlatform> for TestSystem {
+    type Quirks = TestQuirks;
+
+    const ID: SystemId = SystemId::Unknown;
+
+    fn build(
+        &self,
+        quirks: Self::Quirks,
+        machine_builder: MachineBuilder<TestPlatform>,
+    ) -> Result<SealedMachineBuilder<TestPlatform>, Box<dyn std::error::Error + Send + Sync>> {
+        if quirks.refuse {
+            return Err("Refused to build".into());
+        }
+
+        BUILT_WITH.set(Some(quirks));
+
+        Ok(machine_builder.seal())
+    }
+}
+
+fn construct(
+    system: SystemId,
+    quirks: Option<&str>,
+) -> Result<SealedMachineBuilder<TestPlatform>, FactoryError> {
+    let database = Database::builder()
+        .create_with_backend(InMemoryBackend::default())
+        .unwrap();
+    let program_manager = ProgramManager::new(database, []).unwrap();
+
+    let specification = ProgramSpecification {
+        id: ProgramId {
+            system,
+            name: "Quirky".to_string(),
+        },
+        info: ProgramInfo::V1 {
+            names: BTreeSet::from_iter(["Quirky".to_string()]),
+            filesystem: BTreeMap::default(),
+            languages: BTreeSet::default(),
+            version: None,
+            quirks: None,
+        },
+    };
+
+    let mut factories = FactoryManager::default();
+    factories.insert_factory::<TestSystem>();
+
+    BUILT_WITH.set(None);
+    factories.construct_machine(
+        quirks,
+        Machine::build_test(Some(specification), program_manager),
+    )
+}
+
+#[test]
+fn quirks_reach_the_system() {
+    construct(SystemId::Unknown, Some(r#"(fast: true, name: "Tengen")"#)).unwrap();
+
+    assert_eq!(
+        BUILT_WITH.take(),
+        Some(TestQuirks {
+            fast: true,
+            name: Some("Tengen".to_string()),
+            refuse: false,
+        })
+    );
+}
+
+#[test]
+fn missing_quirks_are_the_defaults() {
+    construct(SystemId::Unknown, None).unwrap();
+    assert_eq!(BUILT_WITH.take(), Some(TestQuirks::default()));
+
+    construct(SystemId::Unknown, Some("()")).unwrap();
+    assert_eq!(BUILT_WITH.take(), Some(TestQuirks::default()));
+}
+
+#[test]
+fn malformed_quirks_are_rejected() {
+    assert!(matches!(
+        construct(SystemId::Unknown, Some("(fast: 3)")),
+        Err(FactoryError::InvalidQuirks(_))
+    ));
+    assert_eq!(BUILT_WITH.take(), None);
+}
+
+#[test]
+fn build_failures_are_passed_on() {
+    assert!(matches!(
+        construct(SystemId::Unknown, Some("(refuse: true)")),
+        Err(FactoryError::Build(_))
+    ));
+}
+
+#[test]
+fn systems_without_a_factory_are_rejected() {
+    assert!(matches!(
+        construct(SystemId::Other(OtherSystem::Chip8), None),
+        Err(FactoryError::MissingFactory)
+    ));
+}
+
+#[test]
+fn unknown_peripherals_are_rejected() {
+    let mut factories = FactoryManager::<TestPlatform>::default();
+    factories.insert_factory::<TestSystem>();
+
+    let machine = Machine::build_test_minimal().seal().build(());
+    let runtime_guard = machine.enter_runtime();
+
+    assert!(matches!(
+        factories.plug_peripheral(SystemId::Unknown, &runtime_guard, "zapper", ()),
+        Err(FactoryError::Peripheral(_))
+    ));
+    assert!(matches!(
+        factories.plug_peripheral(
+            SystemId::Other(OtherSystem::Chip8),
+            &runtime_guard,
+            "zapper",
+            ()
+        ),
+        Err(FactoryError::MissingFactory)
+    ));
+}
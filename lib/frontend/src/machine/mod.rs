@@ -1,5 +1,7 @@
 mod factory;
+mod profiler;
 mod simulation_controller;
 
-pub use factory::FactoryManager;
+pub use factory::{FactoryError, FactoryManager};
+pub(crate) use profiler::ProfilerView;
 pub(crate) use simulation_controller::SimulationController;
@@ -0,0 +1,118 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use egui::{Label, Response, ScrollArea, Sense, Ui, Widget};
+use egui_extras::{Column, TableBuilder};
+use fluxemu_runtime::{ComponentPath, machine::Machine, profiling::ComponentProfile};
+use rust_i18n::t;
+
+const UI_UPDATE_RATE: Duration = Duration::from_millis(500);
+
+/// Per component statistics of the running machine
+#[derive(Debug)]
+pub struct ProfilerView {
+    machine: Arc<Machine>,
+    components: BTreeMap<ComponentPath, ComponentProfile>,
+    last_refresh: Option<Instant>,
+}
+
+impl ProfilerView {
+    pub fn new(machine: Arc<Machine>) -> Self {
+        Self {
+            machine,
+            components: BTreeMap::new(),
+            last_refresh: None,
+        }
+    }
+}
+
+impl Widget for &mut ProfilerView {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let runtime_guard = self.machine.enter_runtime();
+        let mut profiling = runtime_guard.profiling();
+
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut profiling, t!("profiler.enabled"))
+                .changed()
+            {
+                runtime_guard.set_profiling(profiling);
+            }
+
+            if ui.button(t!("profiler.reset")).clicked() {
+                runtime_guard.reset_profile();
+                self.last_refresh = None;
+            }
+
+            if ui.button(t!("profiler.copy_trace")).clicked() {
+                // Only here are the spans worth walking
+                ui.ctx()
+                    .copy_text(runtime_guard.profile().to_chrome_trace());
+            }
+        });
+
+        // Merging every thread's statistics isn't free, so don't do it every frame
+        if self
+            .last_refresh
+            .is_none_or(|last_refresh| last_refresh.elapsed() >= UI_UPDATE_RATE)
+        {
+            self.components = runtime_guard.component_profiles();
+            self.last_refresh = Some(Instant::now());
+        }
+
+        drop(runtime_guard);
+
+        if profiling {
+            ui.ctx().request_repaint_after(UI_UPDATE_RATE);
+        }
+
+        ScrollArea::horizontal().show(ui, |ui| {
+            TableBuilder::new(ui)
+                .column(Column::auto().resizable(true))
+                .columns(Column::auto(), 6)
+                .striped(true)
+                .header(20.0, |mut header| {
+                    for title in [
+                        t!("profiler.component"),
+                        t!("profiler.interactions"),
+                        t!("profiler.synchronizations"),
+                        t!("profiler.synchronize_time"),
+                        t!("profiler.emulated_time"),
+                        t!("profiler.events"),
+                        t!("profiler.catch_up_depth"),
+                    ] {
+                        header.col(|ui| {
+                            ui.strong(title);
+                        });
+                    }
+                })
+                .body(|mut body| {
+                    for (path, profile) in &self.components {
+                        body.row(20.0, |mut row| {
+                            row.col(|ui| {
+                                ui.add(Label::new(path.to_string()).extend());
+                            });
+
+                            for value in [
+                                profile.interactions.to_string(),
+                                profile.synchronizations.to_string(),
+                                format!("{:?}", profile.synchronize_time),
+                                format!("{:.6}s", profile.emulated_time.to_num::<f64>()),
+                                format!("{} ({:?})", profile.events_handled, profile.event_time),
+                                profile.max_catch_up_depth.to_string(),
+                            ] {
+                                row.col(|ui| {
+                                    ui.label(value);
+                                });
+                            }
+                        });
+                    }
+                });
+        });
+
+        ui.allocate_rect(ui.min_rect(), Sense::empty())
+    }
+}
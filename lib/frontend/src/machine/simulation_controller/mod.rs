@@ -7,11 +7,13 @@ use std::{
     time::Duration,
 };
 
+use fluxemu_environment::rewind::RewindSettings;
 use fluxemu_runtime::machine::Machine;
 
 use crate::{
     AudioMixer,
     machine::simulation_controller::{
+        rewind::RewindRecorder,
         thread::{SimulationControllerState, simulation_controller_loop},
         ui::UiState,
     },
@@ -29,6 +31,7 @@ const DIMINISHING_RETURNS_ELASTICITY: f32 = 0.4;
 const MIN_PROBE_DELTA: f32 = 0.05;
 const PROBE_WINDOW: usize = 64;
 
+mod rewind;
 mod thread;
 mod ui;
 
@@ -52,9 +55,14 @@ impl Drop for SimulationController {
 }
 
 impl SimulationController {
-    pub fn new(machine: Arc<Machine>, audio_mixer: Arc<AudioMixer>) -> Self {
+    pub fn new(
+        machine: Arc<Machine>,
+        audio_mixer: Arc<AudioMixer>,
+        rewind_settings: &RewindSettings,
+    ) -> Self {
         let shared = Arc::new(SharedState {
             paused: AtomicBool::new(true),
+            rewinding: AtomicBool::new(false),
             should_exit: AtomicBool::new(false),
             state: Mutex::default(),
         });
@@ -63,9 +71,12 @@ impl SimulationController {
             .name("simulation_controller".to_string())
             .spawn({
                 let shared = shared.clone();
+                let rewind_recorder = rewind_settings
+                    .enabled
+                    .then(|| RewindRecorder::new(rewind_settings, machine.refresh_rate()));
 
                 move || {
-                    simulation_controller_loop(machine, audio_mixer, shared);
+                    simulation_controller_loop(machine, audio_mixer, shared, rewind_recorder);
                 }
             })
             .expect("Failed to spawn simulation controller thread");
@@ -82,11 +93,17 @@ impl SimulationController {
 
         self.handle.as_ref().unwrap().thread().unpark();
     }
+
+    /// Step the machine backward through recorded states instead of running it forward
+    pub fn set_rewinding(&self, rewinding: bool) {
+        self.shared.rewinding.store(rewinding, Ordering::Release);
+    }
 }
 
 #[derive(Debug)]
 struct SharedState {
     paused: AtomicBool,
+    rewinding: AtomicBool,
     should_exit: AtomicBool,
     state: Mutex<SimulationControllerState>,
 }
@@ -0,0 +1,201 @@
+use std::{collections::VecDeque, time::Duration};
+
+use fluxemu_environment::rewind::RewindSettings;
+use fluxemu_runtime::{
+    machine::RuntimeGuard,
+    scheduler::{Frequency, Period},
+    snapshot::Snapshot,
+};
+
+use crate::audio::mixer::AudioMixer;
+
+#[cfg(test)]
+mod tests;
+
+/// Rate frames are assumed to run at for the capture interval, for machines that don't declare their own
+const DEFAULT_REFRESH_RATE: Frequency = Frequency::lit("60");
+
+/// Periodically records machine state and steps back through it
+#[derive(Debug)]
+pub struct RewindRecorder {
+    buffer: RewindBuffer,
+    capture_period: Period,
+    last_capture: Period,
+}
+
+impl RewindRecorder {
+    pub fn new(settings: &RewindSettings, refresh_rate: Option<Frequency>) -> Self {
+        let refresh_rate = refresh_rate.unwrap_or(DEFAULT_REFRESH_RATE);
+
+        Self {
+            buffer: RewindBuffer::new(settings),
+            capture_period: Period::from_num(settings.capture_interval.max(1)) / refresh_rate,
+            last_capture: Period::ZERO,
+        }
+    }
+
+    /// How long a single step backward should be presented for
+    pub fn step_duration(&self) -> Duration {
+        Duration::from_secs_f64(self.capture_period.to_num())
+    }
+
+    /// Record the machine state if enough emulated time has passed since the last capture
+    pub fn capture(&mut self, runtime_guard: &RuntimeGuard<'_>) {
+        let timestamp = runtime_guard.safe_advance_timestamp();
+
+        if timestamp.saturating_sub(self.last_capture) < self.capture_period {
+            return;
+        }
+
+        match runtime_guard
+            .snapshot()
+            .and_then(|snapshot| snapshot.to_bytes())
+        {
+            Ok(snapshot) => self.buffer.push(snapshot),
+            Err(error) => tracing::warn!("Could not capture rewind state: {}", error),
+        }
+
+        self.last_capture = timestamp;
+    }
+
+    /// Restore the most recently recorded state
+    ///
+    /// Audio generated past that point is thrown away so the mixer doesn't play sound from the future
+    pub fn step_back(&mut self, runtime_guard: &RuntimeGuard<'_>, audio_mixer: &AudioMixer) {
+        let Some(snapshot) = self.buffer.pop() else {
+            return;
+        };
+
+        let result =
+            Snapshot::from_bytes(&snapshot).and_then(|snapshot| runtime_guard.restore(&snapshot));
+
+        if let Err(error) = result {
+            tracing::error!("Could not restore rewind state: {}", error);
+
+            // Whatever is left is probably just as unusable
+            self.buffer.clear();
+            return;
+        }
+
+        audio_mixer.discard_machine_samples(runtime_guard);
+        self.last_capture = runtime_guard.safe_advance_timestamp();
+    }
+}
+
+#[derive(Debug)]
+struct KeyframeGroup {
+    /// Compressed snapshot every delta in this group is relative to
+    keyframe: Vec<u8>,
+    /// Compressed deltas against the keyframe, oldest first
+    deltas: Vec<Vec<u8>>,
+}
+
+impl KeyframeGroup {
+    fn size(&self) -> usize {
+        self.keyframe.len() + self.deltas.iter().map(Vec::len).sum::<usize>()
+    }
+}
+
+/// Ring of encoded machine snapshots, stored as compressed deltas against periodic keyframes
+#[derive(Debug)]
+pub struct RewindBuffer {
+    groups: VecDeque<KeyframeGroup>,
+    /// Uncompressed keyframe of the newest group
+    current_keyframe: Vec<u8>,
+    keyframe_interval: usize,
+    memory_budget: usize,
+    /// Size of every compressed keyframe and delta
+    size: usize,
+}
+
+impl RewindBuffer {
+    pub fn new(settings: &RewindSettings) -> Self {
+        Self {
+            groups: VecDeque::new(),
+            current_keyframe: Vec::new(),
+            keyframe_interval: usize::from(settings.keyframe_interval),
+            memory_budget: settings.memory_budget,
+            size: 0,
+        }
+    }
+
+    /// Record an encoded snapshot, evicting the oldest ones if the memory budget is exceeded
+    pub fn push(&mut self, snapshot: Vec<u8>) {
+        match self.groups.back_mut() {
+            Some(group) if group.deltas.len() < self.keyframe_interval => {
+                let delta =
+                    lz4_flex::compress_prepend_size(&xor_delta(&snapshot, &self.current_keyframe));
+
+                self.size += delta.len();
+                group.deltas.push(delta);
+            }
+            _ => {
+                let keyframe = lz4_flex::compress_prepend_size(&snapshot);
+
+                self.size += keyframe.len();
+                self.groups.push_back(KeyframeGroup {
+                    keyframe,
+                    deltas: Vec::new(),
+                });
+                self.current_keyframe = snapshot;
+            }
+        }
+
+        // Always keep the newest group so we have something to go back to
+        while self.footprint() > self.memory_budget && self.groups.len() > 1 {
+            let group = self.groups.pop_front().unwrap();
+            self.size -= group.size();
+        }
+    }
+
+    /// Take out the most recently recorded snapshot
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let group = self.groups.back_mut()?;
+
+        if let Some(delta) = group.deltas.pop() {
+            self.size -= delta.len();
+
+            let delta = lz4_flex::decompress_size_prepended(&delta)
+                .expect("Rewind buffer delta is corrupted");
+
+            return Some(xor_delta(&delta, &self.current_keyframe));
+        }
+
+        let group = self.groups.pop_back().unwrap();
+        self.size -= group.size();
+
+        let snapshot = std::mem::take(&mut self.current_keyframe);
+
+        if let Some(group) = self.groups.back() {
+            self.current_keyframe = lz4_flex::decompress_size_prepended(&group.keyframe)
+                .expect("Rewind buffer keyframe is corrupted");
+        }
+
+        Some(snapshot)
+    }
+
+    /// Memory taken by the recorded snapshots, including the uncompressed keyframe deltas are applied against
+    fn footprint(&self) -> usize {
+        self.size + self.current_keyframe.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.current_keyframe.clear();
+        self.size = 0;
+    }
+}
+
+/// XOR the data against a keyframe
+///
+/// Consecutive snapshots are mostly identical, so this produces long runs of zeros that compress well. Applying it twice
+/// with the same keyframe yields the original data
+fn xor_delta(data: &[u8], keyframe: &[u8]) -> Vec<u8> {
+    let mut delta = data.to_vec();
+
+    for (byte, keyframe_byte) in delta.iter_mut().zip(keyframe) {
+        *byte ^= keyframe_byte;
+    }
+
+    delta
+}
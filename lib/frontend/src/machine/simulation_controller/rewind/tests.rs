@@ -0,0 +1,74 @@
+use fluxemu_environment::rewind::RewindSettings;
+
+use super::RewindBuffer;
+
+fn snapshot(index: u8) -> Vec<u8> {
+    let mut snapshot = vec![0xaa; 256];
+    snapshot[usize::from(index)] = index;
+    // Snapshots do not all come out the same size
+    snapshot.resize(256 + usize::from(index % 3), index);
+
+    snapshot
+}
+
+#[test]
+fn pops_snapshots_newest_first_across_keyframes() {
+    let mut buffer = RewindBuffer::new(&RewindSettings {
+        enabled: true,
+        capture_interval: 1,
+        keyframe_interval: 3,
+        memory_budget: usize::MAX,
+    });
+
+    for index in 0..10 {
+        buffer.push(snapshot(index));
+    }
+
+    for index in (0..10).rev() {
+        assert_eq!(buffer.pop(), Some(snapshot(index)));
+    }
+    assert_eq!(buffer.pop(), None);
+}
+
+#[test]
+fn evicts_oldest_groups_over_budget() {
+    let mut buffer = RewindBuffer::new(&RewindSettings {
+        enabled: true,
+        capture_interval: 1,
+        keyframe_interval: 1,
+        memory_budget: 1,
+    });
+
+    for index in 0..10 {
+        buffer.push(snapshot(index));
+    }
+
+    // Only the newest group survives a budget nothing fits in
+    assert_eq!(buffer.pop(), Some(snapshot(9)));
+    assert_eq!(buffer.pop(), Some(snapshot(8)));
+    assert_eq!(buffer.pop(), None);
+}
+
+#[test]
+fn uncompressed_keyframe_counts_against_budget() {
+    let memory_budget = 300;
+    let mut buffer = RewindBuffer::new(&RewindSettings {
+        enabled: true,
+        capture_interval: 1,
+        keyframe_interval: 1,
+        memory_budget,
+    });
+
+    // The compressed groups alone would all fit
+    for index in 0..10 {
+        buffer.push(snapshot(index));
+    }
+
+    assert!(buffer.footprint() <= memory_budget);
+
+    let mut remaining = 0;
+    while buffer.pop().is_some() {
+        remaining += 1;
+    }
+    assert!(remaining < 10);
+}
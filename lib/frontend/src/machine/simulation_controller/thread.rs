@@ -14,7 +14,7 @@ use crate::{
     machine::simulation_controller::{
         COMFORTABLE_HEADROOM, DIMINISHING_RETURNS_ELASTICITY, EXPLORATION_CHANGE,
         HARDWARE_SPEED_EMA, HISTORICAL_SAMPLE_WINDOW, JITTER_CEILING, MAX_SCHEDULE_DRIFT,
-        MIN_PROBE_DELTA, OVERSHOOT_EMA_ALPHA, PROBE_WINDOW, SharedState,
+        MIN_PROBE_DELTA, OVERSHOOT_EMA_ALPHA, PROBE_WINDOW, SharedState, rewind::RewindRecorder,
     },
 };
 
@@ -56,6 +56,7 @@ pub fn simulation_controller_loop(
     machine: Arc<Machine>,
     audio_mixer: Arc<AudioMixer>,
     shared: Arc<SharedState>,
+    mut rewind_recorder: Option<RewindRecorder>,
 ) {
     let mut next_deadline = None;
     let mut sleep_overshoot_ema = 0.0;
@@ -71,6 +72,20 @@ pub fn simulation_controller_loop(
             continue;
         }
 
+        if shared.rewinding.load(Ordering::Acquire)
+            && let Some(rewind_recorder) = &mut rewind_recorder
+        {
+            {
+                let runtime_guard = machine.enter_runtime();
+                rewind_recorder.step_back(&runtime_guard, &audio_mixer);
+            }
+
+            // Present each step for as long as it took to record it
+            std::thread::sleep(rewind_recorder.step_duration());
+            next_deadline = None;
+            continue;
+        }
+
         let execution_timeslice = {
             let guard = shared.state.lock().unwrap();
             guard.execution_timeslice
@@ -81,7 +96,14 @@ pub fn simulation_controller_loop(
             let runtime_guard = machine.enter_runtime();
             runtime_guard.run_duration(Duration::from_secs_f32(execution_timeslice));
             audio_mixer.extract_machine_samples(&runtime_guard);
-            start.elapsed().as_secs_f32()
+            let measured_execution_time = start.elapsed().as_secs_f32();
+
+            // Recording is kept out of the measurement as it's not part of the guest machine's execution
+            if let Some(rewind_recorder) = &mut rewind_recorder {
+                rewind_recorder.capture(&runtime_guard);
+            }
+
+            measured_execution_time
         };
 
         if !measured_execution_time.is_finite() || measured_execution_time <= 0.0 {
@@ -0,0 +1,52 @@
+use egui::TextEdit;
+use rust_i18n::t;
+
+use crate::{Frontend, FrontendPlatform};
+
+impl<P: FrontendPlatform> Frontend<P> {
+    /// Edit the quirks of the loaded program, which take effect when it is next built
+    pub fn handle_program_quirks(&mut self, ui: &mut egui::Ui) {
+        let Some(specification) = self
+            .machine()
+            .and_then(|machine| machine.program_specification())
+            .cloned()
+        else {
+            ui.label(t!("quirks.no_program"));
+            return;
+        };
+
+        ui.heading(specification.id.to_string());
+
+        let mut quirks = self
+            .environment
+            .program_quirks
+            .get(&specification.id)
+            .cloned()
+            .unwrap_or_default();
+
+        // What the database says is shown as long as the user has not set anything
+        let response = ui.add(
+            TextEdit::multiline(&mut quirks)
+                .code_editor()
+                .hint_text(specification.info.quirks().unwrap_or("()")),
+        );
+
+        if response.changed() {
+            if quirks.trim().is_empty() {
+                self.environment.program_quirks.remove(&specification.id);
+            } else {
+                self.environment
+                    .program_quirks
+                    .insert(specification.id.clone(), quirks);
+            }
+        }
+
+        if ui
+            .button(t!("quirks.reload"))
+            .on_hover_text(t!("quirks.reload_hint"))
+            .clicked()
+        {
+            self.build_machine_for_specification(specification);
+        }
+    }
+}
@@ -64,5 +64,12 @@ impl<P: FrontendPlatform> Frontend<P> {
                 self.audio_mixer.set_volume(self.environment.audio.volume);
             }
         });
+
+        ui.add(
+            Slider::new(&mut self.environment.overclock, 25..=400)
+                .text("Overclock")
+                .suffix("%"),
+        )
+        .on_hover_text("Applies from the next boot");
     }
 }
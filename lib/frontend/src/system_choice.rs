@@ -0,0 +1,41 @@
+use egui::{Id, Modal};
+use fluxemu_program::{RomId, SystemGuess};
+use rust_i18n::t;
+
+use crate::{Frontend, FrontendPlatform, MachineInitializationStep};
+
+impl<P: FrontendPlatform> Frontend<P> {
+    /// Let the user pick the system of a ROM when guessing it was inconclusive, until they pick one or back out
+    pub(crate) fn handle_system_choice(
+        &mut self,
+        ui: &mut egui::Ui,
+        rom_id: RomId,
+        guesses: Vec<SystemGuess>,
+    ) {
+        let mut chosen = None;
+
+        let response = Modal::new(Id::new("system_choice")).show(ui.ctx(), |ui| {
+            ui.heading(t!("system_choice.title"));
+            ui.label(t!("system_choice.hint"));
+
+            for guess in &guesses {
+                if ui
+                    .button(guess.system.to_nointro_string())
+                    .on_hover_text(t!("system_choice.score", score = guess.score))
+                    .clicked()
+                {
+                    chosen = Some(guess.system);
+                }
+            }
+        });
+
+        if let Some(system) = chosen {
+            let specification = self.program_manager.generate_specification(rom_id, system);
+
+            self.build_machine_for_specification(specification);
+        } else if !response.should_close() {
+            self.machine_initialization_step =
+                Some(MachineInitializationStep::ChoosingSystem { rom_id, guesses });
+        }
+    }
+}
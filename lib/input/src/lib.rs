@@ -33,7 +33,7 @@ impl InputId {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Default)]
 pub struct InputState(f32);
 
 impl InputState {
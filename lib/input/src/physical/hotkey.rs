@@ -10,6 +10,7 @@ use crate::{GamepadInputId, InputId, KeyboardInputId};
 pub enum Hotkey {
     ToggleMenu,
     FastForward,
+    Rewind,
     LoadSnapshot,
     StoreSnapshot,
     IncrementSnapshotCounter,
@@ -42,6 +43,18 @@ pub fn default_hotkeys() -> impl Iterator<Item = (BTreeSet<InputId>, Hotkey)> {
             [InputId::Keyboard(KeyboardInputId::F2)].into(),
             Hotkey::FastForward,
         ),
+        (
+            [
+                InputId::Gamepad(GamepadInputId::Mode),
+                InputId::Gamepad(GamepadInputId::LeftTrigger),
+            ]
+            .into(),
+            Hotkey::Rewind,
+        ),
+        (
+            [InputId::Keyboard(KeyboardInputId::F7)].into(),
+            Hotkey::Rewind,
+        ),
         (
             [
                 InputId::Gamepad(GamepadInputId::Mode),
@@ -0,0 +1,174 @@
+//! Reading ROMs out of zip and 7z archives
+
+use std::{collections::BTreeSet, fs::File, io::Read, path::Path};
+
+use bytes::Bytes;
+use sevenz_rust2::{ArchiveReader, Password};
+use zip::ZipArchive;
+
+use crate::Error;
+
+#[cfg(test)]
+mod tests;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const EMPTY_ZIP_MAGIC: &[u8] = b"PK\x05\x06";
+const SEVEN_ZIP_MAGIC: &[u8] = b"7z\xbc\xaf\x27\x1c";
+
+/// Archive formats ROMs can be read out of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArchiveFormat {
+    Zip,
+    SevenZip,
+}
+
+impl ArchiveFormat {
+    /// Identify a archive by its leading bytes
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(ZIP_MAGIC) || header.starts_with(EMPTY_ZIP_MAGIC) {
+            Some(Self::Zip)
+        } else if header.starts_with(SEVEN_ZIP_MAGIC) {
+            Some(Self::SevenZip)
+        } else {
+            None
+        }
+    }
+
+    /// Guess the format from the extension of `path` alone, for when opening the file is too costly
+    pub fn from_extension(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?;
+
+        if extension.eq_ignore_ascii_case("zip") {
+            Some(Self::Zip)
+        } else if extension.eq_ignore_ascii_case("7z") {
+            Some(Self::SevenZip)
+        } else {
+            None
+        }
+    }
+
+    /// Identify the file at `path`, returning [`None`] if it is not a archive
+    pub fn detect_file(path: impl AsRef<Path>) -> Result<Option<Self>, std::io::Error> {
+        let file = File::open(path)?;
+        let mut header = Vec::with_capacity(SEVEN_ZIP_MAGIC.len());
+        file.take(SEVEN_ZIP_MAGIC.len() as u64)
+            .read_to_end(&mut header)?;
+
+        Ok(Self::detect(&header))
+    }
+}
+
+/// Names of every file in the archive, directories excluded
+pub fn archive_entries(
+    path: impl AsRef<Path>,
+    format: ArchiveFormat,
+) -> Result<Vec<String>, Error> {
+    let file = File::open(path)?;
+    let mut entries = Vec::default();
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = ZipArchive::new(file)?;
+
+            for index in 0..archive.len() {
+                let entry = archive.by_index(index)?;
+
+                if !entry.is_dir() {
+                    entries.push(entry.name().to_owned());
+                }
+            }
+        }
+        ArchiveFormat::SevenZip => {
+            ArchiveReader::new(file, Password::empty())?.for_each_entries(|entry, _| {
+                if !entry.is_directory() {
+                    entries.push(entry.name().to_owned());
+                }
+
+                Ok(true)
+            })?;
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Decompress the entries of the archive named in `wanted`, or every entry if [`None`]
+///
+/// Reading stops once every wanted entry is found, sparing the rest of a solid 7z from being decompressed
+pub(crate) fn read_archive_entries(
+    path: impl AsRef<Path>,
+    format: ArchiveFormat,
+    wanted: Option<&BTreeSet<String>>,
+) -> Result<Vec<(String, Bytes)>, Error> {
+    let file = File::open(path)?;
+    let mut entries = Vec::default();
+    let is_wanted = |name: &str| wanted.is_none_or(|wanted| wanted.contains(name));
+    let found_all = |entries: &Vec<_>| wanted.is_some_and(|wanted| entries.len() == wanted.len());
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = ZipArchive::new(file)?;
+
+            for index in 0..archive.len() {
+                let mut entry = archive.by_index(index)?;
+
+                if entry.is_dir() || !is_wanted(entry.name()) {
+                    continue;
+                }
+
+                let name = entry.name().to_owned();
+                entries.push((name, read_entry(&mut entry)?));
+
+                if found_all(&entries) {
+                    break;
+                }
+            }
+        }
+        ArchiveFormat::SevenZip => {
+            let mut io_error = None;
+            let mut reader = ArchiveReader::new(file, Password::empty())?;
+            let solid = reader.archive().is_solid;
+
+            // Returning false only ends the current block, so every later call has to bail out as well
+            reader.for_each_entries(|entry, entry_reader| {
+                if io_error.is_some() || found_all(&entries) {
+                    return Ok(false);
+                }
+
+                let result = if !entry.is_directory() && is_wanted(entry.name()) {
+                    read_entry(entry_reader).map(Some)
+                } else if solid {
+                    // Entries of a solid block follow each other in one stream, so skipped ones still have to be
+                    // read past
+                    std::io::copy(entry_reader, &mut std::io::sink()).map(|_| None)
+                } else {
+                    Ok(None)
+                };
+
+                match result {
+                    Ok(bytes) => {
+                        entries.extend(bytes.map(|bytes| (entry.name().to_owned(), bytes)));
+                        Ok(true)
+                    }
+                    Err(err) => {
+                        io_error = Some(err);
+                        Ok(false)
+                    }
+                }
+            })?;
+
+            if let Some(err) = io_error {
+                return Err(err.into());
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+fn read_entry(mut entry: impl Read) -> Result<Bytes, std::io::Error> {
+    let mut buffer = Vec::default();
+    entry.read_to_end(&mut buffer)?;
+
+    Ok(Bytes::from_owner(buffer))
+}
@@ -0,0 +1,106 @@
+use std::{collections::BTreeSet, fs::File, io::Write, path::Path};
+
+use sevenz_rust2::{ArchiveEntry, ArchiveWriter, SourceReader};
+use tempfile::TempDir;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use super::{ArchiveFormat, archive_entries, read_archive_entries};
+
+const ENTRIES: [(&str, &[u8]); 3] = [
+    ("first.nes", b"first"),
+    ("nested/second.nes", b"second"),
+    ("third.nes", b"third"),
+];
+
+fn zip_archive(directory: &TempDir) -> impl AsRef<Path> {
+    let path = directory.path().join("roms.zip");
+    let mut writer = ZipWriter::new(File::create(&path).unwrap());
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    writer.add_directory("nested/", options).unwrap();
+    for (name, contents) in ENTRIES {
+        writer.start_file(name, options).unwrap();
+        writer.write_all(contents).unwrap();
+    }
+    writer.finish().unwrap();
+
+    path
+}
+
+fn solid_seven_zip_archive(directory: &TempDir) -> impl AsRef<Path> {
+    let path = directory.path().join("roms.7z");
+    let mut writer = ArchiveWriter::new(File::create(&path).unwrap()).unwrap();
+
+    writer
+        .push_archive_entries(
+            ENTRIES
+                .iter()
+                .map(|(name, _)| ArchiveEntry::new_file(name))
+                .collect(),
+            ENTRIES
+                .iter()
+                .map(|(_, contents)| SourceReader::new(*contents))
+                .collect(),
+        )
+        .unwrap();
+    writer.finish().unwrap();
+
+    path
+}
+
+fn check_archive(path: impl AsRef<Path>, format: ArchiveFormat) {
+    let path = path.as_ref();
+
+    assert_eq!(ArchiveFormat::detect_file(path).unwrap(), Some(format));
+    assert_eq!(ArchiveFormat::from_extension(path), Some(format));
+    assert_eq!(
+        archive_entries(path, format).unwrap(),
+        ENTRIES.map(|(name, _)| name.to_owned())
+    );
+
+    let everything = read_archive_entries(path, format, None).unwrap();
+    assert_eq!(
+        everything
+            .iter()
+            .map(|(name, contents)| (name.as_str(), &contents[..]))
+            .collect::<Vec<_>>(),
+        ENTRIES
+    );
+
+    let wanted = BTreeSet::from(["nested/second.nes".to_owned(), "missing.nes".to_owned()]);
+    let some = read_archive_entries(path, format, Some(&wanted)).unwrap();
+    assert_eq!(
+        some.iter()
+            .map(|(name, contents)| (name.as_str(), &contents[..]))
+            .collect::<Vec<_>>(),
+        [ENTRIES[1]]
+    );
+}
+
+#[test]
+fn zip_entries_read_back() {
+    let directory = tempfile::tempdir().unwrap();
+
+    check_archive(zip_archive(&directory), ArchiveFormat::Zip);
+}
+
+#[test]
+fn solid_seven_zip_entries_read_back() {
+    let directory = tempfile::tempdir().unwrap();
+
+    check_archive(solid_seven_zip_archive(&directory), ArchiveFormat::SevenZip);
+}
+
+#[test]
+fn other_files_are_not_archives() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("game.nes");
+    std::fs::write(&path, b"NES\x1a").unwrap();
+
+    assert_eq!(ArchiveFormat::detect_file(&path).unwrap(), None);
+    assert_eq!(ArchiveFormat::from_extension(&path), None);
+    assert_eq!(
+        ArchiveFormat::from_extension("GAME.ZIP"),
+        Some(ArchiveFormat::Zip)
+    );
+}
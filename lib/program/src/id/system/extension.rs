@@ -11,6 +11,7 @@ pub fn get_extension(system: SystemId) -> Option<&'static str> {
         SystemId::Nintendo(NintendoSystem::GameCube) => "iso",
         SystemId::Nintendo(NintendoSystem::Wii) => "iso",
         SystemId::Nintendo(NintendoSystem::NintendoEntertainmentSystem) => "nes",
+        SystemId::Nintendo(NintendoSystem::FamicomDiskSystem) => "fds",
         SystemId::Nintendo(NintendoSystem::SuperNintendoEntertainmentSystem) => "sfc",
         SystemId::Nintendo(NintendoSystem::Nintendo64) => "z64",
         SystemId::Sega(SegaSystem::GameGear) => "gg",
@@ -1,99 +1,310 @@
-use std::{collections::HashMap, ops::RangeInclusive, path::Path, sync::LazyLock};
+use std::{collections::HashMap, ops::RangeInclusive, path::Path};
 
 use super::{AtariSystem, NintendoSystem, OtherSystem, SegaSystem, SystemId};
 
 use fluxemu_math::range::ContiguousRange;
 
+/// Weight of a file extension, as a lot of ROMs are misnamed
+const EXTENSION_WEIGHT: u32 = 40;
+/// Weight of a header a system is plausible for, but that could show up in ROMs of others
+const HEADER_WEIGHT: u32 = 60;
+/// Weight of a magic number unlikely to show up by chance
+const MAGIC_WEIGHT: u32 = 100;
+/// Weight added on top when a checksum in the header checks out
+const CHECKSUM_WEIGHT: u32 = 30;
+
+/// A system a ROM might be for, along with how strongly the heuristics point to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SystemGuess {
+    /// System guessed
+    pub system: SystemId,
+    /// Sum of the weights of every heuristic pointing to this system
+    pub score: u32,
+}
+
+impl SystemGuess {
+    /// Whether the ranked guesses are too close to pick the first without asking the user
+    pub fn is_ambiguous(guesses: &[Self]) -> bool {
+        match guesses {
+            [first, second, ..] => second.score * 2 > first.score,
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct MagicTableEntry {
+    system: SystemId,
     bytes: &'static [u8],
     offset: usize,
+    weight: u32,
 }
 
-/// Magic number table
-static MAGIC_TABLE: LazyLock<HashMap<SystemId, Vec<MagicTableEntry>>> = LazyLock::new(|| {
-    let mut table: HashMap<SystemId, Vec<MagicTableEntry>> = HashMap::new();
-
-    table
-        .entry(SystemId::Nintendo(NintendoSystem::GameBoy))
-        .or_default()
-        .extend([MagicTableEntry {
-            bytes: &[0xce, 0xed, 0x66, 0x66, 0xcc, 0x0d, 0x00, 0x0b],
-            offset: 0x134,
-        }]);
-
-    table
-        .entry(SystemId::Nintendo(
-            NintendoSystem::NintendoEntertainmentSystem,
-        ))
-        .or_default()
-        .extend([MagicTableEntry {
-            bytes: b"NES\x1a",
-            offset: 0x00,
-        }]);
-
-    table
-        .entry(SystemId::Sega(SegaSystem::Genesis))
-        .or_default()
-        .extend([
-            MagicTableEntry {
-                bytes: b"SEGA GENESIS",
-                offset: 0x100,
-            },
-            MagicTableEntry {
-                bytes: b"SEGA MEGA DRIVE",
-                offset: 0x100,
-            },
-        ]);
-
-    table
-        .entry(SystemId::Sega(SegaSystem::MasterSystem))
-        .or_default()
-        .extend([
-            MagicTableEntry {
-                bytes: b"TMR SEGA",
-                offset: 0x1ff0,
-            },
-            MagicTableEntry {
-                bytes: b"TMR SEGA",
-                offset: 0x3ff0,
-            },
-            MagicTableEntry {
-                bytes: b"TMR SEGA",
-                offset: 0x7ff0,
-            },
-        ]);
-
-    table
-});
+/// Magic numbers at fixed offsets, for systems that need no further parsing to recognize
+const MAGIC_TABLE: &[MagicTableEntry] = &[
+    MagicTableEntry {
+        system: SystemId::Nintendo(NintendoSystem::NintendoEntertainmentSystem),
+        bytes: b"NES\x1a",
+        offset: 0x00,
+        weight: MAGIC_WEIGHT,
+    },
+    // fwNES header
+    MagicTableEntry {
+        system: SystemId::Nintendo(NintendoSystem::FamicomDiskSystem),
+        bytes: b"FDS\x1a",
+        offset: 0x00,
+        weight: MAGIC_WEIGHT,
+    },
+    // Disk info block of a raw disk image
+    MagicTableEntry {
+        system: SystemId::Nintendo(NintendoSystem::FamicomDiskSystem),
+        bytes: b"\x01*NINTENDO-HVC*",
+        offset: 0x00,
+        weight: MAGIC_WEIGHT,
+    },
+    // Big endian, byte swapped and little endian dumps
+    MagicTableEntry {
+        system: SystemId::Nintendo(NintendoSystem::Nintendo64),
+        bytes: &[0x80, 0x37, 0x12, 0x40],
+        offset: 0x00,
+        weight: MAGIC_WEIGHT,
+    },
+    MagicTableEntry {
+        system: SystemId::Nintendo(NintendoSystem::Nintendo64),
+        bytes: &[0x37, 0x80, 0x40, 0x12],
+        offset: 0x00,
+        weight: MAGIC_WEIGHT,
+    },
+    MagicTableEntry {
+        system: SystemId::Nintendo(NintendoSystem::Nintendo64),
+        bytes: &[0x40, 0x12, 0x37, 0x80],
+        offset: 0x00,
+        weight: MAGIC_WEIGHT,
+    },
+    MagicTableEntry {
+        system: SystemId::Atari(AtariSystem::Lynx),
+        bytes: b"LYNX\0",
+        offset: 0x00,
+        weight: MAGIC_WEIGHT,
+    },
+    MagicTableEntry {
+        system: SystemId::Atari(AtariSystem::_7800),
+        bytes: b"ATARI7800",
+        offset: 0x01,
+        weight: MAGIC_WEIGHT,
+    },
+];
+
+/// Nintendo logo the Game Boy boot ROM checks for
+pub(super) const GAME_BOY_LOGO: [u8; 48] = [
+    0xce, 0xed, 0x66, 0x66, 0xcc, 0x0d, 0x00, 0x0b, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0c, 0x00, 0x0d,
+    0x00, 0x08, 0x11, 0x1f, 0x88, 0x89, 0x00, 0x0e, 0xdc, 0xcc, 0x6e, 0xe6, 0xdd, 0xdd, 0xd9, 0x99,
+    0xbb, 0xbb, 0x67, 0x63, 0x6e, 0x0e, 0xec, 0xcc, 0xdd, 0xdc, 0x99, 0x9f, 0xbb, 0xb9, 0x33, 0x3e,
+];
+
+/// Start of the compressed Nintendo logo the Game Boy Advance BIOS checks for
+pub(super) const GAME_BOY_ADVANCE_LOGO: [u8; 16] = [
+    0x24, 0xff, 0xae, 0x51, 0x69, 0x9a, 0xa2, 0x21, 0x3d, 0x84, 0x82, 0x0a, 0x84, 0xe4, 0x09, 0xad,
+];
+
+/// Heuristics that need more than a magic number
+const DETECTORS: [fn(&[u8], &mut Scores); 5] = [
+    game_boy,
+    game_boy_advance,
+    super_nintendo,
+    sega_8bit,
+    sega_16bit,
+];
+
+#[derive(Debug, Default)]
+struct Scores(HashMap<SystemId, u32>);
+
+impl Scores {
+    fn add(&mut self, system: SystemId, weight: u32) {
+        *self.0.entry(system).or_default() += weight;
+    }
+}
 
 /// Guess a the system from a rom file on disk, using a variety of heuristics
 pub fn guess(path: Option<&Path>, data: Option<&[u8]>) -> Option<SystemId> {
-    // This goes first since a lot of roms have misleading or nonexistent magic bytes
+    guess_ranked(path, data).first().map(|guess| guess.system)
+}
+
+/// Score every system the heuristics point to, best guess first
+pub fn guess_ranked(path: Option<&Path>, data: Option<&[u8]>) -> Vec<SystemGuess> {
+    let mut scores = Scores::default();
+
     if let Some(path) = path
         && let Some(system) = guess_by_extension(path)
     {
-        return Some(system);
+        scores.add(system, EXTENSION_WEIGHT);
     }
 
     if let Some(data) = data {
-        for (system, entry) in MAGIC_TABLE
+        for entry in MAGIC_TABLE {
+            if bytes_at(data, entry.offset, entry.bytes.len()) == Some(entry.bytes) {
+                scores.add(entry.system, entry.weight);
+            }
+        }
+
+        for detector in DETECTORS {
+            detector(data, &mut scores);
+        }
+    }
+
+    let mut guesses: Vec<_> = scores
+        .0
+        .into_iter()
+        .map(|(system, score)| SystemGuess { system, score })
+        .collect();
+
+    guesses.sort_by(|a, b| b.score.cmp(&a.score).then(a.system.cmp(&b.system)));
+
+    guesses
+}
+
+fn bytes_at(data: &[u8], offset: usize, length: usize) -> Option<&[u8]> {
+    let range = RangeInclusive::from_start_and_length(offset, length);
+
+    data.get(range)
+}
+
+/// Logo and header checksum, with the CGB flag telling Game Boy Color games apart
+fn game_boy(rom: &[u8], scores: &mut Scores) {
+    if bytes_at(rom, 0x104, GAME_BOY_LOGO.len()) != Some(&GAME_BOY_LOGO[..]) {
+        return;
+    }
+
+    let Some(header) = bytes_at(rom, 0x134, 0x1a) else {
+        return;
+    };
+
+    let checksum = header[..0x19].iter().fold(0u8, |checksum, byte| {
+        checksum.wrapping_sub(*byte).wrapping_sub(1)
+    });
+    let weight = if checksum == header[0x19] {
+        MAGIC_WEIGHT + CHECKSUM_WEIGHT
+    } else {
+        MAGIC_WEIGHT
+    };
+
+    match header[0x0f] {
+        // Color only
+        0xc0 => scores.add(SystemId::Nintendo(NintendoSystem::GameBoyColor), weight),
+        // Enhanced for color but runs on both
+        0x80 => {
+            scores.add(SystemId::Nintendo(NintendoSystem::GameBoyColor), weight);
+            scores.add(
+                SystemId::Nintendo(NintendoSystem::GameBoy),
+                weight - CHECKSUM_WEIGHT,
+            );
+        }
+        _ => scores.add(SystemId::Nintendo(NintendoSystem::GameBoy), weight),
+    }
+}
+
+/// Logo, fixed value and header complement
+fn game_boy_advance(rom: &[u8], scores: &mut Scores) {
+    if bytes_at(rom, 0x04, GAME_BOY_ADVANCE_LOGO.len()) != Some(&GAME_BOY_ADVANCE_LOGO[..])
+        || rom.get(0xb2) != Some(&0x96)
+    {
+        return;
+    }
+
+    let system = SystemId::Nintendo(NintendoSystem::GameBoyAdvance);
+    scores.add(system, MAGIC_WEIGHT);
+
+    if let Some(header) = bytes_at(rom, 0xa0, 0x1e) {
+        let complement = header[..0x1d]
             .iter()
-            .flat_map(|(system, entries)| entries.iter().map(|entry| (*system, entry)))
-        {
-            let range = RangeInclusive::from_start_and_length(entry.offset, entry.bytes.len());
+            .fold(0u8, |complement, byte| complement.wrapping_sub(*byte))
+            .wrapping_sub(0x19);
 
-            if *range.end() >= data.len() {
-                continue;
-            }
+        if complement == header[0x1d] {
+            scores.add(system, CHECKSUM_WEIGHT);
+        }
+    }
+}
 
-            if &data[range] == entry.bytes {
-                return Some(system);
-            }
+/// Internal header of LoROM, HiROM or ExHiROM cartridges, found by its checksum and complement adding up
+fn super_nintendo(rom: &[u8], scores: &mut Scores) {
+    // Copiers put 512 bytes of their own in front
+    let rom = if rom.len() % 0x400 == 0x200 {
+        &rom[0x200..]
+    } else {
+        rom
+    };
+
+    let checksums: Vec<_> = [0x7fc0, 0xffc0, 0x40ffc0]
+        .into_iter()
+        .filter_map(|offset| {
+            let header = bytes_at(rom, offset, 0x20)?;
+            let complement = u16::from_le_bytes([header[0x1c], header[0x1d]]);
+            let checksum = u16::from_le_bytes([header[0x1e], header[0x1f]]);
+
+            (complement ^ checksum == 0xffff).then_some(checksum)
+        })
+        .collect();
+
+    if checksums.is_empty() {
+        return;
+    }
+
+    // Summing is only straightforward when nothing needs mirroring to fill a power of two
+    let sum = rom.len().is_power_of_two().then(|| {
+        rom.iter()
+            .fold(0u16, |sum, byte| sum.wrapping_add(u16::from(*byte)))
+    });
+
+    let weight = if checksums.iter().any(|checksum| sum == Some(*checksum)) {
+        HEADER_WEIGHT + CHECKSUM_WEIGHT
+    } else {
+        HEADER_WEIGHT
+    };
+
+    scores.add(
+        SystemId::Nintendo(NintendoSystem::SuperNintendoEntertainmentSystem),
+        weight,
+    );
+}
+
+/// "TMR SEGA" header shared by the Master System and Game Gear, told apart by its region code
+fn sega_8bit(rom: &[u8], scores: &mut Scores) {
+    let Some(header) = [0x7ff0, 0x3ff0, 0x1ff0]
+        .into_iter()
+        .filter_map(|offset| bytes_at(rom, offset, 0x10))
+        .find(|header| header.starts_with(b"TMR SEGA"))
+    else {
+        return;
+    };
+
+    match header[0x0f] >> 4 {
+        0x3 | 0x4 => scores.add(SystemId::Sega(SegaSystem::MasterSystem), MAGIC_WEIGHT),
+        0x5..=0x7 => scores.add(SystemId::Sega(SegaSystem::GameGear), MAGIC_WEIGHT),
+        // Anything else could be either
+        _ => {
+            scores.add(SystemId::Sega(SegaSystem::MasterSystem), HEADER_WEIGHT);
+            scores.add(SystemId::Sega(SegaSystem::GameGear), HEADER_WEIGHT);
         }
     }
+}
 
-    None
+/// System name at the start of the Mega Drive header, which only ever reliably starts with "SEGA"
+fn sega_16bit(rom: &[u8], scores: &mut Scores) {
+    let Some(name) = bytes_at(rom, 0x100, 0x10) else {
+        return;
+    };
+
+    // A handful of games pad the name with a leading space
+    let name = name.strip_prefix(b" ").unwrap_or(name);
+
+    if name.starts_with(b"SEGA 32X") {
+        scores.add(SystemId::Sega(SegaSystem::Sega32X), MAGIC_WEIGHT);
+    } else if name.starts_with(b"SEGA GENESIS") || name.starts_with(b"SEGA MEGA DRIVE") {
+        scores.add(SystemId::Sega(SegaSystem::Genesis), MAGIC_WEIGHT);
+    } else if name.starts_with(b"SEGA") {
+        scores.add(SystemId::Sega(SegaSystem::Genesis), HEADER_WEIGHT);
+    }
 }
 
 /// Try to guess the system from the file extension
@@ -108,16 +319,20 @@ fn guess_by_extension(rom: &Path) -> Option<SystemId> {
             "nes" => Some(SystemId::Nintendo(
                 NintendoSystem::NintendoEntertainmentSystem,
             )),
+            "fds" => Some(SystemId::Nintendo(NintendoSystem::FamicomDiskSystem)),
             "sfc" | "smc" => Some(SystemId::Nintendo(
                 NintendoSystem::SuperNintendoEntertainmentSystem,
             )),
-            "n64" | "z64" => Some(SystemId::Nintendo(NintendoSystem::Nintendo64)),
-            "md" => Some(SystemId::Sega(SegaSystem::MasterSystem)),
+            "n64" | "z64" | "v64" => Some(SystemId::Nintendo(NintendoSystem::Nintendo64)),
+            "md" | "gen" => Some(SystemId::Sega(SegaSystem::Genesis)),
+            "sms" => Some(SystemId::Sega(SegaSystem::MasterSystem)),
             "gg" => Some(SystemId::Sega(SegaSystem::GameGear)),
+            "32x" => Some(SystemId::Sega(SegaSystem::Sega32X)),
             "ch8" | "c8" => Some(SystemId::Other(OtherSystem::Chip8)),
             "a26" => Some(SystemId::Atari(AtariSystem::_2600)),
             "a52" => Some(SystemId::Atari(AtariSystem::_5200)),
             "a78" => Some(SystemId::Atari(AtariSystem::_7800)),
+            "lnx" => Some(SystemId::Atari(AtariSystem::Lynx)),
             _ => None,
         }
     {
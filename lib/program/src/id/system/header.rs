@@ -0,0 +1,72 @@
+use super::{AtariSystem, NintendoSystem, SystemId};
+
+/// A header dumping tools put in front of ROMs, which databases like No-Intro leave out of their hashes
+#[derive(Debug)]
+struct RomHeader {
+    system: SystemId,
+    magic: &'static [u8],
+    magic_offset: usize,
+    size: usize,
+    /// Byte offset and mask of a flag saying a trainer follows the header
+    trainer_flag: Option<(usize, u8)>,
+}
+
+/// Code some copiers loaded into cartridge RAM, dumped between the header and the ROM proper
+const TRAINER_SIZE: usize = 512;
+
+impl RomHeader {
+    fn strip<'a>(&self, rom: &'a [u8]) -> Option<&'a [u8]> {
+        let magic = rom.get(self.magic_offset..self.magic_offset + self.magic.len())?;
+
+        if magic != self.magic {
+            return None;
+        }
+
+        let size = match self.trainer_flag {
+            Some((offset, mask)) if rom.get(offset)? & mask != 0 => self.size + TRAINER_SIZE,
+            _ => self.size,
+        };
+
+        (rom.len() > size).then(|| &rom[size..])
+    }
+}
+
+const HEADERS: [RomHeader; 4] = [
+    // iNES and NES 2.0
+    RomHeader {
+        system: SystemId::Nintendo(NintendoSystem::NintendoEntertainmentSystem),
+        magic: b"NES\x1a",
+        magic_offset: 0,
+        size: 16,
+        trainer_flag: Some((6, 0b0000_0100)),
+    },
+    // fwNES
+    RomHeader {
+        system: SystemId::Nintendo(NintendoSystem::FamicomDiskSystem),
+        magic: b"FDS\x1a",
+        magic_offset: 0,
+        size: 16,
+        trainer_flag: None,
+    },
+    RomHeader {
+        system: SystemId::Atari(AtariSystem::Lynx),
+        magic: b"LYNX\0",
+        magic_offset: 0,
+        size: 64,
+        trainer_flag: None,
+    },
+    RomHeader {
+        system: SystemId::Atari(AtariSystem::_7800),
+        magic: b"ATARI7800",
+        magic_offset: 1,
+        size: 128,
+        trainer_flag: None,
+    },
+];
+
+/// Strip the first known header the ROM starts with
+pub fn strip_header(rom: &[u8]) -> Option<(SystemId, &[u8])> {
+    HEADERS
+        .iter()
+        .find_map(|header| Some((header.system, header.strip(rom)?)))
+}
@@ -7,6 +7,12 @@ use strum::{EnumIter, IntoEnumIterator};
 
 mod extension;
 mod guess;
+mod header;
+
+#[cfg(test)]
+mod tests;
+
+pub use guess::SystemGuess;
 
 /// Game systems organized by vendor
 #[derive(
@@ -51,6 +57,16 @@ impl SystemId {
         guess::guess(path, data)
     }
 
+    /// Every system the heuristics point to, best guess first, for when [`SystemId::guess`] picking one is not enough
+    pub fn guess_ranked(path: Option<&Path>, data: Option<&[u8]>) -> Vec<SystemGuess> {
+        guess::guess_ranked(path, data)
+    }
+
+    /// Strip any known header the ROM starts with, along with the system the header belongs to
+    pub fn strip_any_header(rom: &[u8]) -> Option<(Self, &[u8])> {
+        header::strip_header(rom)
+    }
+
     /// Converts the name to a "Nointro" convention string
     pub fn to_nointro_string(&self) -> &'static str {
         match self {
@@ -72,6 +88,9 @@ impl SystemId {
             Self::Nintendo(NintendoSystem::Nintendo3DS) => "Nintendo - Nintendo 3DS",
             Self::Nintendo(NintendoSystem::PokemonMini) => "Nintendo - Pokemon Mini",
             Self::Nintendo(NintendoSystem::VirtualBoy) => "Nintendo - Virtual Boy",
+            Self::Nintendo(NintendoSystem::FamicomDiskSystem) => {
+                "Nintendo - Family Computer Disk System"
+            }
             Self::Sony(SonySystem::Playstation) => "Sony - PlayStation",
             Self::Sony(SonySystem::Playstation2) => "Sony - PlayStation 2",
             Self::Sony(SonySystem::Playstation3) => "Sony - PlayStation 3",
@@ -178,6 +197,9 @@ impl AsRef<str> for SystemId {
             SystemId::Nintendo(NintendoSystem::Nintendo3DS) => "nintendo-nintendo-3ds",
             SystemId::Nintendo(NintendoSystem::PokemonMini) => "nintendo-pokemon-mini",
             SystemId::Nintendo(NintendoSystem::VirtualBoy) => "nintendo-virtual-boy",
+            SystemId::Nintendo(NintendoSystem::FamicomDiskSystem) => {
+                "nintendo-family-computer-disk-system"
+            }
 
             SystemId::Sony(SonySystem::Playstation) => "sony-playstation",
             SystemId::Sony(SonySystem::Playstation2) => "sony-playstation-2",
@@ -239,6 +261,7 @@ pub enum NintendoSystem {
     Nintendo3DS,
     PokemonMini,
     VirtualBoy,
+    FamicomDiskSystem,
 }
 
 #[allow(missing_docs)]
@@ -0,0 +1,248 @@
+use std::path::Path;
+
+use super::{
+    AtariSystem, NintendoSystem, SegaSystem, SystemGuess, SystemId,
+    guess::{GAME_BOY_ADVANCE_LOGO, GAME_BOY_LOGO},
+};
+
+#[test]
+fn md_extension_is_the_genesis() {
+    assert_eq!(
+        SystemId::guess(Some(Path::new("sonic.md")), None),
+        Some(SystemId::Sega(SegaSystem::Genesis))
+    );
+}
+
+#[test]
+fn snes_header_outranks_a_misleading_extension() {
+    let mut rom = vec![0; 0x8000];
+    // LoROM header with a checksum and complement that add up over the whole ROM
+    rom[0x7fdc..0x7fe0].copy_from_slice(&[0x01, 0xfe, 0xfe, 0x01]);
+
+    let guesses = SystemId::guess_ranked(Some(Path::new("game.gb")), Some(&rom));
+
+    assert_eq!(
+        guesses[0].system,
+        SystemId::Nintendo(NintendoSystem::SuperNintendoEntertainmentSystem)
+    );
+    assert_eq!(
+        guesses[1].system,
+        SystemId::Nintendo(NintendoSystem::GameBoy)
+    );
+    assert!(!SystemGuess::is_ambiguous(&guesses));
+}
+
+#[test]
+fn atari_headers_are_recognized() {
+    let mut lynx = b"LYNX\0".to_vec();
+    lynx.resize(64, 0);
+
+    let mut atari_7800 = vec![0x01];
+    atari_7800.extend(b"ATARI7800");
+    atari_7800.resize(128, 0);
+
+    assert_eq!(
+        SystemId::guess(None, Some(&lynx)),
+        Some(SystemId::Atari(AtariSystem::Lynx))
+    );
+    assert_eq!(
+        SystemId::guess(None, Some(&atari_7800)),
+        Some(SystemId::Atari(AtariSystem::_7800))
+    );
+}
+
+#[test]
+fn every_nintendo_64_byte_order_is_recognized() {
+    for magic in [
+        [0x80, 0x37, 0x12, 0x40],
+        [0x37, 0x80, 0x40, 0x12],
+        [0x40, 0x12, 0x37, 0x80],
+    ] {
+        let mut rom = magic.to_vec();
+        rom.resize(0x1000, 0);
+
+        assert_eq!(
+            SystemId::guess(None, Some(&rom)),
+            Some(SystemId::Nintendo(NintendoSystem::Nintendo64))
+        );
+    }
+}
+
+#[test]
+fn famicom_disk_images_are_recognized_with_and_without_header() {
+    let mut headered = b"FDS\x1a".to_vec();
+    headered.resize(16, 0);
+
+    let mut raw = b"\x01*NINTENDO-HVC*".to_vec();
+    raw.resize(56, 0);
+
+    for rom in [headered, raw] {
+        assert_eq!(
+            SystemId::guess(None, Some(&rom)),
+            Some(SystemId::Nintendo(NintendoSystem::FamicomDiskSystem))
+        );
+    }
+}
+
+#[test]
+fn game_boy_advance_header_complement_adds_to_the_score() {
+    let mut rom = vec![0; 0xc0];
+    rom[0x04..0x14].copy_from_slice(&GAME_BOY_ADVANCE_LOGO);
+    rom[0xb2] = 0x96;
+
+    let unchecked = SystemId::guess_ranked(None, Some(&rom));
+
+    // Complement of the fixed value and the 0x19 every header is offset by
+    rom[0xbd] = 0u8.wrapping_sub(0x96).wrapping_sub(0x19);
+
+    let checked = SystemId::guess_ranked(None, Some(&rom));
+
+    assert_eq!(
+        checked[0].system,
+        SystemId::Nintendo(NintendoSystem::GameBoyAdvance)
+    );
+    assert!(checked[0].score > unchecked[0].score);
+}
+
+#[test]
+fn game_boy_color_flag_picks_the_model() {
+    let mut rom = vec![0; 0x150];
+    rom[0x104..0x134].copy_from_slice(&GAME_BOY_LOGO);
+
+    let guesses = SystemId::guess_ranked(None, Some(&rom));
+    assert_eq!(
+        guesses[0].system,
+        SystemId::Nintendo(NintendoSystem::GameBoy)
+    );
+    assert_eq!(guesses.len(), 1);
+
+    rom[0x143] = 0xc0;
+    let guesses = SystemId::guess_ranked(None, Some(&rom));
+    assert_eq!(
+        guesses[0].system,
+        SystemId::Nintendo(NintendoSystem::GameBoyColor)
+    );
+    assert_eq!(guesses.len(), 1);
+
+    // Runs on both, so both are offered
+    rom[0x143] = 0x80;
+    let guesses = SystemId::guess_ranked(None, Some(&rom));
+    assert_eq!(
+        guesses[0].system,
+        SystemId::Nintendo(NintendoSystem::GameBoyColor)
+    );
+    assert_eq!(
+        guesses[1].system,
+        SystemId::Nintendo(NintendoSystem::GameBoy)
+    );
+    assert!(SystemGuess::is_ambiguous(&guesses));
+}
+
+#[test]
+fn sega_8bit_region_code_tells_master_system_and_game_gear_apart() {
+    let mut rom = vec![0; 0x8000];
+    rom[0x7ff0..0x7ff8].copy_from_slice(b"TMR SEGA");
+
+    rom[0x7fff] = 0x4c;
+    assert_eq!(
+        SystemId::guess(None, Some(&rom)),
+        Some(SystemId::Sega(SegaSystem::MasterSystem))
+    );
+
+    rom[0x7fff] = 0x6c;
+    assert_eq!(
+        SystemId::guess(None, Some(&rom)),
+        Some(SystemId::Sega(SegaSystem::GameGear))
+    );
+
+    // Unknown region, so only the extension can settle it
+    rom[0x7fff] = 0x0c;
+    let guesses = SystemId::guess_ranked(None, Some(&rom));
+    assert!(SystemGuess::is_ambiguous(&guesses));
+
+    let guesses = SystemId::guess_ranked(Some(Path::new("game.gg")), Some(&rom));
+    assert_eq!(guesses[0].system, SystemId::Sega(SegaSystem::GameGear));
+}
+
+#[test]
+fn mega_drive_system_names_are_recognized() {
+    for (name, system) in [
+        (b"SEGA MEGA DRIVE ", SegaSystem::Genesis),
+        (b" SEGA GENESIS   ", SegaSystem::Genesis),
+        (b"SEGA_MEGA_DRIVE ", SegaSystem::Genesis),
+        (b"SEGA 32X        ", SegaSystem::Sega32X),
+    ] {
+        let mut rom = vec![0; 0x200];
+        rom[0x100..0x110].copy_from_slice(name);
+
+        assert_eq!(
+            SystemId::guess(None, Some(&rom)),
+            Some(SystemId::Sega(system))
+        );
+    }
+}
+
+#[test]
+fn unrecognizable_data_has_no_guesses() {
+    assert!(SystemId::guess_ranked(Some(Path::new("game.bin")), Some(&[0; 0x8000])).is_empty());
+}
+
+#[test]
+fn ines_header_is_stripped() {
+    let mut rom = b"NES\x1a".to_vec();
+    rom.resize(16, 0);
+    rom.extend([0xaa; 32]);
+
+    assert_eq!(
+        SystemId::strip_any_header(&rom),
+        Some((
+            SystemId::Nintendo(NintendoSystem::NintendoEntertainmentSystem),
+            &[0xaa; 32][..]
+        ))
+    );
+}
+
+#[test]
+fn ines_trainer_is_stripped_along_with_the_header() {
+    let mut rom = b"NES\x1a".to_vec();
+    rom.resize(16, 0);
+    rom[6] = 0b0000_0100;
+    rom.extend([0x55; 512]);
+    rom.extend([0xaa; 32]);
+
+    let (_, stripped) = SystemId::strip_any_header(&rom).unwrap();
+
+    assert_eq!(stripped, &[0xaa; 32]);
+}
+
+#[test]
+fn lynx_header_is_stripped() {
+    let mut rom = b"LYNX\0".to_vec();
+    rom.resize(64, 0);
+    rom.extend([0xaa; 32]);
+
+    assert_eq!(
+        SystemId::strip_any_header(&rom),
+        Some((SystemId::Atari(AtariSystem::Lynx), &[0xaa; 32][..]))
+    );
+}
+
+#[test]
+fn atari_7800_header_is_stripped() {
+    let mut rom = vec![0x01];
+    rom.extend(b"ATARI7800");
+    rom.resize(128, 0);
+    rom.extend([0xaa; 32]);
+
+    assert_eq!(
+        SystemId::strip_any_header(&rom),
+        Some((SystemId::Atari(AtariSystem::_7800), &[0xaa; 32][..]))
+    );
+}
+
+#[test]
+fn headerless_rom_is_left_alone() {
+    assert_eq!(SystemId::strip_any_header(&[0xaa; 256]), None);
+    // Nothing past the header
+    assert_eq!(SystemId::strip_any_header(b"LYNX\0"), None);
+}
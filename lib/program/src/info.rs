@@ -29,30 +29,85 @@ pub enum ProgramInfo {
         /// The version or revision of the program
         version: Option<String>,
     },
+    /// Version 1, adding quirks
+    #[serde(rename = "1")]
+    V1 {
+        /// Identifiable names of the program
+        ///
+        /// Preferably these will be the names associated with the below languages, in their original script
+        names: BTreeSet<String>,
+        /// Paths are unixlike
+        filesystem: BTreeMap<RomId, BTreeSet<String>>,
+        /// The language this program is associated with
+        ///
+        /// Note that this is the languages a coherent title supports
+        ///
+        /// If alternate files are required a different database entry is required
+        languages: BTreeSet<Iso639Alpha3>,
+        /// The version or revision of the program
+        version: Option<String>,
+        /// Quirks the system should be built with for this program, in RON
+        ///
+        /// Parsed by the system itself, as only it knows what quirks it has
+        quirks: Option<String>,
+    },
 }
 
 impl ProgramInfo {
     /// Returns the name of the program
     pub fn names(&self) -> &BTreeSet<String> {
         match self {
-            ProgramInfo::V0 { names, .. } => names,
+            ProgramInfo::V0 { names, .. } | ProgramInfo::V1 { names, .. } => names,
         }
     }
 
     /// Returns the path of the program
     pub fn filesystem(&self) -> &BTreeMap<RomId, BTreeSet<String>> {
         match self {
-            ProgramInfo::V0 { filesystem, .. } => filesystem,
+            ProgramInfo::V0 { filesystem, .. } | ProgramInfo::V1 { filesystem, .. } => filesystem,
+        }
+    }
+
+    /// Returns the quirks override of the program
+    pub fn quirks(&self) -> Option<&str> {
+        match self {
+            ProgramInfo::V0 { .. } => None,
+            ProgramInfo::V1 { quirks, .. } => quirks.as_deref(),
         }
     }
 
     /// Converts this to the latest version
-    pub fn mitigate(mut self) -> Self {
-        match &mut self {
-            ProgramInfo::V0 { filesystem, .. } => {
+    pub fn mitigate(self) -> Self {
+        match self {
+            ProgramInfo::V0 {
+                names,
+                filesystem,
+                languages,
+                version,
+            } => ProgramInfo::V1 {
+                names,
+                filesystem,
+                languages,
+                version,
+                quirks: None,
+            }
+            .mitigate(),
+            ProgramInfo::V1 {
+                names,
+                mut filesystem,
+                languages,
+                version,
+                quirks,
+            } => {
                 filesystem.retain(|_, paths| !paths.is_empty());
 
-                self
+                ProgramInfo::V1 {
+                    names,
+                    filesystem,
+                    languages,
+                    version,
+                    quirks,
+                }
             }
         }
     }
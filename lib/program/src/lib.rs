@@ -1,7 +1,10 @@
+mod archive;
 mod id;
 mod info;
 mod manager;
+pub mod patch;
 
+pub use archive::{ArchiveFormat, archive_entries};
 pub use id::*;
 pub use info::*;
 pub use manager::{ProgramManager, *};
@@ -1,247 +0,0 @@
-use std::{
-    collections::{BTreeMap, BTreeSet},
-    fmt::Debug,
-    fs::File,
-    io::Read,
-    ops::Deref,
-    path::{Path, PathBuf},
-    sync::Arc,
-};
-
-use bytes::Bytes;
-use redb::{Database, MultimapTableDefinition, ReadableDatabase};
-use rustc_hash::FxBuildHasher;
-use sha1::{Digest, Sha1};
-use thiserror::Error;
-
-use crate::{ProgramId, ProgramInfo, ProgramSpecification, RomId, SystemId};
-
-#[derive(Debug, Error)]
-pub enum Error {
-    #[error("{0}")]
-    RedbTransaction(#[from] redb::TransactionError),
-    #[error("{0}")]
-    RedbTable(#[from] redb::TableError),
-    #[error("{0}")]
-    RedbStorage(#[from] redb::StorageError),
-    #[error("{0}")]
-    RedbCommit(#[from] redb::CommitError),
-    #[error("{0}")]
-    Redb(#[from] redb::Error),
-    #[error("{0}")]
-    Io(#[from] std::io::Error),
-}
-
-/// Program id -> Program info mapping
-pub const PROGRAM_INFORMATION_TABLE: MultimapTableDefinition<ProgramId, ProgramInfo> =
-    MultimapTableDefinition::new("program_information");
-/// Hash -> Program id reverse mapping
-pub const HASH_ALIAS_TABLE: MultimapTableDefinition<RomId, ProgramId> =
-    MultimapTableDefinition::new("hash_alias");
-
-/// The ROM manager which contains the database and information about the roms that were loaded
-pub struct ProgramManager {
-    database: Database,
-    external_roms: scc::HashMap<RomId, PathBuf, FxBuildHasher>,
-    embedded_roms: scc::HashMap<RomId, &'static [u8], FxBuildHasher>,
-    rom_cache: scc::HashCache<RomId, Bytes>,
-    rom_stores: Vec<PathBuf>,
-}
-
-impl ProgramManager {
-    /// Opens and loads the default database
-    #[inline]
-    pub fn new(
-        database: Database,
-        rom_stores: impl IntoIterator<Item = PathBuf>,
-    ) -> Result<Arc<Self>, Error> {
-        let mut database_transaction = database.begin_write()?;
-        database_transaction.set_quick_repair(true);
-        database_transaction.open_multimap_table(PROGRAM_INFORMATION_TABLE)?;
-        database_transaction.open_multimap_table(HASH_ALIAS_TABLE)?;
-        database_transaction.commit()?;
-
-        Ok(Arc::new(Self {
-            database,
-            external_roms: scc::HashMap::default(),
-            embedded_roms: scc::HashMap::default(),
-            rom_cache: scc::HashCache::with_capacity(0, 16),
-            rom_stores: rom_stores.into_iter().collect(),
-        }))
-    }
-
-    pub fn register_external(&self, path: impl AsRef<Path>) -> Result<RomId, Error> {
-        let path = path.as_ref();
-        let rom_file = File::open(path)?;
-        let rom_bytes = load_rom_bytes(rom_file)?;
-
-        // Find the ID of the rom
-        let mut hasher = Sha1::new();
-        hasher.update(&rom_bytes);
-        let hash = hasher.finalize();
-        let rom_id = RomId(hash.into());
-
-        self.external_roms.upsert_sync(rom_id, path.to_path_buf());
-        let _ = self.rom_cache.put_sync(rom_id, rom_bytes);
-
-        Ok(rom_id)
-    }
-
-    pub fn load(&self, id: RomId) -> Result<Option<Bytes>, Error> {
-        if let Some(rom_bytes) = self.embedded_roms.get_sync(&id) {
-            return Ok(Some(Bytes::from_static(&rom_bytes)));
-        }
-
-        match self.rom_cache.entry_sync(id) {
-            scc::hash_cache::Entry::Occupied(bytes) => Ok(Some(bytes.clone())),
-            scc::hash_cache::Entry::Vacant(vacant_entry) => {
-                if let Some(external_rom_path) = self.external_roms.get_sync(&id) {
-                    tracing::info!(
-                        "Opening ROM {} from external path: {}",
-                        id,
-                        external_rom_path.display()
-                    );
-
-                    let rom_file = File::open(external_rom_path.deref())?;
-
-                    return load_rom_bytes(rom_file).map(|rom| {
-                        vacant_entry.put_entry(rom.clone());
-
-                        Some(rom)
-                    });
-                }
-
-                let id_as_string = id.to_string();
-
-                for rom_store in &self.rom_stores {
-                    let rom_path = rom_store.join(&id_as_string);
-                    let Ok(rom_file) = File::open(rom_path) else {
-                        continue;
-                    };
-
-                    return load_rom_bytes(rom_file).map(|rom| {
-                        vacant_entry.put_entry(rom.clone());
-                        Some(rom)
-                    });
-                }
-
-                Ok(None)
-            }
-        }
-    }
-
-    /// Attempts to identify a program from its program ids
-    pub fn identify_program(&self, roms: &[RomId]) -> Result<Vec<ProgramSpecification>, Error> {
-        let read_transaction = self.database.begin_read()?;
-
-        let hash_alias_table = read_transaction.open_multimap_table(HASH_ALIAS_TABLE)?;
-        let program_info_table = read_transaction.open_multimap_table(PROGRAM_INFORMATION_TABLE)?;
-
-        let mut possible_programs = Vec::default();
-
-        for rom_id in roms {
-            for access_guard in hash_alias_table.get(rom_id)? {
-                let program_id = access_guard?.value();
-
-                for access_guard in program_info_table.get(&program_id)? {
-                    let program_info = access_guard?.value();
-
-                    let found_all = roms
-                        .iter()
-                        .all(|id| program_info.filesystem().contains_key(id));
-
-                    if found_all {
-                        possible_programs.push(ProgramSpecification {
-                            id: program_id.clone(),
-                            info: program_info,
-                        });
-                    }
-                }
-            }
-        }
-
-        Ok(possible_programs)
-    }
-
-    pub fn auto_generate_specification(
-        &self,
-        rom_id: RomId,
-    ) -> Result<Option<ProgramSpecification>, Error> {
-        let external_path = self.external_roms.get_sync(&rom_id);
-        let rom = self.load(rom_id)?;
-
-        let Some(machine) = SystemId::guess(
-            external_path.as_deref().map(|path| path.as_path()),
-            rom.as_deref(),
-        ) else {
-            return Ok(None);
-        };
-
-        let (file_name, name) = external_path
-            .into_iter()
-            .find_map(|path| {
-                let file_name = path.file_name().unwrap().to_string_lossy().to_string();
-                let name = file_name
-                    .split('.')
-                    .next()
-                    .unwrap_or(&file_name)
-                    .to_string();
-
-                if name.is_empty() {
-                    return None;
-                }
-
-                Some((file_name, name))
-            })
-            .unwrap_or_else(|| (rom_id.to_string(), rom_id.to_string()));
-
-        let program_id = ProgramId {
-            system: machine,
-            name: name.clone(),
-        };
-
-        Ok(Some(ProgramSpecification {
-            id: program_id,
-            info: ProgramInfo::V0 {
-                names: BTreeSet::from_iter([name.clone()]),
-                filesystem: BTreeMap::from_iter([(rom_id, BTreeSet::from_iter([file_name]))]),
-                languages: BTreeSet::default(),
-                version: None,
-            },
-        }))
-    }
-
-    pub fn register_embedded_rom(&mut self, bytes: &'static [u8]) -> RomId {
-        let rom_id = RomId::new_sha1(bytes).unwrap();
-
-        self.embedded_roms.upsert_sync(rom_id, bytes);
-
-        // No need to cache a rom permanently bolted in
-        self.rom_cache.remove_sync(&rom_id);
-        self.external_roms.remove_sync(&rom_id);
-
-        rom_id
-    }
-
-    pub fn database(&self) -> &Database {
-        &self.database
-    }
-}
-
-fn load_rom_bytes(mut rom_file: File) -> Result<Bytes, Error> {
-    #[cfg(feature = "mmap")]
-    {
-        use memmap2::Mmap;
-
-        if let Ok(buffer) = unsafe { Mmap::map(&rom_file) } {
-            return Ok(Bytes::from_owner(buffer));
-        } else {
-            tracing::warn!("Failed to memory-map ROM file, falling back to loading into memory");
-        }
-    }
-
-    let mut buffer = Vec::default();
-    rom_file.read_to_end(&mut buffer)?;
-
-    Ok(Bytes::from_owner(buffer))
-}
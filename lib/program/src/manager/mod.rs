@@ -0,0 +1,537 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use bytes::Bytes;
+use redb::{Database, MultimapTableDefinition, ReadableDatabase};
+use rustc_hash::FxBuildHasher;
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+
+use crate::{
+    ArchiveFormat, ProgramId, ProgramInfo, ProgramSpecification, RomId, SystemGuess, SystemId,
+    archive::read_archive_entries,
+    patch::{PatchError, apply_patch, sibling_patch},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    RedbTransaction(#[from] redb::TransactionError),
+    #[error("{0}")]
+    RedbTable(#[from] redb::TableError),
+    #[error("{0}")]
+    RedbStorage(#[from] redb::StorageError),
+    #[error("{0}")]
+    RedbCommit(#[from] redb::CommitError),
+    #[error("{0}")]
+    Redb(#[from] redb::Error),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("{0}")]
+    SevenZip(#[from] sevenz_rust2::Error),
+    #[error("{0} is not a zip or 7z archive")]
+    NotAnArchive(PathBuf),
+    #[error("{archive} has no entry {entry}")]
+    MissingArchiveEntry { archive: PathBuf, entry: String },
+    #[error("{0}")]
+    Patch(#[from] PatchError),
+    #[error("ROM {0} could not be found")]
+    MissingRom(RomId),
+}
+
+/// Program id -> Program info mapping
+pub const PROGRAM_INFORMATION_TABLE: MultimapTableDefinition<ProgramId, ProgramInfo> =
+    MultimapTableDefinition::new("program_information");
+/// Hash -> Program id reverse mapping
+pub const HASH_ALIAS_TABLE: MultimapTableDefinition<RomId, ProgramId> =
+    MultimapTableDefinition::new("hash_alias");
+
+/// Where a ROM registered from outside the ROM stores is read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExternalRom {
+    File(PathBuf),
+    ArchiveEntry {
+        archive: PathBuf,
+        format: ArchiveFormat,
+        /// Path of the entry within the archive, unixlike
+        entry: String,
+    },
+    /// Another ROM with a patch applied
+    Patched {
+        source: RomId,
+        patch: PathBuf,
+    },
+}
+
+impl ExternalRom {
+    /// The path naming the ROM, which is the path within the archive for archive entries and the patch for patched ROMs
+    pub fn path(&self) -> &Path {
+        match self {
+            ExternalRom::File(path) => path,
+            ExternalRom::ArchiveEntry { entry, .. } => Path::new(entry),
+            ExternalRom::Patched { patch, .. } => patch,
+        }
+    }
+}
+
+/// The ROM manager which contains the database and information about the roms that were loaded
+pub struct ProgramManager {
+    database: Database,
+    external_roms: scc::HashMap<RomId, ExternalRom, FxBuildHasher>,
+    embedded_roms: scc::HashMap<RomId, &'static [u8], FxBuildHasher>,
+    rom_cache: scc::HashCache<RomId, Bytes>,
+    rom_stores: Vec<PathBuf>,
+    /// ID of each ROM with its header stripped, or [`None`] if it has no header
+    headerless_ids: scc::HashMap<RomId, Option<RomId>, FxBuildHasher>,
+    /// Headerless ID -> ID of the ROM as it is, header included
+    headered_ids: scc::HashMap<RomId, RomId, FxBuildHasher>,
+}
+
+impl Debug for ProgramManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgramManager")
+            .field("rom_stores", &self.rom_stores)
+            .field("external_roms", &self.external_roms.len())
+            .field("embedded_roms", &self.embedded_roms.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProgramManager {
+    /// Opens and loads the default database
+    #[inline]
+    pub fn new(
+        database: Database,
+        rom_stores: impl IntoIterator<Item = PathBuf>,
+    ) -> Result<Arc<Self>, Error> {
+        let mut database_transaction = database.begin_write()?;
+        database_transaction.set_quick_repair(true);
+        database_transaction.open_multimap_table(PROGRAM_INFORMATION_TABLE)?;
+        database_transaction.open_multimap_table(HASH_ALIAS_TABLE)?;
+        database_transaction.commit()?;
+
+        Ok(Arc::new(Self {
+            database,
+            external_roms: scc::HashMap::default(),
+            embedded_roms: scc::HashMap::default(),
+            rom_cache: scc::HashCache::with_capacity(0, 16),
+            rom_stores: rom_stores.into_iter().collect(),
+            headerless_ids: scc::HashMap::default(),
+            headered_ids: scc::HashMap::default(),
+        }))
+    }
+
+    pub fn register_external(&self, path: impl AsRef<Path>) -> Result<RomId, Error> {
+        let path = path.as_ref();
+        let rom_file = File::open(path)?;
+        let rom_bytes = load_rom_bytes(rom_file)?;
+
+        let rom_id = self.register_external_bytes(ExternalRom::File(path.to_path_buf()), rom_bytes);
+
+        // A patch named like the ROM next to it is applied without being asked to, though one that does not fit
+        // should not keep the ROM from running as it is
+        if let Some(patch) = sibling_patch(path) {
+            match self.register_patched(rom_id, &patch) {
+                Ok(patched_rom_id) => return Ok(patched_rom_id),
+                Err(err) => tracing::warn!(
+                    "Not applying patch {} found next to the ROM: {}",
+                    patch.display(),
+                    err
+                ),
+            }
+        }
+
+        Ok(rom_id)
+    }
+
+    /// Register a already registered ROM with the patch at `patch` applied
+    ///
+    /// The patched ROM gets its own ID from the hash of the patched output, which can be patched again in turn
+    pub fn register_patched(&self, source: RomId, patch: impl AsRef<Path>) -> Result<RomId, Error> {
+        let patch = patch.as_ref();
+        let source_bytes = self.load(source)?.ok_or(Error::MissingRom(source))?;
+        let rom_bytes = patch_rom(&source_bytes, patch)?;
+
+        // A patch that changes nothing would make the ROM its own source
+        if hash_rom(&rom_bytes) == source {
+            return Ok(source);
+        }
+
+        tracing::info!("Applied patch {} to ROM {}", patch.display(), source);
+
+        Ok(self.register_external_bytes(
+            ExternalRom::Patched {
+                source,
+                patch: patch.to_path_buf(),
+            },
+            rom_bytes,
+        ))
+    }
+
+    /// Register a single file inside a zip or 7z archive
+    pub fn register_archive_entry(
+        &self,
+        archive: impl AsRef<Path>,
+        entry: &str,
+    ) -> Result<RomId, Error> {
+        let archive = archive.as_ref();
+        let format = detect_archive(archive)?;
+
+        let (_, rom_bytes) =
+            read_archive_entries(archive, format, Some(&BTreeSet::from([entry.to_owned()])))?
+                .pop()
+                .ok_or_else(|| Error::MissingArchiveEntry {
+                    archive: archive.to_path_buf(),
+                    entry: entry.to_owned(),
+                })?;
+
+        Ok(self.register_external_bytes(
+            ExternalRom::ArchiveEntry {
+                archive: archive.to_path_buf(),
+                format,
+                entry: entry.to_owned(),
+            },
+            rom_bytes,
+        ))
+    }
+
+    /// Register every file inside a zip or 7z archive, in the order they are stored
+    pub fn register_archive(&self, archive: impl AsRef<Path>) -> Result<Vec<RomId>, Error> {
+        let archive = archive.as_ref();
+        let format = detect_archive(archive)?;
+
+        Ok(read_archive_entries(archive, format, None)?
+            .into_iter()
+            .map(|(entry, rom_bytes)| {
+                self.register_external_bytes(
+                    ExternalRom::ArchiveEntry {
+                        archive: archive.to_path_buf(),
+                        format,
+                        entry,
+                    },
+                    rom_bytes,
+                )
+            })
+            .collect())
+    }
+
+    fn register_external_bytes(&self, external_rom: ExternalRom, rom_bytes: Bytes) -> RomId {
+        let rom_id = hash_rom(&rom_bytes);
+
+        self.external_roms.upsert_sync(rom_id, external_rom);
+        self.register_headerless(rom_id, &rom_bytes);
+        let _ = self.rom_cache.put_sync(rom_id, rom_bytes);
+
+        rom_id
+    }
+
+    /// Remember the ID the ROM has in databases hashing it without its header
+    fn register_headerless(&self, rom_id: RomId, rom: &[u8]) -> Option<RomId> {
+        let headerless_id = SystemId::strip_any_header(rom).map(|(_, rom)| hash_rom(rom));
+
+        self.headerless_ids.upsert_sync(rom_id, headerless_id);
+
+        if let Some(headerless_id) = headerless_id {
+            self.headered_ids.upsert_sync(headerless_id, rom_id);
+        }
+
+        headerless_id
+    }
+
+    /// The ID of the ROM with its header stripped, if it has a header any system is known to use
+    pub fn headerless_id(&self, rom_id: RomId) -> Result<Option<RomId>, Error> {
+        if let Some(headerless_id) = self.headerless_ids.get_sync(&rom_id) {
+            return Ok(*headerless_id);
+        }
+
+        Ok(self
+            .load(rom_id)?
+            .and_then(|rom| self.register_headerless(rom_id, &rom)))
+    }
+
+    /// Load the ROM with the given ID
+    ///
+    /// A ROM asked for by its headerless ID comes with the header still in place, as systems tend to need it
+    pub fn load(&self, id: RomId) -> Result<Option<Bytes>, Error> {
+        if let Some(rom_bytes) = self.embedded_roms.get_sync(&id) {
+            return Ok(Some(Bytes::from_static(&rom_bytes)));
+        }
+
+        if !self.external_roms.contains_sync(&id)
+            && let Some(headered_id) = self.headered_ids.get_sync(&id).map(|entry| *entry)
+        {
+            return self.load(headered_id);
+        }
+
+        if let Some(rom) = self.rom_cache.read_sync(&id, |_, rom| rom.clone()) {
+            return Ok(Some(rom));
+        }
+
+        // No entry of the cache can be held on to from here, as patching loads the source ROM and archives cache
+        // every ROM read out of them
+        let rom = match self.external_rom(id) {
+            Some(ExternalRom::File(path)) => {
+                tracing::info!("Opening ROM {} from external path: {}", id, path.display());
+
+                load_rom_bytes(File::open(path)?)?
+            }
+            Some(ExternalRom::ArchiveEntry {
+                archive, format, ..
+            }) => return self.load_archive(id, &archive, format),
+            Some(ExternalRom::Patched { source, patch }) => {
+                let Some(source_bytes) = self.load(source)? else {
+                    return Ok(None);
+                };
+
+                tracing::info!("Patching ROM {} with {}", source, patch.display());
+
+                patch_rom(&source_bytes, &patch)?
+            }
+            None => {
+                let id_as_string = id.to_string();
+                let Some(rom_file) = self
+                    .rom_stores
+                    .iter()
+                    .find_map(|rom_store| File::open(rom_store.join(&id_as_string)).ok())
+                else {
+                    return Ok(None);
+                };
+
+                load_rom_bytes(rom_file)?
+            }
+        };
+
+        let _ = self.rom_cache.put_sync(id, rom.clone());
+
+        Ok(Some(rom))
+    }
+
+    /// Read every ROM registered from `archive` in a single pass and cache them, returning the one with ID `id`
+    ///
+    /// Getting to a entry of a solid 7z means decompressing everything stored before it, so its siblings come
+    /// along for little extra
+    fn load_archive(
+        &self,
+        id: RomId,
+        archive: &Path,
+        format: ArchiveFormat,
+    ) -> Result<Option<Bytes>, Error> {
+        tracing::info!("Opening ROM {} from archive: {}", id, archive.display());
+
+        let mut registered = BTreeMap::default();
+        self.external_roms.iter_sync(|rom_id, external_rom| {
+            if let ExternalRom::ArchiveEntry {
+                archive: other,
+                entry,
+                ..
+            } = external_rom
+                && other == archive
+            {
+                registered.insert(entry.clone(), *rom_id);
+            }
+
+            true
+        });
+
+        let wanted = registered.keys().cloned().collect();
+        let mut rom = None;
+
+        for (entry, bytes) in read_archive_entries(archive, format, Some(&wanted))? {
+            let rom_id = registered[&entry];
+
+            if rom_id == id {
+                rom = Some(bytes.clone());
+            }
+
+            let _ = self.rom_cache.put_sync(rom_id, bytes);
+        }
+
+        Ok(rom)
+    }
+
+    /// Attempts to identify a program from its program ids
+    pub fn identify_program(&self, roms: &[RomId]) -> Result<Vec<ProgramSpecification>, Error> {
+        let read_transaction = self.database.begin_read()?;
+
+        let hash_alias_table = read_transaction.open_multimap_table(HASH_ALIAS_TABLE)?;
+        let program_info_table = read_transaction.open_multimap_table(PROGRAM_INFORMATION_TABLE)?;
+
+        // Databases may know a ROM by its ID with or without the header
+        let candidates = roms
+            .iter()
+            .map(|rom_id| Ok([Some(*rom_id), self.headerless_id(*rom_id)?]))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut possible_programs = Vec::default();
+        // A headered dump may alias the same program by both of its IDs, as may several ROMs of one program
+        let mut seen_programs = BTreeSet::new();
+
+        for rom_id in candidates.iter().flatten().flatten() {
+            for access_guard in hash_alias_table.get(rom_id)? {
+                let program_id = access_guard?.value();
+
+                if !seen_programs.insert(program_id.clone()) {
+                    continue;
+                }
+
+                for access_guard in program_info_table.get(&program_id)? {
+                    let program_info = access_guard?.value();
+
+                    let found_all = candidates.iter().all(|ids| {
+                        ids.iter()
+                            .flatten()
+                            .any(|id| program_info.filesystem().contains_key(id))
+                    });
+
+                    if found_all {
+                        possible_programs.push(ProgramSpecification {
+                            id: program_id.clone(),
+                            info: program_info,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(possible_programs)
+    }
+
+    /// Specification for a ROM no database knows of, going with the best guess as to its system
+    pub fn auto_generate_specification(
+        &self,
+        rom_id: RomId,
+    ) -> Result<Option<ProgramSpecification>, Error> {
+        let Some(guess) = self.guess_systems(rom_id)?.first().copied() else {
+            return Ok(None);
+        };
+
+        Ok(Some(self.generate_specification(rom_id, guess.system)))
+    }
+
+    /// Every system the ROM could be for, best guess first
+    pub fn guess_systems(&self, rom_id: RomId) -> Result<Vec<SystemGuess>, Error> {
+        let rom = self.load(rom_id)?;
+
+        // Patches name the program, but only the ROM they apply to says anything about the system
+        let mut unpatched_rom = self.external_rom(rom_id);
+        while let Some(ExternalRom::Patched { source, .. }) = unpatched_rom {
+            unpatched_rom = self.external_rom(source);
+        }
+
+        Ok(SystemId::guess_ranked(
+            unpatched_rom.as_ref().map(ExternalRom::path),
+            rom.as_deref(),
+        ))
+    }
+
+    /// Specification for a ROM no database knows of, named after the file it came from
+    pub fn generate_specification(&self, rom_id: RomId, system: SystemId) -> ProgramSpecification {
+        let external_rom = self.external_rom(rom_id);
+
+        let (file_name, name) = external_rom
+            .into_iter()
+            .find_map(|external_rom| {
+                let file_name = external_rom
+                    .path()
+                    .file_name()?
+                    .to_string_lossy()
+                    .to_string();
+                let name = file_name
+                    .split('.')
+                    .next()
+                    .unwrap_or(&file_name)
+                    .to_string();
+
+                if name.is_empty() {
+                    return None;
+                }
+
+                Some((file_name, name))
+            })
+            .unwrap_or_else(|| (rom_id.to_string(), rom_id.to_string()));
+
+        let program_id = ProgramId {
+            system,
+            name: name.clone(),
+        };
+
+        ProgramSpecification {
+            id: program_id,
+            info: ProgramInfo::V1 {
+                names: BTreeSet::from_iter([name.clone()]),
+                filesystem: BTreeMap::from_iter([(rom_id, BTreeSet::from_iter([file_name]))]),
+                languages: BTreeSet::default(),
+                version: None,
+                quirks: None,
+            },
+        }
+    }
+
+    fn external_rom(&self, rom_id: RomId) -> Option<ExternalRom> {
+        self.external_roms
+            .get_sync(&rom_id)
+            .map(|external_rom| external_rom.clone())
+    }
+
+    pub fn register_embedded_rom(&mut self, bytes: &'static [u8]) -> RomId {
+        let rom_id = RomId::new_sha1(bytes).unwrap();
+
+        self.embedded_roms.upsert_sync(rom_id, bytes);
+
+        // No need to cache a rom permanently bolted in
+        self.rom_cache.remove_sync(&rom_id);
+        self.external_roms.remove_sync(&rom_id);
+
+        rom_id
+    }
+
+    pub fn database(&self) -> &Database {
+        &self.database
+    }
+}
+
+fn hash_rom(rom: &[u8]) -> RomId {
+    let mut hasher = Sha1::new();
+    hasher.update(rom);
+
+    RomId(hasher.finalize().into())
+}
+
+fn patch_rom(source: &[u8], patch: &Path) -> Result<Bytes, Error> {
+    let patch = std::fs::read(patch)?;
+
+    Ok(Bytes::from_owner(apply_patch(source, &patch)?))
+}
+
+fn detect_archive(path: &Path) -> Result<ArchiveFormat, Error> {
+    ArchiveFormat::detect_file(path)?.ok_or_else(|| Error::NotAnArchive(path.to_path_buf()))
+}
+
+fn load_rom_bytes(mut rom_file: File) -> Result<Bytes, Error> {
+    #[cfg(feature = "mmap")]
+    {
+        use memmap2::Mmap;
+
+        if let Ok(buffer) = unsafe { Mmap::map(&rom_file) } {
+            return Ok(Bytes::from_owner(buffer));
+        } else {
+            tracing::warn!("Failed to memory-map ROM file, falling back to loading into memory");
+        }
+    }
+
+    let mut buffer = Vec::default();
+    rom_file.read_to_end(&mut buffer)?;
+
+    Ok(Bytes::from_owner(buffer))
+}
+
+#[cfg(test)]
+mod tests;
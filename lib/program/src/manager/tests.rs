@@ -0,0 +1,76 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use redb::{Database, backends::InMemoryBackend};
+
+use super::{HASH_ALIAS_TABLE, PROGRAM_INFORMATION_TABLE, ProgramManager, hash_rom};
+use crate::{NintendoSystem, ProgramId, ProgramInfo, SystemId};
+
+#[test]
+fn programs_are_identified_by_their_headerless_id() {
+    let mut headered_rom = b"NES\x1a".to_vec();
+    headered_rom.resize(16, 0);
+    headered_rom.extend([0xaa; 32]);
+    let headerless_id = hash_rom(&headered_rom[16..]);
+
+    let program_id = ProgramId {
+        system: SystemId::Nintendo(NintendoSystem::NintendoEntertainmentSystem),
+        name: "Game".to_string(),
+    };
+
+    // Databases like No-Intro only know the ROM without its header, others know it with it
+    let database = Database::builder()
+        .create_with_backend(InMemoryBackend::default())
+        .unwrap();
+    let database_transaction = database.begin_write().unwrap();
+    database_transaction
+        .open_multimap_table(HASH_ALIAS_TABLE)
+        .unwrap()
+        .insert(headerless_id, &program_id)
+        .unwrap();
+    database_transaction
+        .open_multimap_table(HASH_ALIAS_TABLE)
+        .unwrap()
+        .insert(hash_rom(&headered_rom), &program_id)
+        .unwrap();
+    database_transaction
+        .open_multimap_table(PROGRAM_INFORMATION_TABLE)
+        .unwrap()
+        .insert(
+            &program_id,
+            ProgramInfo::V1 {
+                names: BTreeSet::from(["Game".to_string()]),
+                filesystem: BTreeMap::from([(
+                    headerless_id,
+                    BTreeSet::from(["game.nes".to_string()]),
+                )]),
+                languages: BTreeSet::default(),
+                version: None,
+                quirks: None,
+            },
+        )
+        .unwrap();
+    database_transaction.commit().unwrap();
+
+    let program_manager = ProgramManager::new(database, []).unwrap();
+
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("game.nes");
+    std::fs::write(&path, &headered_rom).unwrap();
+    let rom_id = program_manager.register_external(&path).unwrap();
+
+    assert_eq!(
+        program_manager.headerless_id(rom_id).unwrap(),
+        Some(headerless_id)
+    );
+
+    // Found by both IDs, but only reported once
+    let programs = program_manager.identify_program(&[rom_id]).unwrap();
+    assert_eq!(programs.len(), 1);
+    assert_eq!(programs[0].id, program_id);
+
+    // Systems get the header they need even when asked by the headerless ID
+    assert_eq!(
+        program_manager.load(headerless_id).unwrap().as_deref(),
+        Some(&headered_rom[..])
+    );
+}
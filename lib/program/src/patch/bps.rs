@@ -0,0 +1,91 @@
+use super::{Footer, PatchError, PatchReader, target_size};
+
+pub(super) const MAGIC: &[u8] = b"BPS1";
+
+pub(super) fn apply(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let (body, footer) = Footer::split(patch)?;
+    // Anything built for another dump would produce garbage, so refuse before touching it
+    footer.check_source(source)?;
+
+    let mut reader = PatchReader::new(body, MAGIC.len());
+
+    let source_size = reader.number()?;
+    let target_size = target_size(reader.number()?)?;
+    let metadata_size = reader.number()?;
+    reader.bytes(metadata_size)?;
+
+    if source_size != source.len() {
+        return Err(PatchError::OutOfBounds);
+    }
+
+    let mut target = Vec::with_capacity(target_size);
+    let mut source_offset: usize = 0;
+    let mut target_offset: usize = 0;
+
+    while reader.position < body.len() {
+        let action = reader.number()?;
+        let length = (action >> 2) + 1;
+
+        if target.len() + length > target_size {
+            return Err(PatchError::OutOfBounds);
+        }
+
+        match action & 0b11 {
+            // Source read, copy from the same offset of the source
+            0 => {
+                let start = target.len();
+                let end = start.checked_add(length).ok_or(PatchError::OutOfBounds)?;
+                let data = source.get(start..end).ok_or(PatchError::OutOfBounds)?;
+
+                target.extend_from_slice(data);
+            }
+            // Target read, copy from the patch itself
+            1 => target.extend_from_slice(reader.bytes(length)?),
+            // Source copy, copy from anywhere in the source
+            2 => {
+                source_offset = relative_offset(source_offset, reader.number()?)?;
+                let end = source_offset
+                    .checked_add(length)
+                    .ok_or(PatchError::OutOfBounds)?;
+                let data = source
+                    .get(source_offset..end)
+                    .ok_or(PatchError::OutOfBounds)?;
+
+                target.extend_from_slice(data);
+                source_offset = end;
+            }
+            // Target copy, copy from what was already output, which may overlap what is being written
+            3 => {
+                target_offset = relative_offset(target_offset, reader.number()?)?;
+
+                if target_offset >= target.len() {
+                    return Err(PatchError::OutOfBounds);
+                }
+
+                for _ in 0..length {
+                    target.push(target[target_offset]);
+                    target_offset = target_offset
+                        .checked_add(1)
+                        .ok_or(PatchError::OutOfBounds)?;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    footer.check_target(&target)?;
+
+    Ok(target)
+}
+
+/// Offsets for copies are stored relative to where the last copy left off, with the lowest bit as sign
+fn relative_offset(offset: usize, encoded: usize) -> Result<usize, PatchError> {
+    let distance = encoded >> 1;
+
+    if encoded & 1 == 0 {
+        offset.checked_add(distance)
+    } else {
+        offset.checked_sub(distance)
+    }
+    .ok_or(PatchError::OutOfBounds)
+}
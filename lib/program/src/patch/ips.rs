@@ -0,0 +1,56 @@
+use super::{PatchError, PatchReader};
+
+pub(super) const MAGIC: &[u8] = b"PATCH";
+const END_OF_FILE: [u8; 3] = *b"EOF";
+
+pub(super) fn apply(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let mut target = source.to_vec();
+    let mut reader = PatchReader::new(patch, MAGIC.len());
+
+    loop {
+        let offset: [u8; 3] = reader.bytes(3)?.try_into().unwrap();
+
+        if offset == END_OF_FILE {
+            break;
+        }
+
+        let offset = u24(offset);
+        let length = usize::from(u16::from_be_bytes(reader.bytes(2)?.try_into().unwrap()));
+
+        // A zero length marks a run of a single byte
+        if length == 0 {
+            let run_length = usize::from(u16::from_be_bytes(reader.bytes(2)?.try_into().unwrap()));
+            let value = reader.byte()?;
+
+            write(&mut target, offset, run_length).fill(value);
+        } else {
+            let data = reader.bytes(length)?;
+
+            write(&mut target, offset, length).copy_from_slice(data);
+        }
+    }
+
+    // Some patches carry the size to cut the ROM down to after the end marker
+    if let Ok(truncated_length) = reader.bytes(3) {
+        target.truncate(u24(truncated_length.try_into().unwrap()));
+    }
+
+    Ok(target)
+}
+
+fn u24(bytes: [u8; 3]) -> usize {
+    let [high, middle, low] = bytes;
+
+    u32::from_be_bytes([0, high, middle, low]) as usize
+}
+
+/// Region of the ROM a record writes to, growing the ROM if it lies past the end
+fn write(target: &mut Vec<u8>, offset: usize, length: usize) -> &mut [u8] {
+    let end = offset + length;
+
+    if target.len() < end {
+        target.resize(end, 0);
+    }
+
+    &mut target[offset..end]
+}
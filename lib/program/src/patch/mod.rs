@@ -0,0 +1,223 @@
+//! Soft patching ROMs with IPS, BPS and UPS patches
+
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+mod bps;
+mod ips;
+mod ups;
+
+#[cfg(test)]
+mod tests;
+
+/// Extensions a patch lying next to a ROM is looked for with
+pub const PATCH_EXTENSIONS: [&str; 3] = ["ips", "bps", "ups"];
+
+/// Largest ROM BPS and UPS patches may ask for, so a corrupt size cannot exhaust memory before the checksum catches it
+const MAX_TARGET_SIZE: usize = 1 << 30;
+
+#[derive(Debug, Error)]
+pub enum PatchError {
+    #[error("Not a IPS, BPS or UPS patch")]
+    UnknownFormat,
+    #[error("Patch ends early")]
+    Truncated,
+    #[error("Patch refers to data outside of the ROM")]
+    OutOfBounds,
+    #[error("Patch asks for a ROM of {0} bytes, larger than any real one")]
+    TargetTooLarge(usize),
+    #[error("Patch is corrupt, its checksum does not match")]
+    PatchChecksumMismatch,
+    #[error("Patch is for a different ROM, expected CRC {expected:08x} but found {actual:08x}")]
+    SourceChecksumMismatch { expected: u32, actual: u32 },
+    #[error("Patched ROM does not match the checksum the patch expects")]
+    TargetChecksumMismatch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PatchFormat {
+    Ips,
+    Bps,
+    Ups,
+}
+
+impl PatchFormat {
+    /// Identify a patch by its leading bytes
+    pub fn detect(patch: &[u8]) -> Option<Self> {
+        if patch.starts_with(ips::MAGIC) {
+            Some(Self::Ips)
+        } else if patch.starts_with(bps::MAGIC) {
+            Some(Self::Bps)
+        } else if patch.starts_with(ups::MAGIC) {
+            Some(Self::Ups)
+        } else {
+            None
+        }
+    }
+}
+
+/// Apply a patch to the ROM, returning the patched ROM
+///
+/// Patches carrying checksums, being BPS and UPS, have them checked before and after patching
+pub fn apply_patch(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    match PatchFormat::detect(patch).ok_or(PatchError::UnknownFormat)? {
+        PatchFormat::Ips => ips::apply(source, patch),
+        PatchFormat::Bps => bps::apply(source, patch),
+        PatchFormat::Ups => ups::apply(source, patch),
+    }
+}
+
+/// Find a patch with the same name as the ROM at `path`, next to it
+pub fn sibling_patch(path: impl AsRef<Path>) -> Option<PathBuf> {
+    let path = path.as_ref();
+
+    PATCH_EXTENSIONS
+        .into_iter()
+        .map(|extension| path.with_extension(extension))
+        .find(|patch| patch != path && patch.is_file())
+}
+
+/// Size of the ROM a patch says it produces, refusing anything no real ROM comes close to
+fn target_size(size: usize) -> Result<usize, PatchError> {
+    if size > MAX_TARGET_SIZE {
+        return Err(PatchError::TargetTooLarge(size));
+    }
+
+    Ok(size)
+}
+
+/// Cursor over the bytes of a patch
+struct PatchReader<'a> {
+    patch: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PatchReader<'a> {
+    fn new(patch: &'a [u8], position: usize) -> Self {
+        Self { patch, position }
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], PatchError> {
+        let bytes = self
+            .patch
+            .get(self.position..)
+            .and_then(|rest| rest.get(..count))
+            .ok_or(PatchError::Truncated)?;
+        self.position += count;
+
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, PatchError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Variable length number as used by BPS and UPS, where every continuation also adds one to avoid redundant
+    /// encodings
+    fn number(&mut self) -> Result<usize, PatchError> {
+        let mut number: usize = 0;
+        let mut shift: usize = 1;
+
+        loop {
+            let byte = self.byte()?;
+            number = usize::from(byte & 0x7f)
+                .checked_mul(shift)
+                .and_then(|value| number.checked_add(value))
+                .ok_or(PatchError::OutOfBounds)?;
+
+            if byte & 0x80 != 0 {
+                return Ok(number);
+            }
+
+            shift = shift.checked_mul(0x80).ok_or(PatchError::OutOfBounds)?;
+            number = number.checked_add(shift).ok_or(PatchError::OutOfBounds)?;
+        }
+    }
+}
+
+/// Source, target and patch checksums at the end of BPS and UPS patches
+struct Footer {
+    source: u32,
+    target: u32,
+}
+
+impl Footer {
+    const SIZE: usize = 12;
+
+    /// Check the patch against its own checksum and split the footer off
+    fn split(patch: &[u8]) -> Result<(&[u8], Self), PatchError> {
+        let body_length = patch
+            .len()
+            .checked_sub(Self::SIZE)
+            .ok_or(PatchError::Truncated)?;
+        let (body, footer) = patch.split_at(body_length);
+        let checksum = |index: usize| {
+            u32::from_le_bytes(footer[index * 4..(index + 1) * 4].try_into().unwrap())
+        };
+
+        if crc32(&patch[..patch.len() - 4]) != checksum(2) {
+            return Err(PatchError::PatchChecksumMismatch);
+        }
+
+        Ok((
+            body,
+            Self {
+                source: checksum(0),
+                target: checksum(1),
+            },
+        ))
+    }
+
+    fn check_source(&self, source: &[u8]) -> Result<(), PatchError> {
+        let actual = crc32(source);
+
+        if actual != self.source {
+            return Err(PatchError::SourceChecksumMismatch {
+                expected: self.source,
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn check_target(&self, target: &[u8]) -> Result<(), PatchError> {
+        if crc32(target) != self.target {
+            return Err(PatchError::TargetChecksumMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut index = 0;
+
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            value = if value & 1 != 0 {
+                (value >> 1) ^ 0xedb8_8320
+            } else {
+                value >> 1
+            };
+            bit += 1;
+        }
+
+        table[index] = value;
+        index += 1;
+    }
+
+    table
+};
+
+/// The CRC-32 used by zip and friends
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, byte| {
+        CRC32_TABLE[usize::from((crc as u8) ^ byte)] ^ (crc >> 8)
+    })
+}
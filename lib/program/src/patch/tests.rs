@@ -0,0 +1,177 @@
+use super::{PatchError, apply_patch, crc32, sibling_patch};
+
+fn push_number(patch: &mut Vec<u8>, mut number: usize) {
+    loop {
+        let byte = (number & 0x7f) as u8;
+        number >>= 7;
+
+        if number == 0 {
+            patch.push(byte | 0x80);
+            return;
+        }
+
+        patch.push(byte);
+        number -= 1;
+    }
+}
+
+#[test]
+fn ips_records_and_runs_grow_the_rom() {
+    let mut patch = b"PATCH".to_vec();
+    patch.extend([0x00, 0x00, 0x02, 0x00, 0x02, 0x01, 0x02]);
+    // Run of three bytes running past the end of the ROM
+    patch.extend([0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x03, 0x09]);
+    patch.extend(b"EOF");
+
+    assert_eq!(
+        apply_patch(&[0; 8], &patch).unwrap(),
+        [0, 0, 1, 2, 0, 0, 9, 9, 9]
+    );
+}
+
+#[test]
+fn ips_truncates_to_the_size_after_the_end_marker() {
+    let mut patch = b"PATCH".to_vec();
+    patch.extend([0x00, 0x00, 0x00, 0x00, 0x01, 0x05]);
+    patch.extend(b"EOF");
+    patch.extend([0x00, 0x00, 0x04]);
+
+    assert_eq!(apply_patch(&[0; 8], &patch).unwrap(), [5, 0, 0, 0]);
+}
+
+/// UPS patch from `source` to `target`, with `differences` being the relative offset and XORed bytes of each record
+fn ups_patch(
+    source: &[u8],
+    target: &[u8],
+    target_size: usize,
+    differences: &[(usize, &[u8])],
+) -> Vec<u8> {
+    let mut patch = b"UPS1".to_vec();
+    push_number(&mut patch, source.len());
+    push_number(&mut patch, target_size);
+
+    for (offset, bytes) in differences {
+        push_number(&mut patch, *offset);
+        patch.extend(*bytes);
+        patch.push(0);
+    }
+
+    patch.extend(crc32(source).to_le_bytes());
+    patch.extend(crc32(target).to_le_bytes());
+    patch.extend(crc32(&patch).to_le_bytes());
+
+    patch
+}
+
+#[test]
+fn ups_xors_differences_and_grows_the_rom() {
+    let source = b"hello world";
+    let target = b"jello world!!";
+
+    let patch = ups_patch(
+        source,
+        target,
+        target.len(),
+        // Past the end of the source the differences are the new bytes themselves
+        &[(0, &[b'h' ^ b'j']), (9, b"!!")],
+    );
+
+    assert_eq!(apply_patch(source, &patch).unwrap(), target);
+    assert!(matches!(
+        apply_patch(b"hello fluxemu", &patch),
+        Err(PatchError::SourceChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn ups_refuses_absurd_sizes() {
+    let source = b"hello world";
+    let patch = ups_patch(source, source, 1 << 40, &[]);
+
+    assert!(matches!(
+        apply_patch(source, &patch),
+        Err(PatchError::TargetTooLarge(_))
+    ));
+}
+
+#[test]
+fn ups_refuses_huge_offsets() {
+    let source = b"hello world";
+    let patch = ups_patch(source, source, source.len(), &[(usize::MAX, &[1])]);
+
+    assert!(matches!(
+        apply_patch(source, &patch),
+        Err(PatchError::OutOfBounds)
+    ));
+}
+
+#[test]
+fn sibling_patches_are_found_by_name() {
+    let directory = tempfile::tempdir().unwrap();
+    let rom = directory.path().join("game.nes");
+    std::fs::write(&rom, []).unwrap();
+
+    assert_eq!(sibling_patch(&rom), None);
+
+    for extension in ["ups", "ips"] {
+        std::fs::write(rom.with_extension(extension), []).unwrap();
+    }
+    std::fs::write(directory.path().join("other.bps"), []).unwrap();
+
+    // IPS is looked for first
+    assert_eq!(sibling_patch(&rom), Some(rom.with_extension("ips")));
+    // A patch is not its own sibling
+    assert_eq!(
+        sibling_patch(rom.with_extension("ips")),
+        Some(rom.with_extension("ups"))
+    );
+}
+
+#[test]
+fn bps_checks_the_source_before_patching() {
+    let source = b"hello world";
+    let target = b"hello there world";
+
+    let mut patch = b"BPS1".to_vec();
+    push_number(&mut patch, source.len());
+    push_number(&mut patch, target.len());
+    push_number(&mut patch, 0);
+    // Source read of "hello "
+    push_number(&mut patch, (6 - 1) << 2);
+    // Target read of "there "
+    push_number(&mut patch, ((6 - 1) << 2) | 1);
+    patch.extend(b"there ");
+    // Source copy of "world", 6 bytes on from the start
+    push_number(&mut patch, ((5 - 1) << 2) | 2);
+    push_number(&mut patch, 6 << 1);
+    patch.extend(crc32(source).to_le_bytes());
+    patch.extend(crc32(target).to_le_bytes());
+    patch.extend(crc32(&patch).to_le_bytes());
+
+    assert_eq!(apply_patch(source, &patch).unwrap(), target);
+    assert!(matches!(
+        apply_patch(b"hello fluxemu", &patch),
+        Err(PatchError::SourceChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn bps_refuses_huge_offsets() {
+    let source = b"hello world";
+
+    let mut patch = b"BPS1".to_vec();
+    push_number(&mut patch, source.len());
+    push_number(&mut patch, source.len());
+    push_number(&mut patch, 0);
+    // Source copy from as far on from the start as the offset can say
+    push_number(&mut patch, ((5 - 1) << 2) | 2);
+    push_number(&mut patch, usize::MAX & !1);
+    patch.extend(crc32(source).to_le_bytes());
+    patch.extend(crc32(source).to_le_bytes());
+    patch.extend(crc32(&patch).to_le_bytes());
+
+    assert!(matches!(
+        apply_patch(source, &patch),
+        Err(PatchError::OutOfBounds)
+    ));
+}
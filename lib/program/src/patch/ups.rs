@@ -0,0 +1,46 @@
+use super::{Footer, PatchError, PatchReader, target_size};
+
+pub(super) const MAGIC: &[u8] = b"UPS1";
+
+pub(super) fn apply(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let (body, footer) = Footer::split(patch)?;
+    footer.check_source(source)?;
+
+    let mut reader = PatchReader::new(body, MAGIC.len());
+
+    let source_size = reader.number()?;
+    let target_size = target_size(reader.number()?)?;
+
+    if source_size != source.len() {
+        return Err(PatchError::OutOfBounds);
+    }
+
+    let mut target = source.to_vec();
+    target.resize(target_size, 0);
+
+    let mut position: usize = 0;
+
+    while reader.position < body.len() {
+        position = position
+            .checked_add(reader.number()?)
+            .ok_or(PatchError::OutOfBounds)?;
+
+        // Differences are XORed in up to and including a terminating zero
+        loop {
+            let difference = reader.byte()?;
+
+            if let Some(byte) = target.get_mut(position) {
+                *byte ^= difference;
+            }
+            position = position.checked_add(1).ok_or(PatchError::OutOfBounds)?;
+
+            if difference == 0 {
+                break;
+            }
+        }
+    }
+
+    footer.check_target(&target)?;
+
+    Ok(target)
+}
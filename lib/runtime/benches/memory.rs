@@ -170,5 +170,54 @@ fn bench_writes(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_reads, bench_writes);
+fn bench_bank_switching(c: &mut Criterion) {
+    const BANK_SIZE: usize = 0x4000;
+
+    let bank = |index: u8| MemoryMapCommand::Map {
+        range: RangeInclusive::from_start_and_length(0x8000, BANK_SIZE),
+        target: MapTarget::ImmutableMemory(vec![index; BANK_SIZE].into()),
+        permissions: Permissions::READ,
+    };
+
+    let (machine, address_space_id) = Machine::build_test_minimal().address_space(16);
+    let machine = machine.map_memory(address_space_id, [bank(0)]);
+    let (machine, first) = machine.mapping_preset(address_space_id, "first", [bank(0)]);
+    let (machine, second) = machine.mapping_preset(address_space_id, "second", [bank(1)]);
+    let machine = machine.seal().build(());
+
+    let runtime_guard = machine.enter_runtime();
+    // Replaced page tables are only reclaimed once no handle is around to see them, like a mapper getting one per write
+    let address_space = || runtime_guard.address_space(address_space_id).unwrap();
+
+    let mut group = c.benchmark_group(format!("{}/memory/bank_switch", env!("CARGO_PKG_NAME")));
+
+    group.bench_function("remap", |b| {
+        let mut index = 0;
+
+        b.iter(|| {
+            index ^= 1;
+            address_space().remap(&Period::default(), [bank(index)]);
+        });
+    });
+
+    group.bench_function("preset", |b| {
+        let mut presets = [first, second].into_iter().cycle();
+
+        b.iter(|| {
+            address_space()
+                .switch_preset(presets.next().unwrap())
+                .unwrap();
+        });
+    });
+
+    group.bench_function("preset_in_place", |b| {
+        b.iter(|| {
+            address_space().switch_preset(black_box(first)).unwrap();
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_reads, bench_writes, bench_bank_switching);
 criterion_main!(benches);
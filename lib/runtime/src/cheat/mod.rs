@@ -0,0 +1,271 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use thiserror::Error;
+
+use crate::{
+    RuntimeHandle,
+    memory::{Address, AddressSpaceId},
+    scheduler::Period,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Letters of the NES Game Genie alphabet, in the order of the values they encode
+const GAME_GENIE_ALPHABET: &[u8; 16] = b"APZLGITYEOXUKSVN";
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum CheatError {
+    #[error("Code has an invalid length of {0}")]
+    InvalidLength(usize),
+    #[error("Code contains invalid character {0:?}")]
+    InvalidCharacter(char),
+    #[error("Code is not in the form address:value[:compare]")]
+    Malformed,
+    #[error("Cheat targets address space {0:?}, which does not exist")]
+    UnknownAddressSpace(AddressSpaceId),
+    #[error("Address {address:#04x} is past the end of the address space ({width_mask:#04x})")]
+    AddressOutOfRange {
+        address: Address,
+        width_mask: Address,
+    },
+}
+
+/// A single byte substitution, as described by a cheat code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CheatCode {
+    pub address: Address,
+    pub value: u8,
+    /// Only substitute when the original byte is this value
+    pub compare: Option<u8>,
+}
+
+impl CheatCode {
+    /// Decode a 6 or 8 letter NES Game Genie code
+    pub fn game_genie(code: &str) -> Result<Self, CheatError> {
+        let letters = code
+            .trim()
+            .chars()
+            .map(|character| {
+                GAME_GENIE_ALPHABET
+                    .iter()
+                    .position(|letter| char::from(*letter) == character.to_ascii_uppercase())
+                    .map(|value| value as u8)
+                    .ok_or(CheatError::InvalidCharacter(character))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if letters.len() != 6 && letters.len() != 8 {
+            return Err(CheatError::InvalidLength(letters.len()));
+        }
+
+        let n = |index: usize| letters[index];
+
+        let address = 0x8000
+            | (usize::from(n(3) & 7) << 12)
+            | (usize::from(n(5) & 7) << 8)
+            | (usize::from(n(4) & 8) << 8)
+            | (usize::from(n(2) & 7) << 4)
+            | (usize::from(n(1) & 8) << 4)
+            | usize::from(n(4) & 7)
+            | usize::from(n(3) & 8);
+
+        let value_high = ((n(1) & 7) << 4) | ((n(0) & 8) << 4) | (n(0) & 7);
+
+        if letters.len() == 6 {
+            Ok(Self {
+                address,
+                value: value_high | (n(5) & 8),
+                compare: None,
+            })
+        } else {
+            Ok(Self {
+                address,
+                value: value_high | (n(7) & 8),
+                compare: Some(((n(7) & 7) << 4) | ((n(6) & 8) << 4) | (n(6) & 7) | (n(5) & 8)),
+            })
+        }
+    }
+
+    /// Decode a Pro Action Replay code in the 8 digit `AAAAAAVV` form
+    pub fn pro_action_replay(code: &str) -> Result<Self, CheatError> {
+        let code = code.trim();
+
+        if code.len() != 8 {
+            return Err(CheatError::InvalidLength(code.len()));
+        }
+
+        let raw = parse_hex(code)?;
+
+        Ok(Self {
+            address: raw >> 8,
+            value: raw as u8,
+            compare: None,
+        })
+    }
+}
+
+/// Parses the generic `address:value[:compare]` syntax, with every part in hexadecimal
+impl FromStr for CheatCode {
+    type Err = CheatError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let mut parts = code.trim().split(':');
+
+        let (Some(address), Some(value)) = (parts.next(), parts.next()) else {
+            return Err(CheatError::Malformed);
+        };
+        let compare = parts.next();
+
+        if parts.next().is_some() {
+            return Err(CheatError::Malformed);
+        }
+
+        let parse_byte = |part: &str| {
+            u8::try_from(parse_hex(part)?).map_err(|_| CheatError::InvalidLength(part.len()))
+        };
+
+        Ok(Self {
+            address: parse_hex(address)?,
+            value: parse_byte(value)?,
+            compare: compare.map(parse_byte).transpose()?,
+        })
+    }
+}
+
+fn parse_hex(digits: &str) -> Result<usize, CheatError> {
+    if digits.is_empty() || digits.len() > (usize::BITS / 4) as usize {
+        return Err(CheatError::InvalidLength(digits.len()));
+    }
+
+    digits.chars().try_fold(0, |accumulator, character| {
+        let digit = character
+            .to_digit(16)
+            .ok_or(CheatError::InvalidCharacter(character))?;
+
+        Ok((accumulator << 4) | digit as usize)
+    })
+}
+
+/// How a cheat is applied to the machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CheatKind {
+    /// Reads of the address return the cheat value instead, without touching what is actually there
+    ///
+    /// This is how cartridge based devices like the Game Genie work
+    ReadOverride,
+    /// The value is written to the address in between every scheduler run
+    ///
+    /// This is how RAM patching devices like the Pro Action Replay work
+    RamWrite,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cheat {
+    pub address_space: AddressSpaceId,
+    pub code: CheatCode,
+    pub kind: CheatKind,
+}
+
+/// Identifier for a cheat added to a machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CheatId(u32);
+
+#[derive(Debug, Default)]
+pub(crate) struct CheatRegistry {
+    cheats: BTreeMap<CheatId, (Cheat, bool)>,
+    next_id: u32,
+}
+
+impl CheatRegistry {
+    pub fn insert(&mut self, cheat: Cheat) -> CheatId {
+        let id = CheatId(self.next_id);
+        self.next_id = self.next_id.checked_add(1).expect("Too many cheats");
+
+        self.cheats.insert(id, (cheat, false));
+
+        id
+    }
+
+    pub fn remove(&mut self, id: CheatId) -> Option<(Cheat, bool)> {
+        self.cheats.remove(&id)
+    }
+
+    /// Mark a cheat as enabled or not, returning the cheat and if its state changed
+    pub fn set_enabled(&mut self, id: CheatId, enabled: bool) -> Option<(Cheat, bool)> {
+        let (cheat, current) = self.cheats.get_mut(&id)?;
+        let changed = *current != enabled;
+        *current = enabled;
+
+        Some((cheat.clone(), changed))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (CheatId, &Cheat, bool)> {
+        self.cheats
+            .iter()
+            .map(|(id, (cheat, enabled))| (*id, cheat, *enabled))
+    }
+}
+
+/// Bring the memory side of a cheat in line with whether it is enabled, refusing cheats the machine can't take
+pub(crate) fn apply(
+    runtime: &RuntimeHandle,
+    id: CheatId,
+    cheat: &Cheat,
+    enabled: bool,
+) -> Result<(), CheatError> {
+    let mut address_space = runtime
+        .address_space(cheat.address_space)
+        .ok_or(CheatError::UnknownAddressSpace(cheat.address_space))?;
+
+    // Codes are easily meant for another system, such as a 24 bit address on a 16 bit bus
+    if cheat.code.address > address_space.width_mask() {
+        return Err(CheatError::AddressOutOfRange {
+            address: cheat.code.address,
+            width_mask: address_space.width_mask(),
+        });
+    }
+
+    if cheat.kind == CheatKind::ReadOverride {
+        if enabled {
+            address_space.insert_read_override(id, cheat.code);
+        } else {
+            address_space.remove_read_override(id);
+        }
+    }
+
+    Ok(())
+}
+
+/// Write every enabled RAM write cheat into the machine
+pub(crate) fn write_ram(runtime: &RuntimeHandle, timestamp: &Period) {
+    let cheats: Vec<_> = runtime
+        .machine()
+        .cheats
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, cheat, enabled)| *enabled && cheat.kind == CheatKind::RamWrite)
+        .map(|(_, cheat, _)| cheat.clone())
+        .collect();
+
+    for cheat in cheats {
+        let Some(mut address_space) = runtime.address_space(cheat.address_space) else {
+            continue;
+        };
+
+        if let Some(compare) = cheat.code.compare {
+            let Ok(current) =
+                address_space.read_le_value::<u8, true>(cheat.code.address, timestamp)
+            else {
+                continue;
+            };
+
+            if current != compare {
+                continue;
+            }
+        }
+
+        let _ = address_space.write_le_value(cheat.code.address, timestamp, cheat.code.value);
+    }
+}
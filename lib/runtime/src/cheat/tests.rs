@@ -0,0 +1,168 @@
+use std::ops::RangeInclusive;
+
+use fluxemu_math::range::ContiguousRange;
+
+use crate::{
+    cheat::{Cheat, CheatCode, CheatError, CheatKind},
+    machine::Machine,
+    memory::{MapTarget, MemoryMapCommand, Permissions},
+    scheduler::Period,
+};
+
+#[test]
+fn game_genie_decoding() {
+    assert_eq!(
+        CheatCode::game_genie("GOSSIP").unwrap(),
+        CheatCode {
+            address: 0xd1dd,
+            value: 0x14,
+            compare: None,
+        }
+    );
+
+    assert_eq!(
+        CheatCode::game_genie("zexpygla").unwrap(),
+        CheatCode {
+            address: 0x94a7,
+            value: 0x02,
+            compare: Some(0x03),
+        }
+    );
+
+    assert_eq!(
+        CheatCode::game_genie("GOSSI"),
+        Err(CheatError::InvalidLength(5))
+    );
+    assert_eq!(
+        CheatCode::game_genie("GOSSIB"),
+        Err(CheatError::InvalidCharacter('B'))
+    );
+}
+
+#[test]
+fn raw_and_pro_action_replay_decoding() {
+    assert_eq!(
+        "7e0dbe:05".parse::<CheatCode>().unwrap(),
+        CheatCode {
+            address: 0x7e0dbe,
+            value: 0x05,
+            compare: None,
+        }
+    );
+    assert_eq!(
+        "0075:09:03".parse::<CheatCode>().unwrap(),
+        CheatCode {
+            address: 0x0075,
+            value: 0x09,
+            compare: Some(0x03),
+        }
+    );
+    assert_eq!(
+        CheatCode::pro_action_replay("7E0DBE05").unwrap(),
+        "7e0dbe:05".parse().unwrap()
+    );
+
+    assert_eq!("0075".parse::<CheatCode>(), Err(CheatError::Malformed));
+    assert_eq!(
+        "0075:09:03:01".parse::<CheatCode>(),
+        Err(CheatError::Malformed)
+    );
+    assert_eq!(
+        "0075:109".parse::<CheatCode>(),
+        Err(CheatError::InvalidLength(3))
+    );
+}
+
+#[test]
+fn cheats_toggle_at_runtime() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let (machine, work_ram_path) = machine.memory("work-ram", 0x100, []);
+    let machine = machine.map_memory(
+        address_space,
+        [MemoryMapCommand::Map {
+            range: RangeInclusive::from_start_and_length(0, 0x100),
+            permissions: Permissions::ALL,
+            target: MapTarget::Memory {
+                path: work_ram_path,
+                subrange: None,
+            },
+        }],
+    );
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+    let mut memory = runtime_guard.address_space(address_space).unwrap();
+
+    memory.write(0x10, &Period::ZERO, &[1, 2, 3, 4]).unwrap();
+
+    let override_id = runtime_guard
+        .add_cheat(Cheat {
+            address_space,
+            code: "11:aa:02".parse().unwrap(),
+            kind: CheatKind::ReadOverride,
+        })
+        .unwrap();
+    let mismatched_id = runtime_guard
+        .add_cheat(Cheat {
+            address_space,
+            code: "12:bb:ff".parse().unwrap(),
+            kind: CheatKind::ReadOverride,
+        })
+        .unwrap();
+
+    let mut buffer = [0; 4];
+    memory
+        .read::<_, false>(0x10, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [1, 0xaa, 3, 4]);
+
+    assert!(runtime_guard.set_cheat_enabled(override_id, false));
+    memory
+        .read::<_, false>(0x10, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [1, 2, 3, 4]);
+
+    assert!(runtime_guard.remove_cheat(mismatched_id).is_some());
+    assert!(!runtime_guard.set_cheat_enabled(mismatched_id, true));
+
+    runtime_guard
+        .add_cheat(Cheat {
+            address_space,
+            code: "13:cc".parse().unwrap(),
+            kind: CheatKind::RamWrite,
+        })
+        .unwrap();
+    runtime_guard.run(Period::ONE);
+
+    memory
+        .read::<_, false>(0x10, &Period::ONE, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [1, 2, 3, 0xcc]);
+}
+
+#[test]
+fn rejects_codes_too_wide_for_the_address_space() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+
+    // A 24 bit Pro Action Replay code on a 16 bit bus
+    let code = CheatCode::pro_action_replay("7e0dbe09").unwrap();
+
+    for kind in [CheatKind::ReadOverride, CheatKind::RamWrite] {
+        assert_eq!(
+            runtime_guard.add_cheat(Cheat {
+                address_space,
+                code,
+                kind,
+            }),
+            Err(CheatError::AddressOutOfRange {
+                address: 0x7e0dbe,
+                width_mask: 0xffff,
+            })
+        );
+    }
+
+    assert!(runtime_guard.cheats().is_empty());
+}
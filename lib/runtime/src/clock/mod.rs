@@ -0,0 +1,186 @@
+//! Clock domains derived from master oscillators by exact ratios
+
+use std::borrow::Cow;
+
+use fixed::{FixedU128, types::extra::U64};
+use num::rational::Ratio;
+
+use crate::scheduler::{Frequency, Period};
+
+#[cfg(test)]
+mod tests;
+
+/// Handle to a clock domain, given to components in place of a raw [`Frequency`]
+///
+/// The exact frequency is kept alongside the rounded one, so spans of many cycles can be converted to a [`Period`]
+/// with a single rounding instead of accumulating the error of [`Clock::period`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    exact: Ratio<u128>,
+    frequency: Frequency,
+    period: Period,
+    /// What rounding [`Clock::period`] down dropped, out of the numerator of the exact frequency
+    period_remainder: u128,
+}
+
+impl Clock {
+    fn new(exact: Ratio<u128>) -> Self {
+        assert_ne!(*exact.numer(), 0, "Clock frequency cannot be zero");
+
+        let mut clock = Self {
+            exact,
+            frequency: ratio_to_fixed(exact),
+            period: Period::ZERO,
+            period_remainder: 0,
+        };
+        (clock.period, clock.period_remainder) = clock.start_of(1);
+
+        clock
+    }
+
+    /// Frequency in hertz, exactly
+    pub fn exact_frequency(&self) -> Ratio<u128> {
+        self.exact
+    }
+
+    pub fn frequency(&self) -> Frequency {
+        self.frequency
+    }
+
+    /// Length of a single cycle
+    pub fn period(&self) -> Period {
+        self.period
+    }
+
+    /// Length of `cycles` cycles, rounded once
+    pub fn cycles(&self, cycles: u64) -> Period {
+        self.start_of(cycles).0
+    }
+
+    /// Number of whole cycles that fit before `timestamp`, which is also the cycle running at it
+    pub fn cycle_at(&self, timestamp: Period) -> u64 {
+        let mut cycle = timestamp
+            .saturating_mul(self.frequency)
+            .saturating_to_num::<u64>();
+
+        // The rounded frequency can put the estimate a cycle off either way
+        while self.cycles(cycle + 1) <= timestamp {
+            cycle += 1;
+        }
+        while cycle > 0 && self.cycles(cycle) > timestamp {
+            cycle -= 1;
+        }
+
+        cycle
+    }
+
+    /// Start of cycle `cycle` rounded down, and the remainder rounding dropped out of the numerator of the exact
+    /// frequency
+    ///
+    /// The remainder is only tracked while the numerator fits in 64 bits, which every real clock does
+    pub(crate) fn start_of(&self, cycle: u64) -> (Period, u128) {
+        let (numer, denom) = (*self.exact.numer(), *self.exact.denom());
+        let seconds = u128::from(cycle)
+            .checked_mul(denom)
+            .expect("Cycle count out of range for this clock");
+        let remainder = seconds % numer;
+
+        match remainder.checked_mul(1 << 64) {
+            Some(shifted) => (
+                Period::from_num(seconds / numer) + Period::from_bits(shifted / numer),
+                shifted % numer,
+            ),
+            None => (ratio_to_fixed(Ratio::new_raw(seconds, numer)), 0),
+        }
+    }
+
+    /// Start of the cycle after the one starting at `start`, as returned by [`Clock::start_of`]
+    ///
+    /// Carries the remainders along, so walking any number of cycles lands exactly where [`Clock::start_of`] would
+    #[inline]
+    pub(crate) fn next_start(&self, (start, remainder): (Period, u128)) -> (Period, u128) {
+        let headroom = *self.exact.numer() - self.period_remainder;
+
+        if remainder >= headroom {
+            (start + self.period + Period::DELTA, remainder - headroom)
+        } else {
+            (start + self.period, remainder + self.period_remainder)
+        }
+    }
+}
+
+/// Round a ratio down to the nearest Q64.64
+fn ratio_to_fixed(ratio: Ratio<u128>) -> FixedU128<U64> {
+    let (numer, denom) = (ratio.numer(), ratio.denom());
+    let integer = FixedU128::<U64>::from_num(numer / denom);
+    let remainder = numer % denom;
+
+    let fraction = match remainder.checked_mul(1 << 64) {
+        Some(shifted) => shifted / denom,
+        // Huge denominators only come from long chains of derived clocks, so drop low bits until the division fits
+        None => {
+            let shift = 64 - denom.leading_zeros();
+
+            ((remainder >> shift) << 64) / (denom >> shift)
+        }
+    };
+
+    integer + FixedU128::from_bits(fraction)
+}
+
+/// Every clock domain declared for a machine
+#[derive(Debug, Clone)]
+pub(crate) struct ClockTree {
+    /// Factor every master oscillator is scaled by
+    overclock: Ratio<u64>,
+    domains: Vec<(Cow<'static, str>, Clock)>,
+}
+
+impl Default for ClockTree {
+    fn default() -> Self {
+        Self {
+            overclock: Ratio::from_integer(1),
+            domains: Vec::default(),
+        }
+    }
+}
+
+impl ClockTree {
+    pub fn set_overclock(&mut self, factor: Ratio<u64>) {
+        assert!(
+            self.domains.is_empty(),
+            "Overclocking has to be set before any clock is declared"
+        );
+
+        self.overclock = factor;
+    }
+
+    pub fn master(&mut self, name: Cow<'static, str>, frequency: Ratio<u64>) -> Clock {
+        let exact = widen(frequency) * widen(self.overclock);
+
+        self.insert(name, Clock::new(exact))
+    }
+
+    pub fn derived(&mut self, name: Cow<'static, str>, parent: Clock, ratio: Ratio<u64>) -> Clock {
+        self.insert(name, Clock::new(parent.exact * widen(ratio)))
+    }
+
+    fn insert(&mut self, name: Cow<'static, str>, clock: Clock) -> Clock {
+        assert!(self.get(&name).is_none(), "Clock {name} already exists");
+
+        self.domains.push((name, clock));
+
+        clock
+    }
+
+    pub fn get(&self, name: &str) -> Option<Clock> {
+        self.domains
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, clock)| *clock)
+    }
+}
+
+fn widen(ratio: Ratio<u64>) -> Ratio<u128> {
+    Ratio::new(u128::from(*ratio.numer()), u128::from(*ratio.denom()))
+}
@@ -0,0 +1,50 @@
+use num::rational::Ratio;
+
+use crate::{machine::Machine, scheduler::Period};
+
+#[test]
+fn derived_clocks_stay_exact() {
+    let machine = Machine::build_test_minimal();
+    let (machine, master) = machine.master_clock("master", Ratio::new(236_250_000, 11));
+    let (machine, cpu) = machine.derived_clock("cpu", master, Ratio::new(1, 12));
+
+    assert_eq!(cpu.exact_frequency(), Ratio::new(236_250_000, 132));
+    assert_eq!(machine.clock("cpu"), Some(cpu));
+    assert_eq!(machine.clock("ppu"), None);
+
+    // A single rounding for the whole span lands exactly on the second, summing rounded periods falls short
+    let cycles_per_second = Ratio::new(236_250_000u64, 132).to_integer();
+    let (_, third) = machine.master_clock("third", Ratio::from_integer(3));
+
+    assert_eq!(third.cycles(3), Period::ONE);
+    assert!(third.period() * 3 < Period::ONE);
+    assert!(cpu.cycles(cycles_per_second) < Period::ONE);
+    assert!(cpu.cycles(cycles_per_second + 1) > Period::ONE);
+}
+
+#[test]
+fn overclock_scales_every_clock() {
+    let machine = Machine::build_test_minimal().overclock(Ratio::new(3, 2));
+    let (machine, master) = machine.master_clock("master", Ratio::from_integer(1_000_000));
+    let (_, divided) = machine.derived_clock("divided", master, Ratio::new(1, 4));
+
+    assert_eq!(master.exact_frequency(), Ratio::from_integer(1_500_000));
+    assert_eq!(divided.exact_frequency(), Ratio::from_integer(375_000));
+    assert_eq!(divided.cycles(375_000), Period::ONE);
+}
+
+#[test]
+fn walking_cycles_lands_on_exact_starts() {
+    let machine = Machine::build_test_minimal();
+    let (machine, master) = machine.master_clock("master", Ratio::new(236_250_000, 11));
+    let (_, cpu) = machine.derived_clock("cpu", master, Ratio::new(1, 12));
+
+    let mut start = cpu.start_of(0);
+    for cycle in 1..=100_000 {
+        start = cpu.next_start(start);
+
+        assert_eq!(start, cpu.start_of(cycle));
+        assert_eq!(cpu.cycle_at(start.0), cycle);
+        assert_eq!(cpu.cycle_at(start.0 - Period::DELTA), cycle - 1);
+    }
+}
@@ -4,11 +4,13 @@ use fluxemu_input::{InputId, InputState};
 use fluxemu_math::range::ContiguousRange;
 use nalgebra::SVector;
 use ringbuffer::AllocRingBuffer;
+use serde::{Serialize, de::DeserializeOwned};
 
 use crate::{
     event::Event,
     memory::{Address, AddressSpaceId, MemoryError, MemoryErrorType},
     scheduler::{Period, SynchronizationContext},
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
 
 /// Component config (factory) related items
@@ -28,8 +30,10 @@ pub trait Component: Send + Sync + Debug + Any {
     /// Use `()` if you don't care about events.
     /// You may still receive dummy events being used as a synchronization barrier however
     ///
+    /// Events must be serializable so pending events can be captured in machine snapshots
+    ///
     /// FIXME: When rust gets default associated types, this should be `()`
-    type Event: Event
+    type Event: Event + Serialize + DeserializeOwned
     where
         Self: Sized;
 
@@ -37,6 +41,8 @@ pub trait Component: Send + Sync + Debug + Any {
     ///
     /// The avoid side effects flag should be respected, state changes should not occur as a result of the operation if it is true
     ///
+    /// On address spaces with a [`DataBus`](crate::memory::DataBus) the request never crosses a bus word
+    ///
     /// The default implementation of this simply denies
     fn memory_read(
         &mut self,
@@ -89,6 +95,45 @@ pub trait Component: Send + Sync + Debug + Any {
     ///
     /// This will as an invariant, only pass in inputs the component registered as supporting
     fn handle_input(&mut self, destination: &str, id: InputId, state: InputState) {}
+
+    /// Capture the internal state of this component for a machine snapshot
+    ///
+    /// Memory regions, component timestamps and pending events are captured by the runtime itself, only state held inside the component needs to be included.
+    ///
+    /// The default implementation captures nothing, which is only correct for stateless components
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        None
+    }
+
+    /// Restore the internal state of this component from what [`Self::snapshot`] produced
+    ///
+    /// This is called from within the runtime after timestamps and memory have been restored, so memory mappings derived from
+    /// component state should be reapplied here
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+
+    /// Respond to the machine being reset
+    ///
+    /// For [`ResetKind::Soft`] this is where reset lines should be pulsed, with the component already synchronized to the
+    /// time of the reset.
+    ///
+    /// For [`ResetKind::Hard`] the runtime has already reinitialized memory, restored the initial memory maps, rewound time
+    /// and requeued the events scheduled while building, so the component only needs to return to its power on state.
+    ///
+    /// The default implementation does nothing
+    fn reset(&mut self, kind: ResetKind) {}
+}
+
+/// How thoroughly a machine is reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResetKind {
+    /// The reset button was pressed, memory contents and time carry on as they were
+    Soft,
+    /// The machine was power cycled
+    ///
+    /// Battery backed memory keeps its contents, as it would on real hardware
+    Hard,
 }
 
 /// A source of audio samples for the runtime
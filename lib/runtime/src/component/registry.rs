@@ -1,17 +1,24 @@
 use std::{
     any::Any,
     cell::UnsafeCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Debug,
     ops::DerefMut,
-    sync::{Arc, Condvar, Mutex},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicU16, Ordering},
+    },
 };
 
 use rustc_hash::FxBuildHasher;
+use scc::Guard;
 
 use crate::{
     RuntimeHandle,
     component::{Component, ComponentId},
+    event::EventCodec,
+    machine::builder::SchedulerParticipation,
+    memory::AddressSpaceId,
     path::ComponentPath,
     scheduler::{Period, SynchronizationContext},
 };
@@ -23,41 +30,54 @@ pub(crate) struct ComponentHandle {
     component: Option<Box<dyn Component>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct GlobalComponentMetadata {
     id: ComponentId,
+    event_codec: EventCodec,
+    scheduler_participation: Option<SchedulerParticipation>,
 }
 
 #[derive(Debug, Default)]
 struct GlobalSyncState {
     global_component_store: HashMap<ComponentId, ComponentHandle, FxBuildHasher>,
     component_condvars: HashMap<ComponentId, Arc<Condvar>, FxBuildHasher>,
+    /// Components that were unplugged, whose IDs are never handed out again
+    removed: HashSet<ComponentId, FxBuildHasher>,
 }
 
 #[derive(Debug, Default)]
 /// The store for components
 pub(crate) struct ComponentRegistryData {
     sync_state: Mutex<GlobalSyncState>,
-    metadata: HashMap<ComponentPath, GlobalComponentMetadata, FxBuildHasher>,
-    next_component_id: u16,
+    /// Read on every interaction by path, so lookups go without locking
+    metadata: scc::HashIndex<ComponentPath, GlobalComponentMetadata, FxBuildHasher>,
+    next_component_id: AtomicU16,
 }
 
 impl ComponentRegistryData {
+    /// Registry data for components that will later be merged into this one with [`Self::adopt`]
+    pub(crate) fn continuation(&self) -> Self {
+        Self {
+            next_component_id: AtomicU16::new(self.next_component_id.load(Ordering::Acquire)),
+            ..Self::default()
+        }
+    }
+
     pub(crate) fn required_local_store_size(&self) -> usize {
-        self.next_component_id as usize
+        self.next_component_id.load(Ordering::Acquire) as usize
     }
 
     pub fn insert_component<C: Component>(
         &mut self,
         path: ComponentPath,
-        synchronize: bool,
+        scheduler_participation: Option<SchedulerParticipation>,
         component: C,
     ) {
         let mut sync_state_guard = self.sync_state.lock().unwrap();
 
-        let id = ComponentId(self.next_component_id);
-        self.next_component_id = self
-            .next_component_id
+        let next_component_id = self.next_component_id.get_mut();
+        let id = ComponentId(*next_component_id);
+        *next_component_id = next_component_id
             .checked_add(1)
             .expect("Too many components");
 
@@ -67,7 +87,7 @@ impl ComponentRegistryData {
                 id,
                 ComponentHandle {
                     current_timestamp: Period::default(),
-                    synchronize,
+                    synchronize: scheduler_participation.is_some(),
                     component: Some(Box::new(component)),
                 },
             )
@@ -76,7 +96,45 @@ impl ComponentRegistryData {
             panic!("Component with the same path already exists")
         }
 
-        self.metadata.insert(path, GlobalComponentMetadata { id });
+        assert!(
+            self.metadata
+                .insert_sync(
+                    path,
+                    GlobalComponentMetadata {
+                        id,
+                        event_codec: EventCodec::new::<C::Event>(),
+                        scheduler_participation,
+                    },
+                )
+                .is_ok(),
+            "Component with the same path already exists"
+        );
+    }
+
+    /// Move every component of a registry created by [`Self::continuation`] into this one
+    ///
+    /// Nothing else may be inserted into this registry between the two calls
+    pub(crate) fn adopt(&self, other: Self) {
+        let mut sync_state_guard = self.sync_state.lock().unwrap();
+
+        let other_sync_state = other.sync_state.into_inner().unwrap();
+        sync_state_guard
+            .global_component_store
+            .extend(other_sync_state.global_component_store);
+
+        other.metadata.iter_sync(|path, metadata| {
+            assert!(
+                self.metadata
+                    .insert_sync(path.clone(), metadata.clone())
+                    .is_ok(),
+                "Component with the same path already exists"
+            );
+
+            true
+        });
+
+        self.next_component_id
+            .store(other.next_component_id.into_inner(), Ordering::Release);
     }
 }
 
@@ -94,6 +152,40 @@ impl<'a> ComponentRegistry<'a> {
         Self { runtime }
     }
 
+    pub(crate) fn runtime(&self) -> &'a RuntimeHandle {
+        self.runtime
+    }
+
+    /// Mark this thread as a worker of the parallel scheduler, which leaves events to the thread running the machine
+    ///
+    /// `address_spaces` are the ones the components it drives were isolated to
+    pub(crate) fn set_isolated(&self, address_spaces: Vec<AddressSpaceId>) {
+        // SAFETY: No active borrows
+        let local_data = unsafe { &mut *self.local_data().get() };
+
+        local_data.isolated = true;
+        local_data.declared_address_spaces = address_spaces;
+    }
+
+    /// If this thread is a worker of the parallel scheduler
+    #[inline]
+    pub(crate) fn is_isolated(&self) -> bool {
+        // SAFETY: No active borrows
+        unsafe { &*self.local_data().get() }.isolated
+    }
+
+    /// Check that a worker of the parallel scheduler keeps to the address spaces its components were isolated to
+    #[cfg(debug_assertions)]
+    pub(crate) fn assert_declared(&self, address_space: AddressSpaceId) {
+        // SAFETY: No active borrows
+        let local_data = unsafe { &*self.local_data().get() };
+
+        assert!(
+            !local_data.isolated || local_data.declared_address_spaces.contains(&address_space),
+            "Isolated component touched {address_space:?}, which it did not declare"
+        );
+    }
+
     fn data(&self) -> &ComponentRegistryData {
         &self.runtime.machine().component_registry_data
     }
@@ -134,6 +226,11 @@ impl<'a> ComponentRegistry<'a> {
         target_timestamp: &Period,
         callback: impl FnOnce(&mut dyn Component) -> T,
     ) -> T {
+        self.runtime
+            .machine()
+            .profiler
+            .record_interaction(&self.runtime.local_data().profile, id);
+
         // Move the component handle to our thread and check if it needs synchronization
         let needs_sync = {
             // SAFETY: No active borrows
@@ -152,6 +249,19 @@ impl<'a> ComponentRegistry<'a> {
             self.synchronize_component(id, target_timestamp);
         }
 
+        // SAFETY: The handle was moved into our local store above
+        unsafe { self.borrow_local_component(id, callback) }
+    }
+
+    /// # Safety
+    ///
+    /// The component handle for the ID must be present in the local store
+    #[inline]
+    unsafe fn borrow_local_component<T>(
+        &self,
+        id: ComponentId,
+        callback: impl FnOnce(&mut dyn Component) -> T,
+    ) -> T {
         // Extract the component
         let mut component = {
             // SAFETY: No active borrows
@@ -218,7 +328,7 @@ impl<'a> ComponentRegistry<'a> {
                     None
                 }
             }
-            ComponentIdentifier::Path(path) => Some(self.data().metadata.get(path)?.id),
+            ComponentIdentifier::Path(path) => self.id_for_path(path),
         }
     }
 
@@ -251,18 +361,50 @@ impl<'a> ComponentRegistry<'a> {
             return;
         }
 
+        let isolated = self.is_isolated();
+
+        let profiler = &self.runtime.machine().profiler;
+
         loop {
             let mut last_attempted_allocation = Period::ZERO;
+            let started_timestamp = current_timestamp;
 
             let context = SynchronizationContext {
                 runtime: self.runtime,
                 current_timestamp: &mut current_timestamp,
                 target_timestamp: *target_timestamp,
                 last_attempted_allocation: &mut last_attempted_allocation,
+                isolated,
             };
 
+            let (previous_synchronizing, depth) = {
+                // SAFETY: The borrow ends before the component runs, which may reenter the registry
+                let store = unsafe { &mut *self.local_data().get() };
+                store.catch_up_depth += 1;
+
+                (store.synchronizing.replace(id), store.catch_up_depth)
+            };
+
+            let started = profiler.start();
             component.synchronize(context);
 
+            if let Some(started) = started {
+                profiler.record_synchronize(
+                    &self.runtime.local_data().profile,
+                    id,
+                    started,
+                    current_timestamp - started_timestamp,
+                    depth,
+                );
+            }
+
+            {
+                // SAFETY: The component has returned, so any reentrant borrows of the store have ended
+                let store = unsafe { &mut *self.local_data().get() };
+                store.synchronizing = previous_synchronizing;
+                store.catch_up_depth -= 1;
+            }
+
             let delta = target_timestamp - current_timestamp;
             let needs_work = component.needs_work(&current_timestamp, &delta);
 
@@ -287,11 +429,19 @@ impl<'a> ComponentRegistry<'a> {
                 return;
             }
 
-            self.runtime
-                .machine()
-                .scheduler
-                .event_manager
-                .consume(self, hazard_timestamp);
+            let scheduler = &self.runtime.machine().scheduler;
+
+            // The machine is paused before this component could do another step, so leave it behind the target
+            if scheduler
+                .pause_timestamp()
+                .is_some_and(|pause_timestamp| hazard_timestamp > pause_timestamp)
+            {
+                return;
+            }
+
+            if !isolated {
+                scheduler.event_manager.consume(self, hazard_timestamp);
+            }
 
             {
                 let store =
@@ -329,6 +479,11 @@ impl<'a> ComponentRegistry<'a> {
 
         loop {
             let Some(handle) = sync_state_guard.global_component_store.remove(&id) else {
+                assert!(
+                    !sync_state_guard.removed.contains(&id),
+                    "Component was unplugged"
+                );
+
                 // Give components back so others can potentially access them
                 self.release_all_inner(&mut sync_state_guard, local_data);
 
@@ -392,7 +547,118 @@ impl<'a> ComponentRegistry<'a> {
     }
 
     pub(crate) fn id_for_path(&self, path: &ComponentPath) -> Option<ComponentId> {
-        Some(self.data().metadata.get(path)?.id)
+        self.data()
+            .metadata
+            .peek_with(path, |_, metadata| metadata.id)
+    }
+
+    /// Path of the component currently synchronizing on this thread, if any
+    pub(crate) fn synchronizing_component(&self) -> Option<ComponentPath> {
+        // SAFETY: No active borrows
+        let id = unsafe { &*self.local_data().get() }.synchronizing?;
+
+        self.data()
+            .metadata
+            .iter(&Guard::new())
+            .find(|(_, metadata)| metadata.id == id)
+            .map(|(path, _)| path.clone())
+    }
+
+    /// Paths of every component in the registry, in no particular order
+    pub(crate) fn paths(&self) -> Vec<ComponentPath> {
+        self.data()
+            .metadata
+            .iter(&Guard::new())
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    pub(crate) fn event_codec(&self, path: &ComponentPath) -> Option<EventCodec> {
+        self.data()
+            .metadata
+            .peek_with(path, |_, metadata| metadata.event_codec)
+    }
+
+    pub(crate) fn scheduler_participation(
+        &self,
+        path: &ComponentPath,
+    ) -> Option<SchedulerParticipation> {
+        self.data()
+            .metadata
+            .peek_with(path, |_, metadata| metadata.scheduler_participation)?
+    }
+
+    /// Take a component out of the machine for good, waiting for any other thread using it to let go
+    ///
+    /// Returns if the component existed
+    pub(crate) fn remove(&self, path: &ComponentPath) -> bool {
+        let Some(id) = self.id_for_path(path) else {
+            return false;
+        };
+
+        {
+            // SAFETY: Unplugging happens through the runtime guard with the scheduler locked, so nothing on this
+            // thread is inside a component interaction holding a reference into the local store
+            //
+            // The borrow ends with this block
+            let local_data = unsafe { &mut *self.local_data().get() };
+            let handle = self.fetch_or_acquire_component(id, local_data);
+
+            assert!(
+                handle.component.is_some(),
+                "Component cannot be unplugged while it is in use"
+            );
+
+            *local_data.get_slot(id) = None;
+        }
+
+        self.data().metadata.remove_sync(path);
+
+        let mut sync_state_guard = self.data().sync_state.lock().unwrap();
+        sync_state_guard.removed.insert(id);
+
+        // Wake anyone waiting on it so they notice it is gone
+        if let Some(condvar) = sync_state_guard.component_condvars.remove(&id) {
+            condvar.notify_all();
+        }
+
+        true
+    }
+
+    /// Interact with a component without bringing it up to any timestamp
+    ///
+    /// This is for whole machine operations like snapshots, where components must be observed exactly as they are
+    pub(crate) fn interact_unsynchronized<'b, T>(
+        &'b self,
+        id: impl Into<ComponentIdentifier<'b>>,
+        callback: impl FnOnce(&mut dyn Component) -> T,
+    ) -> Option<T> {
+        let id = self.convert_identifier(id)?;
+
+        {
+            // SAFETY: No active borrows
+            let local_data = unsafe { &mut *self.local_data().get() };
+            self.fetch_or_acquire_component(id, local_data);
+        }
+
+        // SAFETY: The handle was moved into our local store above
+        Some(unsafe { self.borrow_local_component(id, callback) })
+    }
+
+    /// Forcefully set the timestamp of a component, without doing any synchronization
+    pub(crate) fn set_timestamp<'b>(
+        &'b self,
+        id: impl Into<ComponentIdentifier<'b>>,
+        timestamp: Period,
+    ) -> Option<()> {
+        let id = self.convert_identifier(id)?;
+
+        // SAFETY: No active borrows
+        let local_data = unsafe { &mut *self.local_data().get() };
+        self.fetch_or_acquire_component(id, local_data)
+            .current_timestamp = timestamp;
+
+        Some(())
     }
 
     pub(crate) fn get_timestamp<'b>(
@@ -434,28 +700,52 @@ impl<'a> From<ComponentId> for ComponentIdentifier<'a> {
 }
 
 #[derive(Debug)]
-pub(crate) struct LocalComponentRegistryData(Vec<Option<ComponentHandle>>);
+pub(crate) struct LocalComponentRegistryData {
+    store: Vec<Option<ComponentHandle>>,
+    /// Innermost component running its synchronization routine on this thread
+    synchronizing: Option<ComponentId>,
+    /// If this thread is a worker of the parallel scheduler, driving isolated components
+    isolated: bool,
+    /// Address spaces the isolated components this thread drives were declared to, checked in debug builds
+    declared_address_spaces: Vec<AddressSpaceId>,
+    /// How many synchronizations are nested on this thread
+    catch_up_depth: u32,
+}
 
 impl LocalComponentRegistryData {
     pub fn new(registry_data: &ComponentRegistryData) -> Self {
-        LocalComponentRegistryData(Vec::from_iter(
-            std::iter::repeat_with(|| None).take(registry_data.required_local_store_size()),
-        ))
+        LocalComponentRegistryData {
+            store: Vec::from_iter(
+                std::iter::repeat_with(|| None).take(registry_data.required_local_store_size()),
+            ),
+            synchronizing: None,
+            isolated: false,
+            declared_address_spaces: Vec::new(),
+            catch_up_depth: 0,
+        }
     }
 
     #[inline]
     fn get_slot(&mut self, id: ComponentId) -> &mut Option<ComponentHandle> {
-        debug_assert!(id.0 < self.0.len() as u16);
+        let index = id.0 as usize;
+
+        if index >= self.store.len() {
+            self.grow(index + 1);
+        }
 
-        // SAFETY:
-        //  All ids are validated against required_local_store_size in convert_identifier before this function
-        //  Component store has a static size
-        unsafe { self.0.get_unchecked_mut(id.0 as usize) }
+        // SAFETY: The store was grown to fit the index above
+        unsafe { self.store.get_unchecked_mut(index) }
+    }
+
+    /// Make room for components plugged in after this thread entered the runtime
+    #[cold]
+    fn grow(&mut self, size: usize) {
+        self.store.resize_with(size, || None);
     }
 
     #[inline]
     fn iter_mut(&mut self) -> impl Iterator<Item = (ComponentId, &mut Option<ComponentHandle>)> {
-        self.0
+        self.store
             .iter_mut()
             .enumerate()
             .map(|(id, component_handle)| (ComponentId(id as u16), component_handle))
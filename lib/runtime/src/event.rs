@@ -1,168 +0,0 @@
-use std::{
-    any::Any,
-    cmp::Reverse,
-    collections::BinaryHeap,
-    fmt::Debug,
-    sync::{
-        Mutex,
-        atomic::{AtomicU32, Ordering},
-    },
-};
-
-use dyn_clone::DynClone;
-use serde::{Deserialize, Serialize};
-
-use crate::{
-    ComponentPath,
-    component::{Component, ComponentRegistry},
-    scheduler::{Frequency, Period},
-};
-
-/// Supertrait aggregate type for events
-///
-/// It is implemented for every type that implements the supertraits
-pub trait Event: Any + DynClone + Send + Debug + 'static {}
-impl<T: Any + DynClone + Send + Debug + 'static> Event for T {}
-
-#[derive(Debug, Default)]
-pub(crate) struct EventManager {
-    heap: Mutex<BinaryHeap<QueuedEvent>>,
-    event_preemption_signal: EventPreemptionSignal,
-}
-
-impl EventManager {
-    #[inline]
-    pub fn schedule(
-        &self,
-        time: Period,
-        path: ComponentPath,
-        mode: EventMode,
-        data: Box<dyn Event>,
-    ) {
-        self.heap.lock().unwrap().push(QueuedEvent {
-            path,
-            data,
-            mode,
-            time: Reverse(time),
-        });
-
-        self.event_preemption_signal.event_scheduled();
-    }
-
-    #[inline]
-    pub fn consume(&self, registry: &ComponentRegistry<'_>, upto: Period) {
-        let mut heap_guard = self.heap.lock().unwrap();
-
-        while let Some(event) = heap_guard.peek() {
-            if upto < event.time.0 {
-                // The next event doesn't overlap with the specified period
-                break;
-            }
-            let event = heap_guard.pop().unwrap();
-
-            match event.mode {
-                EventMode::Once => {}
-                EventMode::Repeating { frequency } => {
-                    let time = event.time.0 + frequency.recip();
-
-                    heap_guard.push(QueuedEvent {
-                        path: event.path.clone(),
-                        mode: event.mode,
-                        time: Reverse(time),
-                        data: dyn_clone::clone_box(event.data.as_ref()),
-                    });
-
-                    self.event_preemption_signal.event_scheduled();
-                }
-            }
-
-            // Drop to prevent deadlocks due to reentrancy
-            drop(heap_guard);
-
-            registry.interact_dyn(&event.path, &event.time.0, |component| {
-                component.handle_event(event.data);
-            });
-
-            // Relock for the loop
-            heap_guard = self.heap.lock().unwrap();
-        }
-    }
-
-    /// Get the timestamp when the next event should occur
-    #[inline]
-    pub fn next_event(&self) -> Option<Period> {
-        let queue_guard = self.heap.lock().unwrap();
-
-        if let Some(next_event) = queue_guard.peek() {
-            return Some(next_event.time.0);
-        }
-
-        None
-    }
-
-    pub fn preemption_signal(&self) -> &EventPreemptionSignal {
-        &self.event_preemption_signal
-    }
-}
-
-#[derive(Debug, Default)]
-pub(crate) struct EventPreemptionSignal(AtomicU32);
-
-impl EventPreemptionSignal {
-    fn event_scheduled(&self) {
-        self.0.fetch_add(1, Ordering::Release);
-    }
-
-    #[inline]
-    pub(crate) fn generation(&self) -> u32 {
-        self.0.load(Ordering::Acquire)
-    }
-}
-
-/// Mode of an event
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub enum EventMode {
-    /// This event is a oneshot
-    Once,
-    /// This event will be rescheduled every execution
-    Repeating {
-        /// The frequency at which this event will be rescheduled
-        frequency: Frequency,
-    },
-}
-
-/// Downcast the event down to the type that it should be
-#[inline]
-pub fn downcast_event<C: Component>(event: Box<dyn Event>) -> C::Event {
-    *(event as Box<dyn Any>)
-        .downcast()
-        .expect("invalid type sent as event")
-}
-
-#[derive(Debug)]
-struct QueuedEvent {
-    path: ComponentPath,
-    time: Reverse<Period>,
-    mode: EventMode,
-    data: Box<dyn Event>,
-}
-
-impl PartialEq for QueuedEvent {
-    fn eq(&self, other: &Self) -> bool {
-        self.time.eq(&other.time)
-    }
-}
-
-impl Eq for QueuedEvent {}
-
-impl PartialOrd for QueuedEvent {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for QueuedEvent {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.time.cmp(&other.time)
-    }
-}
@@ -0,0 +1,415 @@
+use std::{
+    any::Any,
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt::Debug,
+    sync::{
+        Mutex,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use dyn_clone::DynClone;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    ComponentPath, RuntimeHandle,
+    component::{Component, ComponentRegistry},
+    scheduler::{Frequency, Period},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Supertrait aggregate type for events
+///
+/// It is implemented for every type that implements the supertraits
+pub trait Event: Any + DynClone + Send + Debug + 'static {}
+impl<T: Any + DynClone + Send + Debug + 'static> Event for T {}
+
+/// Handle to a scheduled event, which can be used to cancel or move it while it is pending
+///
+/// Repeating events keep their handle across every repetition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct EventHandle(u64);
+
+impl EventHandle {
+    /// Remove the event from the queue, returning if it was still pending
+    pub fn cancel(self, runtime: &RuntimeHandle) -> bool {
+        runtime.machine().scheduler.event_manager.cancel(self)
+    }
+
+    /// Move the event to fire at `time` instead, returning if it was still pending
+    ///
+    /// Repeating events continue repeating from the new time
+    pub fn reschedule(self, runtime: &RuntimeHandle, time: Period) -> bool {
+        runtime
+            .machine()
+            .scheduler
+            .event_manager
+            .reschedule(self, time)
+    }
+}
+
+/// A pending event as seen from outside the queue
+pub(crate) type PendingEvent = (
+    EventHandle,
+    Period,
+    ComponentPath,
+    EventMode,
+    Box<dyn Event>,
+);
+
+#[derive(Debug, Default)]
+struct EventQueue {
+    heap: BinaryHeap<QueuedEvent>,
+    /// Incremented on every push, so events at the same timestamp fire in the order they were queued
+    next_sequence: u64,
+}
+
+impl EventQueue {
+    fn push(
+        &mut self,
+        handle: Option<EventHandle>,
+        time: Period,
+        path: ComponentPath,
+        mode: EventMode,
+        data: Box<dyn Event>,
+    ) -> EventHandle {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        let handle = handle.unwrap_or(EventHandle(sequence));
+
+        self.heap.push(QueuedEvent {
+            handle,
+            sequence,
+            path,
+            data,
+            mode,
+            time: Reverse(time),
+        });
+
+        handle
+    }
+
+    /// Pull an event out from anywhere in the queue
+    fn remove(&mut self, handle: EventHandle) -> Option<QueuedEvent> {
+        let mut events = std::mem::take(&mut self.heap).into_vec();
+        let event = events
+            .iter()
+            .position(|event| event.handle == handle)
+            .map(|index| events.swap_remove(index));
+        self.heap = BinaryHeap::from(events);
+
+        event
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct EventManager {
+    queue: Mutex<EventQueue>,
+    /// Events that were pending once the machine finished building
+    initial: Mutex<Vec<PendingEvent>>,
+    event_preemption_signal: EventPreemptionSignal,
+}
+
+impl EventManager {
+    #[inline]
+    pub fn schedule(
+        &self,
+        time: Period,
+        path: ComponentPath,
+        mode: EventMode,
+        data: Box<dyn Event>,
+    ) -> EventHandle {
+        let handle = self
+            .queue
+            .lock()
+            .unwrap()
+            .push(None, time, path, mode, data);
+
+        self.event_preemption_signal.event_scheduled();
+
+        handle
+    }
+
+    pub fn cancel(&self, handle: EventHandle) -> bool {
+        self.queue.lock().unwrap().remove(handle).is_some()
+    }
+
+    pub fn reschedule(&self, handle: EventHandle, time: Period) -> bool {
+        let mut queue_guard = self.queue.lock().unwrap();
+
+        let Some(event) = queue_guard.remove(handle) else {
+            return false;
+        };
+
+        queue_guard.push(Some(handle), time, event.path, event.mode, event.data);
+        drop(queue_guard);
+
+        self.event_preemption_signal.event_scheduled();
+
+        true
+    }
+
+    #[inline]
+    pub fn consume(&self, registry: &ComponentRegistry<'_>, upto: Period) {
+        let mut queue_guard = self.queue.lock().unwrap();
+
+        while let Some(event) = queue_guard.heap.peek() {
+            if upto < event.time.0 {
+                // The next event doesn't overlap with the specified period
+                break;
+            }
+            let event = queue_guard.heap.pop().unwrap();
+
+            match event.mode {
+                EventMode::Once => {}
+                EventMode::Repeating { frequency } => {
+                    queue_guard.push(
+                        Some(event.handle),
+                        event.time.0 + frequency.recip(),
+                        event.path.clone(),
+                        event.mode,
+                        dyn_clone::clone_box(event.data.as_ref()),
+                    );
+
+                    self.event_preemption_signal.event_scheduled();
+                }
+            }
+
+            // Drop to prevent deadlocks due to reentrancy
+            drop(queue_guard);
+
+            let profiler = &registry.runtime().machine().profiler;
+
+            registry.interact_dyn(&event.path, &event.time.0, |component| {
+                let started = profiler.start();
+                component.handle_event(event.data);
+
+                if let Some(started) = started
+                    && let Some(id) = registry.id_for_path(&event.path)
+                {
+                    profiler.record_event(&registry.runtime().local_data().profile, id, started);
+                }
+            });
+
+            // Relock for the loop
+            queue_guard = self.queue.lock().unwrap();
+        }
+    }
+
+    /// Get the timestamp when the next event should occur
+    #[inline]
+    pub fn next_event(&self) -> Option<Period> {
+        let queue_guard = self.queue.lock().unwrap();
+
+        if let Some(next_event) = queue_guard.heap.peek() {
+            return Some(next_event.time.0);
+        }
+
+        None
+    }
+
+    pub fn preemption_signal(&self) -> &EventPreemptionSignal {
+        &self.event_preemption_signal
+    }
+
+    /// Clone out every pending event, in the order they would fire
+    pub fn pending(&self) -> Vec<PendingEvent> {
+        let queue_guard = self.queue.lock().unwrap();
+
+        let mut events: Vec<_> = queue_guard.heap.iter().collect();
+        events.sort_by(|a, b| b.cmp(a));
+
+        events
+            .into_iter()
+            .map(|event| {
+                (
+                    event.handle,
+                    event.time.0,
+                    event.path.clone(),
+                    event.mode,
+                    dyn_clone::clone_box(event.data.as_ref()),
+                )
+            })
+            .collect()
+    }
+
+    /// Remember the events pending right now as the ones the machine starts with
+    pub fn mark_initial(&self) {
+        *self.initial.lock().unwrap() = self.pending();
+    }
+
+    /// Throw away every pending event and replace them with the ones the machine started with
+    pub fn restore_initial(&self) {
+        let initial: Vec<_> = self
+            .initial
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(handle, time, path, mode, data)| {
+                (
+                    *handle,
+                    *time,
+                    path.clone(),
+                    *mode,
+                    dyn_clone::clone_box(data.as_ref()),
+                )
+            })
+            .collect();
+
+        self.replace(initial);
+    }
+
+    /// Queue the build time events of components plugged in at `timestamp`
+    ///
+    /// They are also counted as initial events, as if the components had been there from `start_time`
+    pub fn adopt(
+        &self,
+        events: impl IntoIterator<Item = PendingEvent>,
+        timestamp: Period,
+        start_time: Period,
+    ) {
+        let mut queue_guard = self.queue.lock().unwrap();
+        let mut initial_guard = self.initial.lock().unwrap();
+
+        for (_, time, path, mode, data) in events {
+            let initial_data = dyn_clone::clone_box(data.as_ref());
+            let handle = queue_guard.push(None, timestamp + time, path.clone(), mode, data);
+
+            initial_guard.push((handle, start_time + time, path, mode, initial_data));
+        }
+
+        self.event_preemption_signal.event_scheduled();
+    }
+
+    /// Drop every event, pending or initial, whose target matches
+    pub fn remove_targeting(&self, mut predicate: impl FnMut(&ComponentPath) -> bool) {
+        self.queue
+            .lock()
+            .unwrap()
+            .heap
+            .retain(|event| !predicate(&event.path));
+
+        self.initial
+            .lock()
+            .unwrap()
+            .retain(|(_, _, path, _, _)| !predicate(path));
+    }
+
+    /// Throw away every pending event and replace them with the given ones
+    ///
+    /// Events at the same timestamp will fire in the order they are given in
+    pub fn replace(&self, events: impl IntoIterator<Item = PendingEvent>) {
+        let mut queue_guard = self.queue.lock().unwrap();
+
+        queue_guard.heap.clear();
+
+        for (handle, time, path, mode, data) in events {
+            // Keep new handles from colliding with restored ones
+            queue_guard.next_sequence = queue_guard.next_sequence.max(handle.0 + 1);
+            queue_guard.push(Some(handle), time, path, mode, data);
+        }
+
+        self.event_preemption_signal.event_scheduled();
+    }
+}
+
+type EventEncoder = fn(&dyn Event) -> Result<Vec<u8>, rmp_serde::encode::Error>;
+type EventDecoder = fn(&[u8]) -> Result<Box<dyn Event>, rmp_serde::decode::Error>;
+
+/// Type erased serialization routines for the event type of a specific component
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EventCodec {
+    pub encode: EventEncoder,
+    pub decode: EventDecoder,
+}
+
+impl EventCodec {
+    pub fn new<E: Event + Serialize + DeserializeOwned>() -> Self {
+        Self {
+            encode: |event| {
+                let event = (event as &dyn Any)
+                    .downcast_ref::<E>()
+                    .expect("invalid type sent as event");
+
+                rmp_serde::to_vec_named(event)
+            },
+            decode: |bytes| Ok(Box::new(rmp_serde::from_slice::<E>(bytes)?)),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct EventPreemptionSignal(AtomicU32);
+
+impl EventPreemptionSignal {
+    fn event_scheduled(&self) {
+        self.preempt();
+    }
+
+    /// Force every running quanta allocator to recalculate its budget
+    pub(crate) fn preempt(&self) {
+        self.0.fetch_add(1, Ordering::Release);
+    }
+
+    #[inline]
+    pub(crate) fn generation(&self) -> u32 {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Mode of an event
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum EventMode {
+    /// This event is a oneshot
+    Once,
+    /// This event will be rescheduled every execution
+    Repeating {
+        /// The frequency at which this event will be rescheduled
+        frequency: Frequency,
+    },
+}
+
+/// Downcast the event down to the type that it should be
+#[inline]
+pub fn downcast_event<C: Component>(event: Box<dyn Event>) -> C::Event {
+    *(event as Box<dyn Any>)
+        .downcast()
+        .expect("invalid type sent as event")
+}
+
+#[derive(Debug)]
+struct QueuedEvent {
+    handle: EventHandle,
+    sequence: u64,
+    path: ComponentPath,
+    time: Reverse<Period>,
+    mode: EventMode,
+    data: Box<dyn Event>,
+}
+
+impl PartialEq for QueuedEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.time.eq(&other.time) && self.sequence.eq(&other.sequence)
+    }
+}
+
+impl Eq for QueuedEvent {}
+
+impl PartialOrd for QueuedEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedEvent {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // Lower sequence numbers are queued earlier and come out of the max heap first
+        self.time
+            .cmp(&other.time)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{
+    ComponentPath,
+    event::{EventManager, EventMode},
+    scheduler::Period,
+};
+
+fn pending_order(event_manager: &EventManager) -> Vec<u8> {
+    event_manager
+        .pending()
+        .into_iter()
+        .map(|(_, _, _, _, data)| *(data as Box<dyn std::any::Any>).downcast::<u8>().unwrap())
+        .collect()
+}
+
+#[test]
+fn same_timestamp_events_fire_in_queue_order() {
+    let event_manager = EventManager::default();
+    let path = ComponentPath::new("test").unwrap();
+
+    for index in 0..8u8 {
+        event_manager.schedule(Period::ONE, path.clone(), EventMode::Once, Box::new(index));
+    }
+    event_manager.schedule(Period::ZERO, path.clone(), EventMode::Once, Box::new(8u8));
+
+    assert_eq!(pending_order(&event_manager), [8, 0, 1, 2, 3, 4, 5, 6, 7]);
+}
+
+#[test]
+fn cancel_and_reschedule() {
+    let event_manager = EventManager::default();
+    let path = ComponentPath::new("test").unwrap();
+
+    let first = event_manager.schedule(Period::ONE, path.clone(), EventMode::Once, Box::new(0u8));
+    let second = event_manager.schedule(Period::ONE, path.clone(), EventMode::Once, Box::new(1u8));
+    event_manager.schedule(Period::ONE, path.clone(), EventMode::Once, Box::new(2u8));
+
+    assert!(event_manager.cancel(second));
+    assert!(!event_manager.cancel(second));
+    assert_eq!(pending_order(&event_manager), [0, 2]);
+
+    // Moving an event to the same timestamp puts it at the back of the line
+    assert!(event_manager.reschedule(first, Period::ONE));
+    assert_eq!(pending_order(&event_manager), [2, 0]);
+
+    assert!(event_manager.reschedule(first, Period::ZERO));
+    assert_eq!(event_manager.next_event(), Some(Period::ZERO));
+    assert!(!event_manager.reschedule(second, Period::ZERO));
+}
@@ -3,7 +3,7 @@ use std::{rc::Rc, sync::Arc};
 use crate::{
     ComponentPath,
     component::{Component, ComponentRegistry},
-    event::EventMode,
+    event::{EventHandle, EventMode},
     machine::{CURRENT_THREAD_RUNTIME_HANDLE, Machine, ThreadLocalData},
     memory::{AddressSpace, AddressSpaceId},
     scheduler::Period,
@@ -39,6 +39,12 @@ impl RuntimeHandle {
         &self.machine
     }
 
+    /// The machine, for handing to other threads
+    #[inline]
+    pub(crate) fn shared_machine(&self) -> &Arc<Machine> {
+        &self.machine
+    }
+
     #[inline]
     pub(crate) fn local_data(&self) -> &ThreadLocalData {
         &self.local_data
@@ -50,6 +56,9 @@ impl RuntimeHandle {
     /// so handles should be as long lived as possible
     #[inline]
     pub fn address_space(&self, address_space_id: AddressSpaceId) -> Option<AddressSpace<'_>> {
+        #[cfg(debug_assertions)]
+        self.component_registry().assert_declared(address_space_id);
+
         self.machine.address_spaces.get(&address_space_id).map(
             #[inline]
             |address_space_data| AddressSpace::new(self, address_space_data),
@@ -64,20 +73,26 @@ impl RuntimeHandle {
 
     /// Schedule an event by the [Component]s event type
     ///
-    /// This event will fire at the specified timestamp, or if the timestamp is too early (ie: the period for it had already been allocated) directly after the timestamp
+    /// This event will fire at the specified timestamp, or if the timestamp is too early (ie: the period for it had already been allocated) directly after the timestamp.
+    /// Events scheduled for the same timestamp fire in the order they were scheduled in
     pub fn schedule_event<C: Component>(
         &self,
         target_path: &ComponentPath,
         requeue_mode: EventMode,
         time: Period,
         data: C::Event,
-    ) {
+    ) -> EventHandle {
+        debug_assert!(
+            !self.component_registry().is_isolated(),
+            "Isolated components cannot schedule events"
+        );
+
         self.machine.scheduler.event_manager.schedule(
             time,
             target_path.clone(),
             requeue_mode,
             Box::new(data),
-        );
+        )
     }
 
     /// Get the current timestamp of your component
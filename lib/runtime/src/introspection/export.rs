@@ -0,0 +1,166 @@
+use std::fmt::{Display, Write};
+
+use serde::Serializer;
+
+use crate::{
+    event::EventMode,
+    introspection::{MachineInfo, MemoryMapTarget},
+    machine::builder::SchedulerParticipation,
+    memory::Permissions,
+    scheduler::Period,
+};
+
+impl MachineInfo {
+    /// Render as a pretty printed JSON document
+    ///
+    /// Timestamps are given in seconds
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Render as a Graphviz digraph, with address spaces pointing at what they map
+    pub fn to_graphviz(&self) -> String {
+        let mut output = String::from("digraph machine {\n    rankdir=LR;\n");
+
+        for component in &self.components {
+            let participation = component
+                .scheduler_participation
+                .map(participation_name)
+                .unwrap_or("passive");
+
+            let path = escape(&component.path);
+
+            writeln!(
+                output,
+                "    \"{path}\" [shape=box, label=\"{path}\\n{}\\n{}s\"];",
+                participation,
+                component.timestamp.to_num::<f64>()
+            )
+            .unwrap();
+        }
+
+        for region in &self.memory_regions {
+            let path = escape(&region.path);
+
+            writeln!(
+                output,
+                "    \"{path}\" [shape=cylinder, label=\"{path}\\n{:#x} bytes{}\"];",
+                region.size,
+                if region.sram { "\\nbattery backed" } else { "" }
+            )
+            .unwrap();
+        }
+
+        for address_space in &self.address_spaces {
+            let node = format!("address_space_{}", address_space.id.0);
+
+            writeln!(
+                output,
+                "    \"{node}\" [shape=ellipse, label=\"address space {}\\n{} bit\"];",
+                address_space.id.0, address_space.width
+            )
+            .unwrap();
+
+            let immutable_node = format!("{node}_immutable_memory");
+
+            if address_space
+                .memory_map
+                .iter()
+                .any(|entry| entry.target == MemoryMapTarget::ImmutableMemory)
+            {
+                writeln!(
+                    output,
+                    "    \"{immutable_node}\" [shape=note, label=\"immutable memory\"];"
+                )
+                .unwrap();
+            }
+
+            for entry in &address_space.memory_map {
+                let range = format!(
+                    "{:#x}..={:#x} {}",
+                    entry.range.start(),
+                    entry.range.end(),
+                    permissions_name(entry.permissions)
+                );
+
+                let (destination, label) = match &entry.target {
+                    MemoryMapTarget::Component(path) => (escape(path), range),
+                    MemoryMapTarget::Memory { path, offset } => {
+                        (escape(path), format!("{range}\\n@ {offset:#x}"))
+                    }
+                    MemoryMapTarget::ImmutableMemory => (immutable_node.clone(), range),
+                    MemoryMapTarget::Mirror { destination } => {
+                        (node.clone(), format!("{range}\\nmirrors {destination:#x}"))
+                    }
+                };
+
+                writeln!(
+                    output,
+                    "    \"{node}\" -> \"{destination}\" [label=\"{label}\"];"
+                )
+                .unwrap();
+            }
+        }
+
+        if !self.events.is_empty() {
+            output.push_str("    \"scheduler\" [shape=diamond];\n");
+        }
+
+        for event in &self.events {
+            writeln!(
+                output,
+                "    \"scheduler\" -> \"{}\" [style=dashed, label=\"{}s\"];",
+                escape(&event.target),
+                event.time.to_num::<f64>()
+            )
+            .unwrap();
+        }
+
+        output.push_str("}\n");
+
+        output
+    }
+}
+
+/// Quote a name for use inside a double quoted Graphviz string
+fn escape(name: impl Display) -> String {
+    name.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub(super) fn seconds<S: Serializer>(period: &Period, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(period.to_num())
+}
+
+pub(super) fn permissions<S: Serializer>(
+    permissions: &Permissions,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(permissions_name(*permissions))
+}
+
+/// Repeating events as their frequency, one shot events as nothing
+pub(super) fn repeat_frequency<S: Serializer>(
+    mode: &EventMode,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match mode {
+        EventMode::Once => serializer.serialize_none(),
+        EventMode::Repeating { frequency } => serializer.serialize_some(&frequency.to_num::<f64>()),
+    }
+}
+
+fn participation_name(participation: SchedulerParticipation) -> &'static str {
+    match participation {
+        SchedulerParticipation::OnAccess => "on_access",
+        SchedulerParticipation::SchedulerDriven => "scheduler_driven",
+    }
+}
+
+fn permissions_name(permissions: Permissions) -> &'static str {
+    match (permissions.read, permissions.write) {
+        (true, true) => "rw",
+        (true, false) => "r",
+        (false, true) => "w",
+        (false, false) => "",
+    }
+}
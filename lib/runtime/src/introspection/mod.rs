@@ -0,0 +1,175 @@
+//! Read only queries about what a machine contains, for debuggers and tests
+
+use std::ops::RangeInclusive;
+
+use serde::Serialize;
+
+use crate::{
+    ComponentPath, ResourcePath,
+    event::{EventHandle, EventMode},
+    machine::{RuntimeGuard, builder::SchedulerParticipation},
+    memory::{Address, AddressSpaceId, Permissions},
+    scheduler::Period,
+};
+
+mod export;
+#[cfg(test)]
+mod tests;
+
+/// A component and how far it has been driven
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentInfo {
+    pub path: ComponentPath,
+    pub scheduler_participation: Option<SchedulerParticipation>,
+    #[serde(serialize_with = "export::seconds")]
+    pub timestamp: Period,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MemoryRegionInfo {
+    pub path: ResourcePath,
+    pub size: usize,
+    /// If the region is battery backed
+    pub sram: bool,
+}
+
+/// What a range of an address space leads to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryMapTarget {
+    Component(ComponentPath),
+    /// A memory region, starting at `offset` within it
+    Memory {
+        path: ResourcePath,
+        offset: Address,
+    },
+    ImmutableMemory,
+    /// Another range of the same address space, starting at `destination`
+    Mirror {
+        destination: Address,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MemoryMapEntry {
+    pub range: RangeInclusive<Address>,
+    #[serde(serialize_with = "export::permissions")]
+    pub permissions: Permissions,
+    pub target: MemoryMapTarget,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AddressSpaceInfo {
+    pub id: AddressSpaceId,
+    /// Width of addresses in bits
+    pub width: u8,
+    pub memory_map: Vec<MemoryMapEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingEventInfo {
+    pub handle: EventHandle,
+    #[serde(serialize_with = "export::seconds")]
+    pub time: Period,
+    pub target: ComponentPath,
+    #[serde(
+        rename = "repeat_frequency",
+        serialize_with = "export::repeat_frequency"
+    )]
+    pub mode: EventMode,
+    /// Debug representation of the event data
+    pub data: String,
+}
+
+/// Everything a machine contains, at the point it was captured
+///
+/// Obtain one with [`RuntimeGuard::introspect`]
+#[derive(Debug, Clone, Serialize)]
+pub struct MachineInfo {
+    pub components: Vec<ComponentInfo>,
+    pub memory_regions: Vec<MemoryRegionInfo>,
+    pub address_spaces: Vec<AddressSpaceInfo>,
+    pub events: Vec<PendingEventInfo>,
+}
+
+impl RuntimeGuard<'_> {
+    /// Every component, sorted by path
+    pub fn components(&self) -> Vec<ComponentInfo> {
+        let component_registry = self.component_registry();
+
+        let mut components: Vec<_> = component_registry
+            .paths()
+            .into_iter()
+            .map(|path| ComponentInfo {
+                scheduler_participation: component_registry.scheduler_participation(&path),
+                timestamp: component_registry.get_timestamp(&path).unwrap(),
+                path,
+            })
+            .collect();
+        components.sort_by(|a, b| a.path.cmp(&b.path));
+
+        components
+    }
+
+    /// Every memory region, sorted by path
+    pub fn memory_regions(&self) -> Vec<MemoryRegionInfo> {
+        let memory_registry = self.memory_registry();
+
+        let mut regions: Vec<_> = memory_registry
+            .regions()
+            .map(|(path, id)| MemoryRegionInfo {
+                path: path.clone(),
+                size: memory_registry.region_size(path).unwrap(),
+                sram: memory_registry.is_sram(id),
+            })
+            .collect();
+        regions.sort_by(|a, b| a.path.cmp(&b.path));
+
+        regions
+    }
+
+    /// The memory map of an address space, sorted by address
+    pub fn memory_map(&self, address_space: AddressSpaceId) -> Option<Vec<MemoryMapEntry>> {
+        Some(
+            self.machine()
+                .address_spaces
+                .get(&address_space)?
+                .memory_map(),
+        )
+    }
+
+    /// Every pending event, in the order they will fire
+    pub fn pending_events(&self) -> Vec<PendingEventInfo> {
+        self.machine()
+            .scheduler
+            .event_manager
+            .pending()
+            .into_iter()
+            .map(|(handle, time, target, mode, data)| PendingEventInfo {
+                handle,
+                time,
+                target,
+                mode,
+                data: format!("{data:?}"),
+            })
+            .collect()
+    }
+
+    /// Capture all of the above at once
+    pub fn introspect(&self) -> MachineInfo {
+        MachineInfo {
+            components: self.components(),
+            memory_regions: self.memory_regions(),
+            address_spaces: self
+                .address_spaces()
+                .into_iter()
+                .map(|id| AddressSpaceInfo {
+                    id,
+                    width: self.machine().address_spaces[&id].width(),
+                    memory_map: self.memory_map(id).unwrap(),
+                })
+                .collect(),
+            events: self.pending_events(),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::ops::RangeInclusive;
+
+use fluxemu_math::range::ContiguousRange;
+
+use crate::{
+    introspection::{MemoryMapEntry, MemoryMapTarget, MemoryRegionInfo},
+    machine::Machine,
+    memory::{MapTarget, MemoryMapCommand, Permissions},
+};
+
+#[test]
+fn memory_map_and_regions() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let (machine, work_ram_path) = machine.memory("work-ram", 0x800, []);
+    let machine = machine.map_memory(
+        address_space,
+        [
+            MemoryMapCommand::Map {
+                range: RangeInclusive::from_start_and_length(0, 0x800),
+                permissions: Permissions::ALL,
+                target: MapTarget::Memory {
+                    path: work_ram_path.clone(),
+                    subrange: None,
+                },
+            },
+            MemoryMapCommand::mirror(Permissions::READ, 0x800..=0xfff, 0),
+        ],
+    );
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+
+    assert_eq!(
+        runtime_guard.memory_regions(),
+        [MemoryRegionInfo {
+            path: work_ram_path.clone(),
+            size: 0x800,
+            sram: false,
+        }]
+    );
+
+    assert_eq!(
+        runtime_guard.memory_map(address_space).unwrap(),
+        [
+            MemoryMapEntry {
+                range: 0x000..=0x7ff,
+                permissions: Permissions::ALL,
+                target: MemoryMapTarget::Memory {
+                    path: work_ram_path.clone(),
+                    offset: 0,
+                },
+            },
+            MemoryMapEntry {
+                range: 0x800..=0xfff,
+                permissions: Permissions::READ,
+                target: MemoryMapTarget::Mirror { destination: 0 },
+            },
+        ]
+    );
+
+    let info = runtime_guard.introspect();
+
+    let json: serde_json::Value = serde_json::from_str(&info.to_json()).unwrap();
+    assert_eq!(
+        json["address_spaces"][0]["memory_map"][1]["permissions"],
+        "r"
+    );
+    assert_eq!(json["memory_regions"][0]["size"], 0x800);
+
+    let graph = info.to_graphviz();
+    assert!(graph.starts_with("digraph machine {"));
+    assert!(graph.contains(&format!("\"address_space_0\" -> \"{work_ram_path}\"")));
+}
+
+#[test]
+fn graphviz_escapes_names_and_shares_immutable_memory() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let (machine, quoted_path) = machine.memory(r#"say"hi\"#, 0x10, []);
+    let machine = machine.map_memory(
+        address_space,
+        [
+            MemoryMapCommand::Map {
+                range: RangeInclusive::from_start_and_length(0, 0x10),
+                permissions: Permissions::ALL,
+                target: MapTarget::Memory {
+                    path: quoted_path,
+                    subrange: None,
+                },
+            },
+            MemoryMapCommand::immutable_memory(0x100, vec![0u8; 0x10]),
+            MemoryMapCommand::immutable_memory(0x200, vec![0u8; 0x10]),
+        ],
+    );
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+
+    let graph = runtime_guard.introspect().to_graphviz();
+
+    assert!(graph.contains(r#"say\"hi\\"#));
+    assert!(!graph.contains(r#"say"hi"#));
+    assert_eq!(
+        graph
+            .matches("\"address_space_0_immutable_memory\" [shape=note")
+            .count(),
+        1
+    );
+}
@@ -2,16 +2,24 @@
 //!
 //! Main runtime crate for the FluxEMU framework
 
+pub mod cheat;
+pub mod clock;
 pub mod component;
 pub mod event;
 pub mod graphics;
 mod handle;
 pub mod input;
+pub mod introspection;
+pub mod link;
 pub mod machine;
 pub mod memory;
+pub mod movie;
 pub mod path;
 pub mod platform;
+pub mod profiling;
 pub mod scheduler;
+pub mod signal;
+pub mod snapshot;
 
 pub use handle::*;
 pub use path::{ComponentPath, ResourcePath};
@@ -0,0 +1,255 @@
+//! Running several machines against one timebase, so their components can talk over link cables
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    RuntimeHandle,
+    event::{Event, EventMode},
+    machine::Machine,
+    path::ResourcePath,
+    scheduler::Period,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Turns a arriving message into the event type of the component owning the port
+pub(crate) type LinkMessageConstructor = fn(LinkMessage) -> Box<dyn Event>;
+
+/// Data that arrived through a link port, delivered to the component owning the port as a event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkMessage {
+    /// Name of the port it arrived through
+    pub port: String,
+    pub data: Vec<u8>,
+}
+
+/// A message waiting in the machine it was sent from for the link to pick it up
+#[derive(Debug)]
+pub(crate) struct OutgoingLinkMessage {
+    port: ResourcePath,
+    time: Period,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum LinkError {
+    #[error("No machine {0:?} in this link")]
+    UnknownMachine(LinkedMachineId),
+    #[error("Machine has no link port {0}")]
+    UnknownPort(ResourcePath),
+    #[error("Link port {0} is already connected")]
+    PortInUse(ResourcePath),
+}
+
+/// Identifier for a machine inside a [`MachineLink`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LinkedMachineId(usize);
+
+/// One end of a link cable
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LinkEndpoint {
+    pub machine: LinkedMachineId,
+    pub port: ResourcePath,
+}
+
+/// Coordinator running several machines in lockstep, carrying messages between their link ports
+///
+/// Machines are run in slices no longer than the link latency, one after another in the order they were inserted.
+/// Messages sent during a slice are delivered once it ends, ordered by the time they were sent and then by machine,
+/// so every run of the same inputs plays out identically.
+#[derive(Debug)]
+pub struct MachineLink {
+    machines: Vec<Arc<Machine>>,
+    cables: BTreeMap<LinkEndpoint, LinkEndpoint>,
+    latency: Period,
+    timestamp: Period,
+}
+
+impl MachineLink {
+    /// Create a link where messages take `latency` to arrive
+    pub fn new(latency: Period) -> Self {
+        assert_ne!(latency, Period::ZERO, "Link latency cannot be zero");
+
+        Self {
+            machines: Vec::default(),
+            cables: BTreeMap::default(),
+            latency,
+            timestamp: Period::ZERO,
+        }
+    }
+
+    /// Add a machine to the link
+    ///
+    /// # Panics
+    ///
+    /// Panics if the machine is already part of a link
+    pub fn insert(&mut self, machine: Arc<Machine>) -> LinkedMachineId {
+        let mut outbox_guard = machine.link_outbox.lock().unwrap();
+        assert!(outbox_guard.is_none(), "Machine is already linked");
+        *outbox_guard = Some(Vec::default());
+        drop(outbox_guard);
+
+        // Machines that already ran catch the others up on the next run
+        self.timestamp = self
+            .timestamp
+            .max(machine.scheduler.safe_advance_timestamp());
+
+        let id = LinkedMachineId(self.machines.len());
+        self.machines.push(machine);
+
+        id
+    }
+
+    pub fn machine(&self, id: LinkedMachineId) -> Option<&Arc<Machine>> {
+        self.machines.get(id.0)
+    }
+
+    /// The timestamp every machine in the link has been run to
+    pub fn timestamp(&self) -> Period {
+        self.timestamp
+    }
+
+    /// Plug a cable between two ports, so that messages sent out of either arrive at the other
+    pub fn connect(&mut self, a: LinkEndpoint, b: LinkEndpoint) -> Result<(), LinkError> {
+        if a == b {
+            return Err(LinkError::PortInUse(a.port));
+        }
+
+        for endpoint in [&a, &b] {
+            let machine = self
+                .machine(endpoint.machine)
+                .ok_or(LinkError::UnknownMachine(endpoint.machine))?;
+
+            if !machine
+                .link_ports
+                .read()
+                .unwrap()
+                .contains_key(&endpoint.port)
+            {
+                return Err(LinkError::UnknownPort(endpoint.port.clone()));
+            }
+
+            if self.cables.contains_key(endpoint) {
+                return Err(LinkError::PortInUse(endpoint.port.clone()));
+            }
+        }
+
+        self.cables.insert(a.clone(), b.clone());
+        self.cables.insert(b, a);
+
+        Ok(())
+    }
+
+    /// Unplug the cable attached to a port, returning if there was one
+    ///
+    /// Messages already sent through it are lost
+    pub fn disconnect(&mut self, endpoint: &LinkEndpoint) -> bool {
+        let Some(other) = self.cables.remove(endpoint) else {
+            return false;
+        };
+        self.cables.remove(&other);
+
+        true
+    }
+
+    /// Run every machine forward by the given time
+    pub fn run(&mut self, allocated_time: Period) {
+        let target_timestamp = self.timestamp + allocated_time;
+
+        while self.timestamp < target_timestamp {
+            let slice_end = (self.timestamp + self.latency).min(target_timestamp);
+
+            for machine in &self.machines {
+                let runtime_guard = machine.enter_runtime();
+                let behind = slice_end.saturating_sub(runtime_guard.safe_advance_timestamp());
+
+                if behind != Period::ZERO {
+                    runtime_guard.run(behind);
+                }
+            }
+
+            self.deliver();
+            self.timestamp = slice_end;
+        }
+    }
+
+    /// Move every message sent since the last slice to the other end of its cable
+    fn deliver(&self) {
+        let mut messages = Vec::new();
+
+        for (index, machine) in self.machines.iter().enumerate() {
+            let outbox = std::mem::take(machine.link_outbox.lock().unwrap().as_mut().unwrap());
+
+            messages.extend(outbox.into_iter().map(|message| (index, message)));
+        }
+
+        // Stable, so messages sent at the same time by one machine keep the order they were sent in
+        messages.sort_by_key(|(index, message)| (message.time, *index));
+
+        for (index, OutgoingLinkMessage { port, time, data }) in messages {
+            let Some(destination) = self.cables.get(&LinkEndpoint {
+                machine: LinkedMachineId(index),
+                port,
+            }) else {
+                continue;
+            };
+
+            let machine = &self.machines[destination.machine.0];
+
+            // The receiving component may have been unplugged since the cable was connected
+            let Some(constructor) = machine
+                .link_ports
+                .read()
+                .unwrap()
+                .get(&destination.port)
+                .copied()
+            else {
+                continue;
+            };
+
+            let event = constructor(LinkMessage {
+                port: destination.port.name().to_owned(),
+                data,
+            });
+
+            // A component lagging behind may send from a time the receiver has already passed
+            let arrival = (time + self.latency).max(machine.scheduler.safe_advance_timestamp());
+
+            machine.scheduler.event_manager.schedule(
+                arrival,
+                destination.port.parent().unwrap().clone(),
+                EventMode::Once,
+                event,
+            );
+        }
+    }
+}
+
+impl Drop for MachineLink {
+    fn drop(&mut self) {
+        for machine in &self.machines {
+            *machine.link_outbox.lock().unwrap() = None;
+        }
+    }
+}
+
+impl RuntimeHandle {
+    /// Send data out of a link port, to arrive at the other end of its cable one link latency after `time`
+    ///
+    /// If the receiving machine has already run past that, it arrives at the receiver's current time instead
+    ///
+    /// Messages sent while the machine is not part of a [`MachineLink`], or through a port without a cable, are lost
+    pub fn send_link_message(&self, port: &ResourcePath, time: Period, data: impl Into<Vec<u8>>) {
+        if let Some(outbox) = self.machine().link_outbox.lock().unwrap().as_mut() {
+            outbox.push(OutgoingLinkMessage {
+                port: port.clone(),
+                time,
+                data: data.into(),
+            });
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::{error::Error, sync::Arc};
+
+use crate::{
+    RuntimeHandle,
+    component::{Component, config::ComponentConfig},
+    event::{Event, downcast_event},
+    link::{LinkEndpoint, LinkError, LinkMessage, MachineLink},
+    machine::{Machine, builder::ComponentBuilder},
+    path::{ComponentPath, ResourcePath},
+    platform::Platform,
+    scheduler::Period,
+};
+
+/// Remembers everything that arrived through its port, and when
+#[derive(Debug)]
+struct Serial {
+    path: ComponentPath,
+    received: Vec<LinkMessage>,
+    arrivals: Vec<Period>,
+}
+
+impl Component for Serial {
+    type Event = LinkMessage;
+
+    fn handle_event(&mut self, event: Box<dyn Event>) {
+        self.received.push(downcast_event::<Self>(event));
+        self.arrivals.push(RuntimeHandle::with_current(|runtime| {
+            runtime.current_timestamp(&self.path)
+        }));
+    }
+}
+
+#[derive(Debug, Default)]
+struct SerialConfig;
+
+impl<P: Platform> ComponentConfig<P> for SerialConfig {
+    type Component = Serial;
+
+    fn build_component(
+        self,
+        component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn Error>> {
+        let path = component_builder.path().clone();
+        component_builder.link_port("port");
+
+        Ok(Serial {
+            path,
+            received: Vec::default(),
+            arrivals: Vec::default(),
+        })
+    }
+}
+
+fn serial_machine() -> (Arc<Machine>, ComponentPath, ResourcePath) {
+    let (machine, path) = Machine::build_test_minimal().default_component::<SerialConfig>("serial");
+    let port = path.clone().into_resource("port").unwrap();
+
+    (machine.seal().build(()), path, port)
+}
+
+#[test]
+fn messages_cross_machines_in_order() {
+    let (first, _, first_port) = serial_machine();
+    let (second, second_serial, second_port) = serial_machine();
+
+    let mut link = MachineLink::new(Period::lit("0.25"));
+    let first_id = link.insert(first.clone());
+    let second_id = link.insert(second.clone());
+
+    let first_endpoint = LinkEndpoint {
+        machine: first_id,
+        port: first_port.clone(),
+    };
+    let second_endpoint = LinkEndpoint {
+        machine: second_id,
+        port: second_port,
+    };
+
+    link.connect(first_endpoint.clone(), second_endpoint.clone())
+        .unwrap();
+    assert_eq!(
+        link.connect(first_endpoint, second_endpoint),
+        Err(LinkError::PortInUse(first_port.clone()))
+    );
+
+    let runtime_guard = first.enter_runtime();
+    runtime_guard.send_link_message(&first_port, Period::lit("0.5"), [3]);
+    runtime_guard.send_link_message(&first_port, Period::ZERO, [1]);
+    runtime_guard.send_link_message(&first_port, Period::ZERO, [2]);
+    drop(runtime_guard);
+
+    link.run(Period::ONE);
+    assert_eq!(link.timestamp(), Period::ONE);
+
+    let runtime_guard = second.enter_runtime();
+    let received = runtime_guard
+        .component_registry()
+        .interact::<Serial, _>(&second_serial, &Period::ONE, |serial| {
+            serial.received.clone()
+        })
+        .unwrap();
+
+    assert_eq!(
+        received
+            .iter()
+            .map(|message| message.data.as_slice())
+            .collect::<Vec<_>>(),
+        [[1], [2], [3]]
+    );
+    assert!(received.iter().all(|message| message.port == "port"));
+}
+
+#[test]
+fn late_messages_arrive_in_the_present() {
+    let (first, _, first_port) = serial_machine();
+    let (second, second_serial, second_port) = serial_machine();
+
+    let mut link = MachineLink::new(Period::lit("0.25"));
+    let first_id = link.insert(first.clone());
+    let second_id = link.insert(second.clone());
+    link.connect(
+        LinkEndpoint {
+            machine: first_id,
+            port: first_port.clone(),
+        },
+        LinkEndpoint {
+            machine: second_id,
+            port: second_port,
+        },
+    )
+    .unwrap();
+
+    link.run(Period::ONE);
+
+    // Sent from long before where both machines are now
+    let runtime_guard = first.enter_runtime();
+    runtime_guard.send_link_message(&first_port, Period::ZERO, [1]);
+    drop(runtime_guard);
+
+    link.run(Period::ONE);
+
+    let runtime_guard = second.enter_runtime();
+    let arrivals = runtime_guard
+        .component_registry()
+        .interact::<Serial, _>(&second_serial, &Period::lit("2"), |serial| {
+            serial.arrivals.clone()
+        })
+        .unwrap();
+
+    assert_eq!(arrivals, [Period::lit("1.25")]);
+}
@@ -5,17 +5,20 @@ use fluxemu_input::InputId;
 use fluxemu_program::{ProgramManager, RomId};
 
 use crate::{
+    clock::Clock,
     component::{Component, config::ComponentConfig},
-    event::EventMode,
+    event::{EventHandle, EventMode},
     graphics::GraphicsRequirements,
     input::{LogicalInputDevice, LogicalInputDeviceMetadata},
+    link::LinkMessage,
     machine::builder::{
         ComponentLateInitializer, MachineBuilder, RomRequirement, SchedulerParticipation,
     },
-    memory::{AddressSpaceId, MemoryMapCommand, RegionInitializationData},
+    memory::{AddressSpaceId, MappingPresetId, MemoryMapCommand, RegionInitializationData},
     path::{ComponentPath, ResourcePath},
     platform::Platform,
-    scheduler::Period,
+    scheduler::{Frequency, Period},
+    signal::{SignalChange, Wiring},
 };
 
 /// Overall data extracted from components needed for machine initialization
@@ -68,6 +71,11 @@ impl<P: Platform, C: Component> ComponentBuilder<'_, P, C> {
         self.machine_builder.program_manager()
     }
 
+    /// Look up a clock declared on the machine, see [`MachineBuilder::clock`]
+    pub fn clock(&self, name: &str) -> Option<Clock> {
+        self.machine_builder.clock(name)
+    }
+
     pub fn scheduler_participation(
         self,
         scheduler_participation: Option<SchedulerParticipation>,
@@ -82,6 +90,22 @@ impl<P: Platform, C: Component> ComponentBuilder<'_, P, C> {
         self
     }
 
+    /// Declare that, while synchronizing, this component only touches the machine through the given address spaces
+    /// and neither schedules nor receives events
+    ///
+    /// Nothing outside of the component may use those address spaces either, other than components mapped into them.
+    /// Interacting directly with components that no other scheduler driven component reaches is fine.
+    /// Isolated scheduler driven components may then be advanced on worker threads by
+    /// [`SchedulerMode::Parallel`](crate::scheduler::SchedulerMode::Parallel). Debug builds panic if such a component
+    /// touches any other address space, or schedules events
+    pub fn isolated_to(self, address_spaces: impl IntoIterator<Item = AddressSpaceId>) -> Self {
+        self.machine_builder
+            .scheduler
+            .register_isolated_component(self.path.clone(), address_spaces.into_iter().collect());
+
+        self
+    }
+
     /// Insert a component into the machine
     pub fn component<B: ComponentConfig<P>>(
         self,
@@ -127,6 +151,84 @@ impl<P: Platform, C: Component> ComponentBuilder<'_, P, C> {
         (self, resource_path)
     }
 
+    /// Declare how many frames per second this component presents, which the machine reports as its refresh rate
+    pub fn refresh_rate(self, refresh_rate: Frequency) -> Self {
+        self.machine_builder.refresh_rate = Some(refresh_rate);
+
+        self
+    }
+
+    /// Create a link port, which a [`MachineLink`] can connect to a port of a component in another machine
+    ///
+    /// Messages arriving through it are delivered to this component as events
+    ///
+    /// [`MachineLink`]: crate::link::MachineLink
+    pub fn link_port(self, name: impl Into<Cow<'static, str>>) -> (Self, ResourcePath)
+    where
+        C::Event: From<LinkMessage>,
+    {
+        let resource_path = self.path.clone().into_resource(name).unwrap();
+
+        self.machine_builder
+            .link_ports
+            .insert(resource_path.clone(), |message| {
+                Box::new(<C::Event>::from(message))
+            });
+
+        (self, resource_path)
+    }
+
+    /// Create a signal output pin, which this component drives with [`RuntimeHandle::drive_signal`]
+    ///
+    /// [`RuntimeHandle::drive_signal`]: crate::RuntimeHandle::drive_signal
+    pub fn signal_output(
+        self,
+        name: impl Into<Cow<'static, str>>,
+        initial_level: bool,
+    ) -> (Self, ResourcePath) {
+        let resource_path = self.path.clone().into_resource(name).unwrap();
+
+        self.machine_builder
+            .signals
+            .outputs
+            .push((resource_path.clone(), initial_level));
+
+        (self, resource_path)
+    }
+
+    /// Create a signal input pin, combining the levels of every output connected to it as `wiring` says
+    ///
+    /// Changes of the combined level are delivered to this component as events
+    pub fn signal_input(
+        self,
+        name: impl Into<Cow<'static, str>>,
+        wiring: Wiring,
+    ) -> (Self, ResourcePath)
+    where
+        C::Event: From<SignalChange>,
+    {
+        let resource_path = self.path.clone().into_resource(name).unwrap();
+
+        self.machine_builder
+            .signals
+            .inputs
+            .push((resource_path.clone(), wiring, |change| {
+                Box::new(<C::Event>::from(change))
+            }));
+
+        (self, resource_path)
+    }
+
+    /// Connect a signal output to a signal input, see [`MachineBuilder::connect_signal`]
+    pub fn connect_signal(self, output: ResourcePath, input: ResourcePath) -> Self {
+        self.machine_builder
+            .signals
+            .connections
+            .push((output, input));
+
+        self
+    }
+
     /// Create a input device resource that this component owns
     ///
     /// Note that this also gives the component wake up events for relevant input changes
@@ -208,21 +310,40 @@ impl<P: Platform, C: Component> ComponentBuilder<'_, P, C> {
         self
     }
 
+    /// Register a named mapping preset, see [`MachineBuilder::mapping_preset`]
+    pub fn mapping_preset(
+        self,
+        address_space: AddressSpaceId,
+        name: impl Into<Cow<'static, str>>,
+        commands: impl IntoIterator<Item = MemoryMapCommand>,
+    ) -> (Self, MappingPresetId) {
+        let id = self
+            .machine_builder
+            .register_mapping_preset(address_space, name.into(), commands);
+
+        (self, id)
+    }
+
+    /// Schedule an event to be pending when the machine starts, see [`RuntimeHandle::schedule_event`]
+    ///
+    /// The handle stays valid across hard resets, which put the event back where it was scheduled here
+    ///
+    /// [`RuntimeHandle::schedule_event`]: crate::RuntimeHandle::schedule_event
     pub fn schedule_event<C2: Component>(
         self,
         target_path: &ComponentPath,
         time: Period,
         requeue_mode: EventMode,
         data: C2::Event,
-    ) -> Self {
-        self.machine_builder.scheduler.event_manager.schedule(
+    ) -> (Self, EventHandle) {
+        let handle = self.machine_builder.scheduler.event_manager.schedule(
             time,
             target_path.clone(),
             requeue_mode,
             Box::new(data),
         );
 
-        self
+        (self, handle)
     }
 
     pub fn add_graphics_requirements(
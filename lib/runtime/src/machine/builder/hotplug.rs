@@ -0,0 +1,165 @@
+use fluxemu_graphics::api::GraphicsApi;
+
+use crate::{
+    RuntimeHandle,
+    component::config::{ComponentConfig, LateContext},
+    event::EventMode,
+    machine::{
+        HotplugError, Machine,
+        builder::{AddressSpaceSetupData, ComponentData, MachineBuilder},
+    },
+    memory::AddressSpaceData,
+    path::ComponentPath,
+    platform::Platform,
+    scheduler::{DrivenComponents, Period},
+    signal,
+};
+
+impl<P: Platform> MachineBuilder<P> {
+    /// Staging builder for components that will be plugged into an already running machine
+    fn for_hotplug(machine: &Machine) -> Self {
+        let mut builder = Self::new(
+            machine.program_specification.clone(),
+            machine.program_manager.clone(),
+        );
+
+        builder.component_registry_data = machine.component_registry_data.continuation();
+        builder.clocks = machine.clocks.clone();
+
+        // Only here so components can queue up mappings, the live address spaces are remapped later
+        for (id, data) in &machine.address_spaces {
+            builder.address_spaces.insert(
+                *id,
+                AddressSpaceSetupData {
+                    data: AddressSpaceData::new(*id, data.width()),
+                    commands: Vec::default(),
+                    presets: Vec::default(),
+                    first_preset: data.preset_count(),
+                },
+            );
+        }
+
+        builder
+    }
+}
+
+/// Build a component and splice it into a running machine
+///
+/// The caller must hold the scheduler's component lock for the duration of this call, and pass in what it guards. The
+/// running machine is left untouched if this fails
+pub(crate) fn plug_component<P: Platform, B: ComponentConfig<P>>(
+    runtime: &RuntimeHandle,
+    driven: &mut DrivenComponents,
+    path: ComponentPath,
+    config: B,
+    graphics_initialization_data: <P::GraphicsApi as GraphicsApi>::InitializationData,
+) -> Result<(), HotplugError> {
+    let machine = runtime.machine();
+    let component_registry = runtime.component_registry();
+
+    if component_registry.id_for_path(&path).is_some() {
+        return Err(HotplugError::PathTaken(path));
+    }
+
+    let mut builder = MachineBuilder::<P>::for_hotplug(machine);
+    if let Err(error) = builder.try_insert_component_with_path(path.clone(), config) {
+        return Err(HotplugError::Build { path, error });
+    }
+
+    let MachineBuilder {
+        component_registry_data,
+        address_spaces,
+        component_data,
+        input_devices,
+        framebuffers,
+        audio_channels,
+        link_ports,
+        signals,
+        required_memory_regions,
+        mut scheduler,
+        ..
+    } = builder;
+
+    if !required_memory_regions.is_empty() {
+        return Err(HotplugError::CreatesMemory(path));
+    }
+
+    let timestamp = machine.scheduler.safe_advance_timestamp();
+    let start_time = machine.scheduler.start_time();
+
+    machine
+        .component_registry_data
+        .adopt(component_registry_data);
+    for path in component_data.keys() {
+        component_registry.set_timestamp(path, timestamp).unwrap();
+    }
+
+    driven.extend(scheduler.take_driven_components());
+
+    machine.input_devices.write().unwrap().extend(input_devices);
+    machine.framebuffers.write().unwrap().extend(framebuffers);
+    machine
+        .audio_channels
+        .write()
+        .unwrap()
+        .extend(audio_channels);
+    machine.link_ports.write().unwrap().extend(link_ports);
+
+    // The starting levels of the new inputs go along with its build time events, the changes they cause now
+    let (starting_levels, signal_events) = machine.signals.lock().unwrap().extend(signals);
+    for (input, event) in starting_levels {
+        scheduler.event_manager.schedule(
+            Period::ZERO,
+            input.parent().unwrap().clone(),
+            EventMode::Once,
+            event,
+        );
+    }
+    signal::deliver(runtime, timestamp, signal_events);
+
+    let mut initial_memory_maps = machine.initial_memory_maps.lock().unwrap();
+    for (
+        id,
+        AddressSpaceSetupData {
+            commands, presets, ..
+        },
+    ) in address_spaces
+    {
+        let mut address_space = runtime.address_space(id).unwrap();
+
+        if !commands.is_empty() {
+            address_space.remap(&timestamp, commands.iter().cloned());
+            initial_memory_maps.entry(id).or_default().extend(commands);
+        }
+
+        for (name, commands) in presets {
+            address_space.compile_preset(name, commands);
+        }
+    }
+    drop(initial_memory_maps);
+
+    machine
+        .scheduler
+        .event_manager
+        .adopt(scheduler.event_manager.pending(), timestamp, start_time);
+
+    let late_context = LateContext {
+        graphics_initialization_data,
+    };
+
+    for (
+        path,
+        ComponentData {
+            late_initializer, ..
+        },
+    ) in component_data
+    {
+        component_registry
+            .interact_unsynchronized(&path, |component| {
+                late_initializer(component, &late_context);
+            })
+            .unwrap();
+    }
+
+    Ok(())
+}
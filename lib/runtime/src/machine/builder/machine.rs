@@ -3,29 +3,36 @@ use std::{
     collections::{HashMap, HashSet},
     marker::PhantomData,
     ops::RangeInclusive,
-    sync::Arc,
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
 };
 
 use bytes::Bytes;
 use fluxemu_program::{ProgramManager, ProgramSpecification, RomId, SystemId};
+use num::rational::Ratio;
 use rustc_hash::FxBuildHasher;
 
 use crate::{
     ResourcePath,
+    clock::{Clock, ClockTree},
     component::{ComponentRegistryData, config::ComponentConfig},
+    event::EventMode,
     graphics::GraphicsRequirements,
     input::LogicalInputDevice,
+    link::LinkMessageConstructor,
     machine::{
         Machine,
         builder::{ComponentBuilder, ComponentData, RomRequirement, SealedMachineBuilder},
     },
     memory::{
-        Address, AddressSpaceData, AddressSpaceId, MemoryMapCommand, MemoryRegistryData,
-        RegionInitializationData,
+        Address, AddressSpaceData, AddressSpaceId, DataBus, MappingPresetId, MemoryMapCommand,
+        MemoryRegistryData, OpenBusDecay, RegionInitializationData,
     },
     path::ComponentPath,
     platform::Platform,
-    scheduler::{Period, Scheduler},
+    profiling::Profiler,
+    scheduler::{Frequency, Period, Scheduler, SchedulerMode},
+    signal::{SignalBoard, SignalSetup},
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -39,6 +46,10 @@ pub enum MachineError {
 pub(super) struct AddressSpaceSetupData {
     pub data: AddressSpaceData,
     pub commands: Vec<MemoryMapCommand>,
+    /// Mapping presets to compile once the initial memory map is in place
+    pub presets: Vec<(Cow<'static, str>, Vec<MemoryMapCommand>)>,
+    /// Index the first of the presets will get, nonzero when plugging components into a running machine
+    pub first_preset: u16,
 }
 
 /// Builder to produce a machine, definition crates will want to use this
@@ -51,8 +62,13 @@ pub struct MachineBuilder<P: Platform> {
     pub(super) component_data: HashMap<ComponentPath, ComponentData<P>>,
     pub(super) input_devices: HashMap<ResourcePath, Arc<LogicalInputDevice>, FxBuildHasher>,
     pub(super) framebuffers: HashSet<ResourcePath>,
+    pub(super) refresh_rate: Option<Frequency>,
     pub(super) audio_channels: HashSet<ResourcePath>,
+    pub(super) link_ports: HashMap<ResourcePath, LinkMessageConstructor, FxBuildHasher>,
+    pub(super) signals: SignalSetup,
+    pub(super) clocks: ClockTree,
     pub(super) required_memory_regions: HashMap<ResourcePath, RegionInitializationData>,
+    pub(super) save_directory: Option<PathBuf>,
     pub(super) scheduler: Scheduler,
 }
 
@@ -71,11 +87,70 @@ impl<P: Platform> MachineBuilder<P> {
             component_data: HashMap::default(),
             input_devices: HashMap::default(),
             framebuffers: HashSet::default(),
+            refresh_rate: None,
             audio_channels: HashSet::default(),
+            link_ports: HashMap::default(),
+            signals: SignalSetup::default(),
+            clocks: ClockTree::default(),
+            save_directory: None,
             scheduler: Scheduler::new(),
         }
     }
 
+    /// Choose how the scheduler drives components, which can be changed later at runtime
+    pub fn scheduler_mode(self, mode: SchedulerMode) -> Self {
+        self.scheduler.set_mode(mode);
+
+        self
+    }
+
+    /// Set the directory battery backed memory is persisted to
+    ///
+    /// Saves are only persisted for machines that were set up with a program
+    pub fn save_directory(mut self, save_directory: impl Into<PathBuf>) -> Self {
+        self.save_directory = Some(save_directory.into());
+        self
+    }
+
+    /// Scale every master clock by `factor`, and with them every clock derived from them
+    ///
+    /// # Panics
+    ///
+    /// Panics if any clock has been declared already, so this is meant to be called before the system builds itself
+    pub fn overclock(mut self, factor: Ratio<u64>) -> Self {
+        self.clocks.set_overclock(factor);
+
+        self
+    }
+
+    /// Declare a oscillator running at exactly `frequency` hertz
+    pub fn master_clock(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        frequency: Ratio<u64>,
+    ) -> (Self, Clock) {
+        let clock = self.clocks.master(name.into(), frequency);
+
+        (self, clock)
+    }
+
+    /// Declare a clock running at `ratio` times the frequency of `parent`, such as `Ratio::new(1, 12)` for a divider
+    pub fn derived_clock(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        parent: Clock,
+        ratio: Ratio<u64>,
+    ) -> (Self, Clock) {
+        let clock = self.clocks.derived(name.into(), parent, ratio);
+
+        (self, clock)
+    }
+
+    /// Look up a clock by the name it was declared with
+    pub fn clock(&self, name: &str) -> Option<Clock> {
+        self.clocks.get(name)
+    }
+
     pub fn system_id(&self) -> Option<SystemId> {
         self.program_specification
             .as_ref()
@@ -133,6 +208,16 @@ impl<P: Platform> MachineBuilder<P> {
         path: ComponentPath,
         config: B,
     ) {
+        self.try_insert_component_with_path(path, config)
+            .expect("Failed to build component");
+    }
+
+    #[inline]
+    pub(super) fn try_insert_component_with_path<B: ComponentConfig<P>>(
+        &mut self,
+        path: ComponentPath,
+        config: B,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let mut component_data = ComponentData::new::<B>();
 
         let component_builder = ComponentBuilder::<P, B::Component> {
@@ -142,17 +227,17 @@ impl<P: Platform> MachineBuilder<P> {
             _phantom: PhantomData,
         };
 
-        let component = config
-            .build_component(component_builder)
-            .expect("Failed to build component");
+        let component = config.build_component(component_builder)?;
 
         self.component_registry_data.insert_component(
             path.clone(),
-            component_data.scheduler_participation.is_some(),
+            component_data.scheduler_participation,
             component,
         );
 
         self.component_data.insert(path, component_data);
+
+        Ok(())
     }
 
     /// Insert a component into the machine
@@ -192,6 +277,8 @@ impl<P: Platform> MachineBuilder<P> {
             AddressSpaceSetupData {
                 data: AddressSpaceData::new(address_space_id, width),
                 commands: Vec::default(),
+                presets: Vec::default(),
+                first_preset: 0,
             },
         );
 
@@ -254,11 +341,85 @@ impl<P: Platform> MachineBuilder<P> {
         self
     }
 
+    /// Make reads of unmapped parts of a address space return the last value driven onto its data bus
+    ///
+    /// Without this such reads fail with [`MemoryErrorType::OutOfBus`]
+    ///
+    /// [`MemoryErrorType::OutOfBus`]: crate::memory::MemoryErrorType::OutOfBus
+    pub fn open_bus(mut self, address_space: AddressSpaceId, decay: OpenBusDecay) -> Self {
+        self.address_spaces
+            .get_mut(&address_space)
+            .unwrap()
+            .data
+            .set_open_bus(decay);
+
+        self
+    }
+
+    /// Register a named mapping preset, the memory map the commands would produce on top of the initial one
+    ///
+    /// Presets are compiled into page tables once the machine is built, see [`AddressSpace::switch_preset`]
+    ///
+    /// [`AddressSpace::switch_preset`]: crate::memory::AddressSpace::switch_preset
+    pub fn mapping_preset(
+        mut self,
+        address_space: AddressSpaceId,
+        name: impl Into<Cow<'static, str>>,
+        commands: impl IntoIterator<Item = MemoryMapCommand>,
+    ) -> (Self, MappingPresetId) {
+        let id = self.register_mapping_preset(address_space, name.into(), commands);
+
+        (self, id)
+    }
+
+    pub(super) fn register_mapping_preset(
+        &mut self,
+        address_space: AddressSpaceId,
+        name: Cow<'static, str>,
+        commands: impl IntoIterator<Item = MemoryMapCommand>,
+    ) -> MappingPresetId {
+        let setup_data = self.address_spaces.get_mut(&address_space).unwrap();
+        let index = usize::from(setup_data.first_preset) + setup_data.presets.len();
+
+        setup_data
+            .presets
+            .push((name, commands.into_iter().collect()));
+
+        MappingPresetId {
+            address_space,
+            index: index.try_into().expect("Too many mapping presets"),
+        }
+    }
+
+    /// Give a address space a data bus wider than a byte, a native byte order, or a misaligned access policy
+    ///
+    /// Components mapped into it receive every access one bus word at a time
+    pub fn data_bus(mut self, address_space: AddressSpaceId, data_bus: DataBus) -> Self {
+        self.address_spaces
+            .get_mut(&address_space)
+            .unwrap()
+            .data
+            .set_data_bus(data_bus);
+
+        self
+    }
+
+    /// Connect a signal output to a signal input, both declared by components of this machine
+    ///
+    /// Inputs may be connected to any number of outputs, their level is combined as the input's [`Wiring`] says
+    ///
+    /// [`Wiring`]: crate::signal::Wiring
+    pub fn connect_signal(mut self, output: ResourcePath, input: ResourcePath) -> Self {
+        self.signals.connections.push((output, input));
+
+        self
+    }
+
     /// Seal the machine
     pub fn seal(self) -> SealedMachineBuilder<P> {
         let mut component_late_initializers = HashMap::default();
         let mut graphics_requirements = GraphicsRequirements::default();
-        let mut remapping_commands = HashMap::new();
+        let mut remapping_commands = HashMap::default();
         let mut address_spaces = HashMap::default();
 
         for (path, component_data) in self.component_data {
@@ -267,22 +428,73 @@ impl<P: Platform> MachineBuilder<P> {
             graphics_requirements = component_data.graphics_requirements | graphics_requirements;
         }
 
-        for (id, AddressSpaceSetupData { data, commands }) in self.address_spaces {
+        let mut presets = Vec::default();
+
+        for (
+            id,
+            AddressSpaceSetupData {
+                data,
+                commands,
+                presets: address_space_presets,
+                ..
+            },
+        ) in self.address_spaces
+        {
             address_spaces.insert(id, data);
             remapping_commands.insert(id, commands);
+            presets.push((id, address_space_presets));
         }
 
         let required_memory_regions = self.required_memory_regions;
 
+        // Saves are keyed by the program so they don't collide with each other
+        let save_directory = self
+            .save_directory
+            .zip(self.program_specification.as_ref())
+            .map(|(save_directory, program_specification)| {
+                let id = &program_specification.id;
+
+                save_directory
+                    .join(id.system.to_string())
+                    .join(id.name.replace(['/', '\\'], "_"))
+            });
+
+        // Every input hears its starting level, which stays queued as a initial event so a hard reset repeats it
+        let mut signals = SignalBoard::default();
+        let (starting_levels, _) = signals.extend(self.signals);
+        let start_time = self.scheduler.start_time();
+        for (input, event) in starting_levels {
+            self.scheduler.event_manager.schedule(
+                start_time,
+                input.parent().unwrap().clone(),
+                EventMode::Once,
+                event,
+            );
+        }
+
         let machine = Arc::new(Machine {
             scheduler: self.scheduler,
             address_spaces,
-            input_devices: self.input_devices,
-            framebuffers: self.framebuffers,
+            input_devices: RwLock::new(self.input_devices),
+            framebuffers: RwLock::new(self.framebuffers),
+            refresh_rate: self.refresh_rate,
             program_specification: self.program_specification,
-            audio_channels: self.audio_channels,
+            program_manager: self.program_manager,
+            movie_recording: Mutex::default(),
+            watchpoint_hits: Mutex::default(),
+            cheats: Mutex::default(),
+            initial_memory_maps: Mutex::new(remapping_commands.clone()),
+            audio_channels: RwLock::new(self.audio_channels),
+            link_ports: RwLock::new(self.link_ports),
+            link_outbox: Mutex::default(),
+            signals: Mutex::new(signals),
+            clocks: self.clocks,
+            profiler: Profiler::new(),
             component_registry_data: self.component_registry_data,
-            memory_registry_data: MemoryRegistryData::new(required_memory_regions),
+            memory_registry_data: MemoryRegistryData::new(
+                required_memory_regions,
+                save_directory.as_deref(),
+            ),
         });
 
         // Initialize address spaces
@@ -292,6 +504,14 @@ impl<P: Platform> MachineBuilder<P> {
 
             address_space.remap(&Period::default(), commands);
         }
+
+        for (id, presets) in presets {
+            let address_space = runtime_guard.address_space(id).unwrap();
+
+            for (name, commands) in presets {
+                address_space.compile_preset(name, commands);
+            }
+        }
         drop(runtime_guard);
 
         SealedMachineBuilder {
@@ -10,13 +10,17 @@ use crate::{
 };
 
 mod component;
+mod hotplug;
 mod machine;
 
 pub use component::*;
 use fluxemu_graphics::api::GraphicsApi;
+pub(crate) use hotplug::plug_component;
 pub use machine::*;
+use serde::Serialize;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SchedulerParticipation {
     /// [`crate::component::Component::synchronize`] will only be called upon interaction
     OnAccess,
@@ -71,6 +75,8 @@ impl<P: Platform> SealedMachineBuilder<P> {
 
         drop(runtime_guard);
 
+        self.machine.scheduler.event_manager.mark_initial();
+
         self.machine
     }
 }
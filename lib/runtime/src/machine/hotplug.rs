@@ -0,0 +1,137 @@
+use fluxemu_graphics::api::GraphicsApi;
+use thiserror::Error;
+
+use crate::{
+    component::config::ComponentConfig,
+    machine::{RuntimeGuard, builder},
+    memory::{MapTarget, MemoryMapCommand},
+    path::{ComponentPath, ResourcePath},
+    platform::Platform,
+    signal,
+};
+
+#[derive(Debug, Error)]
+pub enum HotplugError {
+    #[error("A component already exists at {0}")]
+    PathTaken(ComponentPath),
+    #[error("Could not build component {path}: {error}")]
+    Build {
+        path: ComponentPath,
+        error: Box<dyn std::error::Error>,
+    },
+    #[error("Component {0} tried to create memory regions, which cannot be done at runtime")]
+    CreatesMemory(ComponentPath),
+}
+
+impl RuntimeGuard<'_> {
+    /// Build a component and plug it into the running machine at `path`, as of the safe advance timestamp
+    ///
+    /// Everything the component registers while building, such as input devices, framebuffers, memory mappings and
+    /// events, becomes visible at once. The scheduler does not run while this happens.
+    ///
+    /// Graphics requirements of the component are not negotiated, so `graphics_initialization_data` must already
+    /// satisfy them
+    ///
+    /// Fails without changing the machine if a component already exists at `path`, if the component fails to build,
+    /// or if it tries to create memory regions
+    pub fn plug_component<P: Platform, B: ComponentConfig<P>>(
+        &self,
+        path: ComponentPath,
+        config: B,
+        graphics_initialization_data: <P::GraphicsApi as GraphicsApi>::InitializationData,
+    ) -> Result<(), HotplugError> {
+        let mut driven_guard = self.runtime.machine().scheduler.lock_components();
+
+        builder::plug_component(
+            &self.runtime,
+            &mut driven_guard,
+            path,
+            config,
+            graphics_initialization_data,
+        )
+    }
+
+    /// Take a component and all of its children out of the running machine, returning if it existed
+    ///
+    /// Their memory mappings, pending events and resources go with them. Ranges they were mapped to become unmapped, and
+    /// signal lines they drove settle without them.
+    pub fn unplug_component(&self, path: &ComponentPath) -> bool {
+        let machine = self.runtime.machine();
+        let component_registry = self.component_registry();
+
+        let mut driven_guard = machine.scheduler.lock_components();
+
+        let mut paths: Vec<_> = component_registry
+            .paths()
+            .into_iter()
+            .filter(|other| path.contains(other))
+            .collect();
+
+        if paths.is_empty() {
+            return false;
+        }
+
+        driven_guard.retain(|other| !path.contains(other));
+        machine
+            .scheduler
+            .event_manager
+            .remove_targeting(|other| path.contains(other));
+
+        for id in machine.address_spaces.keys() {
+            self.address_space(*id)
+                .unwrap()
+                .unmap_components(|other| path.contains(other));
+        }
+
+        for commands in machine.initial_memory_maps.lock().unwrap().values_mut() {
+            commands.retain(|command| {
+                !matches!(
+                    command,
+                    MemoryMapCommand::Map {
+                        target: MapTarget::Component(other),
+                        ..
+                    } if path.contains(other)
+                )
+            });
+        }
+
+        let is_owned =
+            |resource: &ResourcePath| resource.parent().is_some_and(|p| path.contains(p));
+        machine
+            .input_devices
+            .write()
+            .unwrap()
+            .retain(|resource, _| !is_owned(resource));
+        machine
+            .framebuffers
+            .write()
+            .unwrap()
+            .retain(|resource| !is_owned(resource));
+        machine
+            .audio_channels
+            .write()
+            .unwrap()
+            .retain(|resource| !is_owned(resource));
+        machine
+            .link_ports
+            .write()
+            .unwrap()
+            .retain(|resource, _| !is_owned(resource));
+
+        // Lines the components were pulling on settle without them
+        let signal_events = machine.signals.lock().unwrap().remove(is_owned);
+        signal::deliver(
+            &self.runtime,
+            machine.scheduler.safe_advance_timestamp(),
+            signal_events,
+        );
+
+        // Children first
+        paths.sort_by(|a, b| b.cmp(a));
+        for component_path in &paths {
+            component_registry.remove(component_path);
+        }
+
+        true
+    }
+}
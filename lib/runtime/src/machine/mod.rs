@@ -4,17 +4,18 @@
 
 use std::{
     cell::{RefCell, UnsafeCell},
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Debug,
     marker::PhantomData,
     ops::Deref,
     rc::{Rc, Weak},
-    sync::Arc,
+    sync::{Arc, Mutex, RwLock},
     time::Duration,
 };
 
 use fluxemu_input::{InputId, InputState};
 use fluxemu_program::{ProgramManager, ProgramSpecification};
+pub use hotplug::HotplugError;
 use num::FromPrimitive;
 use redb::{Database, backends::InMemoryBackend};
 use rustc_hash::FxBuildHasher;
@@ -22,17 +23,33 @@ use tracing::Level;
 
 use crate::{
     RuntimeHandle,
-    component::{ComponentRegistryData, LocalComponentRegistryData},
+    cheat::{self, Cheat, CheatError, CheatId, CheatRegistry},
+    clock::ClockTree,
+    component::{ComponentRegistryData, LocalComponentRegistryData, ResetKind},
     input::LogicalInputDevice,
+    link::{LinkMessageConstructor, OutgoingLinkMessage},
     machine::builder::MachineBuilder,
-    memory::{AddressSpaceData, AddressSpaceId, LocalMemoryRegistryData, MemoryRegistryData},
+    memory::{
+        AddressSpaceData, AddressSpaceId, LocalMemoryRegistryData, MemoryMapCommand,
+        MemoryRegistryData, WatchpointHit,
+    },
+    movie::{Movie, MovieError, MovieHeader, MoviePlayback, MovieRecord},
     path::ResourcePath,
     platform::{Platform, TestPlatform},
-    scheduler::{Period, Scheduler},
+    profiling::{Profiler, ThreadProfile},
+    scheduler::{Frequency, Period, Scheduler, SchedulerMode},
+    signal::SignalBoard,
+    snapshot::{Snapshot, SnapshotError},
 };
 
 /// Builder pattern constructor for a [`Machine`]
 pub mod builder;
+mod hotplug;
+#[cfg(test)]
+mod tests;
+
+/// How much emulated time passes between battery backed memory being persisted
+const SAVE_MEMORY_FLUSH_INTERVAL: Period = Period::lit("5");
 
 /// The main context of the runtime, encapsulating all state and resources for a running machine.
 #[derive(Debug)]
@@ -44,17 +61,40 @@ where
     /// Memory translation table
     pub(crate) address_spaces: HashMap<AddressSpaceId, AddressSpaceData, FxBuildHasher>,
     /// All virtual gamepads inserted by components
-    pub(crate) input_devices: HashMap<ResourcePath, Arc<LogicalInputDevice>, FxBuildHasher>,
+    pub(crate) input_devices: RwLock<HashMap<ResourcePath, Arc<LogicalInputDevice>, FxBuildHasher>>,
     /// Component Registry
     pub(crate) component_registry_data: ComponentRegistryData,
     /// Memory Registry
     pub(crate) memory_registry_data: MemoryRegistryData,
     /// All framebuffers this machine has
-    pub(crate) framebuffers: HashSet<ResourcePath>,
+    pub(crate) framebuffers: RwLock<HashSet<ResourcePath>>,
+    /// Frames per second the machine presents, if a component declared it
+    pub(crate) refresh_rate: Option<Frequency>,
     /// All audio outputs this machine has
-    pub(crate) audio_channels: HashSet<ResourcePath>,
+    pub(crate) audio_channels: RwLock<HashSet<ResourcePath>>,
     /// The program that this machine was set up with, if any
     pub(crate) program_specification: Option<ProgramSpecification>,
+    /// Where components plugged in at runtime load their ROMs from
+    pub(crate) program_manager: Arc<ProgramManager>,
+    /// Movie currently being recorded, if any
+    pub(crate) movie_recording: Mutex<Option<Movie>>,
+    /// Watchpoint hits that have not been looked at yet
+    pub(crate) watchpoint_hits: Mutex<Vec<WatchpointHit>>,
+    /// Cheats added at runtime, enabled or not
+    pub(crate) cheats: Mutex<CheatRegistry>,
+    /// Link ports components registered, and how to hand them what arrives
+    pub(crate) link_ports: RwLock<HashMap<ResourcePath, LinkMessageConstructor, FxBuildHasher>>,
+    /// Messages sent out of link ports, present only while the machine is part of a link
+    pub(crate) link_outbox: Mutex<Option<Vec<OutgoingLinkMessage>>>,
+    /// Signal pins components declared, and the levels on them
+    pub(crate) signals: Mutex<SignalBoard>,
+    /// Clock domains the machine was built with, kept for components plugged in later
+    pub(crate) clocks: ClockTree,
+    /// Per component statistics, gathered only while enabled
+    pub(crate) profiler: Profiler,
+    /// Memory maps the address spaces were set up with, plus those of components plugged in since, for hard resets
+    pub(crate) initial_memory_maps:
+        Mutex<HashMap<AddressSpaceId, Vec<MemoryMapCommand>, FxBuildHasher>>,
 }
 
 impl Machine {
@@ -114,6 +154,11 @@ impl Machine {
     pub fn program_specification(&self) -> Option<&ProgramSpecification> {
         self.program_specification.as_ref()
     }
+
+    /// Frames per second the machine presents, if any of its components declared it
+    pub fn refresh_rate(&self) -> Option<Frequency> {
+        self.refresh_rate
+    }
 }
 
 /// Guard for being inside the context of a runtime
@@ -142,7 +187,24 @@ impl RuntimeGuard<'_> {
         self.runtime
             .machine()
             .scheduler
-            .run(&mut registry, allocated_time)
+            .run(&mut registry, allocated_time);
+
+        let timestamp = self.safe_advance_timestamp();
+        cheat::write_ram(&self.runtime, &timestamp);
+
+        let memory_registry = self.memory_registry();
+
+        if timestamp.saturating_sub(memory_registry.last_save_memory_flush())
+            >= SAVE_MEMORY_FLUSH_INTERVAL
+        {
+            memory_registry.flush_save_memory(timestamp);
+        }
+    }
+
+    /// Persist battery backed memory right now, rather than waiting for the periodic flush
+    pub fn flush_save_memory(&self) {
+        self.memory_registry()
+            .flush_save_memory(self.safe_advance_timestamp());
     }
 
     /// Get the last safe time to advance any component to
@@ -155,15 +217,259 @@ impl RuntimeGuard<'_> {
         self.runtime.machine().scheduler.start_time()
     }
 
+    /// Take every watchpoint hit since the last call, in the order they happened
+    ///
+    /// Watchpoints themselves are managed through [`AddressSpace`](crate::memory::AddressSpace)
+    pub fn take_watchpoint_hits(&self) -> Vec<WatchpointHit> {
+        std::mem::take(&mut *self.runtime.machine().watchpoint_hits.lock().unwrap())
+    }
+
+    /// The timestamp the machine was paused at by a watchpoint, if it is paused
+    ///
+    /// While paused, [`Self::run`] will not advance the machine
+    pub fn paused_at(&self) -> Option<Period> {
+        self.runtime.machine().scheduler.pause_timestamp()
+    }
+
+    /// Let the machine run past the point it was paused at
+    pub fn resume(&self) {
+        self.runtime.machine().scheduler.resume();
+    }
+
+    /// How the scheduler currently drives components
+    pub fn scheduler_mode(&self) -> SchedulerMode {
+        self.runtime.machine().scheduler.mode()
+    }
+
+    /// Switch how the scheduler drives components, taking effect from the next run
+    ///
+    /// Switching to [`SchedulerMode::Parallel`] starts its worker threads, which stay around until switching away
+    pub fn set_scheduler_mode(&self, mode: SchedulerMode) {
+        self.runtime.machine().scheduler.set_mode(mode);
+    }
+
+    /// Reset the machine, see [`ResetKind`] for what each kind does
+    pub fn reset(&self, kind: ResetKind) {
+        let machine = self.runtime.machine();
+        let component_registry = self.component_registry();
+
+        // Sorted so components see the reset in the same order every time
+        let mut paths = component_registry.paths();
+        paths.sort();
+
+        match kind {
+            ResetKind::Soft => {
+                let timestamp = self.safe_advance_timestamp();
+
+                for path in &paths {
+                    component_registry.interact_dyn(path, &timestamp, |component| {
+                        component.reset(ResetKind::Soft);
+                    });
+                }
+            }
+            ResetKind::Hard => {
+                let start_time = machine.scheduler.start_time();
+
+                self.memory_registry().reinitialize();
+
+                for (id, commands) in machine.initial_memory_maps.lock().unwrap().iter() {
+                    self.address_space(*id)
+                        .unwrap()
+                        .replace_memory_map(&start_time, commands.iter().cloned());
+                }
+
+                for path in &paths {
+                    component_registry.set_timestamp(path, start_time).unwrap();
+                }
+
+                machine.scheduler.event_manager.restore_initial();
+                machine.signals.lock().unwrap().restore_initial();
+                machine.scheduler.set_safe_advance_timestamp(start_time);
+                machine.scheduler.resume();
+                machine.watchpoint_hits.lock().unwrap().clear();
+
+                for path in &paths {
+                    component_registry
+                        .interact_unsynchronized(path, |component| {
+                            component.reset(ResetKind::Hard);
+                        })
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    /// Add a cheat to the machine, enabled, or refuse it if it does not fit the machine
+    pub fn add_cheat(&self, cheat: Cheat) -> Result<CheatId, CheatError> {
+        let cheats = &self.runtime.machine().cheats;
+        let id = cheats.lock().unwrap().insert(cheat.clone());
+
+        if let Err(error) = cheat::apply(&self.runtime, id, &cheat, true) {
+            cheats.lock().unwrap().remove(id);
+
+            return Err(error);
+        }
+
+        cheats.lock().unwrap().set_enabled(id, true);
+
+        Ok(id)
+    }
+
+    /// Turn a cheat on or off, returning if the cheat exists
+    pub fn set_cheat_enabled(&self, id: CheatId, enabled: bool) -> bool {
+        let Some((cheat, changed)) = self
+            .runtime
+            .machine()
+            .cheats
+            .lock()
+            .unwrap()
+            .set_enabled(id, enabled)
+        else {
+            return false;
+        };
+
+        if changed {
+            cheat::apply(&self.runtime, id, &cheat, enabled)
+                .expect("Cheat was checked when it was added");
+        }
+
+        true
+    }
+
+    /// Remove a cheat, undoing any read override it had in place
+    pub fn remove_cheat(&self, id: CheatId) -> Option<Cheat> {
+        let (cheat, enabled) = self.runtime.machine().cheats.lock().unwrap().remove(id)?;
+
+        if enabled {
+            cheat::apply(&self.runtime, id, &cheat, false)
+                .expect("Cheat was checked when it was added");
+        }
+
+        Some(cheat)
+    }
+
+    /// Every cheat added to this machine and whether it is enabled
+    pub fn cheats(&self) -> Vec<(CheatId, Cheat, bool)> {
+        self.runtime
+            .machine()
+            .cheats
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, cheat, enabled)| (id, cheat.clone(), enabled))
+            .collect()
+    }
+
+    /// Capture the entire state of the machine
+    ///
+    /// Components are captured at whatever timestamp they are currently at, so this is best done between calls to [`Self::run`]
+    pub fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
+        Snapshot::capture(&self.runtime)
+    }
+
+    /// Restore the machine to the state captured in a snapshot
+    ///
+    /// The snapshot is validated against the machine before anything is modified, and if a component fails to restore its
+    /// own state the machine is rolled back to where it was
+    pub fn restore(&self, snapshot: &Snapshot) -> Result<(), SnapshotError> {
+        snapshot.apply(&self.runtime)
+    }
+
+    /// Begin recording every input inserted into the machine, discarding any recording already in progress
+    pub fn start_movie_recording(&self) {
+        let machine = self.runtime.machine();
+
+        *machine.movie_recording.lock().unwrap() = Some(Movie::new(MovieHeader::new(machine)));
+    }
+
+    /// Stop recording inputs, returning the movie if one was being recorded
+    pub fn stop_movie_recording(&self) -> Option<Movie> {
+        self.runtime
+            .machine()
+            .movie_recording
+            .lock()
+            .unwrap()
+            .take()
+    }
+
+    /// Prepare a movie for playback, checking that it was recorded on an identical machine
+    pub fn play_movie(&self, movie: Movie) -> Result<MoviePlayback, MovieError> {
+        movie.validate(self.runtime.machine())?;
+
+        Ok(MoviePlayback::new(movie))
+    }
+
+    /// Drive the scheduler like [`Self::run`], inserting the inputs of a movie at exactly the timestamps they were
+    /// recorded at
+    ///
+    /// If the machine is paused, playback stops where the machine did and picks up from there on the next call
+    pub fn run_movie(&self, playback: &mut MoviePlayback, allocated_time: Period) {
+        let target_timestamp = self.safe_advance_timestamp() + allocated_time;
+
+        while let Some(record) = playback.peek_due(target_timestamp) {
+            if self.paused_at().is_some() {
+                return;
+            }
+
+            let remaining = record
+                .timestamp
+                .saturating_sub(self.safe_advance_timestamp());
+
+            if remaining != Period::ZERO {
+                self.run(remaining);
+            }
+
+            // A watchpoint may have stopped the machine short of the record
+            if self.paused_at().is_some() || self.safe_advance_timestamp() < record.timestamp {
+                return;
+            }
+
+            self.insert_inputs(&record.path, record.inputs.iter().copied());
+            playback.advance();
+        }
+
+        let remaining = target_timestamp.saturating_sub(self.safe_advance_timestamp());
+
+        if remaining != Period::ZERO {
+            self.run(remaining);
+        }
+    }
+
     /// Insert inputs into the machine, storing them into the logical device state and directly giving input devices the
     /// new input change
+    ///
+    /// If a movie is being recorded, the inputs are added to it
     #[inline]
     pub fn insert_inputs(
         &self,
         path: &ResourcePath,
         inputs: impl IntoIterator<Item = (InputId, InputState)>,
     ) {
-        let logical_input_device = self.runtime.machine().input_devices.get(path).unwrap();
+        let logical_input_device = self
+            .runtime
+            .machine()
+            .input_devices
+            .read()
+            .unwrap()
+            .get(path)
+            .cloned()
+            .unwrap();
+        let inputs: Vec<_> = inputs.into_iter().collect();
+
+        if let Some(movie) = self
+            .runtime
+            .machine()
+            .movie_recording
+            .lock()
+            .unwrap()
+            .as_mut()
+        {
+            movie.record(MovieRecord {
+                timestamp: self.safe_advance_timestamp(),
+                path: path.clone(),
+                inputs: inputs.clone(),
+            });
+        }
 
         self.component_registry()
             .interact_dyn(
@@ -180,22 +486,53 @@ impl RuntimeGuard<'_> {
             .unwrap();
     }
 
-    /// List of paths to any audio outputs this machine was created with
+    /// List of paths to any audio outputs this machine currently has
     #[inline]
-    pub fn audio_outputs(&self) -> &HashSet<ResourcePath> {
-        &self.runtime.machine().audio_channels
+    pub fn audio_outputs(&self) -> HashSet<ResourcePath> {
+        self.runtime
+            .machine()
+            .audio_channels
+            .read()
+            .unwrap()
+            .clone()
     }
 
-    /// Input devices this machine was created with
+    /// Input devices this machine currently has
+    ///
+    /// These change as components are plugged in and unplugged
     #[inline]
-    pub fn input_devices(&self) -> &HashMap<ResourcePath, Arc<LogicalInputDevice>, FxBuildHasher> {
-        &self.runtime.machine().input_devices
+    pub fn input_devices(&self) -> HashMap<ResourcePath, Arc<LogicalInputDevice>, FxBuildHasher> {
+        self.runtime.machine().input_devices.read().unwrap().clone()
     }
 
-    /// Framebuffers this machine was created with
+    /// Framebuffers this machine currently has
     #[inline]
-    pub fn framebuffer_paths(&self) -> &HashSet<ResourcePath> {
-        &self.runtime.machine().framebuffers
+    pub fn framebuffer_paths(&self) -> HashSet<ResourcePath> {
+        self.runtime.machine().framebuffers.read().unwrap().clone()
+    }
+
+    /// Address spaces this machine was created with, in creation order
+    pub fn address_spaces(&self) -> Vec<AddressSpaceId> {
+        let mut address_spaces: Vec<_> = self
+            .runtime
+            .machine()
+            .address_spaces
+            .keys()
+            .copied()
+            .collect();
+        address_spaces.sort();
+
+        address_spaces
+    }
+
+    /// Copy out the contents of every memory region this machine was created with
+    pub fn memory_region_contents(&self) -> BTreeMap<ResourcePath, Vec<u8>> {
+        let memory_registry = self.memory_registry();
+
+        memory_registry
+            .regions()
+            .map(|(path, id)| (path.clone(), memory_registry.read_region(id)))
+            .collect()
     }
 }
 
@@ -233,6 +570,7 @@ impl<'a> Drop for RuntimeGuard<'a> {
 pub(crate) struct ThreadLocalData {
     pub component_registry_data: UnsafeCell<LocalComponentRegistryData>,
     pub memory_registry_data: UnsafeCell<LocalMemoryRegistryData>,
+    pub profile: ThreadProfile,
     // Ensure this is !Send and !Sync
     _phantom: PhantomData<*const ()>,
 }
@@ -246,6 +584,7 @@ impl ThreadLocalData {
             memory_registry_data: UnsafeCell::new(LocalMemoryRegistryData::new(
                 &machine.memory_registry_data,
             )),
+            profile: machine.profiler.thread_profile(),
             _phantom: PhantomData,
         }
     }
@@ -0,0 +1,437 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    ops::RangeInclusive,
+    path::Path,
+};
+
+use bytes::Bytes;
+use fluxemu_math::range::ContiguousRange;
+use fluxemu_program::{ProgramId, ProgramInfo, ProgramManager, ProgramSpecification, SystemId};
+use redb::{Database, backends::InMemoryBackend};
+
+use crate::{
+    component::{Component, ResetKind, config::ComponentConfig},
+    event::{Event, EventMode},
+    machine::{
+        HotplugError, Machine,
+        builder::{ComponentBuilder, SchedulerParticipation},
+    },
+    memory::{Address, AddressSpaceId, MapTarget, MemoryError, MemoryMapCommand, Permissions},
+    path::ComponentPath,
+    platform::{Platform, TestPlatform},
+    scheduler::{Frequency, Period, SchedulerMode, SynchronizationContext},
+};
+
+const TICK: Period = Period::lit("0.001");
+
+/// Answers every read with the same byte
+#[derive(Debug)]
+struct Latch(u8);
+
+impl Component for Latch {
+    type Event = ();
+
+    fn memory_read(
+        &mut self,
+        _address: Address,
+        _address_space: AddressSpaceId,
+        _avoid_side_effects: bool,
+        buffer: &mut [u8],
+    ) -> Result<(), MemoryError> {
+        buffer.fill(self.0);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct LatchConfig {
+    address_space: AddressSpaceId,
+    value: u8,
+}
+
+impl<P: Platform> ComponentConfig<P> for LatchConfig {
+    type Component = Latch;
+
+    fn build_component(
+        self,
+        component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn Error>> {
+        let path = component_builder.path().clone();
+
+        let (component_builder, _) = component_builder.input("port", [], []);
+        component_builder.map_memory(
+            self.address_space,
+            [MemoryMapCommand::Map {
+                range: 0x10..=0x1f,
+                permissions: Permissions::READ,
+                target: MapTarget::Component(path),
+            }],
+        );
+
+        Ok(Latch(self.value))
+    }
+}
+
+/// Counts up a byte at the start of its address space every tick, starting over on every event
+#[derive(Debug)]
+struct Ticker {
+    address_space: AddressSpaceId,
+    count: u8,
+}
+
+impl Component for Ticker {
+    type Event = ();
+
+    fn synchronize(&mut self, mut context: SynchronizationContext) {
+        let mut address_space = context.runtime().address_space(self.address_space).unwrap();
+        let mut allocator = context.quanta_allocator(TICK);
+
+        while let Some(timestamp) = allocator.allocate() {
+            self.count = self.count.wrapping_add(1);
+            address_space
+                .write_le_value(0, timestamp, self.count)
+                .unwrap();
+        }
+    }
+
+    fn needs_work(&self, _current_timestamp: &Period, delta: &Period) -> bool {
+        *delta >= TICK
+    }
+
+    fn handle_event(&mut self, _event: Box<dyn Event>) {
+        self.count = 0;
+    }
+}
+
+#[derive(Debug)]
+struct TickerConfig {
+    address_space: AddressSpaceId,
+    isolated_to: Option<AddressSpaceId>,
+    reset_frequency: Option<Frequency>,
+}
+
+impl<P: Platform> ComponentConfig<P> for TickerConfig {
+    type Component = Ticker;
+
+    fn build_component(
+        self,
+        component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn Error>> {
+        let path = component_builder.path().clone();
+
+        let mut component_builder = component_builder
+            .scheduler_participation(Some(SchedulerParticipation::SchedulerDriven));
+
+        if let Some(address_space) = self.isolated_to {
+            component_builder = component_builder.isolated_to([address_space]);
+        }
+
+        if let Some(frequency) = self.reset_frequency {
+            let _ = component_builder.schedule_event::<Ticker>(
+                &path,
+                frequency.recip(),
+                EventMode::Repeating { frequency },
+                (),
+            );
+        }
+
+        Ok(Ticker {
+            address_space: self.address_space,
+            count: 0,
+        })
+    }
+}
+
+#[test]
+fn hard_reset_restores_power_on_state() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let (machine, work_ram_path) = machine.memory(
+        "work-ram",
+        0x100,
+        [(0..=3, Bytes::from_static(&[1, 2, 3, 4]))],
+    );
+    let (machine, battery_ram_path) = machine.save_memory("battery-ram", 0x100, []);
+    let machine = machine.map_memory(
+        address_space,
+        [
+            MemoryMapCommand::Map {
+                range: RangeInclusive::from_start_and_length(0, 0x100),
+                permissions: Permissions::ALL,
+                target: MapTarget::Memory {
+                    path: work_ram_path,
+                    subrange: None,
+                },
+            },
+            MemoryMapCommand::Map {
+                range: RangeInclusive::from_start_and_length(0x100, 0x100),
+                permissions: Permissions::ALL,
+                target: MapTarget::Memory {
+                    path: battery_ram_path,
+                    subrange: None,
+                },
+            },
+        ],
+    );
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+    let mut memory = runtime_guard.address_space(address_space).unwrap();
+
+    memory.write(0x0000, &Period::ZERO, &[9; 4]).unwrap();
+    memory.write(0x0100, &Period::ZERO, &[9; 4]).unwrap();
+    memory.remap(
+        &Period::ZERO,
+        [MemoryMapCommand::Unmap {
+            range: 0x0100..=0x01ff,
+            permissions: Permissions::ALL,
+        }],
+    );
+    runtime_guard.run(Period::ONE);
+
+    let mut buffer = [0; 4];
+
+    runtime_guard.reset(ResetKind::Soft);
+    memory
+        .read::<_, false>(0x0000, &Period::ONE, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [9; 4]);
+
+    runtime_guard.reset(ResetKind::Hard);
+    assert_eq!(
+        runtime_guard.safe_advance_timestamp(),
+        runtime_guard.start_time()
+    );
+
+    memory
+        .read::<_, false>(0x0000, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [1, 2, 3, 4]);
+
+    // Battery backed memory survives a power cycle, and its mapping is back
+    memory
+        .read::<_, false>(0x0100, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [9; 4]);
+}
+
+#[test]
+fn save_memory_survives_rebuilding_the_machine() {
+    let save_directory = tempfile::tempdir().unwrap();
+    let program_manager = ProgramManager::new(
+        Database::builder()
+            .create_with_backend(InMemoryBackend::default())
+            .unwrap(),
+        [],
+    )
+    .unwrap();
+    let program_specification = ProgramSpecification {
+        id: ProgramId {
+            system: SystemId::Unknown,
+            name: "Battery".to_string(),
+        },
+        info: ProgramInfo::V1 {
+            names: BTreeSet::from_iter(["Battery".to_string()]),
+            filesystem: BTreeMap::default(),
+            languages: BTreeSet::default(),
+            version: None,
+            quirks: None,
+        },
+    };
+
+    let build = |save_directory: &Path| {
+        let (machine, address_space) =
+            Machine::build_test(Some(program_specification.clone()), program_manager.clone())
+                .save_directory(save_directory)
+                .address_space(16);
+        let (machine, battery_ram_path) = machine.save_memory("battery-ram", 0x100, []);
+        let machine = machine.map_memory(
+            address_space,
+            [MemoryMapCommand::Map {
+                range: 0..=0xff,
+                permissions: Permissions::ALL,
+                target: MapTarget::Memory {
+                    path: battery_ram_path,
+                    subrange: None,
+                },
+            }],
+        );
+
+        (machine.seal().build(()), address_space)
+    };
+
+    let (machine, address_space) = build(save_directory.path());
+    let runtime_guard = machine.enter_runtime();
+    runtime_guard
+        .address_space(address_space)
+        .unwrap()
+        .write(0x10, &Period::ZERO, &[1, 2, 3, 4])
+        .unwrap();
+    drop(runtime_guard);
+    drop(machine);
+
+    let (machine, address_space) = build(save_directory.path());
+    let runtime_guard = machine.enter_runtime();
+    let mut buffer = [0; 4];
+    runtime_guard
+        .address_space(address_space)
+        .unwrap()
+        .read::<_, false>(0x10, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [1, 2, 3, 4]);
+}
+
+#[test]
+fn components_plug_and_unplug_at_runtime() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+    let mut memory = runtime_guard.address_space(address_space).unwrap();
+    let latch_path = ComponentPath::new("latch").unwrap();
+
+    runtime_guard.run(Period::ONE);
+    runtime_guard
+        .plug_component::<TestPlatform, _>(
+            latch_path.clone(),
+            LatchConfig {
+                address_space,
+                value: 0x5a,
+            },
+            (),
+        )
+        .unwrap();
+
+    let mut buffer = [0; 2];
+    memory
+        .read::<_, false>(0x10, &Period::ONE, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [0x5a; 2]);
+    assert_eq!(runtime_guard.input_devices().len(), 1);
+
+    // A second component at the same path is refused without disturbing the first
+    assert!(matches!(
+        runtime_guard.plug_component::<TestPlatform, _>(
+            latch_path.clone(),
+            LatchConfig {
+                address_space,
+                value: 0xa5,
+            },
+            (),
+        ),
+        Err(HotplugError::PathTaken(_))
+    ));
+    memory
+        .read::<_, false>(0x10, &Period::ONE, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [0x5a; 2]);
+
+    // Plugged components are part of the machine's power on state from now on
+    runtime_guard.reset(ResetKind::Hard);
+    memory
+        .read::<_, false>(0x10, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [0x5a; 2]);
+
+    assert!(runtime_guard.unplug_component(&latch_path));
+    assert!(!runtime_guard.unplug_component(&latch_path));
+    assert!(
+        memory
+            .read::<_, false>(0x10, &Period::ZERO, &mut buffer)
+            .is_err()
+    );
+    assert!(runtime_guard.input_devices().is_empty());
+
+    runtime_guard.reset(ResetKind::Hard);
+    runtime_guard.run(Period::ONE);
+    assert!(
+        memory
+            .read::<_, false>(0x10, &Period::ONE, &mut buffer)
+            .is_err()
+    );
+}
+
+#[test]
+fn parallel_scheduler_matches_sequential() {
+    let run = |mode| {
+        let mut machine = Machine::build_test_minimal().scheduler_mode(mode);
+
+        for (index, (isolated, reset_frequency)) in [
+            (true, None),
+            (true, None),
+            (false, Some(Frequency::lit("7"))),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let (builder, address_space) = machine.address_space(16);
+            let (builder, ram_path) = builder.memory(format!("ram-{index}"), 0x100, []);
+            let builder = builder.map_memory(
+                address_space,
+                [MemoryMapCommand::Map {
+                    range: 0..=0xff,
+                    permissions: Permissions::ALL,
+                    target: MapTarget::Memory {
+                        path: ram_path,
+                        subrange: None,
+                    },
+                }],
+            );
+
+            (machine, _) = builder.component(
+                format!("ticker-{index}"),
+                TickerConfig {
+                    address_space,
+                    isolated_to: isolated.then_some(address_space),
+                    reset_frequency,
+                },
+            );
+        }
+
+        let machine = machine.seal().build(());
+        let runtime_guard = machine.enter_runtime();
+        for _ in 0..4 {
+            runtime_guard.run(Period::lit("0.3"));
+        }
+
+        runtime_guard.memory_region_contents()
+    };
+
+    assert_eq!(run(SchedulerMode::Parallel), run(SchedulerMode::Sequential));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "did not declare")]
+fn isolated_components_keep_to_their_address_spaces() {
+    let (machine, address_space) = Machine::build_test_minimal()
+        .scheduler_mode(SchedulerMode::Parallel)
+        .address_space(16);
+    let (machine, declared_address_space) = machine.address_space(16);
+    let (machine, ram_path) = machine.memory("ram", 0x100, []);
+    let (machine, _) = machine
+        .map_memory(
+            address_space,
+            [MemoryMapCommand::Map {
+                range: 0..=0xff,
+                permissions: Permissions::ALL,
+                target: MapTarget::Memory {
+                    path: ram_path,
+                    subrange: None,
+                },
+            }],
+        )
+        .component(
+            "ticker",
+            TickerConfig {
+                address_space,
+                isolated_to: Some(declared_address_space),
+                reset_frequency: None,
+            },
+        );
+
+    let machine = machine.seal().build(());
+    machine.enter_runtime().run(Period::lit("0.3"));
+}
@@ -0,0 +1,187 @@
+use fluxemu_math::range::ContiguousRange;
+use num::traits::{FromBytes, ToBytes};
+
+use crate::{
+    memory::{Address, AddressSpace, AddressSpaceData, MemoryError, MemoryErrorType},
+    scheduler::Period,
+};
+
+/// How many bits the data bus of a address space moves per access
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BusWidth {
+    #[default]
+    Bits8,
+    Bits16,
+    Bits32,
+}
+
+impl BusWidth {
+    pub const fn bytes(self) -> usize {
+        match self {
+            BusWidth::Bits8 => 1,
+            BusWidth::Bits16 => 2,
+            BusWidth::Bits32 => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+/// What happens to an access whose address is not a multiple of its size, or the bus width if it is wider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MisalignedAccess {
+    /// The access goes through as is, split across the bus words it touches
+    #[default]
+    Split,
+    /// The low address bits are ignored, moving the access down to the aligned address
+    ForceAlign,
+    /// The access fails with [`MemoryErrorType::Misaligned`]
+    Fault,
+}
+
+/// Shape of the data bus of a address space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DataBus {
+    pub width: BusWidth,
+    /// Byte order used by [`AddressSpace::read_value`] and [`AddressSpace::write_value`]
+    pub endianness: Endianness,
+    pub misaligned: MisalignedAccess,
+}
+
+impl DataBus {
+    /// Alignment an access of `length` bytes is expected to have
+    #[inline]
+    fn alignment(&self, length: usize) -> usize {
+        1 << length.min(self.width.bytes()).ilog2()
+    }
+}
+
+impl AddressSpaceData {
+    pub(crate) fn set_data_bus(&mut self, data_bus: DataBus) {
+        self.data_bus = Some(data_bus);
+        // Byte wide buses are always aligned, and splitting leaves the address as is
+        self.enforces_alignment =
+            data_bus.width != BusWidth::Bits8 && data_bus.misaligned != MisalignedAccess::Split;
+    }
+
+    /// Apply the misaligned access policy, returning where the access should actually go
+    #[inline]
+    pub(crate) fn align_access(
+        &self,
+        address: Address,
+        length: usize,
+    ) -> Result<Address, MemoryError> {
+        if !self.enforces_alignment {
+            return Ok(address);
+        }
+
+        self.enforce_alignment(address, length)
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn enforce_alignment(&self, address: Address, length: usize) -> Result<Address, MemoryError> {
+        let Some(data_bus) = &self.data_bus else {
+            return Ok(address);
+        };
+
+        if length == 0 {
+            return Ok(address);
+        }
+
+        let alignment = data_bus.alignment(length);
+
+        if address.is_multiple_of(alignment) {
+            return Ok(address);
+        }
+
+        match data_bus.misaligned {
+            MisalignedAccess::Split => Ok(address),
+            MisalignedAccess::ForceAlign => Ok(address & !(alignment - 1)),
+            MisalignedAccess::Fault => Err(MemoryError(
+                std::iter::once((
+                    std::ops::RangeInclusive::from_start_and_length(address, length),
+                    MemoryErrorType::Misaligned,
+                ))
+                .collect(),
+            )),
+        }
+    }
+}
+
+impl<'a> AddressSpace<'a> {
+    pub fn data_bus(&self) -> Option<&DataBus> {
+        self.data.data_bus.as_ref()
+    }
+
+    fn endianness(&self) -> Endianness {
+        self.data
+            .data_bus
+            .map(|data_bus| data_bus.endianness)
+            .unwrap_or_default()
+    }
+
+    /// Convenience method for reading a value in the native byte order of the data bus
+    ///
+    /// Address spaces without a configured data bus are treated as little endian
+    #[inline]
+    pub fn read_value<T: FromBytes, const AVOID_SIDE_EFFECTS: bool>(
+        &mut self,
+        address: Address,
+        current_timestamp: &Period,
+    ) -> Result<T, MemoryError>
+    where
+        T::Bytes: Default,
+    {
+        match self.endianness() {
+            Endianness::Little => {
+                self.read_le_value::<T, AVOID_SIDE_EFFECTS>(address, current_timestamp)
+            }
+            Endianness::Big => {
+                self.read_be_value::<T, AVOID_SIDE_EFFECTS>(address, current_timestamp)
+            }
+        }
+    }
+
+    /// Convenience method for fetching a value in the native byte order of the data bus, see [`fetch`](Self::fetch)
+    ///
+    /// Address spaces without a configured data bus are treated as little endian
+    #[inline]
+    pub fn fetch_value<T: FromBytes>(
+        &mut self,
+        address: Address,
+        current_timestamp: &Period,
+    ) -> Result<T, MemoryError>
+    where
+        T::Bytes: Default,
+    {
+        let mut buffer = T::Bytes::default();
+        self.fetch(address, current_timestamp, &mut buffer)?;
+
+        Ok(match self.endianness() {
+            Endianness::Little => T::from_le_bytes(&buffer),
+            Endianness::Big => T::from_be_bytes(&buffer),
+        })
+    }
+
+    /// Convenience method for writing a value in the native byte order of the data bus
+    ///
+    /// Address spaces without a configured data bus are treated as little endian
+    #[inline]
+    pub fn write_value<T: ToBytes>(
+        &mut self,
+        address: Address,
+        current_timestamp: &Period,
+        value: T,
+    ) -> Result<(), MemoryError> {
+        match self.endianness() {
+            Endianness::Little => self.write_le_value(address, current_timestamp, value),
+            Endianness::Big => self.write_be_value(address, current_timestamp, value),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    introspection::{MemoryMapEntry, MemoryMapTarget},
+    memory::{Address, AddressSpaceData, MasterTableEntry, Permissions},
+};
+
+impl AddressSpaceData {
+    pub(crate) fn width(&self) -> u8 {
+        self.address_space_width
+    }
+
+    /// The memory map as it currently stands, after every remap so far has been applied
+    ///
+    /// Ranges mapped identically for reads and writes are reported once with both permissions
+    pub(crate) fn memory_map(&self) -> Vec<MemoryMapEntry> {
+        let master_tables_guard = self.master.lock().unwrap();
+
+        let mut reads = master_tables_guard
+            .read
+            .iter()
+            .map(|(range, entry)| (range.clone(), map_target(*range.start(), entry)))
+            .peekable();
+        let mut writes = master_tables_guard
+            .write
+            .iter()
+            .map(|(range, entry)| (range.clone(), map_target(*range.start(), entry)))
+            .peekable();
+        let mut entries = Vec::new();
+
+        // Both tables are sorted by address, so walk them side by side, preferring reads for ranges starting at the same address
+        loop {
+            let permissions = match (reads.peek(), writes.peek()) {
+                (None, None) => break,
+                (Some(read), Some(write)) if read == write => Permissions::ALL,
+                (Some((read_range, _)), Some((write_range, _)))
+                    if write_range.start() < read_range.start() =>
+                {
+                    Permissions::WRITE
+                }
+                (Some(_), _) => Permissions::READ,
+                (None, Some(_)) => Permissions::WRITE,
+            };
+
+            let (range, target) = if permissions.read {
+                if permissions.write {
+                    writes.next();
+                }
+
+                reads.next().unwrap()
+            } else {
+                writes.next().unwrap()
+            };
+
+            entries.push(MemoryMapEntry {
+                range,
+                permissions,
+                target,
+            });
+        }
+
+        entries
+    }
+}
+
+fn map_target(start: Address, entry: &MasterTableEntry) -> MemoryMapTarget {
+    match entry {
+        MasterTableEntry::Memory {
+            path,
+            source_base,
+            region_base,
+            ..
+        } => MemoryMapTarget::Memory {
+            path: path.clone(),
+            offset: region_base + (start - source_base),
+        },
+        MasterTableEntry::ImmutableMemory { .. } => MemoryMapTarget::ImmutableMemory,
+        MasterTableEntry::Component(path) => MemoryMapTarget::Component(path.clone()),
+        MasterTableEntry::Mirror {
+            source_base,
+            destination_base,
+        } => MemoryMapTarget::Mirror {
+            destination: destination_base + (start - source_base),
+        },
+    }
+}
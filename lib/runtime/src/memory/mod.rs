@@ -1,30 +1,48 @@
 use std::{
+    borrow::Cow,
+    collections::BTreeMap,
     fmt::Debug,
     hash::Hash,
     ops::RangeInclusive,
     sync::{Arc, Mutex, atomic::Ordering},
 };
 
+pub use bus::{BusWidth, DataBus, Endianness, MisalignedAccess};
 use bytes::Bytes;
 use fluxemu_math::range::ContiguousRange;
-use rangemap::RangeInclusiveMap;
+pub use open_bus::OpenBusDecay;
+use rangemap::{RangeInclusiveMap, RangeInclusiveSet};
 pub(crate) use registry::{
     LocalMemoryRegistryData, MemoryId, MemoryRegistryData, RegionInitializationData,
 };
+pub use remap::{MappingPresetError, MappingPresetId};
 use sdd::{AtomicOwned, Guard};
+use serde::Serialize;
 use thin_vec::ThinVec;
 use thiserror::Error;
+pub use watchpoint::{WatchpointHit, WatchpointId, WatchpointKind};
 
 use crate::{
-    ResourcePath, RuntimeHandle, component::ComponentId, memory::registry::MemoryRegistry,
-    path::ComponentPath, scheduler::Period,
+    ResourcePath, RuntimeHandle,
+    cheat::{CheatCode, CheatId},
+    component::ComponentId,
+    memory::{
+        open_bus::OpenBus, registry::MemoryRegistry, remap::MappingPreset, watchpoint::Watchpoint,
+    },
+    path::ComponentPath,
+    scheduler::Period,
 };
 
+mod bus;
+mod introspection;
+mod open_bus;
 mod ops;
+mod overlay;
 mod registry;
 mod remap;
 #[cfg(test)]
 mod tests;
+mod watchpoint;
 
 pub type Address = usize;
 const CHUNK_SIZE: Address = 0x1000;
@@ -38,6 +56,7 @@ pub struct AddressSpace<'a> {
     runtime: &'a RuntimeHandle,
     data: &'a AddressSpaceData,
     guard: Guard,
+    wait_states: u32,
 }
 
 impl<'a> AddressSpace<'a> {
@@ -47,6 +66,7 @@ impl<'a> AddressSpace<'a> {
             runtime,
             data,
             guard: Guard::new(),
+            wait_states: 0,
         }
     }
 
@@ -59,7 +79,8 @@ impl<'a> AddressSpace<'a> {
     ///   is completing
     /// - If two remappings from different threads are done at the same time, its unspecified which one "wins"
     /// - As of the current implementation, remapping is somewhat expensive. Much of the overhead is from the overhead of the remap setup itself.
-    ///   Group together commands into as large of lists as you can.
+    ///   Group together commands into as large of lists as you can, or register mapping presets for states that are switched between
+    ///   often, see [`AddressSpace::switch_preset`]
     #[inline]
     pub fn remap(
         &mut self,
@@ -74,19 +95,70 @@ impl<'a> AddressSpace<'a> {
         self.data
             .remap(timestamp, &self.guard, self.runtime, commands);
     }
+
+    /// Throw away the entire memory map and build it up again from the commands given
+    pub(crate) fn replace_memory_map(
+        &mut self,
+        timestamp: &Period,
+        commands: impl IntoIterator<Item = MemoryMapCommand>,
+    ) {
+        let unmap_everything = MemoryMapCommand::Unmap {
+            range: 0..=self.data.width_mask,
+            permissions: Permissions::ALL,
+        };
+
+        self.remap(timestamp, std::iter::once(unmap_everything).chain(commands));
+    }
+
+    pub(crate) fn compile_preset(
+        &self,
+        name: Cow<'static, str>,
+        commands: impl IntoIterator<Item = MemoryMapCommand>,
+    ) {
+        self.data
+            .compile_preset(name, commands, &self.guard, self.runtime);
+    }
+
+    /// Unmap every range that targets a component matching the predicate, leaving whatever else was mapped alone
+    pub(crate) fn unmap_components(&mut self, predicate: impl Fn(&ComponentPath) -> bool) {
+        self.data
+            .unmap_components(&self.guard, self.runtime, predicate);
+    }
 }
 
 impl<'a> AddressSpace<'a> {
     pub fn id(&self) -> AddressSpaceId {
         self.data.id
     }
+
+    /// Highest address this address space can decode
+    pub(crate) fn width_mask(&self) -> Address {
+        self.data.width_mask
+    }
+
+    /// Take the wait states accumulated by accesses through this handle since the last call
+    ///
+    /// Processors should stall for this many cycles after an access to model slow or contended memory. Accesses made
+    /// while avoiding side effects do not accumulate any
+    #[inline]
+    pub fn take_wait_states(&mut self) -> u32 {
+        std::mem::take(&mut self.wait_states)
+    }
 }
 
 #[derive(Clone, Debug)]
 enum PageTableTarget {
     ImmutableMemory(Bytes),
-    Memory { offset: Address, id: MemoryId },
-    Component { offset: Address, id: ComponentId },
+    Memory {
+        offset: Address,
+        id: MemoryId,
+    },
+    Component {
+        offset: Address,
+        id: ComponentId,
+    },
+    /// Only present while watchpoints or cheats cover the range, so ordinary accesses never pay for the check
+    Hooked(Box<PageTableTarget>),
 }
 
 #[derive(Debug, Clone)]
@@ -94,6 +166,7 @@ struct PageTableEntry {
     /// Full, uncropped relevant range
     pub range: std::range::RangeInclusive<Address>,
     pub target: PageTableTarget,
+    pub wait_states: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -109,7 +182,7 @@ impl PageTable {
 }
 
 /// Identifier for a address space
-#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize)]
 pub struct AddressSpaceId(pub(crate) u16);
 
 /// Why a memory operation failed
@@ -118,11 +191,15 @@ pub enum MemoryErrorType {
     /// Access was denied
     Denied,
     /// Nothing is mapped there
+    ///
+    /// Not reported for reads on address spaces with open bus emulation enabled
     OutOfBus,
     /// It would be impossible to view this memory without a state change
     ///
     /// Only applicable for read operations
     Impossible,
+    /// The address is not aligned for the size of the access, on a address space that faults on misaligned accesses
+    Misaligned,
 }
 
 /// Wrapper around the error type in order to specify ranges
@@ -145,6 +222,28 @@ impl Debug for MemoryError {
 struct MasterTables {
     read: RangeInclusiveMap<Address, MasterTableEntry>,
     write: RangeInclusiveMap<Address, MasterTableEntry>,
+    watchpoints: BTreeMap<WatchpointId, Watchpoint>,
+    next_watchpoint_index: u32,
+    read_overrides: BTreeMap<CheatId, CheatCode>,
+    read_wait_states: RangeInclusiveMap<Address, u32>,
+    write_wait_states: RangeInclusiveMap<Address, u32>,
+    presets: Vec<Arc<MappingPreset>>,
+    /// Preset each range of the read master table was last switched to, for as long as nothing is mapped over it
+    read_preset_owners: RangeInclusiveMap<Address, u16>,
+    write_preset_owners: RangeInclusiveMap<Address, u16>,
+}
+
+impl MasterTables {
+    /// Ranges of the read or write page table that must go through the hooked access path
+    fn hooked_ranges(&self, write: bool) -> RangeInclusiveSet<Address> {
+        let mut ranges = self.watched_ranges(write);
+
+        if !write {
+            ranges.extend(self.overridden_ranges());
+        }
+
+        ranges
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -155,7 +254,11 @@ enum MasterTableEntry {
         region_base: Address,
         length: usize,
     },
-    ImmutableMemory(Bytes),
+    ImmutableMemory {
+        /// Address the first byte is mapped to, so the entry stays valid when the range is split
+        source_base: Address,
+        bytes: Bytes,
+    },
     Component(ComponentPath),
     Mirror {
         source_base: Address,
@@ -211,6 +314,10 @@ pub(crate) struct AddressSpaceData {
     read_table: AtomicOwned<PageTable>,
     write_table: AtomicOwned<PageTable>,
     master: Mutex<MasterTables>,
+    open_bus: Option<OpenBus>,
+    data_bus: Option<DataBus>,
+    /// If the data bus has a misaligned access policy that can move or fail accesses
+    enforces_alignment: bool,
 }
 
 impl AddressSpaceData {
@@ -229,9 +336,17 @@ impl AddressSpaceData {
             read_table: AtomicOwned::new(PageTable::new(width)),
             write_table: AtomicOwned::new(PageTable::new(width)),
             master: Mutex::default(),
+            open_bus: None,
+            data_bus: None,
+            enforces_alignment: false,
         }
     }
 
+    /// Make unmapped reads return the value left on the bus instead of failing
+    pub(crate) fn set_open_bus(&mut self, decay: OpenBusDecay) {
+        self.open_bus = Some(OpenBus::new(decay));
+    }
+
     #[inline]
     fn get_read_table<'a>(&'a self, guard: &'a Guard) -> &'a PageTable {
         // SAFETY: We never set an null members mapping, and we don't set any tag bits
@@ -273,6 +388,8 @@ pub enum MapTarget {
 #[derive(Debug, Clone)]
 pub enum MemoryMapCommand {
     /// Add a target to the memory map, or add a map to an existing one
+    ///
+    /// Accesses to the range cost nothing extra until a [`MemoryMapCommand::WaitStates`] says otherwise
     Map {
         range: RangeInclusive<Address>,
         permissions: Permissions,
@@ -283,6 +400,14 @@ pub enum MemoryMapCommand {
         range: RangeInclusive<Address>,
         permissions: Permissions,
     },
+    /// Charge extra cycles for accesses to an already mapped range, see [`AddressSpace::take_wait_states`]
+    ///
+    /// Mapping or unmapping the range again clears them
+    WaitStates {
+        range: RangeInclusive<Address>,
+        permissions: Permissions,
+        cycles: u32,
+    },
 }
 
 impl MemoryMapCommand {
@@ -338,7 +463,7 @@ impl MemoryMapCommand {
 }
 
 #[allow(missing_docs)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Permissions {
     pub read: bool,
     pub write: bool,
@@ -0,0 +1,142 @@
+use std::sync::atomic::{AtomicU8, AtomicU32, Ordering};
+
+use crate::{
+    memory::{AddressSpace, AddressSpaceData},
+    scheduler::Period,
+};
+
+/// What happens to the value left on a data bus while nothing is driving it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OpenBusDecay {
+    /// The last driven value is held indefinitely
+    #[default]
+    Hold,
+    /// Once undriven for `period`, the bus settles to `value`
+    Settle { period: Period, value: u8 },
+}
+
+/// Tracks the last value driven onto the data bus of a address space
+///
+/// Every access drives the bus, so this is lock free. The value and timestamp are stored separately, which is fine as
+/// concurrent accesses to the same bus have no defined order anyway
+#[derive(Debug)]
+pub(crate) struct OpenBus {
+    decay: OpenBusDecay,
+    /// Timestamps are kept as wrapping ticks of `1 << tick_shift` raw [`Period`] bits, at least 256 to a decay period
+    tick_shift: u32,
+    /// Ticks the bus has to be left undriven for before it settles
+    settle_ticks: u32,
+    /// The last driven byte
+    value: AtomicU8,
+    /// When the last byte was driven, only kept up to date when the bus decays
+    driven_at: AtomicU32,
+}
+
+impl OpenBus {
+    pub fn new(decay: OpenBusDecay) -> Self {
+        let (tick_shift, settle_ticks) = match decay {
+            OpenBusDecay::Hold => (0, 0),
+            OpenBusDecay::Settle { period, .. } => {
+                let period = period.to_bits().max(1);
+                let tick_shift = (period / 256).checked_ilog2().unwrap_or(0);
+
+                (tick_shift, (period >> tick_shift) as u32)
+            }
+        };
+
+        Self {
+            decay,
+            tick_shift,
+            settle_ticks,
+            value: AtomicU8::new(0),
+            driven_at: AtomicU32::new(0),
+        }
+    }
+
+    /// Wraps every few million decay periods, far longer than any bus goes undriven while something is running
+    #[inline]
+    fn ticks(&self, timestamp: &Period) -> u32 {
+        (timestamp.to_bits() >> self.tick_shift) as u32
+    }
+
+    #[inline]
+    pub fn drive(&self, value: u8, timestamp: &Period) {
+        self.value.store(value, Ordering::Relaxed);
+
+        if matches!(self.decay, OpenBusDecay::Settle { .. }) {
+            self.driven_at
+                .store(self.ticks(timestamp), Ordering::Relaxed);
+        }
+    }
+
+    /// The value a undriven read would see at `timestamp`
+    pub fn value(&self, timestamp: &Period) -> u8 {
+        let value = self.value.load(Ordering::Relaxed);
+
+        match self.decay {
+            OpenBusDecay::Hold => value,
+            OpenBusDecay::Settle { value: settled, .. } => {
+                // Reads from before the last drive come out negative
+                let elapsed = self
+                    .ticks(timestamp)
+                    .wrapping_sub(self.driven_at.load(Ordering::Relaxed))
+                    as i32;
+
+                if elapsed >= 0 && elapsed as u32 >= self.settle_ticks {
+                    settled
+                } else {
+                    value
+                }
+            }
+        }
+    }
+
+    /// The last driven byte and when it was driven, truncated to the ticks the bus keeps
+    pub fn state(&self) -> (u8, Period) {
+        (
+            self.value.load(Ordering::Relaxed),
+            Period::from_bits(
+                u128::from(self.driven_at.load(Ordering::Relaxed)) << self.tick_shift,
+            ),
+        )
+    }
+
+    pub fn restore(&self, (value, driven_at): (u8, Period)) {
+        self.value.store(value, Ordering::Relaxed);
+        self.driven_at
+            .store(self.ticks(&driven_at), Ordering::Relaxed);
+    }
+}
+
+impl AddressSpaceData {
+    /// Record the last byte of a completed access as the value left on the bus
+    #[inline]
+    pub(crate) fn drive_open_bus(&self, buffer: &[u8], timestamp: &Period) {
+        if let Some(open_bus) = &self.open_bus
+            && let Some(value) = buffer.last()
+        {
+            open_bus.drive(*value, timestamp);
+        }
+    }
+
+    /// The last value driven onto the bus and when, for snapshots
+    pub(crate) fn open_bus_state(&self) -> Option<(u8, Period)> {
+        self.open_bus.as_ref().map(OpenBus::state)
+    }
+
+    pub(crate) fn restore_open_bus(&self, state: (u8, Period)) {
+        if let Some(open_bus) = &self.open_bus {
+            open_bus.restore(state);
+        }
+    }
+}
+
+impl<'a> AddressSpace<'a> {
+    /// The value an unmapped read would currently return, if open bus emulation is enabled for this address space
+    pub fn open_bus_value(&self, timestamp: &Period) -> Option<u8> {
+        self.data
+            .open_bus
+            .as_ref()
+            .map(|open_bus| open_bus.value(timestamp))
+    }
+}
@@ -8,8 +8,8 @@ use crate::{
     RuntimeHandle,
     component::ComponentId,
     memory::{
-        Address, AddressSpaceId, CHUNK_SIZE, MemoryError, MemoryErrorType, PageTable,
-        PageTableEntry, PageTableTarget,
+        Address, AddressSpaceData, AddressSpaceId, CHUNK_SIZE, MemoryError, MemoryErrorType,
+        PageTable, PageTableEntry, PageTableTarget, WatchpointKind,
     },
     scheduler::Period,
 };
@@ -29,66 +29,89 @@ impl<'a> AddressSpace<'a> {
         current_timestamp: &Period,
         buffer: &mut B,
     ) -> Result<(), MemoryError> {
+        self.read_inner::<AVOID_SIDE_EFFECTS>(
+            address,
+            current_timestamp,
+            buffer.as_mut(),
+            WatchpointKind::Read,
+        )
+    }
+
+    /// Read a buffer from an address as an instruction fetch
+    ///
+    /// Behaves exactly like [`read`](Self::read), except it is reported to execute watchpoints instead of read ones.
+    /// Processors should use this for opcode fetches
+    #[inline]
+    pub fn fetch<B: NumBytes + ?Sized>(
+        &mut self,
+        address: Address,
+        current_timestamp: &Period,
+        buffer: &mut B,
+    ) -> Result<(), MemoryError> {
+        self.read_inner::<false>(
+            address,
+            current_timestamp,
+            buffer.as_mut(),
+            WatchpointKind::Execute,
+        )
+    }
+
+    #[inline]
+    fn read_inner<const AVOID_SIDE_EFFECTS: bool>(
+        &mut self,
+        address: Address,
+        current_timestamp: &Period,
+        buffer: &mut [u8],
+        kind: WatchpointKind,
+    ) -> Result<(), MemoryError> {
+        let address = self.data.align_access(address, buffer.len())?;
         let page_table = self.data.get_read_table(&self.guard);
 
         for Chunk {
             address,
             page_table_slice,
             buffer: chunk_buffer,
-        } in ChunkIter::new(address, self.data.width_mask, buffer.as_mut(), page_table)
+        } in ChunkIter::new(address, self.data.width_mask, buffer, page_table)
         {
-            visit_page_entries(
+            let wait_states = visit_page_entries(
                 address,
                 chunk_buffer,
                 page_table_slice,
                 #[inline]
-                |target, offset, adjusted| {
-                    match target {
-                        PageTableTarget::ImmutableMemory(bytes) => {
-                            let memory_range =
-                                RangeInclusive::from_start_and_length(offset, adjusted.len());
-
-                            // SAFETY: `commit` ensures memory byte entries are the same size as the range they are assigned to
-                            let bytes = unsafe { bytes.get_unchecked(memory_range) };
-
-                            adjusted.copy_from_slice(bytes);
-                        }
-                        PageTableTarget::Memory {
-                            offset: destination_start,
-                            id,
-                        } => {
-                            let destination = destination_start + offset;
-
-                            self.runtime
-                                .memory_registry()
-                                .read(*id, destination, adjusted);
-
-                            // HACK:
-                            //
-                            // Do not allow the copy at the end of both of these blocks to be merged
-                            //
-                            // LLVM often gets confused and generates a actual memcpy
-                            black_box(());
-                        }
-                        PageTableTarget::Component {
-                            offset: destination_start,
-                            id,
-                        } => {
-                            let destination = destination_start + offset;
-
-                            virtual_memory_read::<AVOID_SIDE_EFFECTS>(
-                                *id,
-                                current_timestamp,
-                                destination,
-                                self.data.id,
-                                self.runtime,
-                                adjusted,
-                            )?;
-                        }
+                |target, offset, address, adjusted| {
+                    read_target::<AVOID_SIDE_EFFECTS>(
+                        self.runtime,
+                        self.data,
+                        target,
+                        offset,
+                        address,
+                        adjusted,
+                        current_timestamp,
+                        kind,
+                    )?;
+
+                    if !AVOID_SIDE_EFFECTS {
+                        self.data.drive_open_bus(adjusted, current_timestamp);
                     }
+
                     Ok(())
                 },
+                #[inline]
+                |address, adjusted| match &self.data.open_bus {
+                    Some(open_bus) => {
+                        adjusted.fill(open_bus.value(current_timestamp));
+                        Ok(())
+                    }
+                    None => Err(form_error(RangeInclusive::from_start_and_length(
+                        address,
+                        adjusted.len(),
+                    ))),
+                },
             )?;
+
+            if !AVOID_SIDE_EFFECTS {
+                self.wait_states += wait_states;
+            }
         }
 
         Ok(())
@@ -140,6 +163,7 @@ impl<'a> AddressSpace<'a> {
         current_timestamp: &Period,
         buffer: &B,
     ) -> Result<(), MemoryError> {
+        let address = self.data.align_access(address, buffer.as_ref().len())?;
         let page_table = self.data.get_write_table(&self.guard);
 
         for Chunk {
@@ -148,43 +172,33 @@ impl<'a> AddressSpace<'a> {
             buffer: chunk_buffer,
         } in ChunkIter::new(address, self.data.width_mask, buffer.as_ref(), page_table)
         {
-            visit_page_entries(
+            self.wait_states += visit_page_entries(
                 address,
                 chunk_buffer,
                 page_table_slice,
                 #[inline]
-                |target, offset, adjusted| {
-                    match target {
-                        PageTableTarget::Memory {
-                            offset: destination_start,
-                            id,
-                        } => {
-                            let destination = destination_start + offset;
-
-                            self.runtime
-                                .memory_registry()
-                                .write(*id, destination, adjusted);
-                        }
-                        PageTableTarget::Component {
-                            offset: destination_start,
-                            id,
-                        } => {
-                            let destination = destination_start + offset;
-
-                            virtual_memory_write(
-                                *id,
-                                current_timestamp,
-                                destination,
-                                self.data.id,
-                                self.runtime,
-                                adjusted,
-                            )?;
-                        }
-                        PageTableTarget::ImmutableMemory(_) => unreachable!(),
-                    }
+                |target, offset, address, adjusted| {
+                    write_target(
+                        self.runtime,
+                        self.data,
+                        target,
+                        offset,
+                        address,
+                        adjusted,
+                        current_timestamp,
+                    )?;
+
+                    self.data.drive_open_bus(adjusted, current_timestamp);
 
                     Ok(())
                 },
+                #[inline]
+                |address, adjusted| {
+                    Err(form_error(RangeInclusive::from_start_and_length(
+                        address,
+                        adjusted.len(),
+                    )))
+                },
             )?;
         }
 
@@ -319,20 +333,24 @@ impl<'a, BUFFER: SplitableBuffer> Iterator for ChunkIter<'a, BUFFER> {
     }
 }
 
+/// Walk the entries an access covers, returning the total wait states of the ones it touched
 #[inline]
 fn visit_page_entries<BUFFER: SplitableBuffer>(
     address: Address,
     buffer: BUFFER,
     page_table_slice: &[Arc<[PageTableEntry]>],
-    mut callback: impl FnMut(&PageTableTarget, usize, BUFFER) -> Result<(), MemoryError>,
-) -> Result<(), MemoryError> {
+    mut callback: impl FnMut(&PageTableTarget, usize, Address, BUFFER) -> Result<(), MemoryError>,
+    mut gap: impl FnMut(Address, BUFFER) -> Result<(), MemoryError>,
+) -> Result<u32, MemoryError> {
     let access_range = RangeInclusive::from_start_and_length(address, buffer.len());
     let mut remaining = buffer;
+    let mut total_wait_states = 0;
 
     'outer: for page in page_table_slice {
         for PageTableEntry {
             range: entry_assigned_range,
             target,
+            wait_states,
         } in page.iter()
         {
             if entry_assigned_range.last < access_range.start {
@@ -348,12 +366,12 @@ fn visit_page_entries<BUFFER: SplitableBuffer>(
                 ..=(entry_access_range.last - access_range.start);
 
             let consumed = access_range.len() - remaining.len();
-            let gap = buffer_range.start() - consumed;
-            if gap > 0 {
-                return Err(form_error(RangeInclusive::from_start_and_length(
-                    access_range.start + consumed,
-                    gap,
-                )));
+            let gap_length = buffer_range.start() - consumed;
+            if gap_length > 0 {
+                let (gap_buffer, rest) = remaining.split(gap_length);
+                remaining = rest;
+
+                gap(access_range.start + consumed, gap_buffer)?;
             }
 
             let offset = entry_access_range.start - entry_assigned_range.start;
@@ -361,7 +379,8 @@ fn visit_page_entries<BUFFER: SplitableBuffer>(
             let (adjusted_buffer, rest) = remaining.split(buffer_range.len());
             remaining = rest;
 
-            callback(target, offset, adjusted_buffer)?;
+            callback(target, offset, entry_access_range.start, adjusted_buffer)?;
+            total_wait_states += wait_states;
 
             if entry_access_range.last == access_range.last {
                 break 'outer;
@@ -372,15 +391,236 @@ fn visit_page_entries<BUFFER: SplitableBuffer>(
     if remaining.len() > 0 {
         let consumed = access_range.len() - remaining.len();
 
-        return Err(form_error(RangeInclusive::from_start_and_length(
-            access_range.start + consumed,
-            remaining.len(),
-        )));
+        gap(access_range.start + consumed, remaining)?;
+    }
+
+    Ok(total_wait_states)
+}
+
+#[inline]
+#[allow(clippy::too_many_arguments)]
+fn read_target<const AVOID_SIDE_EFFECTS: bool>(
+    runtime: &RuntimeHandle,
+    data: &AddressSpaceData,
+    target: &PageTableTarget,
+    offset: usize,
+    address: Address,
+    buffer: &mut [u8],
+    current_timestamp: &Period,
+    kind: WatchpointKind,
+) -> Result<(), MemoryError> {
+    match target {
+        PageTableTarget::ImmutableMemory(bytes) => {
+            let memory_range = RangeInclusive::from_start_and_length(offset, buffer.len());
+
+            // SAFETY: `commit` ensures memory byte entries are the same size as the range they are assigned to
+            let bytes = unsafe { bytes.get_unchecked(memory_range) };
+
+            buffer.copy_from_slice(bytes);
+        }
+        PageTableTarget::Memory {
+            offset: destination_start,
+            id,
+        } => {
+            let destination = destination_start + offset;
+
+            runtime.memory_registry().read(*id, destination, buffer);
+
+            // HACK:
+            //
+            // Do not allow the copy at the end of both of these blocks to be merged
+            //
+            // LLVM often gets confused and generates a actual memcpy
+            black_box(());
+        }
+        PageTableTarget::Component {
+            offset: destination_start,
+            id,
+        } => {
+            let destination = destination_start + offset;
+
+            for_each_bus_word(data, address, buffer, |skipped, buffer| {
+                virtual_memory_read::<AVOID_SIDE_EFFECTS>(
+                    *id,
+                    current_timestamp,
+                    destination + skipped,
+                    data.id,
+                    runtime,
+                    buffer,
+                )
+            })?;
+        }
+        PageTableTarget::Hooked(target) => {
+            return hooked_read::<AVOID_SIDE_EFFECTS>(
+                runtime,
+                data,
+                target,
+                offset,
+                address,
+                buffer,
+                current_timestamp,
+                kind,
+            );
+        }
     }
 
     Ok(())
 }
 
+#[inline]
+fn write_target(
+    runtime: &RuntimeHandle,
+    data: &AddressSpaceData,
+    target: &PageTableTarget,
+    offset: usize,
+    address: Address,
+    buffer: &[u8],
+    current_timestamp: &Period,
+) -> Result<(), MemoryError> {
+    match target {
+        PageTableTarget::Memory {
+            offset: destination_start,
+            id,
+        } => {
+            let destination = destination_start + offset;
+
+            runtime.memory_registry().write(*id, destination, buffer);
+        }
+        PageTableTarget::Component {
+            offset: destination_start,
+            id,
+        } => {
+            let destination = destination_start + offset;
+
+            for_each_bus_word(data, address, buffer, |skipped, buffer| {
+                virtual_memory_write(
+                    *id,
+                    current_timestamp,
+                    destination + skipped,
+                    data.id,
+                    runtime,
+                    buffer,
+                )
+            })?;
+        }
+        PageTableTarget::Hooked(target) => {
+            return hooked_write(
+                runtime,
+                data,
+                target,
+                offset,
+                address,
+                buffer,
+                current_timestamp,
+            );
+        }
+        PageTableTarget::ImmutableMemory(_) => unreachable!(),
+    }
+
+    Ok(())
+}
+
+/// Split an access into the pieces falling into each data bus word, so components never see a request crossing one
+///
+/// Address spaces without a configured data bus pass the access through whole
+#[inline]
+fn for_each_bus_word<BUFFER: SplitableBuffer>(
+    data: &AddressSpaceData,
+    address: Address,
+    buffer: BUFFER,
+    mut callback: impl FnMut(usize, BUFFER) -> Result<(), MemoryError>,
+) -> Result<(), MemoryError> {
+    let Some(data_bus) = &data.data_bus else {
+        return callback(0, buffer);
+    };
+
+    let word = data_bus.width.bytes();
+    let mut remaining = buffer;
+    let mut skipped = 0;
+
+    while remaining.len() > 0 {
+        let word_remaining = word - (address + skipped) % word;
+        let length = word_remaining.min(remaining.len());
+        let (piece, rest) = remaining.split(length);
+        remaining = rest;
+
+        callback(skipped, piece)?;
+        skipped += length;
+    }
+
+    Ok(())
+}
+
+#[cold]
+#[inline(never)]
+#[allow(clippy::too_many_arguments)]
+fn hooked_read<const AVOID_SIDE_EFFECTS: bool>(
+    runtime: &RuntimeHandle,
+    data: &AddressSpaceData,
+    target: &PageTableTarget,
+    offset: usize,
+    address: Address,
+    buffer: &mut [u8],
+    current_timestamp: &Period,
+    kind: WatchpointKind,
+) -> Result<(), MemoryError> {
+    if !AVOID_SIDE_EFFECTS {
+        data.watchpoint_triggered(
+            runtime,
+            kind,
+            std::ops::RangeInclusive::from_start_and_length(address, buffer.len()),
+            current_timestamp,
+        );
+    }
+
+    read_target::<AVOID_SIDE_EFFECTS>(
+        runtime,
+        data,
+        target,
+        offset,
+        address,
+        buffer,
+        current_timestamp,
+        kind,
+    )?;
+
+    data.apply_read_overrides(
+        std::ops::RangeInclusive::from_start_and_length(address, buffer.len()),
+        buffer,
+    );
+
+    Ok(())
+}
+
+#[cold]
+#[inline(never)]
+fn hooked_write(
+    runtime: &RuntimeHandle,
+    data: &AddressSpaceData,
+    target: &PageTableTarget,
+    offset: usize,
+    address: Address,
+    buffer: &[u8],
+    current_timestamp: &Period,
+) -> Result<(), MemoryError> {
+    data.watchpoint_triggered(
+        runtime,
+        WatchpointKind::Write,
+        std::ops::RangeInclusive::from_start_and_length(address, buffer.len()),
+        current_timestamp,
+    );
+
+    write_target(
+        runtime,
+        data,
+        target,
+        offset,
+        address,
+        buffer,
+        current_timestamp,
+    )
+}
+
 #[cold]
 #[inline]
 fn virtual_memory_read<const AVOID_SIDE_EFFECTS: bool>(
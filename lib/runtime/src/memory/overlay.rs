@@ -0,0 +1,82 @@
+use std::ops::RangeInclusive;
+
+use rangemap::RangeInclusiveSet;
+
+use crate::{
+    cheat::{CheatCode, CheatId},
+    memory::{Address, AddressSpace, AddressSpaceData, MasterTables},
+};
+
+impl MasterTables {
+    /// Addresses whose reads are substituted by a cheat
+    pub(crate) fn overridden_ranges(&self) -> RangeInclusiveSet<Address> {
+        self.read_overrides
+            .values()
+            .map(|code| code.address..=code.address)
+            .collect()
+    }
+}
+
+impl<'a> AddressSpace<'a> {
+    /// Make reads of the code's address return its value, leaving what is actually mapped there untouched
+    pub(crate) fn insert_read_override(&mut self, id: CheatId, code: CheatCode) {
+        assert!(
+            code.address <= self.data.width_mask,
+            "Address {:#04x} is invalid for this address space",
+            code.address
+        );
+
+        let mut master_tables_guard = self.data.master.lock().unwrap();
+        master_tables_guard.read_overrides.insert(id, code);
+
+        self.data.recommit_range(
+            &master_tables_guard,
+            code.address..=code.address,
+            false,
+            &self.guard,
+            self.runtime,
+        );
+    }
+
+    pub(crate) fn remove_read_override(&mut self, id: CheatId) {
+        let mut master_tables_guard = self.data.master.lock().unwrap();
+
+        let Some(code) = master_tables_guard.read_overrides.remove(&id) else {
+            return;
+        };
+
+        self.data.recommit_range(
+            &master_tables_guard,
+            code.address..=code.address,
+            false,
+            &self.guard,
+            self.runtime,
+        );
+    }
+}
+
+impl AddressSpaceData {
+    /// Substitute overridden bytes in a buffer that was just read from `access_range`
+    ///
+    /// Only called for accesses that landed on a hooked page table entry
+    #[cold]
+    pub(crate) fn apply_read_overrides(
+        &self,
+        access_range: RangeInclusive<Address>,
+        buffer: &mut [u8],
+    ) {
+        let master_tables_guard = self.master.lock().unwrap();
+
+        for code in master_tables_guard.read_overrides.values() {
+            if !access_range.contains(&code.address) {
+                continue;
+            }
+
+            let byte = &mut buffer[code.address - access_range.start()];
+
+            if code.compare.is_none_or(|compare| *byte == compare) {
+                *byte = code.value;
+            }
+        }
+    }
+}
@@ -2,6 +2,8 @@ use std::{
     alloc::Layout,
     cell::UnsafeCell,
     collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
     ptr::NonNull,
     range::RangeInclusive,
     sync::{Condvar, Mutex},
@@ -14,14 +16,13 @@ use itertools::Itertools;
 use rangemap::RangeInclusiveMap;
 use rustc_hash::FxBuildHasher;
 
-use crate::{ResourcePath, RuntimeHandle, memory::CHUNK_SIZE};
+use crate::{ResourcePath, RuntimeHandle, memory::CHUNK_SIZE, scheduler::Period};
 
 pub type MemoryId = u16;
 
 #[derive(Debug)]
 pub(crate) struct RegionInitializationData {
     pub size: usize,
-    #[allow(unused)]
     pub sram: bool,
     pub initial_contents: RangeInclusiveMap<usize, Bytes>,
 }
@@ -38,6 +39,10 @@ struct LocalMemoryRegionData {
 struct MemoryRegion {
     /// Pointer to the allocation
     base_ptr: NonNull<[u8]>,
+    /// If this region is battery backed
+    sram: bool,
+    /// What the region holds at power on, anything not covered is zeroed
+    initial_contents: RangeInclusiveMap<usize, Bytes>,
     /// Chunk borrowed tracker
     ///
     /// Note that the last chunk can be smaller than [CHUNK_SIZE]
@@ -69,19 +74,30 @@ impl Drop for MemoryRegion {
 pub struct MemoryRegistryData {
     regions: HashMap<MemoryId, MemoryRegion, FxBuildHasher>,
     id_for_path: HashMap<ResourcePath, MemoryId, FxBuildHasher>,
+    /// Files battery backed regions are persisted to
+    save_memory_files: HashMap<MemoryId, PathBuf, FxBuildHasher>,
+    /// Emulated time battery backed regions were last persisted at
+    last_save_memory_flush: Mutex<Period>,
 }
 
 impl MemoryRegistryData {
-    pub fn new(required_regions: HashMap<ResourcePath, RegionInitializationData>) -> Self {
+    /// Allocate all regions
+    ///
+    /// If a save directory is given, battery backed regions are loaded from and later persisted to it
+    pub fn new(
+        required_regions: HashMap<ResourcePath, RegionInitializationData>,
+        save_directory: Option<&Path>,
+    ) -> Self {
         let mut regions = HashMap::default();
         let mut id_for_path = HashMap::default();
+        let mut save_memory_files = HashMap::default();
         let mut next_id: MemoryId = 0;
 
         for (
             path,
             RegionInitializationData {
                 size,
-                sram: _,
+                sram,
                 initial_contents,
             },
         ) in required_regions
@@ -102,15 +118,43 @@ impl MemoryRegistryData {
                 // SAFETY: We validated that this pointer is a valid allocation
                 let representation_slice = unsafe { base_ptr.as_mut() };
 
-                for (addresses, bytes) in initial_contents {
-                    representation_slice[addresses].copy_from_slice(&bytes);
+                for (addresses, bytes) in initial_contents.iter() {
+                    representation_slice[addresses.clone()].copy_from_slice(bytes);
                 }
             }
 
+            if sram && let Some(save_directory) = save_directory {
+                let file = save_memory_file(save_directory, &path);
+
+                match std::fs::read(&file) {
+                    Ok(contents) if contents.len() == size => {
+                        // SAFETY: We validated that this pointer is a valid allocation
+                        unsafe { base_ptr.as_mut() }.copy_from_slice(&contents);
+                    }
+                    Ok(contents) => {
+                        tracing::warn!(
+                            "Ignoring save memory {} of size {} as {} requires {} bytes",
+                            file.display(),
+                            contents.len(),
+                            path,
+                            size
+                        );
+                    }
+                    Err(error) if error.kind() == ErrorKind::NotFound => {}
+                    Err(error) => {
+                        tracing::error!("Could not read save memory {}: {}", file.display(), error);
+                    }
+                }
+
+                save_memory_files.insert(id, file);
+            }
+
             regions.insert(
                 id,
                 MemoryRegion {
                     base_ptr,
+                    sram,
+                    initial_contents,
                     borrowed_chunks: Mutex::new(BitVec::from_elem_general(
                         region_chunk_count,
                         false,
@@ -128,6 +172,8 @@ impl MemoryRegistryData {
         Self {
             regions,
             id_for_path,
+            save_memory_files,
+            last_save_memory_flush: Mutex::new(Period::ZERO),
         }
     }
 
@@ -136,6 +182,46 @@ impl MemoryRegistryData {
     }
 }
 
+impl Drop for MemoryRegistryData {
+    fn drop(&mut self) {
+        for (id, file) in &self.save_memory_files {
+            // SAFETY: We have exclusive access, so no thread can be holding any chunks
+            let contents = unsafe { self.regions[id].base_ptr.as_ref() };
+
+            write_save_memory(file, contents);
+        }
+    }
+}
+
+/// Where a battery backed region is stored inside of a program specific save directory
+fn save_memory_file(save_directory: &Path, path: &ResourcePath) -> PathBuf {
+    let mut file = save_directory.to_path_buf();
+
+    if let Some(component) = path.parent() {
+        file.extend(component.iter());
+    }
+
+    file.push(format!("{}.sav", path.name()));
+    file
+}
+
+fn write_save_memory(file: &Path, contents: &[u8]) {
+    if let Some(parent) = file.parent()
+        && let Err(error) = std::fs::create_dir_all(parent)
+    {
+        tracing::error!(
+            "Could not create save directory {}: {}",
+            parent.display(),
+            error
+        );
+        return;
+    }
+
+    if let Err(error) = std::fs::write(file, contents) {
+        tracing::error!("Could not write save memory {}: {}", file.display(), error);
+    }
+}
+
 // SAFETY: We manage the raw pointers to memory ourselves
 unsafe impl Send for MemoryRegistryData {}
 unsafe impl Sync for MemoryRegistryData {}
@@ -311,6 +397,84 @@ impl<'a> MemoryRegistry<'a> {
 
         Some(self.data().regions[&id].base_ptr.len())
     }
+
+    /// If the region is battery backed
+    #[inline]
+    pub fn is_sram(&self, id: MemoryId) -> bool {
+        self.data().regions[&id].sram
+    }
+
+    /// Every region path alongside its ID, in no particular order
+    pub fn regions(&self) -> impl Iterator<Item = (&'a ResourcePath, MemoryId)> + use<'a> {
+        self.runtime
+            .machine()
+            .memory_registry_data
+            .id_for_path
+            .iter()
+            .map(|(path, id)| (path, *id))
+    }
+
+    /// Copy out the entire contents of a region
+    pub fn read_region(&self, id: MemoryId) -> Vec<u8> {
+        let mut buffer = vec![0; self.data().regions[&id].base_ptr.len()];
+
+        if !buffer.is_empty() {
+            self.read(id, 0, &mut buffer);
+        }
+
+        buffer
+    }
+
+    /// Return every region that is not battery backed to its power on contents
+    pub fn reinitialize(&self) {
+        for (id, region) in &self.data().regions {
+            if region.sram {
+                continue;
+            }
+
+            let mut contents = vec![0; region.base_ptr.len()];
+            for (addresses, bytes) in region.initial_contents.iter() {
+                contents[addresses.clone()].copy_from_slice(bytes);
+            }
+
+            if !contents.is_empty() {
+                self.write_region(*id, &contents);
+            }
+        }
+    }
+
+    /// Persist every battery backed region to the save directory
+    pub fn flush_save_memory(&self, timestamp: Period) {
+        let data = self.data();
+
+        for (id, file) in &data.save_memory_files {
+            write_save_memory(file, &self.read_region(*id));
+        }
+
+        *data.last_save_memory_flush.lock().unwrap() = timestamp;
+    }
+
+    /// Emulated time battery backed regions were last persisted at
+    pub fn last_save_memory_flush(&self) -> Period {
+        *self.data().last_save_memory_flush.lock().unwrap()
+    }
+
+    /// Overwrite the entire contents of a region
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is not exactly the size of the region
+    pub fn write_region(&self, id: MemoryId, buffer: &[u8]) {
+        assert_eq!(
+            buffer.len(),
+            self.data().regions[&id].base_ptr.len(),
+            "Buffer does not match the region size"
+        );
+
+        if !buffer.is_empty() {
+            self.write(id, 0, buffer);
+        }
+    }
 }
 
 pub struct LocalMemoryRegistryData {
@@ -23,7 +23,9 @@ impl PageTable {
         &mut self,
         previous_table: &Self,
         master: &RangeInclusiveMap<Address, MasterTableEntry>,
+        wait_states: &RangeInclusiveMap<Address, u32>,
         dirty: &RangeInclusiveSet<Address>,
+        hooked: &RangeInclusiveSet<Address>,
         runtime: &RuntimeHandle,
     ) {
         for page_index in 0..self.0.len() {
@@ -43,6 +45,7 @@ impl PageTable {
                                     id: runtime.component_registry().id_for_path(path).unwrap(),
                                 },
                                 range: source_range.clone().into(),
+                                wait_states: 0,
                             });
                         }
                         MasterTableEntry::Mirror {
@@ -69,13 +72,15 @@ impl PageTable {
                                 0,
                             );
                         }
-                        MasterTableEntry::ImmutableMemory(memory) => {
-                            // Validate the buffer subrange matches the range its being put into
-                            assert_eq!(memory.len(), source_range.len());
+                        MasterTableEntry::ImmutableMemory { source_base, bytes } => {
+                            let offset = source_range.start() - source_base;
 
                             page_contents.push(PageTableEntry {
                                 range: source_range.clone().into(),
-                                target: PageTableTarget::ImmutableMemory(memory.clone()),
+                                target: PageTableTarget::ImmutableMemory(
+                                    bytes.slice(offset..offset + source_range.len()),
+                                ),
+                                wait_states: 0,
                             });
                         }
                         MasterTableEntry::Memory {
@@ -97,6 +102,7 @@ impl PageTable {
                                     offset: region_base + region_offset,
                                     id: runtime.memory_registry().id_for_path(path).unwrap(),
                                 },
+                                wait_states: 0,
                             });
                         }
                     }
@@ -169,6 +175,15 @@ impl PageTable {
                     },
                 );
 
+                if wait_states.overlaps(&page_address_range) {
+                    page_contents = apply_wait_states(page_contents, wait_states);
+                }
+
+                // Wrap anything under a watchpoint or cheat, splitting entries that are only partially hooked
+                if hooked.overlaps(&page_address_range) {
+                    page_contents = hook_entries(page_contents, hooked);
+                }
+
                 let previous_page = page_index.checked_sub(1).map(|index| &self.0[index]);
 
                 if let Some(previous_page) = previous_page
@@ -217,24 +232,19 @@ impl PageTable {
                             offset: *destination_overlap.start(),
                             id: runtime.component_registry().id_for_path(path).unwrap(),
                         },
+                        wait_states: 0,
                     });
                 }
-                MasterTableEntry::ImmutableMemory(memory) => {
-                    let buffer_subrange = (destination_overlap.start() - destination_range.start())
-                        ..=(destination_overlap.end() - destination_range.start());
-
-                    let memory = memory.slice(buffer_subrange.clone());
-
-                    assert_eq!(
-                        memory.len(),
-                        buffer_subrange.len(),
-                        "Buffers have to be the same length as the range they are being mapped \
-                         into"
+                MasterTableEntry::ImmutableMemory { source_base, bytes } => {
+                    let memory = bytes.slice(
+                        (destination_overlap.start() - source_base)
+                            ..=(destination_overlap.end() - source_base),
                     );
 
                     page.push(PageTableEntry {
                         range: calculated_source_range.into(),
                         target: PageTableTarget::ImmutableMemory(memory),
+                        wait_states: 0,
                     });
                 }
                 MasterTableEntry::Memory {
@@ -256,6 +266,7 @@ impl PageTable {
                             offset: region_base + region_offset,
                             id: runtime.memory_registry().id_for_path(path).unwrap(),
                         },
+                        wait_states: 0,
                     });
                 }
                 MasterTableEntry::Mirror {
@@ -314,15 +325,120 @@ impl PageTableTarget {
                     id: id_b,
                 },
             ) => offset_a == offset_b && id_a == id_b,
+            (Self::Hooked(a), Self::Hooked(b)) => a.same_mapping(b),
             _ => false,
         }
     }
 }
 
+impl PageTableEntry {
+    /// Narrow this entry down to a subrange of itself
+    #[inline]
+    fn subentry(&self, range: RangeInclusive<Address>) -> Self {
+        let shift = range.start() - self.range.start;
+
+        let target = match &self.target {
+            PageTableTarget::ImmutableMemory(bytes) => {
+                PageTableTarget::ImmutableMemory(bytes.slice(shift..shift + range.len()))
+            }
+            PageTableTarget::Memory { offset, id } => PageTableTarget::Memory {
+                offset: offset + shift,
+                id: *id,
+            },
+            PageTableTarget::Component { offset, id } => PageTableTarget::Component {
+                offset: offset + shift,
+                id: *id,
+            },
+            PageTableTarget::Hooked(_) => unreachable!("Entries are only hooked once"),
+        };
+
+        Self {
+            range: range.into(),
+            target,
+            wait_states: self.wait_states,
+        }
+    }
+}
+
+#[inline]
+fn hook_entries(
+    entries: Vec<PageTableEntry>,
+    hooked: &RangeInclusiveSet<Address>,
+) -> Vec<PageTableEntry> {
+    let mut page_contents = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let entry_range = entry.range.start..=entry.range.last;
+        let mut unhooked_start = Some(entry.range.start);
+
+        for hooked_range in hooked.overlapping(&entry_range) {
+            let hooked_range = entry_range.intersection(hooked_range);
+
+            if let Some(start) = unhooked_start
+                && start < *hooked_range.start()
+            {
+                page_contents.push(entry.subentry(start..=(hooked_range.start() - 1)));
+            }
+
+            let mut hooked_entry = entry.subentry(hooked_range.clone());
+            hooked_entry.target = PageTableTarget::Hooked(Box::new(hooked_entry.target));
+            page_contents.push(hooked_entry);
+
+            unhooked_start = hooked_range.end().checked_add(1);
+        }
+
+        if let Some(start) = unhooked_start
+            && start <= entry.range.last
+        {
+            page_contents.push(entry.subentry(start..=entry.range.last));
+        }
+    }
+
+    page_contents
+}
+
+/// Split entries so each carries the wait states of the range it covers
+#[inline]
+fn apply_wait_states(
+    entries: Vec<PageTableEntry>,
+    wait_states: &RangeInclusiveMap<Address, u32>,
+) -> Vec<PageTableEntry> {
+    let mut page_contents = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let entry_range = entry.range.start..=entry.range.last;
+        let mut uncosted_start = Some(entry.range.start);
+
+        for (costed_range, cycles) in wait_states.overlapping(&entry_range) {
+            let costed_range = entry_range.intersection(costed_range);
+
+            if let Some(start) = uncosted_start
+                && start < *costed_range.start()
+            {
+                page_contents.push(entry.subentry(start..=(costed_range.start() - 1)));
+            }
+
+            let mut costed_entry = entry.subentry(costed_range.clone());
+            costed_entry.wait_states = *cycles;
+            page_contents.push(costed_entry);
+
+            uncosted_start = costed_range.end().checked_add(1);
+        }
+
+        if let Some(start) = uncosted_start
+            && start <= entry.range.last
+        {
+            page_contents.push(entry.subentry(start..=entry.range.last));
+        }
+    }
+
+    page_contents
+}
+
 #[inline]
 fn pages_have_same_mapping(a: &[PageTableEntry], b: &[PageTableEntry]) -> bool {
     a.len() == b.len()
-        && a.iter()
-            .zip(b)
-            .all(|(a, b)| a.range == b.range && a.target.same_mapping(&b.target))
+        && a.iter().zip(b).all(|(a, b)| {
+            a.range == b.range && a.wait_states == b.wait_states && a.target.same_mapping(&b.target)
+        })
 }
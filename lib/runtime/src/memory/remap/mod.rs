@@ -10,10 +10,15 @@ use crate::{
         Address, AddressSpaceData, MapTarget, MasterTableEntry, MasterTables, MemoryMapCommand,
         PageTable,
     },
+    path::ComponentPath,
     scheduler::Period,
 };
 
 mod commit;
+mod preset;
+
+pub(crate) use preset::MappingPreset;
+pub use preset::{MappingPresetError, MappingPresetId};
 
 impl AddressSpaceData {
     #[inline]
@@ -24,24 +29,182 @@ impl AddressSpaceData {
         runtime: &RuntimeHandle,
         commands: impl IntoIterator<Item = MemoryMapCommand>,
     ) {
-        let max = 2usize.pow(u32::from(self.address_space_width)) - 1;
-        let valid_range = 0..=max;
-
         let mut dirty_read = RangeInclusiveSet::new();
         let mut dirty_write = RangeInclusiveSet::new();
 
         // We are also using this as a write serializer
         let mut master_tables_guard = self.master.lock().unwrap();
+
+        master_tables_guard.apply_commands(
+            commands,
+            self.address_space_width,
+            runtime,
+            &mut dirty_read,
+            &mut dirty_write,
+        );
+
+        master_tables_guard.disown_presets(&dirty_read, &dirty_write);
+        mirror_dirtying_pass(&master_tables_guard.read, &mut dirty_read);
+        mirror_dirtying_pass(&master_tables_guard.write, &mut dirty_write);
+
+        self.commit_tables(
+            &master_tables_guard,
+            &dirty_read,
+            &dirty_write,
+            guard,
+            runtime,
+        );
+    }
+
+    /// Unmap every range that targets a component matching the predicate
+    pub(crate) fn unmap_components(
+        &self,
+        guard: &Guard,
+        runtime: &RuntimeHandle,
+        predicate: impl Fn(&ComponentPath) -> bool,
+    ) {
+        let mut master_tables_guard = self.master.lock().unwrap();
+        master_tables_guard.forget_preset_components(&predicate);
+
         let MasterTables {
             read: master_read,
             write: master_write,
+            read_wait_states,
+            write_wait_states,
+            ..
         } = &mut *master_tables_guard;
 
-        let current_read = self.read_table.load(Ordering::Acquire, guard);
-        let current_read_page_table = current_read.as_ref().unwrap();
+        let mut dirty_read = RangeInclusiveSet::new();
+        let mut dirty_write = RangeInclusiveSet::new();
+
+        for (master, wait_states, dirty) in [
+            (master_read, read_wait_states, &mut dirty_read),
+            (master_write, write_wait_states, &mut dirty_write),
+        ] {
+            let ranges: Vec<_> = master
+                .iter()
+                .filter(|(_, entry)| {
+                    matches!(entry, MasterTableEntry::Component(path) if predicate(path))
+                })
+                .map(|(range, _)| range.clone())
+                .collect();
+
+            for range in ranges {
+                master.remove(range.clone());
+                wait_states.remove(range.clone());
+                dirty.insert(range);
+            }
+
+            mirror_dirtying_pass(master, dirty);
+        }
+
+        master_tables_guard.disown_presets(&dirty_read, &dirty_write);
+
+        self.commit_tables(
+            &master_tables_guard,
+            &dirty_read,
+            &dirty_write,
+            guard,
+            runtime,
+        );
+    }
+
+    /// Recompile a range of either the read or write page table, for when something other than the mappings changed
+    pub(super) fn recommit_range(
+        &self,
+        master: &MasterTables,
+        range: RangeInclusive<Address>,
+        write: bool,
+        guard: &Guard,
+        runtime: &RuntimeHandle,
+    ) {
+        let dirty = RangeInclusiveSet::from_iter([range]);
+        let untouched = RangeInclusiveSet::new();
+
+        if write {
+            self.commit_tables(master, &untouched, &dirty, guard, runtime);
+        } else {
+            self.commit_tables(master, &dirty, &untouched, guard, runtime);
+        }
+    }
+
+    /// Recompile the dirty parts of both page tables from the master tables and swap them in
+    ///
+    /// The master tables lock must be held for the whole operation, as it serializes writers
+    pub(super) fn commit_tables(
+        &self,
+        master: &MasterTables,
+        dirty_read: &RangeInclusiveSet<Address>,
+        dirty_write: &RangeInclusiveSet<Address>,
+        guard: &Guard,
+        runtime: &RuntimeHandle,
+    ) {
+        if !dirty_read.is_empty() {
+            let current_read = self.read_table.load(Ordering::Acquire, guard);
+            let current_read_page_table = current_read.as_ref().unwrap();
+
+            let mut read_table = PageTable(
+                vec![Default::default(); current_read_page_table.0.len()].into_boxed_slice(),
+            );
+
+            read_table.commit(
+                current_read_page_table,
+                &master.read,
+                &master.read_wait_states,
+                dirty_read,
+                &master.hooked_ranges(false),
+                runtime,
+            );
+
+            let _ = self
+                .read_table
+                .swap((Some(Owned::new(read_table)), Tag::None), Ordering::AcqRel);
+        };
+
+        if !dirty_write.is_empty() {
+            let current_write = self.write_table.load(Ordering::Acquire, guard);
+            let current_write_page_table = current_write.as_ref().unwrap();
+
+            let mut write_table = PageTable(
+                vec![Default::default(); current_write_page_table.0.len()].into_boxed_slice(),
+            );
+
+            write_table.commit(
+                current_write_page_table,
+                &master.write,
+                &master.write_wait_states,
+                dirty_write,
+                &master.hooked_ranges(true),
+                runtime,
+            );
+
+            let _ = self
+                .write_table
+                .swap((Some(Owned::new(write_table)), Tag::None), Ordering::AcqRel);
+        }
+    }
+}
+
+impl MasterTables {
+    /// Apply memory map commands to the master tables, marking what they touched as dirty
+    fn apply_commands(
+        &mut self,
+        commands: impl IntoIterator<Item = MemoryMapCommand>,
+        address_space_width: u8,
+        runtime: &RuntimeHandle,
+        dirty_read: &mut RangeInclusiveSet<Address>,
+        dirty_write: &mut RangeInclusiveSet<Address>,
+    ) {
+        let max = 2usize.pow(u32::from(address_space_width)) - 1;
+        let valid_range = 0..=max;
 
-        let current_write = self.write_table.load(Ordering::Acquire, guard);
-        let current_write_page_table = current_write.as_ref().unwrap();
+        let MasterTables {
+            read: master_read,
+            write: master_write,
+            read_wait_states,
+            write_wait_states,
+            ..
+        } = self;
 
         for command in commands {
             match command {
@@ -58,10 +221,12 @@ impl AddressSpaceData {
 
                     if permissions.read {
                         dirty_read.insert(range.clone());
+                        read_wait_states.remove(range.clone());
                     }
 
                     if permissions.write {
                         dirty_write.insert(range.clone());
+                        write_wait_states.remove(range.clone());
                     }
 
                     match target {
@@ -81,10 +246,20 @@ impl AddressSpaceData {
                             }
                         }
                         MapTarget::ImmutableMemory(bytes) => {
+                            assert_eq!(
+                                bytes.len(),
+                                range.len(),
+                                "Buffers have to be the same length as the range they are being \
+                                 mapped into"
+                            );
+
                             if permissions.read {
                                 master_read.insert(
                                     range.clone(),
-                                    MasterTableEntry::ImmutableMemory(bytes.clone()),
+                                    MasterTableEntry::ImmutableMemory {
+                                        source_base: *range.start(),
+                                        bytes: bytes.clone(),
+                                    },
                                 );
                             }
 
@@ -164,48 +339,46 @@ impl AddressSpaceData {
                 MemoryMapCommand::Unmap { range, permissions } => {
                     if permissions.read {
                         master_read.remove(range.clone());
+                        read_wait_states.remove(range.clone());
                         dirty_read.insert(range.clone());
                     }
 
                     if permissions.write {
                         master_write.remove(range.clone());
+                        write_wait_states.remove(range.clone());
+                        dirty_write.insert(range.clone());
+                    }
+                }
+                MemoryMapCommand::WaitStates {
+                    range,
+                    permissions,
+                    cycles,
+                } => {
+                    if permissions.read {
+                        set_wait_states(read_wait_states, range.clone(), cycles);
+                        dirty_read.insert(range.clone());
+                    }
+
+                    if permissions.write {
+                        set_wait_states(write_wait_states, range.clone(), cycles);
                         dirty_write.insert(range.clone());
                     }
                 }
             }
         }
+    }
+}
 
-        mirror_dirtying_pass(master_read, &mut dirty_read);
-        mirror_dirtying_pass(master_write, &mut dirty_write);
-
-        if !dirty_read.is_empty() {
-            let mut read_table = PageTable(
-                vec![Default::default(); current_read_page_table.0.len()].into_boxed_slice(),
-            );
-
-            read_table.commit(current_read_page_table, master_read, &dirty_read, runtime);
-
-            let _ = self
-                .read_table
-                .swap((Some(Owned::new(read_table)), Tag::None), Ordering::AcqRel);
-        };
-
-        if !dirty_write.is_empty() {
-            let mut write_table = PageTable(
-                vec![Default::default(); current_write_page_table.0.len()].into_boxed_slice(),
-            );
-
-            write_table.commit(
-                current_write_page_table,
-                master_write,
-                &dirty_write,
-                runtime,
-            );
-
-            let _ = self
-                .write_table
-                .swap((Some(Owned::new(write_table)), Tag::None), Ordering::AcqRel);
-        }
+#[inline]
+fn set_wait_states(
+    wait_states: &mut RangeInclusiveMap<Address, u32>,
+    range: RangeInclusive<Address>,
+    cycles: u32,
+) {
+    if cycles == 0 {
+        wait_states.remove(range);
+    } else {
+        wait_states.insert(range, cycles);
     }
 }
 
@@ -0,0 +1,447 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    ops::RangeInclusive,
+    sync::{Arc, atomic::Ordering},
+};
+
+use fluxemu_math::range::{ContiguousRange, RangeIntersection};
+use rangemap::{RangeInclusiveMap, RangeInclusiveSet};
+use sdd::{AtomicOwned, Guard, Owned, Tag};
+use thiserror::Error;
+
+use crate::{
+    RuntimeHandle,
+    memory::{
+        Address, AddressSpace, AddressSpaceData, AddressSpaceId, CHUNK_SIZE, MasterTableEntry,
+        MasterTables, MemoryMapCommand, PageTable, PageTableEntry, remap::mirror_dirtying_pass,
+    },
+    path::ComponentPath,
+};
+
+/// Identifier for a mapping preset, only meaningful for the address space it was registered on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MappingPresetId {
+    pub(crate) address_space: AddressSpaceId,
+    pub(crate) index: u16,
+}
+
+/// Error switching mapping presets
+#[derive(Debug, Error)]
+pub enum MappingPresetError {
+    /// The preset was not registered on this address space
+    #[error("Mapping preset {0:?} does not belong to {1:?}")]
+    Foreign(MappingPresetId, AddressSpaceId),
+}
+
+/// A memory map state compiled ahead of time, which can be switched to without going through [`PageTable::commit`]
+#[derive(Debug)]
+pub(crate) struct MappingPreset {
+    name: Cow<'static, str>,
+    read: PresetTables,
+    write: PresetTables,
+}
+
+/// One direction of a preset
+#[derive(Debug)]
+struct PresetTables {
+    /// Ranges the preset takes over, everything outside is left alone when switching to it
+    ranges: RangeInclusiveSet<Address>,
+    entries: RangeInclusiveMap<Address, MasterTableEntry>,
+    wait_states: RangeInclusiveMap<Address, u32>,
+    /// Compiled pages lying entirely within the preset, by page index
+    pages: Vec<(usize, Arc<[PageTableEntry]>)>,
+}
+
+impl PresetTables {
+    fn compile(
+        ranges: RangeInclusiveSet<Address>,
+        master: &RangeInclusiveMap<Address, MasterTableEntry>,
+        wait_states: &RangeInclusiveMap<Address, u32>,
+        previous_table: &PageTable,
+        runtime: &RuntimeHandle,
+    ) -> Self {
+        let mut entries = RangeInclusiveMap::new();
+        let mut preset_wait_states = RangeInclusiveMap::new();
+
+        for range in ranges.iter() {
+            for (entry_range, entry) in master.overlapping(range) {
+                entries.insert(entry_range.intersection(range), entry.clone());
+            }
+
+            for (costed_range, cycles) in wait_states.overlapping(range) {
+                preset_wait_states.insert(costed_range.intersection(range), *cycles);
+            }
+        }
+
+        // Mirrors resolve through the rest of the memory map, which may have changed by the time the preset is used
+        let has_mirrors = entries
+            .iter()
+            .any(|(_, entry)| matches!(entry, MasterTableEntry::Mirror { .. }));
+
+        let mut full_pages = RangeInclusiveSet::new();
+        if !has_mirrors {
+            for range in ranges.iter() {
+                let first_page = range.start().div_ceil(CHUNK_SIZE);
+                let end_page = (range.end() + 1) / CHUNK_SIZE;
+
+                if first_page < end_page {
+                    full_pages.insert((first_page * CHUNK_SIZE)..=(end_page * CHUNK_SIZE - 1));
+                }
+            }
+        }
+
+        let mut table =
+            PageTable(vec![Default::default(); previous_table.0.len()].into_boxed_slice());
+        table.commit(
+            previous_table,
+            master,
+            wait_states,
+            &full_pages,
+            &RangeInclusiveSet::new(),
+            runtime,
+        );
+
+        let pages = full_pages
+            .iter()
+            .flat_map(|range| (range.start() / CHUNK_SIZE)..=(range.end() / CHUNK_SIZE))
+            .map(|index| (index, table.0[index].clone()))
+            .collect();
+
+        Self {
+            ranges,
+            entries,
+            wait_states: preset_wait_states,
+            pages,
+        }
+    }
+
+    /// Write the preset into the master tables, claiming its ranges for preset `id`
+    fn apply(
+        &self,
+        id: u16,
+        master: &mut RangeInclusiveMap<Address, MasterTableEntry>,
+        wait_states: &mut RangeInclusiveMap<Address, u32>,
+        owners: &mut RangeInclusiveMap<Address, u16>,
+    ) {
+        for range in self.ranges.iter() {
+            master.remove(range.clone());
+            wait_states.remove(range.clone());
+            owners.insert(range.clone(), id);
+        }
+
+        for (range, entry) in self.entries.iter() {
+            master.insert(range.clone(), entry.clone());
+        }
+
+        for (range, cycles) in self.wait_states.iter() {
+            wait_states.insert(range.clone(), *cycles);
+        }
+    }
+
+    /// If every range of the preset still holds what switching to preset `id` put there
+    fn in_effect(&self, id: u16, owners: &RangeInclusiveMap<Address, u16>) -> bool {
+        self.ranges.iter().all(|range| {
+            owners.gaps(range).next().is_none()
+                && owners.overlapping(range).all(|(_, owner)| *owner == id)
+        })
+    }
+
+    fn targets(&self, predicate: &impl Fn(&ComponentPath) -> bool) -> bool {
+        self.entries
+            .iter()
+            .any(|(_, entry)| matches!(entry, MasterTableEntry::Component(path) if predicate(path)))
+    }
+
+    fn without_components(&self, predicate: &impl Fn(&ComponentPath) -> bool) -> Self {
+        Self {
+            ranges: self.ranges.clone(),
+            entries: self
+                .entries
+                .iter()
+                .filter(|(_, entry)| {
+                    !matches!(entry, MasterTableEntry::Component(path) if predicate(path))
+                })
+                .map(|(range, entry)| (range.clone(), entry.clone()))
+                .collect(),
+            wait_states: self.wait_states.clone(),
+            // Compiled pages may point at the components, so the preset is always compiled on switch from now on
+            pages: Vec::new(),
+        }
+    }
+}
+
+/// Swap the updated page table in, using the compiled pages of the presets wherever they are still valid
+///
+/// Later presets take precedence over earlier ones, and pages they only partly cover are compiled from the master table
+#[allow(clippy::too_many_arguments)]
+fn switch_table<'p>(
+    table: &AtomicOwned<PageTable>,
+    presets: impl IntoIterator<Item = &'p PresetTables>,
+    mut dirty: RangeInclusiveSet<Address>,
+    master: &RangeInclusiveMap<Address, MasterTableEntry>,
+    wait_states: &RangeInclusiveMap<Address, u32>,
+    hooked: &RangeInclusiveSet<Address>,
+    guard: &Guard,
+    runtime: &RuntimeHandle,
+) {
+    if dirty.is_empty() {
+        return;
+    }
+
+    let mut pages = BTreeMap::new();
+    for preset in presets {
+        for range in preset.ranges.iter() {
+            pages.retain(|index, _| {
+                !(range.start() / CHUNK_SIZE..=range.end() / CHUNK_SIZE).contains(index)
+            });
+        }
+
+        pages.extend(preset.pages.iter().map(|(index, page)| (*index, page)));
+    }
+
+    let current = table.load(Ordering::Acquire, guard);
+    let mut page_table = current.as_ref().unwrap().clone();
+
+    for (index, page) in pages {
+        let page_range = RangeInclusive::from_start_and_length(index * CHUNK_SIZE, CHUNK_SIZE);
+
+        // Watchpoints and cheats have to be wrapped around the entries, which the compiled page knows nothing of
+        if !hooked.overlaps(&page_range) {
+            page_table.0[index] = page.clone();
+            dirty.remove(page_range);
+        }
+    }
+
+    if !dirty.is_empty() {
+        let mut committed =
+            PageTable(vec![Default::default(); page_table.0.len()].into_boxed_slice());
+        committed.commit(&page_table, master, wait_states, &dirty, hooked, runtime);
+
+        page_table = committed;
+    }
+
+    let _ = table.swap((Some(Owned::new(page_table)), Tag::None), Ordering::AcqRel);
+}
+
+impl MasterTables {
+    /// Forget which presets own ranges that were mapped over by other means
+    pub(super) fn disown_presets(
+        &mut self,
+        dirty_read: &RangeInclusiveSet<Address>,
+        dirty_write: &RangeInclusiveSet<Address>,
+    ) {
+        for range in dirty_read.iter() {
+            self.read_preset_owners.remove(range.clone());
+        }
+
+        for range in dirty_write.iter() {
+            self.write_preset_owners.remove(range.clone());
+        }
+    }
+
+    /// Strip mappings to components matching the predicate out of every preset
+    pub(super) fn forget_preset_components(&mut self, predicate: &impl Fn(&ComponentPath) -> bool) {
+        for preset in &mut self.presets {
+            if preset.read.targets(predicate) || preset.write.targets(predicate) {
+                *preset = Arc::new(MappingPreset {
+                    name: preset.name.clone(),
+                    read: preset.read.without_components(predicate),
+                    write: preset.write.without_components(predicate),
+                });
+            }
+        }
+    }
+}
+
+impl AddressSpaceData {
+    /// Compile a preset from the commands applied on top of the current memory map
+    pub(crate) fn compile_preset(
+        &self,
+        name: Cow<'static, str>,
+        commands: impl IntoIterator<Item = MemoryMapCommand>,
+        guard: &Guard,
+        runtime: &RuntimeHandle,
+    ) {
+        let mut master_tables_guard = self.master.lock().unwrap();
+
+        assert!(
+            !master_tables_guard
+                .presets
+                .iter()
+                .any(|preset| preset.name == name),
+            "Mapping preset {name} already exists"
+        );
+
+        let mut scratch = MasterTables {
+            read: master_tables_guard.read.clone(),
+            write: master_tables_guard.write.clone(),
+            read_wait_states: master_tables_guard.read_wait_states.clone(),
+            write_wait_states: master_tables_guard.write_wait_states.clone(),
+            ..Default::default()
+        };
+
+        let mut read_ranges = RangeInclusiveSet::new();
+        let mut write_ranges = RangeInclusiveSet::new();
+        scratch.apply_commands(
+            commands,
+            self.address_space_width,
+            runtime,
+            &mut read_ranges,
+            &mut write_ranges,
+        );
+
+        let preset = MappingPreset {
+            name,
+            read: PresetTables::compile(
+                read_ranges,
+                &scratch.read,
+                &scratch.read_wait_states,
+                self.get_read_table(guard),
+                runtime,
+            ),
+            write: PresetTables::compile(
+                write_ranges,
+                &scratch.write,
+                &scratch.write_wait_states,
+                self.get_write_table(guard),
+                runtime,
+            ),
+        };
+
+        master_tables_guard.presets.push(Arc::new(preset));
+    }
+
+    pub(crate) fn preset_count(&self) -> u16 {
+        self.master
+            .lock()
+            .unwrap()
+            .presets
+            .len()
+            .try_into()
+            .unwrap()
+    }
+
+    fn switch_presets(
+        &self,
+        ids: impl IntoIterator<Item = MappingPresetId>,
+        guard: &Guard,
+        runtime: &RuntimeHandle,
+    ) -> Result<(), MappingPresetError> {
+        // Also serializes writers to the page tables, which are swapped as a whole
+        let mut master_tables_guard = self.master.lock().unwrap();
+        let master = &mut *master_tables_guard;
+
+        // Checked up front so a bad preset leaves the memory map as it was
+        let presets = ids
+            .into_iter()
+            .map(|id| {
+                master
+                    .presets
+                    .get(usize::from(id.index))
+                    .filter(|_| id.address_space == self.id)
+                    .map(|preset| (id.index, preset.clone()))
+                    .ok_or(MappingPresetError::Foreign(id, self.id))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut switched = Vec::new();
+        let mut dirty_read = RangeInclusiveSet::new();
+        let mut dirty_write = RangeInclusiveSet::new();
+
+        for (index, preset) in presets {
+            // Switching to a preset again only writes back what is already there
+            if preset.read.in_effect(index, &master.read_preset_owners)
+                && preset.write.in_effect(index, &master.write_preset_owners)
+            {
+                continue;
+            }
+
+            preset.read.apply(
+                index,
+                &mut master.read,
+                &mut master.read_wait_states,
+                &mut master.read_preset_owners,
+            );
+            preset.write.apply(
+                index,
+                &mut master.write,
+                &mut master.write_wait_states,
+                &mut master.write_preset_owners,
+            );
+
+            dirty_read.extend(preset.read.ranges.iter().cloned());
+            dirty_write.extend(preset.write.ranges.iter().cloned());
+            switched.push(preset);
+        }
+
+        mirror_dirtying_pass(&master.read, &mut dirty_read);
+        mirror_dirtying_pass(&master.write, &mut dirty_write);
+
+        switch_table(
+            &self.read_table,
+            switched.iter().map(|preset| &preset.read),
+            dirty_read,
+            &master.read,
+            &master.read_wait_states,
+            &master.hooked_ranges(false),
+            guard,
+            runtime,
+        );
+        switch_table(
+            &self.write_table,
+            switched.iter().map(|preset| &preset.write),
+            dirty_write,
+            &master.write,
+            &master.write_wait_states,
+            &master.hooked_ranges(true),
+            guard,
+            runtime,
+        );
+
+        Ok(())
+    }
+}
+
+impl<'a> AddressSpace<'a> {
+    /// Switch to a mapping preset registered at build time on this address space
+    ///
+    /// Ranges the preset covers take on the mappings it was registered with, everything else is left alone. This is an
+    /// incremental rebuild of the page tables rather than a pointer swap: pages lying entirely within the preset are
+    /// reused as compiled at build time, the rest are compiled as a [`remap`](Self::remap) would. Switching to the
+    /// preset already in place costs next to nothing
+    ///
+    /// Like a remap, this takes effect immediately for every accessor
+    #[inline]
+    pub fn switch_preset(&mut self, preset: MappingPresetId) -> Result<(), MappingPresetError> {
+        self.switch_presets([preset])
+    }
+
+    /// Switch to several mapping presets at once, in order, swapping in the page tables only once
+    ///
+    /// Nothing is switched if any of the presets belongs to another address space
+    #[inline]
+    pub fn switch_presets(
+        &mut self,
+        presets: impl IntoIterator<Item = MappingPresetId>,
+    ) -> Result<(), MappingPresetError> {
+        self.guard.accelerate();
+
+        self.data.switch_presets(presets, &self.guard, self.runtime)
+    }
+
+    /// Look up a mapping preset by the name it was registered with
+    pub fn preset(&self, name: &str) -> Option<MappingPresetId> {
+        self.data
+            .master
+            .lock()
+            .unwrap()
+            .presets
+            .iter()
+            .position(|preset| preset.name == name)
+            .map(|index| MappingPresetId {
+                address_space: self.data.id,
+                index: index as u16,
+            })
+    }
+}
@@ -1,14 +1,60 @@
-use std::ops::RangeInclusive;
+use std::{error::Error, ops::RangeInclusive};
 
 use bytes::Bytes;
 use fluxemu_math::range::ContiguousRange;
 
 use crate::{
-    machine::Machine,
-    memory::{CHUNK_SIZE, MapTarget, MemoryErrorType, MemoryMapCommand, Permissions},
+    component::{Component, config::ComponentConfig},
+    machine::{Machine, builder::ComponentBuilder},
+    memory::{
+        Address, AddressSpace, AddressSpaceId, BusWidth, CHUNK_SIZE, DataBus, Endianness,
+        MapTarget, MappingPresetError, MemoryError, MemoryErrorType, MemoryMapCommand,
+        MisalignedAccess, OpenBusDecay, Permissions, WatchpointKind,
+    },
+    platform::Platform,
     scheduler::Period,
 };
 
+/// Remembers the shape of every read it gets, answering with the low byte of each address
+#[derive(Debug, Default)]
+struct BusProbe {
+    requests: Vec<(Address, usize)>,
+}
+
+impl Component for BusProbe {
+    type Event = ();
+
+    fn memory_read(
+        &mut self,
+        address: Address,
+        _address_space: AddressSpaceId,
+        _avoid_side_effects: bool,
+        buffer: &mut [u8],
+    ) -> Result<(), MemoryError> {
+        self.requests.push((address, buffer.len()));
+
+        for (offset, byte) in buffer.iter_mut().enumerate() {
+            *byte = (address + offset) as u8;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct BusProbeConfig;
+
+impl<P: Platform> ComponentConfig<P> for BusProbeConfig {
+    type Component = BusProbe;
+
+    fn build_component(
+        self,
+        _component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn Error>> {
+        Ok(BusProbe::default())
+    }
+}
+
 #[test]
 fn reads_and_writes_sanity() {
     let (machine, address_space) = Machine::build_test_minimal().address_space(16);
@@ -270,3 +316,397 @@ fn initial_contents_are_applied() {
 
     assert_eq!(buffer, [1, 2, 3, 4, 0, 0, 0, 0]);
 }
+
+#[test]
+fn watchpoints_report_and_pause() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let (machine, work_ram_path) = machine.memory("work-ram", CHUNK_SIZE, []);
+    let machine = machine.map_memory(
+        address_space,
+        [MemoryMapCommand::Map {
+            range: RangeInclusive::from_start_and_length(0, CHUNK_SIZE),
+            permissions: Permissions::ALL,
+            target: MapTarget::Memory {
+                path: work_ram_path,
+                subrange: None,
+            },
+        }],
+    );
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+    let mut address_space = runtime_guard.address_space(address_space).unwrap();
+
+    let id = address_space.add_watchpoint(0x10..=0x1f, WatchpointKind::Write);
+    let timestamp = Period::from_num(2);
+
+    // Reads and writes outside of the watched range go through untouched
+    address_space.write(0x00, &timestamp, &[1; 0x10]).unwrap();
+    let mut buffer = [0; 0x20];
+    address_space
+        .read::<_, false>(0x00, &timestamp, &mut buffer)
+        .unwrap();
+    assert!(runtime_guard.take_watchpoint_hits().is_empty());
+    assert_eq!(runtime_guard.paused_at(), None);
+
+    // A write straddling the start of the range is still applied in full
+    address_space.write(0x0e, &timestamp, &[2; 4]).unwrap();
+    address_space
+        .read::<_, false>(0x00, &timestamp, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer[0x0e..0x12], [2; 4]);
+
+    let hits = runtime_guard.take_watchpoint_hits();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].id, id);
+    assert_eq!(hits[0].kind, WatchpointKind::Write);
+    assert_eq!(hits[0].address, 0x10);
+    assert_eq!(hits[0].accessor, None);
+    assert_eq!(runtime_guard.paused_at(), Some(timestamp));
+
+    runtime_guard.resume();
+    assert!(address_space.remove_watchpoint(id));
+
+    address_space.write(0x10, &timestamp, &[3; 4]).unwrap();
+    assert!(runtime_guard.take_watchpoint_hits().is_empty());
+}
+
+#[test]
+fn open_bus_fills_unmapped_reads() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let (machine, ram_path) = machine.memory("ram", 0x10, []);
+    let machine = machine
+        .map_memory(
+            address_space,
+            [MemoryMapCommand::Map {
+                range: RangeInclusive::from_start_and_length(0x0000, 0x10),
+                permissions: Permissions::ALL,
+                target: MapTarget::Memory {
+                    path: ram_path,
+                    subrange: None,
+                },
+            }],
+        )
+        .open_bus(
+            address_space,
+            OpenBusDecay::Settle {
+                period: Period::ONE,
+                value: 0xff,
+            },
+        );
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+    let mut address_space = runtime_guard.address_space(address_space).unwrap();
+
+    address_space
+        .write(0x0000, &Period::ZERO, &[0x12, 0x34])
+        .unwrap();
+
+    let value: u8 = address_space
+        .read_le_value::<_, false>(0x8000, &Period::ZERO)
+        .unwrap();
+    assert_eq!(value, 0x34);
+
+    // Partially mapped reads get real data where there is some
+    address_space
+        .write(0x000e, &Period::ZERO, &[0x56, 0x78])
+        .unwrap();
+    let mut buffer = [0; 4];
+    address_space
+        .read::<_, false>(0x000e, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [0x56, 0x78, 0x78, 0x78]);
+
+    address_space
+        .read::<_, false>(0x0000, &Period::ZERO, &mut buffer)
+        .unwrap();
+    let value: u8 = address_space
+        .read_le_value::<_, false>(0x8000, &Period::ZERO)
+        .unwrap();
+    assert_eq!(value, 0x00);
+
+    let value: u8 = address_space
+        .read_le_value::<_, false>(0x8000, &Period::ONE)
+        .unwrap();
+    assert_eq!(value, 0xff);
+
+    // Writes to unmapped space still fail
+    assert!(address_space.write(0x8000, &Period::ONE, &[0]).is_err());
+}
+
+#[test]
+fn wait_states_accumulate() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let (machine, ram_path) = machine.memory("ram", 0x20, []);
+    let machine = machine.map_memory(
+        address_space,
+        [
+            MemoryMapCommand::Map {
+                range: RangeInclusive::from_start_and_length(0x0000, 0x20),
+                permissions: Permissions::ALL,
+                target: MapTarget::Memory {
+                    path: ram_path.clone(),
+                    subrange: None,
+                },
+            },
+            MemoryMapCommand::WaitStates {
+                range: RangeInclusive::from_start_and_length(0x0010, 0x10),
+                permissions: Permissions::READ,
+                cycles: 2,
+            },
+        ],
+    );
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+    let mut address_space = runtime_guard.address_space(address_space).unwrap();
+
+    let mut buffer = [0; 4];
+    address_space
+        .read::<_, false>(0x0000, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(address_space.take_wait_states(), 0);
+
+    address_space
+        .read::<_, false>(0x000e, &Period::ZERO, &mut buffer)
+        .unwrap();
+    address_space
+        .read::<_, false>(0x0010, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(address_space.take_wait_states(), 4);
+    assert_eq!(address_space.take_wait_states(), 0);
+
+    address_space
+        .read::<_, true>(0x0010, &Period::ZERO, &mut buffer)
+        .unwrap();
+    address_space.write(0x0010, &Period::ZERO, &buffer).unwrap();
+    assert_eq!(address_space.take_wait_states(), 0);
+
+    // Mapping over the range makes it free again
+    address_space.remap(
+        &Period::ZERO,
+        [MemoryMapCommand::Map {
+            range: RangeInclusive::from_start_and_length(0x0010, 0x10),
+            permissions: Permissions::READ,
+            target: MapTarget::Memory {
+                path: ram_path,
+                subrange: Some(RangeInclusive::from_start_and_length(0x10, 0x10)),
+            },
+        }],
+    );
+    address_space
+        .read::<_, false>(0x0010, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(address_space.take_wait_states(), 0);
+}
+
+#[test]
+fn data_bus_shapes_accesses() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let (machine, ram_path) = machine.memory("ram", 0x10, []);
+    let (machine, probe_path) = machine.default_component::<BusProbeConfig>("probe");
+    let machine = machine
+        .map_memory(
+            address_space,
+            [
+                MemoryMapCommand::Map {
+                    range: RangeInclusive::from_start_and_length(0x0000, 0x10),
+                    permissions: Permissions::ALL,
+                    target: MapTarget::Memory {
+                        path: ram_path,
+                        subrange: None,
+                    },
+                },
+                MemoryMapCommand::Map {
+                    range: RangeInclusive::from_start_and_length(0x0100, 0x10),
+                    permissions: Permissions::READ,
+                    target: MapTarget::Component(probe_path.clone()),
+                },
+            ],
+        )
+        .data_bus(
+            address_space,
+            DataBus {
+                width: BusWidth::Bits16,
+                endianness: Endianness::Big,
+                misaligned: MisalignedAccess::Split,
+            },
+        );
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+    let mut address_space = runtime_guard.address_space(address_space).unwrap();
+
+    address_space
+        .write_value(0x0000, &Period::ZERO, 0x1234u16)
+        .unwrap();
+    let mut buffer = [0; 2];
+    address_space
+        .read::<_, false>(0x0000, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [0x12, 0x34]);
+    assert_eq!(
+        address_space
+            .read_value::<u16, false>(0x0000, &Period::ZERO)
+            .unwrap(),
+        0x1234
+    );
+
+    // Components never see a request crossing a bus word
+    let mut buffer = [0; 4];
+    address_space
+        .read::<_, false>(0x0101, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [0x01, 0x02, 0x03, 0x04]);
+
+    let requests = runtime_guard
+        .component_registry()
+        .interact::<BusProbe, _>(&probe_path, &Period::ZERO, |probe| {
+            std::mem::take(&mut probe.requests)
+        })
+        .unwrap();
+    assert_eq!(requests, [(0x0101, 1), (0x0102, 2), (0x0104, 1)]);
+}
+
+#[test]
+fn misaligned_access_policies() {
+    let run = |misaligned| {
+        let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+        let (machine, ram_path) =
+            machine.memory("ram", 0x10, [(0..=3, Bytes::from_static(&[1, 2, 3, 4]))]);
+        let machine = machine
+            .map_memory(
+                address_space,
+                [MemoryMapCommand::Map {
+                    range: RangeInclusive::from_start_and_length(0x0000, 0x10),
+                    permissions: Permissions::ALL,
+                    target: MapTarget::Memory {
+                        path: ram_path,
+                        subrange: None,
+                    },
+                }],
+            )
+            .data_bus(
+                address_space,
+                DataBus {
+                    width: BusWidth::Bits32,
+                    endianness: Endianness::Little,
+                    misaligned,
+                },
+            );
+
+        let machine = machine.seal().build(());
+        let runtime_guard = machine.enter_runtime();
+        let mut address_space = runtime_guard.address_space(address_space).unwrap();
+
+        // Byte accesses are always aligned
+        assert!(
+            address_space
+                .read_value::<u8, false>(0x0003, &Period::ZERO)
+                .is_ok()
+        );
+
+        address_space
+            .read_value::<u16, false>(0x0001, &Period::ZERO)
+            .map_err(|error| error.0[0].1)
+    };
+
+    assert_eq!(run(MisalignedAccess::Split), Ok(0x0302));
+    assert_eq!(run(MisalignedAccess::ForceAlign), Ok(0x0201));
+    assert_eq!(
+        run(MisalignedAccess::Fault),
+        Err(MemoryErrorType::Misaligned)
+    );
+}
+
+#[test]
+fn mapping_presets_switch_banks() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let bank = |value| MemoryMapCommand::immutable_memory(CHUNK_SIZE, vec![value; CHUNK_SIZE]);
+    let machine = machine.map_memory(
+        address_space,
+        [
+            bank(0),
+            MemoryMapCommand::mirror(
+                Permissions::READ,
+                RangeInclusive::from_start_and_length(CHUNK_SIZE * 2, CHUNK_SIZE),
+                CHUNK_SIZE,
+            ),
+        ],
+    );
+
+    let (machine, bank_0) = machine.mapping_preset(address_space, "bank-0", [bank(0)]);
+    let (machine, bank_1) = machine.mapping_preset(address_space, "bank-1", [bank(1)]);
+
+    // Only covers half a page, so it has to be compiled when switched to
+    let (machine, half) = machine.mapping_preset(
+        address_space,
+        "half",
+        [MemoryMapCommand::immutable_memory(
+            CHUNK_SIZE + CHUNK_SIZE / 2,
+            vec![2; CHUNK_SIZE / 2],
+        )],
+    );
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+    let mut address_space = runtime_guard.address_space(address_space).unwrap();
+
+    assert_eq!(address_space.preset("bank-1"), Some(bank_1));
+    assert_eq!(address_space.preset("bank-2"), None);
+
+    let read_bank_edges = |address_space: &mut AddressSpace| {
+        [CHUNK_SIZE, CHUNK_SIZE * 2 - 1, CHUNK_SIZE * 3 - 1].map(|address| {
+            address_space
+                .read_le_value::<u8, false>(address, &Period::ZERO)
+                .unwrap()
+        })
+    };
+
+    // The last address is mirrored from the bank
+    for (preset, expected) in [(bank_1, [1, 1, 1]), (half, [1, 2, 2]), (bank_0, [0, 0, 0])] {
+        address_space.switch_preset(preset).unwrap();
+        assert_eq!(read_bank_edges(&mut address_space), expected);
+    }
+
+    // Mapping over a preset means switching back to it is not a no-op
+    address_space.remap(&Period::ZERO, [bank(3)]);
+    address_space.switch_preset(bank_0).unwrap();
+    assert_eq!(read_bank_edges(&mut address_space), [0, 0, 0]);
+
+    address_space.switch_presets([bank_1, half]).unwrap();
+    assert_eq!(read_bank_edges(&mut address_space), [1, 2, 2]);
+}
+
+#[test]
+fn mapping_presets_of_other_address_spaces_are_refused() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+    let (machine, other_address_space) = machine.address_space(16);
+
+    let bank = |value| MemoryMapCommand::immutable_memory(0, vec![value; CHUNK_SIZE]);
+    let machine = machine.map_memory(address_space, [bank(0)]);
+    let (machine, own) = machine.mapping_preset(address_space, "bank-1", [bank(1)]);
+    let (machine, foreign) = machine.mapping_preset(other_address_space, "bank-2", [bank(2)]);
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+    let mut address_space = runtime_guard.address_space(address_space).unwrap();
+
+    assert!(matches!(
+        address_space.switch_presets([own, foreign]),
+        Err(MappingPresetError::Foreign(..))
+    ));
+    assert_eq!(
+        address_space
+            .read_le_value::<u8, false>(0, &Period::ZERO)
+            .unwrap(),
+        0
+    );
+}
@@ -0,0 +1,207 @@
+use std::ops::RangeInclusive;
+
+use fluxemu_math::range::RangeIntersection;
+use rangemap::RangeInclusiveSet;
+use sdd::Guard;
+
+use crate::{
+    RuntimeHandle,
+    memory::{Address, AddressSpace, AddressSpaceData, AddressSpaceId, MasterTables},
+    path::ComponentPath,
+    scheduler::Period,
+};
+
+/// What kind of access a watchpoint reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WatchpointKind {
+    /// Data reads
+    Read,
+    /// Writes
+    Write,
+    /// Instruction fetches, as done through [`AddressSpace::fetch`]
+    Execute,
+}
+
+/// Identifier for a watchpoint registered on a address space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WatchpointId {
+    address_space: AddressSpaceId,
+    index: u32,
+}
+
+impl WatchpointId {
+    /// The address space this watchpoint was registered on
+    pub fn address_space(&self) -> AddressSpaceId {
+        self.address_space
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Watchpoint {
+    range: RangeInclusive<Address>,
+    kind: WatchpointKind,
+}
+
+/// Record of an access that landed on a watchpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchpointHit {
+    /// The watchpoint that was hit
+    pub id: WatchpointId,
+    /// What kind of access hit it
+    pub kind: WatchpointKind,
+    /// The first watched address the access touched
+    pub address: Address,
+    /// When the access happened, which is also where the machine was paused
+    pub timestamp: Period,
+    /// The component that was synchronizing when the access happened, if any
+    pub accessor: Option<ComponentPath>,
+}
+
+impl MasterTables {
+    /// Ranges that need to be wrapped in the read or write page table
+    pub(crate) fn watched_ranges(&self, write: bool) -> RangeInclusiveSet<Address> {
+        self.watchpoints
+            .values()
+            .filter(|watchpoint| (watchpoint.kind == WatchpointKind::Write) == write)
+            .map(|watchpoint| watchpoint.range.clone())
+            .collect()
+    }
+}
+
+impl<'a> AddressSpace<'a> {
+    /// Report every access of `kind` to `range` on this address space
+    ///
+    /// When hit, the scheduler pauses at the timestamp of the access. Use [`RuntimeGuard::take_watchpoint_hits`] to see
+    /// what happened and [`RuntimeGuard::resume`] to continue.
+    ///
+    /// Watchpoints work on addresses as seen by this address space, so accesses through mirrors of the range are
+    /// not reported. Accesses made while avoiding side effects are never reported.
+    ///
+    /// [`RuntimeGuard::take_watchpoint_hits`]: crate::machine::RuntimeGuard::take_watchpoint_hits
+    /// [`RuntimeGuard::resume`]: crate::machine::RuntimeGuard::resume
+    pub fn add_watchpoint(
+        &mut self,
+        range: RangeInclusive<Address>,
+        kind: WatchpointKind,
+    ) -> WatchpointId {
+        self.data
+            .add_watchpoint(range, kind, &self.guard, self.runtime)
+    }
+
+    /// Remove a watchpoint, returning if it existed
+    ///
+    /// Once the last watchpoint is removed, the address space goes back to its unwatched page tables
+    pub fn remove_watchpoint(&mut self, id: WatchpointId) -> bool {
+        assert_eq!(
+            id.address_space, self.data.id,
+            "Watchpoint belongs to another address space"
+        );
+
+        self.data.remove_watchpoint(id, &self.guard, self.runtime)
+    }
+}
+
+impl AddressSpaceData {
+    fn add_watchpoint(
+        &self,
+        range: RangeInclusive<Address>,
+        kind: WatchpointKind,
+        guard: &Guard,
+        runtime: &RuntimeHandle,
+    ) -> WatchpointId {
+        assert!(
+            *range.end() <= self.width_mask && !range.is_empty(),
+            "Range {range:#04x?} is invalid for this address space"
+        );
+
+        let mut master_tables_guard = self.master.lock().unwrap();
+
+        let id = WatchpointId {
+            address_space: self.id,
+            index: master_tables_guard.next_watchpoint_index,
+        };
+        master_tables_guard.next_watchpoint_index = master_tables_guard
+            .next_watchpoint_index
+            .checked_add(1)
+            .expect("Too many watchpoints");
+
+        master_tables_guard.watchpoints.insert(
+            id,
+            Watchpoint {
+                range: range.clone(),
+                kind,
+            },
+        );
+
+        self.recommit_range(
+            &master_tables_guard,
+            range,
+            kind == WatchpointKind::Write,
+            guard,
+            runtime,
+        );
+
+        id
+    }
+
+    fn remove_watchpoint(&self, id: WatchpointId, guard: &Guard, runtime: &RuntimeHandle) -> bool {
+        let mut master_tables_guard = self.master.lock().unwrap();
+
+        let Some(Watchpoint { range, kind }) = master_tables_guard.watchpoints.remove(&id) else {
+            return false;
+        };
+
+        self.recommit_range(
+            &master_tables_guard,
+            range,
+            kind == WatchpointKind::Write,
+            guard,
+            runtime,
+        );
+
+        true
+    }
+
+    /// Figure out which watchpoints an access hit, record them and pause the machine
+    ///
+    /// Only called for accesses that landed on a watched page table entry
+    #[cold]
+    pub(crate) fn watchpoint_triggered(
+        &self,
+        runtime: &RuntimeHandle,
+        kind: WatchpointKind,
+        access_range: RangeInclusive<Address>,
+        timestamp: &Period,
+    ) {
+        let master_tables_guard = self.master.lock().unwrap();
+
+        let mut hits = Vec::new();
+        for (id, watchpoint) in &master_tables_guard.watchpoints {
+            if watchpoint.kind != kind || !watchpoint.range.intersects(&access_range) {
+                continue;
+            }
+
+            hits.push(WatchpointHit {
+                id: *id,
+                kind,
+                address: *watchpoint.range.intersection(&access_range).start(),
+                timestamp: *timestamp,
+                accessor: None,
+            });
+        }
+        drop(master_tables_guard);
+
+        if hits.is_empty() {
+            return;
+        }
+
+        let accessor = runtime.component_registry().synchronizing_component();
+        for hit in &mut hits {
+            hit.accessor = accessor.clone();
+        }
+
+        let machine = runtime.machine();
+        machine.watchpoint_hits.lock().unwrap().extend(hits);
+        machine.scheduler.pause_at(*timestamp);
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::BTreeSet;
+
+use fluxemu_input::{InputId, InputState};
+use fluxemu_program::{ProgramId, RomId};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{ResourcePath, machine::Machine, scheduler::Period};
+
+#[cfg(test)]
+mod tests;
+
+/// Current version of the movie format
+///
+/// Bump this whenever the layout of [`Movie`] changes in an incompatible way
+pub const MOVIE_VERSION: u16 = 0;
+const MOVIE_MAGIC: [u8; 8] = *b"FLUXMOVI";
+
+#[derive(Debug, Error)]
+pub enum MovieError {
+    #[error("Data is not a movie")]
+    BadMagic,
+    #[error("Movie version {found} is not supported (expected {expected})")]
+    UnsupportedVersion { found: u16, expected: u16 },
+    #[error("Movie was recorded with a different program ({0:?})")]
+    ProgramMismatch(Option<ProgramId>),
+    #[error("Movie was recorded with a different set of ROMs")]
+    RomMismatch,
+    #[error("Movie starts at {expected} but the machine is at {found}")]
+    StartTimeMismatch { found: Period, expected: Period },
+    #[error("Movie contains inputs for unknown input device {0}")]
+    UnknownInputDevice(ResourcePath),
+    #[error("Failed to encode movie: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+    #[error("Failed to decode movie: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+}
+
+/// Identifies what a movie must be played back on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MovieHeader {
+    /// The program the movie was recorded with, if any
+    pub program: Option<ProgramId>,
+    /// Hashes of every ROM the program consists of
+    pub roms: BTreeSet<RomId>,
+    /// The timestamp the machine was at when recording started
+    pub start_time: Period,
+}
+
+impl MovieHeader {
+    pub(crate) fn new(machine: &Machine) -> Self {
+        let program_specification = machine.program_specification();
+
+        Self {
+            program: program_specification.map(|specification| specification.id.clone()),
+            roms: program_specification
+                .map(|specification| specification.info.filesystem().keys().copied().collect())
+                .unwrap_or_default(),
+            start_time: machine.scheduler.safe_advance_timestamp(),
+        }
+    }
+}
+
+/// A single call to [`RuntimeGuard::insert_inputs`](crate::machine::RuntimeGuard::insert_inputs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MovieRecord {
+    /// The safe advance timestamp the inputs were inserted at
+    pub timestamp: Period,
+    /// The logical input device the inputs were inserted into
+    pub path: ResourcePath,
+    pub inputs: Vec<(InputId, InputState)>,
+}
+
+/// A recording of every input given to a machine, which can be replayed deterministically
+///
+/// Record one with [`RuntimeGuard::start_movie_recording`](crate::machine::RuntimeGuard::start_movie_recording)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Movie {
+    header: MovieHeader,
+    records: Vec<MovieRecord>,
+}
+
+impl Movie {
+    pub(crate) fn new(header: MovieHeader) -> Self {
+        Self {
+            header,
+            records: Vec::new(),
+        }
+    }
+
+    pub(crate) fn record(&mut self, record: MovieRecord) {
+        self.records.push(record);
+    }
+
+    pub fn header(&self) -> &MovieHeader {
+        &self.header
+    }
+
+    /// Every recorded input insertion, in order of insertion
+    pub fn records(&self) -> &[MovieRecord] {
+        &self.records
+    }
+
+    /// Encode into the versioned on disk format
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MovieError> {
+        let mut bytes = Vec::from(MOVIE_MAGIC);
+        bytes.extend_from_slice(&MOVIE_VERSION.to_le_bytes());
+        rmp_serde::encode::write_named(&mut bytes, self)?;
+
+        Ok(bytes)
+    }
+
+    /// Decode from the versioned on disk format, rejecting other versions
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MovieError> {
+        let (magic, bytes) = bytes
+            .split_at_checked(MOVIE_MAGIC.len())
+            .ok_or(MovieError::BadMagic)?;
+
+        if magic != MOVIE_MAGIC {
+            return Err(MovieError::BadMagic);
+        }
+
+        let (version, bytes) = bytes.split_first_chunk::<2>().ok_or(MovieError::BadMagic)?;
+        let version = u16::from_le_bytes(*version);
+
+        if version != MOVIE_VERSION {
+            return Err(MovieError::UnsupportedVersion {
+                found: version,
+                expected: MOVIE_VERSION,
+            });
+        }
+
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+
+    /// Check that this movie can be played back on the machine as it currently is
+    pub(crate) fn validate(&self, machine: &Machine) -> Result<(), MovieError> {
+        let header = MovieHeader::new(machine);
+
+        if header.program != self.header.program {
+            return Err(MovieError::ProgramMismatch(self.header.program.clone()));
+        }
+
+        if header.roms != self.header.roms {
+            return Err(MovieError::RomMismatch);
+        }
+
+        if header.start_time != self.header.start_time {
+            return Err(MovieError::StartTimeMismatch {
+                found: header.start_time,
+                expected: self.header.start_time,
+            });
+        }
+
+        let input_devices = machine.input_devices.read().unwrap();
+        if let Some(record) = self
+            .records
+            .iter()
+            .find(|record| !input_devices.contains_key(&record.path))
+        {
+            return Err(MovieError::UnknownInputDevice(record.path.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Progress through playing back a [`Movie`]
+///
+/// Obtain one with [`RuntimeGuard::play_movie`](crate::machine::RuntimeGuard::play_movie)
+#[derive(Debug)]
+pub struct MoviePlayback {
+    movie: Movie,
+    cursor: usize,
+}
+
+impl MoviePlayback {
+    pub(crate) fn new(movie: Movie) -> Self {
+        Self { movie, cursor: 0 }
+    }
+
+    pub fn movie(&self) -> &Movie {
+        &self.movie
+    }
+
+    /// Whether every record has been replayed
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.movie.records.len()
+    }
+
+    /// The next record, if it is due at or before the timestamp
+    pub(crate) fn peek_due(&self, timestamp: Period) -> Option<&MovieRecord> {
+        self.movie
+            .records
+            .get(self.cursor)
+            .filter(|record| record.timestamp <= timestamp)
+    }
+
+    /// Move past the record [`Self::peek_due`] returned, once it has been replayed
+    pub(crate) fn advance(&mut self) {
+        self.cursor += 1;
+    }
+}
@@ -0,0 +1,235 @@
+use std::{error::Error, sync::Arc};
+
+use fluxemu_input::{GamepadInputId, InputId, InputState};
+
+use crate::{
+    ResourcePath,
+    component::{Component, config::ComponentConfig},
+    machine::{
+        Machine,
+        builder::{ComponentBuilder, SchedulerParticipation},
+    },
+    memory::{AddressSpaceId, MapTarget, MemoryMapCommand, Permissions, WatchpointKind},
+    movie::{MOVIE_VERSION, Movie, MovieError},
+    platform::Platform,
+    scheduler::{Period, SynchronizationContext},
+};
+
+const TICK: Period = Period::lit("0.001");
+const BUTTON: InputId = InputId::Gamepad(GamepadInputId::FPadDown);
+
+/// Counts the ticks its button is held down for into memory, so the result depends on exactly when inputs arrive
+#[derive(Debug)]
+struct HoldCounter {
+    address_space: AddressSpaceId,
+    held: bool,
+    count: u16,
+}
+
+impl Component for HoldCounter {
+    type Event = ();
+
+    fn synchronize(&mut self, mut context: SynchronizationContext) {
+        let mut address_space = context.runtime().address_space(self.address_space).unwrap();
+        let mut allocator = context.quanta_allocator(TICK);
+
+        while let Some(timestamp) = allocator.allocate() {
+            if self.held {
+                self.count = self.count.wrapping_add(1);
+                address_space
+                    .write_le_value(0, timestamp, self.count)
+                    .unwrap();
+            }
+        }
+    }
+
+    fn needs_work(&self, _current_timestamp: &Period, delta: &Period) -> bool {
+        *delta >= TICK
+    }
+
+    fn handle_input(&mut self, _destination: &str, _id: InputId, state: InputState) {
+        self.held = state.as_digital(None);
+    }
+}
+
+#[derive(Debug)]
+struct HoldCounterConfig {
+    address_space: AddressSpaceId,
+}
+
+impl<P: Platform> ComponentConfig<P> for HoldCounterConfig {
+    type Component = HoldCounter;
+
+    fn build_component(
+        self,
+        component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn Error>> {
+        component_builder
+            .scheduler_participation(Some(SchedulerParticipation::SchedulerDriven))
+            .input("pad", [BUTTON], []);
+
+        Ok(HoldCounter {
+            address_space: self.address_space,
+            held: false,
+            count: 0,
+        })
+    }
+}
+
+fn build_machine() -> (Arc<Machine>, ResourcePath, AddressSpaceId) {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+    let (machine, ram_path) = machine.memory("ram", 0x10, []);
+    let machine = machine.map_memory(
+        address_space,
+        [MemoryMapCommand::Map {
+            range: 0..=0xf,
+            permissions: Permissions::ALL,
+            target: MapTarget::Memory {
+                path: ram_path,
+                subrange: None,
+            },
+        }],
+    );
+    let (machine, counter_path) = machine.component("counter", HoldCounterConfig { address_space });
+
+    (
+        machine.seal().build(()),
+        counter_path.into_resource("pad").unwrap(),
+        address_space,
+    )
+}
+
+#[test]
+fn round_trip_and_playback() {
+    let (machine, pad, _) = build_machine();
+    let runtime_guard = machine.enter_runtime();
+
+    runtime_guard.start_movie_recording();
+    for (duration, state) in [
+        ("0.1", InputState::PRESSED),
+        ("0.25", InputState::RELEASED),
+        ("0.05", InputState::PRESSED),
+        ("0.125", InputState::RELEASED),
+    ] {
+        runtime_guard.run(Period::lit(duration));
+        runtime_guard.insert_inputs(&pad, [(BUTTON, state)]);
+    }
+    runtime_guard.run(Period::lit("0.5"));
+    let end = runtime_guard.safe_advance_timestamp();
+    let movie = runtime_guard.stop_movie_recording().unwrap();
+    let recorded_contents = runtime_guard.memory_region_contents();
+
+    assert!(runtime_guard.stop_movie_recording().is_none());
+    assert_eq!(movie.records().len(), 4);
+
+    let movie = Movie::from_bytes(&movie.to_bytes().unwrap()).unwrap();
+    assert_eq!(movie.header().start_time, Period::ZERO);
+
+    drop(runtime_guard);
+
+    let (machine, _, _) = build_machine();
+    let runtime_guard = machine.enter_runtime();
+
+    // The button being held has to have left its mark, or comparing proves nothing
+    assert_ne!(
+        recorded_contents,
+        runtime_guard.memory_region_contents(),
+        "Recording left no trace"
+    );
+
+    let mut playback = runtime_guard.play_movie(movie).unwrap();
+    runtime_guard.run_movie(&mut playback, end);
+
+    assert!(playback.is_finished());
+    assert_eq!(runtime_guard.safe_advance_timestamp(), end);
+    assert_eq!(runtime_guard.memory_region_contents(), recorded_contents);
+}
+
+#[test]
+fn playback_stops_at_watchpoints() {
+    let (machine, pad, _) = build_machine();
+    let runtime_guard = machine.enter_runtime();
+
+    runtime_guard.start_movie_recording();
+    runtime_guard.run(Period::lit("0.1"));
+    runtime_guard.insert_inputs(&pad, [(BUTTON, InputState::PRESSED)]);
+    runtime_guard.run(Period::lit("0.1"));
+    runtime_guard.insert_inputs(&pad, [(BUTTON, InputState::RELEASED)]);
+    runtime_guard.run(Period::lit("0.1"));
+    let end = runtime_guard.safe_advance_timestamp();
+    let movie = runtime_guard.stop_movie_recording().unwrap();
+    let recorded_contents = runtime_guard.memory_region_contents();
+    drop(runtime_guard);
+
+    let (machine, _, address_space) = build_machine();
+    let runtime_guard = machine.enter_runtime();
+    let watchpoint = runtime_guard
+        .address_space(address_space)
+        .unwrap()
+        .add_watchpoint(0..=1, WatchpointKind::Write);
+
+    // The first count written after the press stops the machine well before the release is due
+    let mut playback = runtime_guard.play_movie(movie).unwrap();
+    runtime_guard.run_movie(&mut playback, end);
+
+    let paused_at = runtime_guard.paused_at().unwrap();
+    assert!(paused_at < Period::lit("0.2"));
+    assert_eq!(runtime_guard.safe_advance_timestamp(), paused_at);
+    assert!(!playback.is_finished());
+    assert!(!runtime_guard.take_watchpoint_hits().is_empty());
+
+    // Calling again while paused must not let the release slip in early
+    runtime_guard.run_movie(&mut playback, end);
+    assert!(!playback.is_finished());
+
+    assert!(
+        runtime_guard
+            .address_space(address_space)
+            .unwrap()
+            .remove_watchpoint(watchpoint)
+    );
+    runtime_guard.resume();
+    runtime_guard.run_movie(&mut playback, end - runtime_guard.safe_advance_timestamp());
+
+    assert!(playback.is_finished());
+    assert_eq!(runtime_guard.safe_advance_timestamp(), end);
+    assert_eq!(runtime_guard.memory_region_contents(), recorded_contents);
+}
+
+#[test]
+fn rejects_other_versions() {
+    let machine = Machine::build_test_minimal().seal().build(());
+    let runtime_guard = machine.enter_runtime();
+
+    runtime_guard.start_movie_recording();
+    let mut bytes = runtime_guard
+        .stop_movie_recording()
+        .unwrap()
+        .to_bytes()
+        .unwrap();
+    bytes[8..10].copy_from_slice(&(MOVIE_VERSION + 1).to_le_bytes());
+
+    assert!(matches!(
+        Movie::from_bytes(&bytes),
+        Err(MovieError::UnsupportedVersion { .. })
+    ));
+}
+
+#[test]
+fn rejects_movies_for_other_input_devices() {
+    let (machine, pad, _) = build_machine();
+    let runtime_guard = machine.enter_runtime();
+
+    runtime_guard.start_movie_recording();
+    runtime_guard.insert_inputs(&pad, [(BUTTON, InputState::PRESSED)]);
+    let movie = runtime_guard.stop_movie_recording().unwrap();
+    drop(runtime_guard);
+
+    let machine = Machine::build_test_minimal().seal().build(());
+    let runtime_guard = machine.enter_runtime();
+
+    assert!(matches!(
+        runtime_guard.play_movie(movie),
+        Err(MovieError::UnknownInputDevice(path)) if path == pad
+    ));
+}
@@ -74,6 +74,14 @@ impl ComponentPath {
         self.iter().last().unwrap()
     }
 
+    /// If `other` is this path or lies beneath it
+    pub fn contains(&self, other: &ComponentPath) -> bool {
+        other
+            .0
+            .strip_prefix(self.0.as_ref())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
     pub fn into_resource(
         self,
         resource: impl Into<Cow<'static, str>>,
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::profiling::{Profile, SpanKind};
+
+impl Profile {
+    /// Render the spans in the Chrome trace event format, which Perfetto and `chrome://tracing` can open
+    ///
+    /// Each host thread gets its own track, with every span named after its component
+    pub fn to_chrome_trace(&self) -> String {
+        let trace_events: Vec<_> = self
+            .spans
+            .iter()
+            .map(|span| {
+                json!({
+                    "name": span.path.to_string(),
+                    "cat": match span.kind {
+                        SpanKind::Synchronize => "synchronize",
+                        SpanKind::Event => "event",
+                    },
+                    "ph": "X",
+                    "ts": microseconds(span.start),
+                    "dur": microseconds(span.duration),
+                    "pid": 0,
+                    "tid": span.thread,
+                })
+            })
+            .collect();
+
+        serde_json::to_string(&json!({
+            "traceEvents": trace_events,
+            "displayTimeUnit": "ns",
+        }))
+        .unwrap()
+    }
+}
+
+fn microseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
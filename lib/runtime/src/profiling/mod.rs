@@ -0,0 +1,278 @@
+//! Per component statistics, for finding out which components break the lazy execution model
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use rustc_hash::FxBuildHasher;
+
+use crate::{ComponentPath, component::ComponentId, machine::RuntimeGuard, scheduler::Period};
+
+mod export;
+#[cfg(test)]
+mod tests;
+
+/// Upper bound on recorded spans, so leaving the profiler on doesn't eat all memory
+const MAX_SPANS: usize = 1 << 20;
+
+/// What the profiler gathered about a single component
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComponentProfile {
+    /// Interactions through the component registry, including those by the scheduler and events
+    pub interactions: u64,
+    /// Calls to [`Component::synchronize`](crate::component::Component::synchronize)
+    pub synchronizations: u64,
+    /// Wall time spent synchronizing
+    pub synchronize_time: Duration,
+    /// Emulated time synchronizing advanced the component by
+    pub emulated_time: Period,
+    pub events_handled: u64,
+    /// Wall time spent handling events
+    pub event_time: Duration,
+    /// Deepest nesting of synchronizations this component was caught up at
+    ///
+    /// A component driven by the scheduler alone has a depth of 1, one caught up while another component was
+    /// synchronizing has a depth of 2, and so on
+    pub max_catch_up_depth: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpanKind {
+    Synchronize,
+    Event,
+}
+
+/// A single synchronization or event handler call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSpan {
+    pub path: ComponentPath,
+    pub kind: SpanKind,
+    /// When it started, relative to when the profile was last reset
+    pub start: Duration,
+    pub duration: Duration,
+    /// Host thread it ran on, numbered in the order threads first entered a runtime
+    pub thread: u32,
+}
+
+/// Everything gathered since the profile was last reset
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub components: BTreeMap<ComponentPath, ComponentProfile>,
+    /// Ordered by when they started, up to a limit per thread
+    pub spans: Vec<ProfileSpan>,
+}
+
+impl ComponentProfile {
+    fn merge(&mut self, other: &Self) {
+        self.interactions += other.interactions;
+        self.synchronizations += other.synchronizations;
+        self.synchronize_time += other.synchronize_time;
+        self.emulated_time += other.emulated_time;
+        self.events_handled += other.events_handled;
+        self.event_time += other.event_time;
+        self.max_catch_up_depth = self.max_catch_up_depth.max(other.max_catch_up_depth);
+    }
+}
+
+#[derive(Debug, Default)]
+struct ThreadRecords {
+    components: HashMap<ComponentId, ComponentProfile, FxBuildHasher>,
+    spans: Vec<(ComponentId, SpanKind, Instant, Duration)>,
+}
+
+impl ThreadRecords {
+    fn push_span(&mut self, id: ComponentId, kind: SpanKind, started: Instant) -> Duration {
+        let duration = started.elapsed();
+
+        if self.spans.len() < MAX_SPANS {
+            self.spans.push((id, kind, started, duration));
+        }
+
+        duration
+    }
+}
+
+/// What a single host thread recorded
+///
+/// Only the thread itself records into it, so its lock is only ever contended while a profile is gathered
+#[derive(Debug, Clone)]
+pub(crate) struct ThreadProfile(Arc<Mutex<ThreadRecords>>);
+
+#[derive(Debug)]
+pub(crate) struct Profiler {
+    enabled: AtomicBool,
+    epoch: Mutex<Instant>,
+    /// Keyed by [`current_thread_index`], kept around after threads leave the runtime so nothing is lost
+    threads: Mutex<BTreeMap<u32, ThreadProfile>>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            epoch: Mutex::new(Instant::now()),
+            threads: Mutex::default(),
+        }
+    }
+
+    /// The records of the current thread, for its runtime handle to hold on to
+    pub fn thread_profile(&self) -> ThreadProfile {
+        self.threads
+            .lock()
+            .unwrap()
+            .entry(current_thread_index())
+            .or_insert_with(|| ThreadProfile(Arc::default()))
+            .clone()
+    }
+
+    /// Start timing something, if profiling is enabled
+    #[inline]
+    pub fn start(&self) -> Option<Instant> {
+        self.enabled.load(Ordering::Relaxed).then(Instant::now)
+    }
+
+    #[inline]
+    pub fn record_interaction(&self, thread: &ThreadProfile, id: ComponentId) {
+        if self.enabled.load(Ordering::Relaxed) {
+            thread
+                .0
+                .lock()
+                .unwrap()
+                .components
+                .entry(id)
+                .or_default()
+                .interactions += 1;
+        }
+    }
+
+    pub fn record_synchronize(
+        &self,
+        thread: &ThreadProfile,
+        id: ComponentId,
+        started: Instant,
+        emulated_time: Period,
+        depth: u32,
+    ) {
+        let mut records = thread.0.lock().unwrap();
+        let duration = records.push_span(id, SpanKind::Synchronize, started);
+
+        let profile = records.components.entry(id).or_default();
+        profile.synchronizations += 1;
+        profile.synchronize_time += duration;
+        profile.emulated_time += emulated_time;
+        profile.max_catch_up_depth = profile.max_catch_up_depth.max(depth);
+    }
+
+    pub fn record_event(&self, thread: &ThreadProfile, id: ComponentId, started: Instant) {
+        let mut records = thread.0.lock().unwrap();
+        let duration = records.push_span(id, SpanKind::Event, started);
+
+        let profile = records.components.entry(id).or_default();
+        profile.events_handled += 1;
+        profile.event_time += duration;
+    }
+}
+
+/// Small stable number for the current thread, as [`std::thread::ThreadId`] can't be turned into one
+fn current_thread_index() -> u32 {
+    static NEXT_THREAD_INDEX: AtomicU32 = AtomicU32::new(0);
+
+    thread_local! {
+        static THREAD_INDEX: u32 = NEXT_THREAD_INDEX.fetch_add(1, Ordering::Relaxed);
+    }
+
+    THREAD_INDEX.with(|index| *index)
+}
+
+impl RuntimeGuard<'_> {
+    /// Start or stop gathering per component statistics
+    ///
+    /// Recording still costs every interaction a uncontended lock, so it is off by default
+    pub fn set_profiling(&self, enabled: bool) {
+        self.machine()
+            .profiler
+            .enabled
+            .store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn profiling(&self) -> bool {
+        self.machine().profiler.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Throw away everything gathered so far
+    pub fn reset_profile(&self) {
+        let profiler = &self.machine().profiler;
+        let threads = profiler.threads.lock().unwrap();
+
+        *profiler.epoch.lock().unwrap() = Instant::now();
+        for thread in threads.values() {
+            *thread.0.lock().unwrap() = ThreadRecords::default();
+        }
+    }
+
+    /// Per component statistics gathered since the profile was last reset, without the spans
+    ///
+    /// Components that were unplugged since are left out
+    pub fn component_profiles(&self) -> BTreeMap<ComponentPath, ComponentProfile> {
+        let paths = self.component_paths();
+        let mut components = BTreeMap::new();
+
+        for thread in self.machine().profiler.threads.lock().unwrap().values() {
+            for (id, profile) in &thread.0.lock().unwrap().components {
+                if let Some(path) = paths.get(id) {
+                    components
+                        .entry(path.clone())
+                        .or_insert_with(ComponentProfile::default)
+                        .merge(profile);
+                }
+            }
+        }
+
+        components
+    }
+
+    /// Statistics and spans gathered since the profile was last reset
+    ///
+    /// Walks every recorded span, prefer [`Self::component_profiles`] when they aren't needed
+    pub fn profile(&self) -> Profile {
+        let paths = self.component_paths();
+        let profiler = &self.machine().profiler;
+        let epoch = *profiler.epoch.lock().unwrap();
+        let mut spans = Vec::new();
+
+        for (thread, records) in profiler.threads.lock().unwrap().iter() {
+            spans.extend(records.0.lock().unwrap().spans.iter().filter_map(
+                |(id, kind, started, duration)| {
+                    Some(ProfileSpan {
+                        path: paths.get(id)?.clone(),
+                        kind: *kind,
+                        start: started.saturating_duration_since(epoch),
+                        duration: *duration,
+                        thread: *thread,
+                    })
+                },
+            ));
+        }
+        spans.sort_by_key(|span| span.start);
+
+        Profile {
+            components: self.component_profiles(),
+            spans,
+        }
+    }
+
+    fn component_paths(&self) -> HashMap<ComponentId, ComponentPath, FxBuildHasher> {
+        let component_registry = self.component_registry();
+
+        component_registry
+            .paths()
+            .into_iter()
+            .filter_map(|path| Some((component_registry.id_for_path(&path)?, path)))
+            .collect()
+    }
+}
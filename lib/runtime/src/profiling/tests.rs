@@ -0,0 +1,103 @@
+use std::error::Error;
+
+use crate::{
+    component::{Component, config::ComponentConfig},
+    event::{Event, EventMode},
+    machine::{
+        Machine,
+        builder::{ComponentBuilder, SchedulerParticipation},
+    },
+    platform::Platform,
+    scheduler::{Frequency, Period, SynchronizationContext},
+};
+
+const TICK: Period = Period::lit("0.01");
+
+/// Burns through time in ticks, counting the events it gets
+#[derive(Debug, Default)]
+struct Metronome {
+    beats: u32,
+}
+
+impl Component for Metronome {
+    type Event = ();
+
+    fn synchronize(&mut self, mut context: SynchronizationContext) {
+        let mut allocator = context.quanta_allocator(TICK);
+        while allocator.allocate().is_some() {}
+    }
+
+    fn needs_work(&self, _current_timestamp: &Period, delta: &Period) -> bool {
+        *delta >= TICK
+    }
+
+    fn handle_event(&mut self, _event: Box<dyn Event>) {
+        self.beats += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetronomeConfig;
+
+impl<P: Platform> ComponentConfig<P> for MetronomeConfig {
+    type Component = Metronome;
+
+    fn build_component(
+        self,
+        component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn Error>> {
+        let path = component_builder.path().clone();
+        let frequency = Frequency::lit("4");
+
+        let _ = component_builder
+            .scheduler_participation(Some(SchedulerParticipation::SchedulerDriven))
+            .schedule_event::<Metronome>(
+                &path,
+                frequency.recip(),
+                EventMode::Repeating { frequency },
+                (),
+            );
+
+        Ok(Metronome::default())
+    }
+}
+
+#[test]
+fn profile_counts_synchronizations_and_events() {
+    let (machine, path) =
+        Machine::build_test_minimal().default_component::<MetronomeConfig>("metronome");
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+
+    runtime_guard.run(Period::lit("0.5"));
+    assert!(!runtime_guard.profiling());
+    assert!(runtime_guard.profile().components.is_empty());
+
+    runtime_guard.set_profiling(true);
+    runtime_guard.run(Period::ONE);
+    runtime_guard.set_profiling(false);
+
+    let profile = runtime_guard.profile();
+    assert_eq!(runtime_guard.component_profiles(), profile.components);
+    let metronome = &profile.components[&path];
+
+    assert_eq!(metronome.events_handled, 4);
+    assert!(metronome.synchronizations > 0);
+    assert!(metronome.interactions >= metronome.events_handled);
+    assert!(metronome.emulated_time > Period::lit("0.9"));
+    assert!(metronome.emulated_time <= Period::ONE + TICK);
+    assert_eq!(metronome.max_catch_up_depth, 1);
+    assert_eq!(
+        profile.spans.len() as u64,
+        metronome.synchronizations + metronome.events_handled
+    );
+
+    let trace: serde_json::Value = serde_json::from_str(&profile.to_chrome_trace()).unwrap();
+    assert_eq!(
+        trace["traceEvents"].as_array().unwrap().len(),
+        profile.spans.len()
+    );
+
+    runtime_guard.reset_profile();
+    assert_eq!(runtime_guard.profile(), Default::default());
+}
@@ -1,26 +1,245 @@
-use std::{fmt::Debug, sync::Mutex};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    num::NonZero,
+    panic::AssertUnwindSafe,
+    sync::{
+        Arc, Mutex, RwLock, RwLockWriteGuard,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::JoinHandle,
+};
 
 use fixed::{FixedU128, types::extra::U64};
 
 use crate::{
-    RuntimeHandle, component::ComponentRegistry, event::EventManager, path::ComponentPath,
+    RuntimeHandle, clock::Clock, component::ComponentRegistry, event::EventManager,
+    machine::Machine, memory::AddressSpaceId, path::ComponentPath,
 };
 
+/// How the scheduler drives its components
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchedulerMode {
+    /// Every driven component is advanced one after another on the thread running the machine
+    #[default]
+    Sequential,
+    /// Isolated components are advanced on worker threads, grouped by the address spaces they touch, up to the next
+    /// pending event at a time
+    ///
+    /// Results are identical to [`Self::Sequential`] as long as isolated components keep to what they declared, see
+    /// [`ComponentBuilder::isolated_to`](crate::machine::builder::ComponentBuilder::isolated_to)
+    Parallel,
+}
+
+/// The components the scheduler advances when it runs
+#[derive(Debug, Default)]
+pub(crate) struct DrivenComponents {
+    /// In the order they are driven
+    pub paths: Vec<ComponentPath>,
+    /// Address spaces each isolated component is confined to
+    pub isolated: HashMap<ComponentPath, Vec<AddressSpaceId>>,
+}
+
+impl DrivenComponents {
+    pub fn extend(&mut self, other: Self) {
+        self.paths.extend(other.paths);
+        self.isolated.extend(other.isolated);
+    }
+
+    pub fn retain(&mut self, mut predicate: impl FnMut(&ComponentPath) -> bool) {
+        self.paths.retain(&mut predicate);
+        self.isolated.retain(|path, _| predicate(path));
+    }
+
+    /// Split off isolated components into groups that share no address spaces with each other
+    ///
+    /// Returns the components that have to stay on the calling thread and the groups, both in driven order
+    fn partition(&self) -> (Vec<&ComponentPath>, Vec<Vec<&ComponentPath>>) {
+        let mut local = Vec::new();
+        let mut groups: Vec<(Vec<AddressSpaceId>, Vec<&ComponentPath>)> = Vec::new();
+
+        for path in &self.paths {
+            let Some(address_spaces) = self.isolated.get(path) else {
+                local.push(path);
+                continue;
+            };
+
+            let mut merged = (address_spaces.clone(), Vec::from([path]));
+
+            // Fold every group touching one of our address spaces into ours
+            let mut index = 0;
+            while index < groups.len() {
+                if groups[index]
+                    .0
+                    .iter()
+                    .any(|address_space| merged.0.contains(address_space))
+                {
+                    let (address_spaces, paths) = groups.remove(index);
+
+                    merged.0.extend(address_spaces);
+                    merged.1.splice(0..0, paths);
+                } else {
+                    index += 1;
+                }
+            }
+
+            groups.push(merged);
+        }
+
+        let mut groups: Vec<_> = groups.into_iter().map(|(_, paths)| paths).collect();
+        for group in &mut groups {
+            group.sort_by_key(|path| self.paths.iter().position(|other| other == *path));
+        }
+
+        (local, groups)
+    }
+}
+
+/// What a worker of the parallel scheduler is told to do
+#[derive(Debug)]
+enum Job {
+    /// Enter the runtime of `machine` for the duration of a run, taking on `paths`
+    Enter {
+        machine: Arc<Machine>,
+        paths: Vec<ComponentPath>,
+        address_spaces: Vec<AddressSpaceId>,
+    },
+    /// Drive the components taken on up to the timestamp
+    Segment(Period),
+    /// Leave the runtime at the end of a run, so the workers never keep the machine alive
+    Leave,
+}
+
+/// Threads the parallel scheduler hands groups of isolated components to, alive for as long as it is switched on
+#[derive(Debug)]
+struct WorkerPool {
+    workers: Vec<(Sender<Job>, JoinHandle<()>)>,
+    /// Every job but [`Job::Enter`] is answered here, with the panic of the worker if it had one
+    finished: Receiver<std::thread::Result<()>>,
+}
+
+impl WorkerPool {
+    /// One worker for every core but the one running the machine
+    fn new() -> Self {
+        let count = std::thread::available_parallelism()
+            .map_or(1, NonZero::get)
+            .saturating_sub(1)
+            .max(1);
+        let (finished_sender, finished) = mpsc::channel();
+
+        let workers = (0..count)
+            .map(|index| {
+                let (jobs, job_receiver) = mpsc::channel();
+                let finished = finished_sender.clone();
+
+                let thread = std::thread::Builder::new()
+                    .name(format!("scheduler-worker-{index}"))
+                    .spawn(move || Self::work(&job_receiver, &finished))
+                    .expect("Failed to spawn scheduler worker");
+
+                (jobs, thread)
+            })
+            .collect();
+
+        Self { workers, finished }
+    }
+
+    fn work(jobs: &Receiver<Job>, finished: &Sender<std::thread::Result<()>>) {
+        while let Ok(Job::Enter {
+            machine,
+            paths,
+            address_spaces,
+        }) = jobs.recv()
+        {
+            {
+                let runtime_guard = machine.enter_runtime();
+                let component_registry = runtime_guard.component_registry();
+                component_registry.set_isolated(address_spaces);
+
+                while let Ok(Job::Segment(segment_end)) = jobs.recv() {
+                    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                        for path in &paths {
+                            component_registry.interact_dyn(path, &segment_end, |_| {});
+                        }
+                    }));
+
+                    // Let the thread running the machine have whatever the components here touched
+                    //
+                    // SAFETY: No component interaction is ongoing on this thread
+                    unsafe { component_registry.release_all() };
+                    runtime_guard.memory_registry().release_all();
+
+                    let _ = finished.send(result);
+                }
+            }
+
+            // Let go of the machine before answering, so it is never dropped here
+            drop(machine);
+
+            let _ = finished.send(Ok(()));
+        }
+    }
+
+    /// Send a job to the first `count` workers, run `local` on this thread meanwhile and wait for all of them to finish
+    ///
+    /// Returns the first panic any of them had
+    fn dispatch(
+        &self,
+        count: usize,
+        job: impl Fn() -> Job,
+        local: impl FnOnce(),
+    ) -> std::thread::Result<()> {
+        for (jobs, _) in &self.workers[..count] {
+            jobs.send(job()).expect("Scheduler worker went away");
+        }
+
+        let mut result = std::panic::catch_unwind(AssertUnwindSafe(local));
+        for _ in 0..count {
+            let finished = self.finished.recv().expect("Scheduler worker went away");
+            result = result.and(finished);
+        }
+
+        result
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        // Hanging up on the workers ends them
+        for (jobs, thread) in self.workers.drain(..) {
+            drop(jobs);
+            let _ = thread.join();
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Scheduler {
     pub event_manager: EventManager,
     safe_advance_timestamp: Mutex<Period>,
-    driven: Vec<ComponentPath>,
+    /// Held for reading for the whole of a run, so the set of components can't change in the middle of one
+    driven: RwLock<DrivenComponents>,
+    mode: Mutex<SchedulerMode>,
+    /// Present while in [`SchedulerMode::Parallel`]
+    workers: Mutex<Option<WorkerPool>>,
     start_time: Period,
+    /// Timestamp nothing is allowed to advance past, set by debugging facilities
+    pause_timestamp: Mutex<Option<Period>>,
+    paused: AtomicBool,
 }
 
 impl Scheduler {
     pub fn new() -> Self {
         Scheduler {
             event_manager: EventManager::default(),
-            driven: Vec::default(),
+            driven: RwLock::default(),
+            mode: Mutex::default(),
+            workers: Mutex::default(),
             safe_advance_timestamp: Mutex::default(),
             start_time: Period::default(),
+            pause_timestamp: Mutex::default(),
+            paused: AtomicBool::new(false),
         }
     }
 
@@ -33,29 +252,246 @@ impl Scheduler {
         self.start_time
     }
 
+    /// Overwrite the safe advance timestamp, for restoring the machine to a previous point in time
+    pub fn set_safe_advance_timestamp(&self, timestamp: Period) {
+        *self.safe_advance_timestamp.lock().unwrap() = timestamp;
+    }
+
+    /// Stop every component at the given timestamp, or at the earliest one if already paused
+    ///
+    /// Components already running are preempted so they notice as soon as possible
+    pub fn pause_at(&self, timestamp: Period) {
+        let mut pause_timestamp_guard = self.pause_timestamp.lock().unwrap();
+        *pause_timestamp_guard =
+            Some(pause_timestamp_guard.map_or(timestamp, |existing| existing.min(timestamp)));
+        self.paused.store(true, Ordering::Release);
+
+        self.event_manager.preemption_signal().preempt();
+    }
+
+    /// Allow the machine to advance past the pause timestamp again
+    pub fn resume(&self) {
+        *self.pause_timestamp.lock().unwrap() = None;
+        self.paused.store(false, Ordering::Release);
+    }
+
+    /// The timestamp the machine is paused at, if any
+    #[inline]
+    pub fn pause_timestamp(&self) -> Option<Period> {
+        if !self.paused.load(Ordering::Acquire) {
+            return None;
+        }
+
+        *self.pause_timestamp.lock().unwrap()
+    }
+
+    pub fn mode(&self) -> SchedulerMode {
+        *self.mode.lock().unwrap()
+    }
+
+    /// Switch modes, starting up the worker threads for [`SchedulerMode::Parallel`] and shutting them down otherwise
+    pub fn set_mode(&self, mode: SchedulerMode) {
+        let mut workers_guard = self.workers.lock().unwrap();
+
+        match mode {
+            SchedulerMode::Sequential => *workers_guard = None,
+            SchedulerMode::Parallel => {
+                workers_guard.get_or_insert_with(WorkerPool::new);
+            }
+        }
+
+        *self.mode.lock().unwrap() = mode;
+    }
+
+    /// The latest timestamp a component targeting `target_timestamp` may run to before it has to stop
+    ///
+    /// Isolated components never see events, the parallel scheduler already stops them at the next one
+    #[inline]
+    fn stop_time(&self, target_timestamp: Period, isolated: bool) -> Period {
+        let mut stop_time = target_timestamp;
+
+        // If a event exists, allow it to cut our budget short
+        if !isolated && let Some(next_event) = self.event_manager.next_event() {
+            stop_time = stop_time.min(next_event);
+        }
+
+        if let Some(pause_timestamp) = self.pause_timestamp() {
+            std::hint::cold_path();
+
+            stop_time = stop_time.min(pause_timestamp);
+        }
+
+        stop_time
+    }
+
     /// Register a new component that is directly driven by the scheduler
     ///
     /// For machine builder purposes
     pub fn register_driven_component(&mut self, path: ComponentPath) {
-        self.driven.push(path);
+        self.driven.get_mut().unwrap().paths.push(path);
+    }
+
+    /// Mark a driven component as confined to the given address spaces
+    ///
+    /// For machine builder purposes
+    pub fn register_isolated_component(
+        &mut self,
+        path: ComponentPath,
+        address_spaces: Vec<AddressSpaceId>,
+    ) {
+        self.driven
+            .get_mut()
+            .unwrap()
+            .isolated
+            .insert(path, address_spaces);
+    }
+
+    /// Take the scheduler driven components registered so far
+    ///
+    /// For machine builder purposes
+    pub fn take_driven_components(&mut self) -> DrivenComponents {
+        std::mem::take(self.driven.get_mut().unwrap())
+    }
+
+    /// Lock out runs of the scheduler while components are plugged in or unplugged
+    ///
+    /// The guard gives access to the scheduler driven components
+    pub fn lock_components(&self) -> RwLockWriteGuard<'_, DrivenComponents> {
+        self.driven.write().unwrap()
     }
 
     /// Run the scheduler for a given amount of time, advancing the machine's timestamp and interacting with driven components
     ///
     /// After all driven components (ie: cpus) are successfully advanced, the safe advance timestamp is updated to reflect the new time
+    ///
+    /// If the scheduler is paused, the machine will not be advanced past the pause timestamp
     pub fn run(&self, component_registry: &mut ComponentRegistry<'_>, allocated_time: Period) {
         // Grab current time
-        let safe_advance_timestamp = self.safe_advance_timestamp() + allocated_time;
+        let mut safe_advance_timestamp = self.safe_advance_timestamp() + allocated_time;
+
+        if let Some(pause_timestamp) = self.pause_timestamp() {
+            safe_advance_timestamp = safe_advance_timestamp.min(pause_timestamp);
+        }
+
+        let driven_guard = self.driven.read().unwrap();
+
+        match self.mode() {
+            SchedulerMode::Sequential => {
+                // Advance the time forward for all driven components
+                for path in &driven_guard.paths {
+                    component_registry.interact_dyn(path, &safe_advance_timestamp, |_| {});
+                }
+            }
+            SchedulerMode::Parallel => {
+                self.run_parallel(component_registry, &driven_guard, safe_advance_timestamp);
+            }
+        }
 
-        // Advance the time forward for all driven components
-        for path in &self.driven {
-            component_registry.interact_dyn(path, &safe_advance_timestamp, |_| {});
+        // A component may have hit a watchpoint while we were driving it
+        if let Some(pause_timestamp) = self.pause_timestamp() {
+            safe_advance_timestamp = safe_advance_timestamp.min(pause_timestamp);
         }
 
+        // Components only consume events on their way to the target, so whatever lies past the last step of every one of
+        // them, or everything in a machine without driven components, is still pending
+        self.event_manager
+            .consume(component_registry, safe_advance_timestamp);
+
         // Set the new time, marking that the machine has officially advanced to this time
         let mut safe_advance_timestamp_guard = self.safe_advance_timestamp.lock().unwrap();
         *safe_advance_timestamp_guard = (*safe_advance_timestamp_guard).max(safe_advance_timestamp);
     }
+
+    /// Drive isolated components on worker threads and everything else on this one, a event at a time
+    fn run_parallel(
+        &self,
+        component_registry: &ComponentRegistry<'_>,
+        driven: &DrivenComponents,
+        target_timestamp: Period,
+    ) {
+        let (local, groups) = driven.partition();
+        let runtime = component_registry.runtime();
+
+        let workers_guard = self.workers.lock().unwrap();
+        let Some(workers) = workers_guard.as_ref().filter(|_| !groups.is_empty()) else {
+            for path in local {
+                component_registry.interact_dyn(path, &target_timestamp, |_| {});
+            }
+
+            return;
+        };
+
+        // Groups share nothing, so a worker can take on several of them one after another
+        let count = groups.len().min(workers.workers.len());
+        let mut assignments = vec![(Vec::new(), Vec::new()); count];
+        for (index, group) in groups.into_iter().enumerate() {
+            let (paths, address_spaces) = &mut assignments[index % count];
+
+            for path in group {
+                paths.push(path.clone());
+                address_spaces.extend(driven.isolated[path].iter().copied());
+            }
+        }
+
+        for ((jobs, _), (paths, address_spaces)) in workers.workers.iter().zip(assignments) {
+            jobs.send(Job::Enter {
+                machine: runtime.shared_machine().clone(),
+                paths,
+                address_spaces,
+            })
+            .expect("Scheduler worker went away");
+        }
+
+        let result = loop {
+            // Pending events are the synchronization points, nothing isolated runs past one
+            let segment_end = self
+                .event_manager
+                .next_event()
+                .map_or(target_timestamp, |next_event| {
+                    next_event.min(target_timestamp)
+                });
+
+            // Workers can't take anything this thread is holding on to
+            //
+            // SAFETY: The scheduler only runs outside of any component interaction
+            unsafe { component_registry.release_all() };
+            runtime.memory_registry().release_all();
+
+            let result = workers.dispatch(
+                count,
+                || Job::Segment(segment_end),
+                || {
+                    for path in &local {
+                        component_registry.interact_dyn(*path, &segment_end, |_| {});
+                    }
+
+                    // Let workers have whatever the components here touched
+                    //
+                    // SAFETY: Same as above
+                    unsafe { component_registry.release_all() };
+                    runtime.memory_registry().release_all();
+                },
+            );
+
+            if result.is_err()
+                || segment_end >= target_timestamp
+                || self
+                    .pause_timestamp()
+                    .is_some_and(|pause_timestamp| pause_timestamp <= segment_end)
+            {
+                break result;
+            }
+
+            self.event_manager.consume(component_registry, segment_end);
+        };
+
+        // Wait for the workers to let go of the machine, even if one of them panicked
+        let _ = workers.dispatch(count, || Job::Leave, || {});
+
+        if let Err(panic) = result {
+            std::panic::resume_unwind(panic);
+        }
+    }
 }
 
 /// Type representing a period, or a inverse frequency, as a Q64.64
@@ -70,6 +506,8 @@ pub struct SynchronizationContext<'a> {
     pub(crate) current_timestamp: &'a mut Period,
     pub(crate) target_timestamp: Period,
     pub(crate) last_attempted_allocation: &'a mut Period,
+    /// If this is a isolated component on a worker thread of the parallel scheduler
+    pub(crate) isolated: bool,
 }
 
 impl<'a> SynchronizationContext<'a> {
@@ -82,7 +520,11 @@ impl<'a> SynchronizationContext<'a> {
     /// or the runtime preempts the task
     #[inline]
     pub fn quanta_allocator<'b>(&'b mut self, period: Period) -> QuantaAllocator<'b, 'a> {
-        let (last_seen_event_generation, budget) = self.check_allocation_preconditions(period);
+        let (last_seen_event_generation, stop_time) = self.check_allocation_preconditions(period);
+        let budget = (stop_time.saturating_sub(*self.current_timestamp) / period)
+            .floor()
+            .checked_to_num::<u32>()
+            .unwrap_or(u32::MAX);
 
         QuantaAllocator {
             period,
@@ -92,25 +534,63 @@ impl<'a> SynchronizationContext<'a> {
         }
     }
 
+    /// Create an iterator that allocates one cycle of `clock` at a time, until either the target timestamp is reached
+    /// or the runtime preempts the task
+    ///
+    /// Cycles start where [`Clock::cycles`] puts them counting from zero, so unlike adding up [`Clock::period`] with
+    /// [`SynchronizationContext::quanta_allocator`] no rounding error piles up. The first allocation may be shorter
+    /// than a cycle if the component is not on a cycle boundary yet
     #[inline]
-    fn check_allocation_preconditions(&mut self, period: Period) -> (u32, u32) {
+    pub fn cycle_allocator<'b>(&'b mut self, clock: Clock) -> CycleAllocator<'b, 'a> {
+        let (last_seen_event_generation, stop_time) =
+            self.check_allocation_preconditions(clock.period());
+
+        let cycle = clock.cycle_at(*self.current_timestamp);
+        let budget = clock
+            .cycle_at(stop_time)
+            .saturating_sub(cycle)
+            .try_into()
+            .unwrap_or(u32::MAX);
+
+        CycleAllocator {
+            clock,
+            cycle,
+            start: clock.start_of(cycle),
+            budget,
+            last_seen_event_generation,
+            context: self,
+        }
+    }
+
+    /// Returns the current event generation and the time allocations have to stop at
+    #[inline]
+    fn check_allocation_preconditions(&mut self, period: Period) -> (u32, Period) {
         assert_ne!(period, Period::ZERO, "Cannot allocate zero period");
         *self.last_attempted_allocation = period;
 
         let scheduler = &self.runtime.machine().scheduler;
         let last_seen_event_generation = scheduler.event_manager.preemption_signal().generation();
 
-        let mut stop_time = self.target_timestamp;
-        if let Some(next_event) = scheduler.event_manager.next_event() {
-            stop_time = stop_time.min(next_event);
-        }
+        (
+            last_seen_event_generation,
+            scheduler.stop_time(self.target_timestamp, self.isolated),
+        )
+    }
 
-        let budget = (stop_time.saturating_sub(*self.current_timestamp) / period)
-            .floor()
-            .checked_to_num::<u32>()
-            .unwrap_or(u32::MAX);
+    fn stop_time(&self) -> Period {
+        self.runtime
+            .machine()
+            .scheduler
+            .stop_time(self.target_timestamp, self.isolated)
+    }
 
-        (last_seen_event_generation, budget)
+    fn event_generation(&self) -> u32 {
+        self.runtime
+            .machine()
+            .scheduler
+            .event_manager
+            .preemption_signal()
+            .generation()
     }
 }
 
@@ -154,19 +634,12 @@ impl QuantaAllocator<'_, '_> {
 
     #[cold]
     fn rebudget(&mut self) {
-        let mut stop_time = self.context.target_timestamp;
-
-        // If a event exists, allow it to cut our budget short
-        if let Some(next_event) = self
+        let stop_time = self
             .context
             .runtime
             .machine()
             .scheduler
-            .event_manager
-            .next_event()
-        {
-            stop_time = stop_time.min(next_event);
-        }
+            .stop_time(self.context.target_timestamp, self.context.isolated);
 
         // Recalculate budget
         let new_budget = (stop_time.saturating_sub(*self.context.current_timestamp) / self.period)
@@ -177,3 +650,58 @@ impl QuantaAllocator<'_, '_> {
         self.budget = self.budget.min(new_budget);
     }
 }
+
+/// Helper iterator to allocate single cycles of a clock until the time budget is exhausted
+pub struct CycleAllocator<'b, 'a> {
+    clock: Clock,
+    /// Number of the cycle running at the current timestamp
+    cycle: u64,
+    /// Start of that cycle, with the remainder [`Clock::start_of`] dropped
+    start: (Period, u128),
+    budget: u32,
+    last_seen_event_generation: u32,
+    context: &'b mut SynchronizationContext<'a>,
+}
+
+impl CycleAllocator<'_, '_> {
+    #[inline]
+    pub fn allocate(&mut self) -> Option<&Period> {
+        let current_generation = self.context.event_generation();
+        if current_generation != self.last_seen_event_generation {
+            self.last_seen_event_generation = current_generation;
+            self.rebudget();
+        }
+
+        if self.budget != 0 {
+            self.budget -= 1;
+        } else {
+            std::hint::cold_path();
+
+            return None;
+        }
+
+        self.cycle += 1;
+        self.start = self.clock.next_start(self.start);
+        *self.context.current_timestamp = self.start.0;
+
+        Some(self.context.current_timestamp)
+    }
+
+    /// Number of the cycle the last allocation started, counted from zero
+    #[inline]
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    #[cold]
+    fn rebudget(&mut self) {
+        let new_budget = self
+            .clock
+            .cycle_at(self.context.stop_time())
+            .saturating_sub(self.cycle)
+            .try_into()
+            .unwrap_or(u32::MAX);
+
+        self.budget = self.budget.min(new_budget);
+    }
+}
@@ -0,0 +1,307 @@
+//! Signal lines carrying pin levels between components, such as interrupt, reset and ready pins
+
+use std::collections::{BTreeMap, HashMap};
+
+use rustc_hash::FxBuildHasher;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    RuntimeHandle,
+    event::{Event, EventMode},
+    path::ResourcePath,
+    scheduler::Period,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Turns a level change into the event type of the component owning the input pin
+pub(crate) type SignalChangeConstructor = fn(SignalChange) -> Box<dyn Event>;
+
+/// Level changes waiting to be handed to the components owning the inputs
+pub(crate) type SignalEvents = Vec<(ResourcePath, Box<dyn Event>)>;
+
+/// A change of the combined level seen by a input pin, delivered to the component owning the pin as a event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignalChange {
+    /// Name of the input pin
+    pub pin: String,
+    pub level: bool,
+}
+
+/// How the levels of every output connected to a input pin combine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Wiring {
+    /// High while any output drives it high, low while nothing is connected
+    #[default]
+    WiredOr,
+    /// Low while any output drives it low, pulled high while nothing is connected
+    ///
+    /// This is how open collector outputs sharing a active low line behave, like the IRQ line of most 8 bit machines
+    WiredAnd,
+}
+
+impl Wiring {
+    fn resolve(self, mut levels: impl Iterator<Item = bool>) -> bool {
+        match self {
+            Wiring::WiredOr => levels.any(|level| level),
+            Wiring::WiredAnd => levels.all(|level| level),
+        }
+    }
+}
+
+/// Pins and connections declared while building, resolved once the components they belong to exist
+#[derive(Debug, Default)]
+pub(crate) struct SignalSetup {
+    pub outputs: Vec<(ResourcePath, bool)>,
+    pub inputs: Vec<(ResourcePath, Wiring, SignalChangeConstructor)>,
+    pub connections: Vec<(ResourcePath, ResourcePath)>,
+}
+
+#[derive(Debug)]
+struct OutputPin {
+    level: bool,
+    /// Level the machine starts with, restored on a hard reset
+    initial_level: bool,
+    inputs: Vec<ResourcePath>,
+}
+
+#[derive(Debug)]
+struct InputPin {
+    wiring: Wiring,
+    level: bool,
+    drivers: Vec<ResourcePath>,
+    constructor: SignalChangeConstructor,
+}
+
+/// Every signal pin in the machine and what drives what
+#[derive(Debug, Default)]
+pub(crate) struct SignalBoard {
+    outputs: HashMap<ResourcePath, OutputPin, FxBuildHasher>,
+    inputs: HashMap<ResourcePath, InputPin, FxBuildHasher>,
+}
+
+impl SignalBoard {
+    /// Add the pins and connections of newly built components
+    ///
+    /// Returns the events telling every new input its starting level, and the events for existing inputs whose level
+    /// changed
+    ///
+    /// # Panics
+    ///
+    /// Panics if a connection refers to a pin that does not exist, or a pin is declared twice
+    pub fn extend(&mut self, setup: SignalSetup) -> (SignalEvents, SignalEvents) {
+        for (path, level) in setup.outputs {
+            let previous = self.outputs.insert(
+                path.clone(),
+                OutputPin {
+                    level,
+                    initial_level: level,
+                    inputs: Vec::default(),
+                },
+            );
+            assert!(previous.is_none(), "Signal output {path} already exists");
+        }
+
+        let mut new_inputs = Vec::with_capacity(setup.inputs.len());
+
+        for (path, wiring, constructor) in setup.inputs {
+            let previous = self.inputs.insert(
+                path.clone(),
+                InputPin {
+                    wiring,
+                    level: wiring.resolve(std::iter::empty()),
+                    drivers: Vec::default(),
+                    constructor,
+                },
+            );
+            assert!(previous.is_none(), "Signal input {path} already exists");
+
+            new_inputs.push(path);
+        }
+
+        let mut touched = Vec::new();
+
+        for (output, input) in setup.connections {
+            let output_pin = self
+                .outputs
+                .get_mut(&output)
+                .unwrap_or_else(|| panic!("No signal output {output}"));
+            let input_pin = self
+                .inputs
+                .get_mut(&input)
+                .unwrap_or_else(|| panic!("No signal input {input}"));
+
+            if !output_pin.inputs.contains(&input) {
+                output_pin.inputs.push(input.clone());
+                input_pin.drivers.push(output);
+            }
+
+            touched.push(input);
+        }
+
+        touched.sort();
+        touched.dedup();
+        touched.retain(|path| !new_inputs.contains(path));
+
+        let starting_levels = new_inputs
+            .into_iter()
+            .map(|path| {
+                self.recombine(&path);
+                let event = self.announce(&path);
+                (path, event)
+            })
+            .collect();
+
+        (starting_levels, self.resolve(touched))
+    }
+
+    /// Set the level of a output, returning the events for inputs whose level changed
+    fn drive(&mut self, output: &ResourcePath, level: bool) -> SignalEvents {
+        let output_pin = self
+            .outputs
+            .get_mut(output)
+            .unwrap_or_else(|| panic!("No signal output {output}"));
+
+        if std::mem::replace(&mut output_pin.level, level) == level {
+            return Vec::new();
+        }
+
+        let inputs = output_pin.inputs.clone();
+        self.resolve(inputs)
+    }
+
+    /// Recombine the levels of the given inputs
+    fn resolve(&mut self, inputs: impl IntoIterator<Item = ResourcePath>) -> SignalEvents {
+        let mut events = Vec::new();
+
+        for path in inputs {
+            if self.recombine(&path) {
+                let event = self.announce(&path);
+                events.push((path, event));
+            }
+        }
+
+        events
+    }
+
+    /// Recombine the level of a input, returning if it changed
+    fn recombine(&mut self, path: &ResourcePath) -> bool {
+        let input_pin = self.inputs.get_mut(path).unwrap();
+        let level = input_pin.wiring.resolve(
+            input_pin
+                .drivers
+                .iter()
+                .map(|driver| self.outputs[driver].level),
+        );
+
+        std::mem::replace(&mut input_pin.level, level) != level
+    }
+
+    /// The event telling the owner of a input its current level
+    fn announce(&self, path: &ResourcePath) -> Box<dyn Event> {
+        let input_pin = &self.inputs[path];
+
+        (input_pin.constructor)(SignalChange {
+            pin: path.name().to_owned(),
+            level: input_pin.level,
+        })
+    }
+
+    /// Drop every pin matching the predicate, returning the events for remaining inputs whose level changed
+    pub fn remove(&mut self, predicate: impl Fn(&ResourcePath) -> bool) -> SignalEvents {
+        self.outputs.retain(|path, _| !predicate(path));
+        self.inputs.retain(|path, _| !predicate(path));
+
+        let mut touched = Vec::new();
+
+        for (path, input_pin) in &mut self.inputs {
+            let driver_count = input_pin.drivers.len();
+            input_pin.drivers.retain(|driver| !predicate(driver));
+
+            if input_pin.drivers.len() != driver_count {
+                touched.push(path.clone());
+            }
+        }
+
+        for output_pin in self.outputs.values_mut() {
+            output_pin.inputs.retain(|input| !predicate(input));
+        }
+
+        touched.sort();
+        self.resolve(touched)
+    }
+
+    pub fn level(&self, pin: &ResourcePath) -> Option<bool> {
+        self.outputs
+            .get(pin)
+            .map(|output_pin| output_pin.level)
+            .or_else(|| self.inputs.get(pin).map(|input_pin| input_pin.level))
+    }
+
+    /// Levels of every output, which is all the state the board has
+    pub fn output_levels(&self) -> BTreeMap<ResourcePath, bool> {
+        self.outputs
+            .iter()
+            .map(|(path, output_pin)| (path.clone(), output_pin.level))
+            .collect()
+    }
+
+    /// Set the levels of every output at once, without notifying anyone
+    ///
+    /// Outputs missing from `levels` go back to their initial level
+    pub fn restore_output_levels(&mut self, levels: &BTreeMap<ResourcePath, bool>) {
+        for (path, output_pin) in &mut self.outputs {
+            output_pin.level = levels
+                .get(path)
+                .copied()
+                .unwrap_or(output_pin.initial_level);
+        }
+
+        let inputs: Vec<_> = self.inputs.keys().cloned().collect();
+        self.resolve(inputs);
+    }
+
+    pub fn restore_initial(&mut self) {
+        self.restore_output_levels(&BTreeMap::new());
+    }
+}
+
+/// Hand level changes to the components owning the inputs
+pub(crate) fn deliver(runtime: &RuntimeHandle, timestamp: Period, events: SignalEvents) {
+    for (input, event) in events {
+        runtime.machine().scheduler.event_manager.schedule(
+            timestamp,
+            input.parent().unwrap().clone(),
+            EventMode::Once,
+            event,
+        );
+    }
+}
+
+impl RuntimeHandle {
+    /// Drive a output pin to `level` at `timestamp`
+    ///
+    /// Every input pin whose combined level changes because of it is notified with a [`SignalChange`] event at
+    /// `timestamp`. Levels are combined in the order outputs are driven in, so a component should only drive its
+    /// outputs at its own current timestamp
+    ///
+    /// # Panics
+    ///
+    /// Panics if the output does not exist
+    pub fn drive_signal(&self, output: &ResourcePath, timestamp: Period, level: bool) {
+        debug_assert!(
+            !self.component_registry().is_isolated(),
+            "Isolated components cannot drive signals"
+        );
+
+        let events = self.machine().signals.lock().unwrap().drive(output, level);
+
+        deliver(self, timestamp, events);
+    }
+
+    /// Current level of a signal pin, combined from everything connected to it for inputs
+    pub fn signal_level(&self, pin: &ResourcePath) -> Option<bool> {
+        self.machine().signals.lock().unwrap().level(pin)
+    }
+}
@@ -0,0 +1,112 @@
+use std::error::Error;
+
+use crate::{
+    component::{Component, config::ComponentConfig},
+    event::{Event, downcast_event},
+    machine::{Machine, builder::ComponentBuilder},
+    platform::Platform,
+    scheduler::Period,
+    signal::{SignalChange, Wiring},
+};
+
+#[derive(Debug)]
+struct Source;
+
+impl Component for Source {
+    type Event = ();
+}
+
+#[derive(Debug, Default)]
+struct SourceConfig;
+
+impl<P: Platform> ComponentConfig<P> for SourceConfig {
+    type Component = Source;
+
+    fn build_component(
+        self,
+        component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn Error>> {
+        component_builder.signal_output("irq", false);
+
+        Ok(Source)
+    }
+}
+
+/// Remembers every level change its input saw
+#[derive(Debug, Default)]
+struct Sink {
+    changes: Vec<SignalChange>,
+}
+
+impl Component for Sink {
+    type Event = SignalChange;
+
+    fn handle_event(&mut self, event: Box<dyn Event>) {
+        self.changes.push(downcast_event::<Self>(event));
+    }
+}
+
+#[derive(Debug, Default)]
+struct SinkConfig;
+
+impl<P: Platform> ComponentConfig<P> for SinkConfig {
+    type Component = Sink;
+
+    fn build_component(
+        self,
+        component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn Error>> {
+        component_builder.signal_input("irq", Wiring::WiredOr);
+
+        Ok(Sink::default())
+    }
+}
+
+#[test]
+fn wired_or_line_combines_sources() {
+    let (machine, first) = Machine::build_test_minimal().default_component::<SourceConfig>("first");
+    let (machine, second) = machine.default_component::<SourceConfig>("second");
+    let (machine, sink) = machine.default_component::<SinkConfig>("sink");
+
+    let first_irq = first.into_resource("irq").unwrap();
+    let second_irq = second.clone().into_resource("irq").unwrap();
+    let sink_irq = sink.clone().into_resource("irq").unwrap();
+
+    let machine = machine
+        .connect_signal(first_irq.clone(), sink_irq.clone())
+        .connect_signal(second_irq.clone(), sink_irq.clone())
+        .seal()
+        .build(());
+    let runtime_guard = machine.enter_runtime();
+
+    assert_eq!(runtime_guard.signal_level(&sink_irq), Some(false));
+
+    runtime_guard.drive_signal(&first_irq, Period::lit("0.1"), true);
+    runtime_guard.drive_signal(&second_irq, Period::lit("0.2"), true);
+    runtime_guard.drive_signal(&first_irq, Period::lit("0.3"), false);
+    assert_eq!(runtime_guard.signal_level(&sink_irq), Some(true));
+
+    runtime_guard.drive_signal(&second_irq, Period::lit("0.4"), false);
+    assert_eq!(runtime_guard.signal_level(&sink_irq), Some(false));
+
+    // Pulling the line from a source that goes away releases it
+    runtime_guard.drive_signal(&second_irq, Period::lit("0.5"), true);
+    runtime_guard.run(Period::ONE);
+    assert!(runtime_guard.unplug_component(&second));
+    assert_eq!(runtime_guard.signal_level(&sink_irq), Some(false));
+    runtime_guard.run(Period::ONE);
+
+    let levels: Vec<_> = runtime_guard
+        .component_registry()
+        .interact::<Sink, _>(&sink, &Period::lit("2"), |sink| sink.changes.clone())
+        .unwrap()
+        .into_iter()
+        .map(|change| {
+            assert_eq!(change.pin, "irq");
+            change.level
+        })
+        .collect();
+
+    // Starting with the level the line settled on when the machine was sealed
+    assert_eq!(levels, [false, true, false, true, false]);
+}
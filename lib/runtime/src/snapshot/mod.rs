@@ -0,0 +1,347 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use fluxemu_program::ProgramId;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_with::{Bytes, serde_as};
+use thiserror::Error;
+
+use crate::{
+    ComponentPath, ResourcePath, RuntimeHandle,
+    event::{EventHandle, EventMode, PendingEvent},
+    memory::{AddressSpaceId, MemoryId},
+    scheduler::Period,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Current version of the snapshot format
+///
+/// Bump this whenever the layout of [`Snapshot`] or the state of any component changes in an incompatible way
+pub const SNAPSHOT_VERSION: u16 = 4;
+const SNAPSHOT_MAGIC: [u8; 8] = *b"FLUXSNAP";
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Data is not a snapshot")]
+    BadMagic,
+    #[error("Snapshot version {found} is not supported (expected {expected})")]
+    UnsupportedVersion { found: u16, expected: u16 },
+    #[error("Snapshot was taken from a different program ({0:?})")]
+    ProgramMismatch(Option<ProgramId>),
+    #[error("Snapshot components do not match the machine")]
+    ComponentMismatch,
+    #[error("Snapshot memory regions do not match the machine")]
+    MemoryMismatch,
+    #[error("Snapshot signal outputs do not match the machine")]
+    SignalMismatch,
+    #[error("Snapshot contains an event for unknown component {0}")]
+    UnknownEventTarget(ComponentPath),
+    #[error("Component {path} could not be restored: {message}")]
+    Component {
+        path: ComponentPath,
+        message: String,
+    },
+    #[error("Failed to encode snapshot: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+    #[error("Failed to decode snapshot: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+}
+
+/// Opaque serialized state of a single component
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentSnapshot(#[serde_as(as = "Bytes")] Vec<u8>);
+
+impl ComponentSnapshot {
+    /// Serialize component state
+    pub fn new<T: Serialize>(state: &T) -> Self {
+        Self(rmp_serde::to_vec_named(state).expect("Component state could not be serialized"))
+    }
+
+    /// Deserialize component state
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, SnapshotError> {
+        Ok(rmp_serde::from_slice(&self.0)?)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ComponentEntry {
+    timestamp: Period,
+    state: Option<ComponentSnapshot>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EventEntry {
+    handle: EventHandle,
+    time: Period,
+    path: ComponentPath,
+    mode: EventMode,
+    #[serde_as(as = "Bytes")]
+    data: Vec<u8>,
+}
+
+/// A point in time capture of an entire machine
+///
+/// Obtain one with [`RuntimeGuard::snapshot`](crate::machine::RuntimeGuard::snapshot)
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    program: Option<ProgramId>,
+    safe_advance_timestamp: Period,
+    components: BTreeMap<ComponentPath, ComponentEntry>,
+    #[serde_as(as = "BTreeMap<_, Bytes>")]
+    memory: BTreeMap<ResourcePath, Vec<u8>>,
+    events: Vec<EventEntry>,
+    signals: BTreeMap<ResourcePath, bool>,
+    /// Value left on the data bus of every address space emulating open bus, and when it was driven
+    open_bus: BTreeMap<u16, (u8, Period)>,
+}
+
+impl Snapshot {
+    /// The timestamp the machine had been driven to when this snapshot was taken
+    pub fn timestamp(&self) -> Period {
+        self.safe_advance_timestamp
+    }
+
+    /// Encode into the versioned on disk format
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut bytes = Vec::from(SNAPSHOT_MAGIC);
+        bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        rmp_serde::encode::write_named(&mut bytes, self)?;
+
+        Ok(bytes)
+    }
+
+    /// Decode from the versioned on disk format, rejecting other versions
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let (magic, bytes) = bytes
+            .split_at_checked(SNAPSHOT_MAGIC.len())
+            .ok_or(SnapshotError::BadMagic)?;
+
+        if magic != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+
+        let (version, bytes) = bytes
+            .split_first_chunk::<2>()
+            .ok_or(SnapshotError::BadMagic)?;
+        let version = u16::from_le_bytes(*version);
+
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                found: version,
+                expected: SNAPSHOT_VERSION,
+            });
+        }
+
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+
+    pub(crate) fn capture(runtime: &RuntimeHandle) -> Result<Self, SnapshotError> {
+        let machine = runtime.machine();
+        let component_registry = runtime.component_registry();
+        let memory_registry = runtime.memory_registry();
+
+        let mut components = BTreeMap::new();
+        for path in component_registry.paths() {
+            let timestamp = component_registry.get_timestamp(&path).unwrap();
+            let state = component_registry
+                .interact_unsynchronized(&path, |component| component.snapshot())
+                .unwrap();
+
+            components.insert(path, ComponentEntry { timestamp, state });
+        }
+
+        let memory = memory_registry
+            .regions()
+            .map(|(path, id)| (path.clone(), memory_registry.read_region(id)))
+            .collect();
+
+        let events = machine
+            .scheduler
+            .event_manager
+            .pending()
+            .into_iter()
+            .map(|(handle, time, path, mode, data)| {
+                let codec = component_registry
+                    .event_codec(&path)
+                    .ok_or_else(|| SnapshotError::UnknownEventTarget(path.clone()))?;
+
+                Ok(EventEntry {
+                    handle,
+                    time,
+                    mode,
+                    data: (codec.encode)(data.as_ref())?,
+                    path,
+                })
+            })
+            .collect::<Result<_, SnapshotError>>()?;
+
+        Ok(Self {
+            program: machine
+                .program_specification()
+                .map(|specification| specification.id.clone()),
+            safe_advance_timestamp: machine.scheduler.safe_advance_timestamp(),
+            components,
+            memory,
+            events,
+            signals: machine.signals.lock().unwrap().output_levels(),
+            open_bus: machine
+                .address_spaces
+                .iter()
+                .filter_map(|(id, address_space)| Some((id.0, address_space.open_bus_state()?)))
+                .collect(),
+        })
+    }
+
+    pub(crate) fn apply(&self, runtime: &RuntimeHandle) -> Result<(), SnapshotError> {
+        let validated = self.validate(runtime)?;
+
+        // Components only find out whether they accept their state by restoring it, so keep what they had to go back to
+        let rollback = Self::capture(runtime)?;
+
+        if let Err(error) = self.write(runtime, validated) {
+            let validated = rollback
+                .validate(runtime)
+                .expect("Machine state was just captured from this machine");
+            rollback
+                .write(runtime, validated)
+                .expect("Components could not restore their own state");
+
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// Check the snapshot against the machine without touching it, decoding what needs decoding along the way
+    fn validate<'a>(&'a self, runtime: &RuntimeHandle) -> Result<Validated<'a>, SnapshotError> {
+        let machine = runtime.machine();
+        let component_registry = runtime.component_registry();
+        let memory_registry = runtime.memory_registry();
+
+        let program = machine
+            .program_specification()
+            .map(|specification| &specification.id);
+
+        if self.program.as_ref() != program {
+            return Err(SnapshotError::ProgramMismatch(self.program.clone()));
+        }
+
+        let machine_components: BTreeSet<_> = component_registry.paths().into_iter().collect();
+        if !machine_components.iter().eq(self.components.keys()) {
+            return Err(SnapshotError::ComponentMismatch);
+        }
+
+        let mut regions = Vec::with_capacity(self.memory.len());
+        for (path, id) in memory_registry.regions() {
+            let contents = self.memory.get(path).ok_or(SnapshotError::MemoryMismatch)?;
+
+            if memory_registry.region_size(path) != Some(contents.len()) {
+                return Err(SnapshotError::MemoryMismatch);
+            }
+
+            regions.push((id, contents.as_slice()));
+        }
+
+        if regions.len() != self.memory.len() {
+            return Err(SnapshotError::MemoryMismatch);
+        }
+
+        let signal_outputs = machine.signals.lock().unwrap().output_levels();
+        if !signal_outputs.keys().eq(self.signals.keys()) {
+            return Err(SnapshotError::SignalMismatch);
+        }
+
+        let open_bus_spaces = machine
+            .address_spaces
+            .iter()
+            .filter(|(_, address_space)| address_space.open_bus_state().is_some())
+            .map(|(id, _)| id.0)
+            .collect::<BTreeSet<_>>();
+        if !open_bus_spaces.iter().eq(self.open_bus.keys()) {
+            return Err(SnapshotError::MemoryMismatch);
+        }
+
+        let events = self
+            .events
+            .iter()
+            .map(|event| {
+                let codec = component_registry
+                    .event_codec(&event.path)
+                    .ok_or_else(|| SnapshotError::UnknownEventTarget(event.path.clone()))?;
+
+                Ok((
+                    event.handle,
+                    event.time,
+                    event.path.clone(),
+                    event.mode,
+                    (codec.decode)(&event.data)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, SnapshotError>>()?;
+
+        Ok(Validated { regions, events })
+    }
+
+    /// Write a validated snapshot into the machine, which is left half restored if a component rejects its state
+    fn write(
+        &self,
+        runtime: &RuntimeHandle,
+        validated: Validated<'_>,
+    ) -> Result<(), SnapshotError> {
+        let machine = runtime.machine();
+        let component_registry = runtime.component_registry();
+        let memory_registry = runtime.memory_registry();
+
+        // Restore in order of dependency, components may want to look at memory or their timestamps during their restore
+
+        for (id, contents) in validated.regions {
+            memory_registry.write_region(id, contents);
+        }
+
+        for (id, state) in &self.open_bus {
+            machine.address_spaces[&AddressSpaceId(*id)].restore_open_bus(*state);
+        }
+
+        for (path, entry) in &self.components {
+            component_registry
+                .set_timestamp(path, entry.timestamp)
+                .unwrap();
+        }
+
+        for (path, entry) in &self.components {
+            if let Some(state) = &entry.state {
+                component_registry
+                    .interact_unsynchronized(path, |component| component.restore(state))
+                    .unwrap()
+                    .map_err(|error| SnapshotError::Component {
+                        path: path.clone(),
+                        message: error.to_string(),
+                    })?;
+            }
+        }
+
+        // Input levels follow from the outputs, and events for changes already in flight are among the restored ones
+        machine
+            .signals
+            .lock()
+            .unwrap()
+            .restore_output_levels(&self.signals);
+
+        machine.scheduler.event_manager.replace(validated.events);
+        machine
+            .scheduler
+            .set_safe_advance_timestamp(self.safe_advance_timestamp);
+
+        Ok(())
+    }
+}
+
+/// What [`Snapshot::validate`] found to line up with the machine, ready to be written into it
+struct Validated<'a> {
+    regions: Vec<(MemoryId, &'a [u8])>,
+    events: Vec<PendingEvent>,
+}
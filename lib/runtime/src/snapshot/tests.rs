@@ -0,0 +1,219 @@
+use std::{error::Error, ops::RangeInclusive};
+
+use fluxemu_math::range::ContiguousRange;
+
+use crate::{
+    component::{Component, config::ComponentConfig},
+    machine::{Machine, builder::ComponentBuilder},
+    memory::{
+        Address, AddressSpaceId, MapTarget, MemoryError, MemoryMapCommand, OpenBusDecay,
+        Permissions,
+    },
+    platform::Platform,
+    scheduler::Period,
+    snapshot::{ComponentSnapshot, SNAPSHOT_VERSION, Snapshot, SnapshotError},
+};
+
+/// Holds the last byte written to it, and refuses to be restored to an odd one
+#[derive(Debug)]
+struct EvenRegister(u8);
+
+impl Component for EvenRegister {
+    type Event = ();
+
+    fn memory_write(
+        &mut self,
+        _address: Address,
+        _address_space: AddressSpaceId,
+        buffer: &[u8],
+    ) -> Result<(), MemoryError> {
+        self.0 = buffer[0];
+
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.0))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        let value: u8 = snapshot.decode()?;
+
+        if value % 2 != 0 {
+            return Err(SnapshotError::ComponentMismatch);
+        }
+
+        self.0 = value;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct EvenRegisterConfig {
+    address_space: AddressSpaceId,
+}
+
+impl<P: Platform> ComponentConfig<P> for EvenRegisterConfig {
+    type Component = EvenRegister;
+
+    fn build_component(
+        self,
+        component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn Error>> {
+        let path = component_builder.path().clone();
+
+        component_builder.map_memory(
+            self.address_space,
+            [MemoryMapCommand::Map {
+                range: 0x100..=0x100,
+                permissions: Permissions::WRITE,
+                target: MapTarget::Component(path),
+            }],
+        );
+
+        Ok(EvenRegister(0))
+    }
+}
+
+#[test]
+fn memory_round_trip() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let (machine, work_ram_path) = machine.memory("work-ram", 0x100, []);
+    let machine = machine.map_memory(
+        address_space,
+        [MemoryMapCommand::Map {
+            range: RangeInclusive::from_start_and_length(0, 0x100),
+            permissions: Permissions::ALL,
+            target: MapTarget::Memory {
+                path: work_ram_path,
+                subrange: None,
+            },
+        }],
+    );
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+
+    let mut address_space = runtime_guard.address_space(address_space).unwrap();
+
+    address_space
+        .write(0x0000, &Period::ZERO, &[34; 0x100])
+        .unwrap();
+
+    let snapshot = runtime_guard.snapshot().unwrap();
+    let snapshot = Snapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+
+    address_space
+        .write(0x0000, &Period::ZERO, &[0; 0x100])
+        .unwrap();
+
+    runtime_guard.restore(&snapshot).unwrap();
+
+    let mut buffer = [0; 0x100];
+    address_space
+        .read::<_, false>(0x0000, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [34; 0x100]);
+}
+
+#[test]
+fn open_bus_round_trip() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let (machine, ram_path) = machine.memory("ram", 0x10, []);
+    let machine = machine
+        .map_memory(
+            address_space,
+            [MemoryMapCommand::Map {
+                range: RangeInclusive::from_start_and_length(0, 0x10),
+                permissions: Permissions::ALL,
+                target: MapTarget::Memory {
+                    path: ram_path,
+                    subrange: None,
+                },
+            }],
+        )
+        .open_bus(address_space, OpenBusDecay::Hold);
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+
+    let mut address_space = runtime_guard.address_space(address_space).unwrap();
+
+    address_space.write(0x0000, &Period::ZERO, &[0x12]).unwrap();
+    let snapshot = runtime_guard.snapshot().unwrap();
+    let snapshot = Snapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+
+    address_space.write(0x0000, &Period::ZERO, &[0x34]).unwrap();
+    runtime_guard.restore(&snapshot).unwrap();
+
+    assert_eq!(address_space.open_bus_value(&Period::ZERO), Some(0x12));
+}
+
+#[test]
+fn rejects_other_versions() {
+    let machine = Machine::build_test_minimal().seal().build(());
+    let runtime_guard = machine.enter_runtime();
+
+    let mut bytes = runtime_guard.snapshot().unwrap().to_bytes().unwrap();
+    bytes[8..10].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+
+    assert!(matches!(
+        Snapshot::from_bytes(&bytes),
+        Err(SnapshotError::UnsupportedVersion { .. })
+    ));
+    assert!(matches!(
+        Snapshot::from_bytes(b"not a snapshot"),
+        Err(SnapshotError::BadMagic)
+    ));
+}
+
+#[test]
+fn failed_component_restore_leaves_memory_intact() {
+    let (machine, address_space) = Machine::build_test_minimal().address_space(16);
+
+    let (machine, ram_path) = machine.memory("ram", 0x10, []);
+    let machine = machine.map_memory(
+        address_space,
+        [MemoryMapCommand::Map {
+            range: RangeInclusive::from_start_and_length(0, 0x10),
+            permissions: Permissions::ALL,
+            target: MapTarget::Memory {
+                path: ram_path,
+                subrange: None,
+            },
+        }],
+    );
+    let (machine, _) = machine.component("register", EvenRegisterConfig { address_space });
+
+    let machine = machine.seal().build(());
+    let runtime_guard = machine.enter_runtime();
+
+    let mut address_space = runtime_guard.address_space(address_space).unwrap();
+
+    address_space
+        .write(0x0000, &Period::ZERO, &[0x12; 0x10])
+        .unwrap();
+    address_space.write(0x0100, &Period::ZERO, &[1]).unwrap();
+    let snapshot = runtime_guard.snapshot().unwrap();
+
+    address_space
+        .write(0x0000, &Period::ZERO, &[0x34; 0x10])
+        .unwrap();
+    address_space.write(0x0100, &Period::ZERO, &[2]).unwrap();
+    let memory = runtime_guard.memory_region_contents();
+
+    assert!(matches!(
+        runtime_guard.restore(&snapshot),
+        Err(SnapshotError::Component { .. })
+    ));
+    assert_eq!(runtime_guard.memory_region_contents(), memory);
+
+    let mut buffer = [0; 0x10];
+    address_space
+        .read::<_, false>(0x0000, &Period::ZERO, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, [0x34; 0x10]);
+}
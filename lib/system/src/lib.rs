@@ -1,17 +1,40 @@
+use fluxemu_graphics::api::GraphicsApi;
 use fluxemu_program::SystemId;
 use fluxemu_runtime::{
     Platform,
-    machine::builder::{MachineBuilder, SealedMachineBuilder},
+    machine::{
+        RuntimeGuard,
+        builder::{MachineBuilder, SealedMachineBuilder},
+    },
 };
 use serde::{Serialize, de::DeserializeOwned};
 
 pub trait System<P: Platform> {
-    type Quirks: Serialize + DeserializeOwned;
+    /// Per program deviations from how the system is normally built, the default being none
+    type Quirks: Serialize + DeserializeOwned + Default;
     const ID: SystemId;
 
+    /// Set up the machine for the program `machine_builder` was created with
+    ///
+    /// Fails if the program needs hardware this system does not emulate
     fn build(
         &self,
         quirks: Self::Quirks,
         machine_builder: MachineBuilder<P>,
-    ) -> SealedMachineBuilder<P>;
+    ) -> Result<SealedMachineBuilder<P>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Plug the peripheral named `peripheral` into a running machine of this system, in place of whatever occupied its
+    /// port
+    ///
+    /// Fails if the system has no such peripheral, or the machine refuses it
+    fn plug_peripheral(
+        &self,
+        runtime_guard: &RuntimeGuard<'_>,
+        peripheral: &str,
+        graphics_initialization_data: <P::GraphicsApi as GraphicsApi>::InitializationData,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _ = (runtime_guard, graphics_initialization_data);
+
+        Err(format!("No peripheral named {peripheral:?}").into())
+    }
 }
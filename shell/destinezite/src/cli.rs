@@ -20,5 +20,8 @@ pub enum CliAction {
     Run {
         #[clap(required=true, num_args=1..)]
         roms: Vec<PathBuf>,
+        /// Patches to apply to the first ROM, in order
+        #[clap(long)]
+        patch: Vec<PathBuf>,
     },
 }
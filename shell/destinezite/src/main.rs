@@ -89,13 +89,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     if let Some(action) = cli.action {
         match action {
-            CliAction::Run { roms } => {
+            CliAction::Run { roms, patch } => {
                 let mut rom_ids = Vec::default();
 
                 for rom in &roms {
                     rom_ids.push(program_manager.register_external(rom)?);
                 }
 
+                for patch in &patch {
+                    rom_ids[0] = program_manager.register_patched(rom_ids[0], patch)?;
+                }
+
                 initial_program = Some(rom_ids);
             }
         };
@@ -0,0 +1,88 @@
+use std::{path::PathBuf, sync::Arc};
+
+use fluxemu_frontend::machine::FactoryManager;
+use fluxemu_program::{ArchiveFormat, ProgramManager, SystemGuess};
+use fluxemu_runtime::machine::Machine;
+use fluxemu_system_atari_2600::Atari2600;
+use fluxemu_system_atari_lynx::AtariLynx;
+use fluxemu_system_nintendo_gameboy::Gameboy;
+use fluxemu_system_nintendo_nes::Nes;
+use fluxemu_system_other_chip8::Chip8;
+use num::rational::Ratio;
+
+use crate::platform::HeadlessPlatform;
+
+pub fn get_software_factories() -> FactoryManager<HeadlessPlatform> {
+    let mut factories = FactoryManager::default();
+
+    factories.insert_factory::<Atari2600>();
+    factories.insert_factory::<AtariLynx>();
+    factories.insert_factory::<Chip8>();
+    factories.insert_factory::<Nes>();
+    factories.insert_factory::<Gameboy>();
+
+    factories
+}
+
+/// Identify the given ROMs, with the patches applied to the first one, and build a machine for them
+///
+/// No save directory is given to the machine, so every run starts from the same state. Every clock is scaled by
+/// `overclock`
+pub fn build_machine(
+    program_manager: Arc<ProgramManager>,
+    roms: &[PathBuf],
+    patches: &[PathBuf],
+    overclock: Ratio<u64>,
+) -> Result<Arc<Machine>, Box<dyn std::error::Error>> {
+    let mut rom_ids = Vec::default();
+
+    for rom in roms {
+        if ArchiveFormat::detect_file(rom)?.is_some() {
+            rom_ids.extend(program_manager.register_archive(rom)?);
+        } else {
+            rom_ids.push(program_manager.register_external(rom)?);
+        }
+    }
+
+    for patch in patches {
+        let rom_id = rom_ids.first_mut().ok_or("No ROMs were given to patch")?;
+        *rom_id = program_manager.register_patched(*rom_id, patch)?;
+    }
+
+    let mut specifications = program_manager.identify_program(&rom_ids)?;
+
+    let specification = if specifications.is_empty() {
+        let rom_id = *rom_ids.first().ok_or("No ROMs were given")?;
+        let guesses = program_manager.guess_systems(rom_id)?;
+        let best_guess = guesses
+            .first()
+            .ok_or("Could not properly identify program")?;
+
+        // Nobody is around to ask, so go with the best guess but say so
+        if SystemGuess::is_ambiguous(&guesses) {
+            tracing::warn!(
+                "Program could be for any of {}, going with {}",
+                guesses
+                    .iter()
+                    .map(|guess| guess.system.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                best_guess.system
+            );
+        }
+
+        program_manager.generate_specification(rom_id, best_guess.system)
+    } else {
+        specifications.remove(0)
+    };
+
+    tracing::info!("Building machine for {}", specification.id);
+
+    let quirks = specification.info.quirks().map(str::to_owned);
+    let machine_builder = Machine::build(Some(specification), program_manager).overclock(overclock);
+
+    let sealed_machine_builder =
+        get_software_factories().construct_machine(quirks.as_deref(), machine_builder)?;
+
+    Ok(sealed_machine_builder.build(()))
+}
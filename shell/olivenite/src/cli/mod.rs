@@ -0,0 +1,151 @@
+use std::{path::PathBuf, str::FromStr};
+
+use clap::Parser;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+pub struct Cli {
+    /// ROMs to run
+    #[clap(required=true, num_args=1..)]
+    pub roms: Vec<PathBuf>,
+    /// Patches to apply to the first ROM, in order
+    #[clap(long)]
+    pub patch: Vec<PathBuf>,
+    /// Number of frames to run for
+    #[clap(short, long, conflicts_with = "duration")]
+    pub frames: Option<u64>,
+    /// Number of seconds to run for
+    #[clap(short, long)]
+    pub duration: Option<f64>,
+    /// Frames per second of emulated time
+    #[clap(long, default_value_t = 60)]
+    pub frame_rate: u32,
+    /// Speed of every emulated clock in percent of the real hardware
+    #[clap(long, default_value_t = 100)]
+    pub overclock: u64,
+    /// Frames after which to write every framebuffer as a PNG
+    #[clap(long, value_delimiter = ',')]
+    pub screenshot: Vec<u64>,
+    /// Frames after which to print a hash of every memory region
+    #[clap(long, value_delimiter = ',')]
+    pub hash_memory: Vec<u64>,
+    /// Peripherals to plug into the running machine, as frame:peripheral
+    #[clap(long, value_delimiter = ',')]
+    pub plug: Vec<PeripheralPlug>,
+    /// Write every audio output as a WAV once the run finishes
+    #[clap(long)]
+    pub record_audio: bool,
+    /// Where screenshots and audio recordings go
+    #[clap(short, long, default_value = ".")]
+    pub output_directory: PathBuf,
+    /// Stop early once this memory condition holds, checked after every frame
+    #[clap(long)]
+    pub until: Option<MemoryCondition>,
+    /// Memory condition the exit status is derived from
+    ///
+    /// With a value the exit status is 0 when the condition holds and 1 when it does not. Without one the byte itself
+    /// becomes the exit status.
+    #[clap(long)]
+    pub exit_condition: Option<MemoryCondition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+}
+
+/// A byte in one of the machine's address spaces, optionally compared to a value
+///
+/// Written as `space:address[=value]` or `space:address[!=value]`, where `space` is the index of the address space in
+/// creation order and `address` and `value` are hexadecimal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryCondition {
+    pub address_space: usize,
+    pub address: usize,
+    pub comparison: Option<(Comparison, u8)>,
+}
+
+impl MemoryCondition {
+    /// Check the condition against the byte read from memory
+    pub fn holds(&self, byte: u8) -> bool {
+        match self.comparison {
+            Some((Comparison::Equal, value)) => byte == value,
+            Some((Comparison::NotEqual, value)) => byte != value,
+            None => byte != 0,
+        }
+    }
+}
+
+impl FromStr for MemoryCondition {
+    type Err = String;
+
+    fn from_str(condition: &str) -> Result<Self, Self::Err> {
+        let (location, comparison) = if let Some((location, value)) = condition.split_once("!=") {
+            (location, Some((Comparison::NotEqual, value)))
+        } else if let Some((location, value)) = condition.split_once('=') {
+            (location, Some((Comparison::Equal, value)))
+        } else {
+            (condition, None)
+        };
+
+        let (address_space, address) = location
+            .split_once(':')
+            .ok_or_else(|| format!("{condition:?} is not in the form space:address[=value]"))?;
+
+        let address_space = address_space
+            .parse()
+            .map_err(|err| format!("Invalid address space index {address_space:?}: {err}"))?;
+        let address = usize::from_str_radix(address.trim_start_matches("0x"), 16)
+            .map_err(|err| format!("Invalid address {address:?}: {err}"))?;
+
+        let comparison = comparison
+            .map(|(comparison, value)| {
+                u8::from_str_radix(value.trim_start_matches("0x"), 16)
+                    .map(|value| (comparison, value))
+                    .map_err(|err| format!("Invalid value {value:?}: {err}"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            address_space,
+            address,
+            comparison,
+        })
+    }
+}
+
+/// A peripheral to plug in once `frame` frames have run, zero being before the first
+///
+/// Written as `frame:peripheral`, where `peripheral` is a name the system of the program gives it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeripheralPlug {
+    pub frame: u64,
+    pub peripheral: String,
+}
+
+impl FromStr for PeripheralPlug {
+    type Err = String;
+
+    fn from_str(plug: &str) -> Result<Self, Self::Err> {
+        let (frame, peripheral) = plug
+            .split_once(':')
+            .ok_or_else(|| format!("{plug:?} is not in the form frame:peripheral"))?;
+
+        let frame = frame
+            .parse()
+            .map_err(|err| format!("Invalid frame {frame:?}: {err}"))?;
+
+        if peripheral.is_empty() {
+            return Err(format!("{plug:?} names no peripheral"));
+        }
+
+        Ok(Self {
+            frame,
+            peripheral: peripheral.to_string(),
+        })
+    }
+}
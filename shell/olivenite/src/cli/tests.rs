@@ -0,0 +1,70 @@
+use crate::cli::{Comparison, MemoryCondition, PeripheralPlug};
+
+#[test]
+fn parses_every_form() {
+    assert_eq!(
+        "0:6000".parse(),
+        Ok(MemoryCondition {
+            address_space: 0,
+            address: 0x6000,
+            comparison: None,
+        })
+    );
+    assert_eq!(
+        "1:0x10=80".parse(),
+        Ok(MemoryCondition {
+            address_space: 1,
+            address: 0x10,
+            comparison: Some((Comparison::Equal, 0x80)),
+        })
+    );
+    assert_eq!(
+        "0:6000!=0x80".parse(),
+        Ok(MemoryCondition {
+            address_space: 0,
+            address: 0x6000,
+            comparison: Some((Comparison::NotEqual, 0x80)),
+        })
+    );
+}
+
+#[test]
+fn rejects_malformed_conditions() {
+    for condition in ["6000", "a:6000", "0:zz", "0:6000=100", "0:6000!="] {
+        assert!(
+            condition.parse::<MemoryCondition>().is_err(),
+            "{condition:?} parsed"
+        );
+    }
+}
+
+#[test]
+fn holds() {
+    let condition = |comparison| MemoryCondition {
+        address_space: 0,
+        address: 0,
+        comparison,
+    };
+
+    assert!(condition(None).holds(1));
+    assert!(!condition(None).holds(0));
+    assert!(condition(Some((Comparison::Equal, 0x80))).holds(0x80));
+    assert!(!condition(Some((Comparison::Equal, 0x80))).holds(0x81));
+    assert!(condition(Some((Comparison::NotEqual, 0x80))).holds(0x81));
+    assert!(!condition(Some((Comparison::NotEqual, 0x80))).holds(0x80));
+}
+
+#[test]
+fn parses_peripheral_plugs() {
+    assert_eq!(
+        "120:zapper".parse(),
+        Ok(PeripheralPlug {
+            frame: 120,
+            peripheral: "zapper".to_string(),
+        })
+    );
+
+    for plug in ["zapper", "x:zapper", "120:"] {
+        assert!(plug.parse::<PeripheralPlug>().is_err(), "{plug:?} parsed");
+    }
+}
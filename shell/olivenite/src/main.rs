@@ -0,0 +1,293 @@
+//! Headless runner for the FluxEMU emulation framework, for scripted runs and test ROM automation
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+use clap::Parser;
+use data_encoding::HEXLOWER;
+use fluxemu_environment::load_environment;
+use fluxemu_graphics::api::{GraphicsApi, software::Software};
+use fluxemu_program::ProgramManager;
+use fluxemu_runtime::{ResourcePath, machine::RuntimeGuard, scheduler::Period};
+use num::rational::Ratio;
+use redb::Database;
+use ringbuffer::RingBuffer;
+use sha2::{Digest, Sha256};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
+
+use crate::cli::{Cli, MemoryCondition};
+
+mod build_machine;
+mod cli;
+mod platform;
+
+/// Audio captured from a single output over the whole run
+#[derive(Debug, Default)]
+struct AudioCapture {
+    sample_rate: f32,
+    samples: Vec<f32>,
+}
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let environment = load_environment();
+
+    // Standard output is reserved for the results of the run
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            EnvFilter::builder()
+                .with_default_directive(LevelFilter::WARN.into())
+                .from_env_lossy(),
+        )
+        .init();
+
+    let cli = Cli::parse();
+
+    let frame_count = match (cli.frames, cli.duration) {
+        (Some(frames), _) => frames,
+        (None, Some(duration)) => (duration * f64::from(cli.frame_rate)).ceil() as u64,
+        (None, None) => return Err("Either --frames or --duration must be given".into()),
+    };
+
+    if cli.frame_rate == 0 {
+        return Err("Frame rate must be above zero".into());
+    }
+
+    if cli.overclock == 0 {
+        return Err("Overclock must be above zero".into());
+    }
+
+    let frame_length = Period::ONE / u128::from(cli.frame_rate);
+
+    let database = Database::create(&environment.database_location)?;
+    let program_manager = ProgramManager::new(database, environment.rom_store_directories.clone())?;
+
+    let machine = build_machine::build_machine(
+        program_manager,
+        &cli.roms,
+        &cli.patch,
+        Ratio::new(cli.overclock, 100),
+    )?;
+    let factories = build_machine::get_software_factories();
+    let system = machine
+        .program_specification()
+        .map(|specification| specification.id.system);
+    let runtime_guard = machine.enter_runtime();
+
+    std::fs::create_dir_all(&cli.output_directory)?;
+
+    let mut audio_captures: HashMap<ResourcePath, AudioCapture> = HashMap::default();
+
+    for frame in 1..=frame_count {
+        for plug in cli.plug.iter().filter(|plug| plug.frame == frame - 1) {
+            let system = system.ok_or("Machine was built without a program")?;
+            factories.plug_peripheral(system, &runtime_guard, &plug.peripheral, ())?;
+        }
+
+        runtime_guard.run(frame_length);
+
+        if cli.record_audio {
+            capture_audio(&runtime_guard, &mut audio_captures);
+        }
+
+        if cli.screenshot.contains(&frame) {
+            write_screenshots(&runtime_guard, &cli.output_directory, frame)?;
+        }
+
+        if cli.hash_memory.contains(&frame) {
+            print_memory_hashes(&runtime_guard, frame);
+        }
+
+        let stop = match &cli.until {
+            Some(condition) => condition.holds(read_condition_byte(&runtime_guard, condition)?),
+            None => false,
+        };
+
+        if stop {
+            tracing::info!("Stop condition met after frame {}", frame);
+            break;
+        }
+    }
+
+    for (path, capture) in &audio_captures {
+        let location = cli
+            .output_directory
+            .join(format!("{}.wav", file_stem(path)));
+
+        write_wav(&location, capture)?;
+    }
+
+    let Some(condition) = &cli.exit_condition else {
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    let byte = read_condition_byte(&runtime_guard, condition)?;
+
+    Ok(match condition.comparison {
+        Some(_) if condition.holds(byte) => ExitCode::SUCCESS,
+        Some(_) => ExitCode::FAILURE,
+        None => ExitCode::from(byte),
+    })
+}
+
+fn read_condition_byte(
+    runtime_guard: &RuntimeGuard<'_>,
+    condition: &MemoryCondition,
+) -> Result<u8, Box<dyn Error>> {
+    let address_space = runtime_guard
+        .address_spaces()
+        .get(condition.address_space)
+        .copied()
+        .ok_or_else(|| format!("Machine has no address space {}", condition.address_space))?;
+
+    let mut address_space = runtime_guard.address_space(address_space).unwrap();
+
+    address_space
+        .read_le_value::<u8, true>(condition.address, &runtime_guard.safe_advance_timestamp())
+        .map_err(|err| format!("Could not read {:#x}: {:?}", condition.address, err).into())
+}
+
+fn capture_audio(
+    runtime_guard: &RuntimeGuard<'_>,
+    audio_captures: &mut HashMap<ResourcePath, AudioCapture>,
+) {
+    for audio_output_path in &runtime_guard.audio_outputs() {
+        let Some(component_path) = audio_output_path.parent() else {
+            continue;
+        };
+
+        let capture = audio_captures.entry(audio_output_path.clone()).or_default();
+
+        runtime_guard.component_registry().interact_dyn(
+            component_path,
+            &runtime_guard.safe_advance_timestamp(),
+            |component| {
+                let source = component.get_audio_channel(audio_output_path.name());
+
+                capture.sample_rate = source.sample_rate;
+                capture
+                    .samples
+                    .extend(source.audio_ring.drain().map(|sample| sample.x));
+            },
+        );
+    }
+}
+
+fn write_screenshots(
+    runtime_guard: &RuntimeGuard<'_>,
+    output_directory: &Path,
+    frame: u64,
+) -> Result<(), Box<dyn Error>> {
+    for framebuffer_path in &runtime_guard.framebuffer_paths() {
+        let Some(component_path) = framebuffer_path.parent() else {
+            continue;
+        };
+
+        let location =
+            output_directory.join(format!("{}-{}.png", file_stem(framebuffer_path), frame));
+
+        runtime_guard
+            .component_registry()
+            .interact_dyn(
+                component_path,
+                &runtime_guard.safe_advance_timestamp(),
+                |component| -> Result<(), Box<dyn Error>> {
+                    let framebuffer: &<Software as GraphicsApi>::Framebuffer = component
+                        .get_framebuffer(framebuffer_path.name())
+                        .downcast_ref()
+                        .unwrap();
+
+                    let mut encoder = png::Encoder::new(
+                        BufWriter::new(File::create(&location)?),
+                        framebuffer.width() as u32,
+                        framebuffer.height() as u32,
+                    );
+                    encoder.set_color(png::ColorType::Rgba);
+                    encoder.set_depth(png::BitDepth::Eight);
+
+                    let data: Vec<u8> = framebuffer
+                        .rows()
+                        .flat_map(|row| bytemuck::cast_slice::<_, u8>(row).iter().copied())
+                        .collect();
+
+                    encoder.write_header()?.write_image_data(&data)?;
+
+                    Ok(())
+                },
+            )
+            .unwrap_or(Ok(()))?;
+
+        println!("screenshot {} {}", frame, location.display());
+    }
+
+    Ok(())
+}
+
+fn print_memory_hashes(runtime_guard: &RuntimeGuard<'_>, frame: u64) {
+    for (path, contents) in runtime_guard.memory_region_contents() {
+        println!(
+            "memory {} {} {}",
+            frame,
+            path,
+            HEXLOWER.encode(&Sha256::digest(&contents))
+        );
+    }
+}
+
+/// Write mono 16 bit PCM
+fn write_wav(location: &Path, capture: &AudioCapture) -> Result<(), Box<dyn Error>> {
+    const CHANNELS: u16 = 1;
+    const BITS_PER_SAMPLE: u16 = 16;
+
+    let sample_rate = capture.sample_rate.round() as u32;
+    let block_align = CHANNELS * BITS_PER_SAMPLE / 8;
+    let data_length = u32::try_from(capture.samples.len() * usize::from(block_align))?;
+
+    let mut file = BufWriter::new(File::create(location)?);
+
+    file.write_all(b"RIFF")?;
+    file.write_all(&(36 + data_length).to_le_bytes())?;
+    file.write_all(b"WAVE")?;
+
+    file.write_all(b"fmt ")?;
+    file.write_all(&16u32.to_le_bytes())?;
+    // PCM
+    file.write_all(&1u16.to_le_bytes())?;
+    file.write_all(&CHANNELS.to_le_bytes())?;
+    file.write_all(&sample_rate.to_le_bytes())?;
+    file.write_all(&(sample_rate * u32::from(block_align)).to_le_bytes())?;
+    file.write_all(&block_align.to_le_bytes())?;
+    file.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+
+    file.write_all(b"data")?;
+    file.write_all(&data_length.to_le_bytes())?;
+
+    for sample in &capture.samples {
+        let sample = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+        file.write_all(&sample.to_le_bytes())?;
+    }
+
+    file.flush()?;
+
+    println!("audio {}", location.display());
+
+    Ok(())
+}
+
+/// Turn a resource path into something usable as a file name
+fn file_stem(path: &ResourcePath) -> String {
+    let mut segments: Vec<_> = path
+        .parent()
+        .map(|component_path| component_path.iter().collect())
+        .unwrap_or_default();
+    segments.push(path.name());
+
+    segments.join("-")
+}
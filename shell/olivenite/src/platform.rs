@@ -0,0 +1,10 @@
+use fluxemu_graphics::api::software::Software;
+use fluxemu_runtime::platform::Platform;
+
+/// Platform without any window or audio device attached
+#[derive(Clone, Debug)]
+pub struct HeadlessPlatform;
+
+impl Platform for HeadlessPlatform {
+    type GraphicsApi = Software;
+}
@@ -5,7 +5,7 @@ use fluxemu_environment::load_environment;
 use fluxemu_program::ProgramManager;
 use fluxemu_runtime::machine::Machine;
 use fluxemu_system::System;
-use fluxemu_system_atari_2600::Atari2600;
+use fluxemu_system_atari_2600::{Atari2600, Atari2600Quirks};
 use redb::Database;
 
 fn emulation_performance(c: &mut Criterion) {
@@ -39,7 +39,10 @@ fn emulation_performance(c: &mut Criterion) {
             && program_manager.load(rom_id).unwrap().is_some()
         {
             let machine = Machine::build_test(Some(specification), program_manager.clone());
-            let machine = Atari2600.build((), machine).build(());
+            let machine = Atari2600
+                .build(Atari2600Quirks::default(), machine)
+                .unwrap()
+                .build(());
 
             group.bench_function(program_name, |b| {
                 b.iter(|| {
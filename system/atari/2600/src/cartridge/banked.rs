@@ -3,13 +3,14 @@ use std::ops::RangeInclusive;
 use bytes::Bytes;
 use fluxemu_math::range::ContiguousRange;
 use fluxemu_runtime::{
-    component::{Component, config::ComponentConfig},
+    component::{Component, ResetKind, config::ComponentConfig},
     machine::builder::ComponentBuilder,
     memory::{
         Address, AddressSpaceId, MapTarget, MemoryError, MemoryErrorType, MemoryMapCommand,
         Permissions,
     },
     platform::Platform,
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
 
 use crate::cartridge::{CartType, get_cart_range};
@@ -84,6 +85,23 @@ impl Component for BankedCart {
             .collect(),
         ))
     }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.current_bank))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        self.current_bank = snapshot.decode()?;
+
+        Ok(())
+    }
+
+    fn reset(&mut self, kind: ResetKind) {
+        // Nothing on the cartridge port sees the reset switch
+        if kind == ResetKind::Hard {
+            self.current_bank = 0;
+        }
+    }
 }
 
 #[derive(Debug)]
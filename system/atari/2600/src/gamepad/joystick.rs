@@ -2,21 +2,29 @@ use std::sync::Arc;
 
 use fluxemu_input::{GamepadInputId, InputId, KeyboardInputId};
 use fluxemu_runtime::{
+    RuntimeHandle,
     component::{Component, config::ComponentConfig},
     input::LogicalInputDevice,
     machine::builder::ComponentBuilder,
     memory::{Address, AddressSpaceId},
+    path::ResourcePath,
     platform::Platform,
+    signal::{SignalChange, Wiring},
 };
 
+/// Bits of SWCHA other devices in the controller ports can pull low, paddle buttons share them with the joystick
+pub(crate) const SHARED_SWCHA_BITS: [u8; 4] = [7, 6, 3, 2];
+
 #[derive(Debug)]
 pub struct Joystick {
     player1: Arc<LogicalInputDevice>,
     player2: Arc<LogicalInputDevice>,
+    shared_pins: [(u8, ResourcePath); 4],
 }
 
 impl Component for Joystick {
-    type Event = ();
+    // Shared pins are sampled when read, so changes to them need no handling
+    type Event = SignalChange;
 
     fn memory_read(
         &mut self,
@@ -66,6 +74,14 @@ impl Component for Joystick {
         buffer[0] |=
             !((up as u8) << 4 | (down as u8) << 5 | (left as u8) << 6 | (right as u8) << 7);
 
+        RuntimeHandle::with_current(|runtime| {
+            for (bit, pin) in &self.shared_pins {
+                if runtime.signal_level(pin) == Some(false) {
+                    buffer[0] &= !(1 << bit);
+                }
+            }
+        });
+
         Ok(())
     }
 
@@ -88,9 +104,23 @@ impl<P: Platform> ComponentConfig<P> for JoystickConfig {
     ) -> Result<Self::Component, Box<dyn std::error::Error>> {
         let (component_builder, player1) =
             component_builder.input("player-1", PRESENT_INPUTS, DEFAULT_MAPPINGS);
-        let (_, player2) = component_builder.input("player-2", PRESENT_INPUTS, DEFAULT_MAPPINGS);
+        let (mut component_builder, player2) =
+            component_builder.input("player-2", PRESENT_INPUTS, DEFAULT_MAPPINGS);
+
+        let mut shared_pins = Vec::with_capacity(SHARED_SWCHA_BITS.len());
+        for bit in SHARED_SWCHA_BITS {
+            let pin;
+            (component_builder, pin) =
+                component_builder.signal_input(format!("swcha-{bit}"), Wiring::WiredAnd);
+
+            shared_pins.push((bit, pin));
+        }
 
-        Ok(Joystick { player1, player2 })
+        Ok(Joystick {
+            player1,
+            player2,
+            shared_pins: shared_pins.try_into().unwrap(),
+        })
     }
 }
 
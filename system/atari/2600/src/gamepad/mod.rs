@@ -1 +1,2 @@
 pub mod joystick;
+pub mod paddles;
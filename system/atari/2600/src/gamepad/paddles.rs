@@ -0,0 +1,265 @@
+use std::sync::Arc;
+
+use fluxemu_input::{GamepadInputId, InputId, InputState, KeyboardInputId};
+use fluxemu_runtime::{
+    RuntimeHandle,
+    component::{Component, ResetKind, config::ComponentConfig},
+    event::{Event, EventMode, downcast_event},
+    input::LogicalInputDevice,
+    machine::builder::ComponentBuilder,
+    path::{ComponentPath, ResourcePath},
+    platform::Platform,
+    scheduler::Period,
+    signal::{SignalChange, Wiring},
+    snapshot::{ComponentSnapshot, SnapshotError},
+};
+use serde::{Deserialize, Serialize};
+
+use super::joystick::SHARED_SWCHA_BITS;
+
+/// How long a paddle turned all the way left takes to charge its capacitor past the threshold, about 380 scanlines
+const MAX_CHARGE_TIME: Period = Period::lit("0.0242");
+
+/// Which controller port a device is plugged into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerPort {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PaddlesEvent {
+    Signal(SignalChange),
+    /// A paddle charged its capacitor, if the pots have not been dumped since release number `release`
+    Charged {
+        paddle: usize,
+        release: u64,
+    },
+}
+
+impl From<SignalChange> for PaddlesEvent {
+    fn from(change: SignalChange) -> Self {
+        Self::Signal(change)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    dumped: bool,
+    /// Counts releases of the dump, so charges started before the last one are ignored
+    release: u64,
+}
+
+/// A pair of paddle controllers, which can be plugged into a running machine
+#[derive(Debug)]
+pub struct Paddles {
+    path: ComponentPath,
+    state: State,
+    devices: [Arc<LogicalInputDevice>; 2],
+    pots: [ResourcePath; 2],
+    buttons: [ResourcePath; 2],
+}
+
+impl Paddles {
+    fn position(&self, paddle: usize) -> f32 {
+        let device = &self.devices[paddle];
+        let left = device
+            .get_state(InputId::Gamepad(GamepadInputId::LeftStickLeft))
+            .as_analog();
+        let right = device
+            .get_state(InputId::Gamepad(GamepadInputId::LeftStickRight))
+            .as_analog();
+
+        ((1.0 + right - left) / 2.0).clamp(0.0, 1.0)
+    }
+
+    fn release_pots(&mut self, runtime: &RuntimeHandle, timestamp: Period) {
+        self.state.release = self.state.release.wrapping_add(1);
+
+        for paddle in 0..2 {
+            // Less resistance the further right it is turned
+            let charge_time = MAX_CHARGE_TIME * Period::from_num(1.0 - self.position(paddle));
+
+            runtime.schedule_event::<Self>(
+                &self.path,
+                EventMode::Once,
+                timestamp + charge_time,
+                PaddlesEvent::Charged {
+                    paddle,
+                    release: self.state.release,
+                },
+            );
+        }
+    }
+}
+
+impl Component for Paddles {
+    type Event = PaddlesEvent;
+
+    fn handle_event(&mut self, event: Box<dyn Event>) {
+        let event = downcast_event::<Self>(event);
+
+        RuntimeHandle::with_current(|runtime| {
+            let timestamp = runtime.current_timestamp(&self.path);
+
+            match event {
+                PaddlesEvent::Signal(SignalChange { level, .. }) => {
+                    self.state.dumped = level;
+
+                    if level {
+                        for pot in &self.pots {
+                            runtime.drive_signal(pot, timestamp, false);
+                        }
+                    } else {
+                        self.release_pots(runtime, timestamp);
+                    }
+                }
+                PaddlesEvent::Charged { paddle, release } => {
+                    if !self.state.dumped && release == self.state.release {
+                        runtime.drive_signal(&self.pots[paddle], timestamp, true);
+                    }
+                }
+            }
+        });
+    }
+
+    fn handle_input(&mut self, destination: &str, id: InputId, state: InputState) {
+        if id != InputId::Gamepad(GamepadInputId::FPadDown) {
+            return;
+        }
+
+        let paddle = usize::from(destination == "paddle-1");
+
+        RuntimeHandle::with_current(|runtime| {
+            let timestamp = runtime.current_timestamp(&self.path);
+
+            runtime.drive_signal(&self.buttons[paddle], timestamp, !state.as_digital(None));
+        });
+    }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.state))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        self.state = snapshot.decode()?;
+
+        Ok(())
+    }
+
+    fn reset(&mut self, kind: ResetKind) {
+        // The runtime repeats the starting level of the dump line afterwards, which releases the pots again
+        if kind == ResetKind::Hard {
+            self.state = State::default();
+        }
+    }
+}
+
+/// Configuration for a pair of paddles in one of the controller ports
+///
+/// Plug it into a running machine with [`RuntimeGuard::plug_component`], their buttons are read alongside any joystick
+/// still in the port
+///
+/// [`RuntimeGuard::plug_component`]: fluxemu_runtime::machine::RuntimeGuard::plug_component
+#[derive(Debug)]
+pub struct PaddlesConfig {
+    pub port: ControllerPort,
+}
+
+impl<P: Platform> ComponentConfig<P> for PaddlesConfig {
+    type Component = Paddles;
+
+    fn build_component(
+        self,
+        component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn std::error::Error>> {
+        let tia = ComponentPath::new("tia")?;
+        let joystick = ComponentPath::new("joystick")?;
+
+        // Each port has two pot lines, and two joystick pins the buttons pull low
+        let (pot_lines, button_bits) = match self.port {
+            ControllerPort::Left => ([0, 1], &SHARED_SWCHA_BITS[..2]),
+            ControllerPort::Right => ([2, 3], &SHARED_SWCHA_BITS[2..]),
+        };
+
+        let path = component_builder.path().clone();
+        let (mut component_builder, dump) = component_builder.signal_input("dump", Wiring::WiredOr);
+        component_builder =
+            component_builder.connect_signal(tia.clone().into_resource("dump-pots")?, dump);
+
+        let mut devices = Vec::with_capacity(2);
+        let mut pots = Vec::with_capacity(2);
+        let mut buttons = Vec::with_capacity(2);
+
+        for paddle in 0..2 {
+            let (device, pot, button);
+            (component_builder, device) = component_builder.input(
+                format!("paddle-{paddle}"),
+                PRESENT_INPUTS,
+                DEFAULT_MAPPINGS,
+            );
+            (component_builder, pot) =
+                component_builder.signal_output(format!("pot-{paddle}"), false);
+            (component_builder, button) =
+                component_builder.signal_output(format!("button-{paddle}"), true);
+
+            component_builder = component_builder
+                .connect_signal(
+                    pot.clone(),
+                    tia.clone()
+                        .into_resource(format!("inpt{}", pot_lines[paddle]))?,
+                )
+                .connect_signal(
+                    button.clone(),
+                    joystick
+                        .clone()
+                        .into_resource(format!("swcha-{}", button_bits[paddle]))?,
+                );
+
+            devices.push(device);
+            pots.push(pot);
+            buttons.push(button);
+        }
+
+        Ok(Paddles {
+            path,
+            state: State::default(),
+            devices: devices.try_into().unwrap(),
+            pots: pots.try_into().unwrap(),
+            buttons: buttons.try_into().unwrap(),
+        })
+    }
+}
+
+const PRESENT_INPUTS: [InputId; 3] = [
+    InputId::Gamepad(GamepadInputId::LeftStickLeft),
+    InputId::Gamepad(GamepadInputId::LeftStickRight),
+    InputId::Gamepad(GamepadInputId::FPadDown),
+];
+
+const DEFAULT_MAPPINGS: [(InputId, InputId); 6] = [
+    (
+        InputId::Gamepad(GamepadInputId::LeftStickLeft),
+        InputId::Gamepad(GamepadInputId::LeftStickLeft),
+    ),
+    (
+        InputId::Gamepad(GamepadInputId::LeftStickRight),
+        InputId::Gamepad(GamepadInputId::LeftStickRight),
+    ),
+    (
+        InputId::Gamepad(GamepadInputId::FPadDown),
+        InputId::Gamepad(GamepadInputId::FPadDown),
+    ),
+    (
+        InputId::Keyboard(KeyboardInputId::ArrowLeft),
+        InputId::Gamepad(GamepadInputId::LeftStickLeft),
+    ),
+    (
+        InputId::Keyboard(KeyboardInputId::ArrowRight),
+        InputId::Gamepad(GamepadInputId::LeftStickRight),
+    ),
+    (
+        InputId::Keyboard(KeyboardInputId::KeyZ),
+        InputId::Gamepad(GamepadInputId::FPadDown),
+    ),
+];
@@ -2,14 +2,21 @@ use std::marker::PhantomData;
 
 use fluxemu_definition_mos6502::variant::Mos6507;
 use fluxemu_definition_mos6532::Mos6532RiotConfig;
+use fluxemu_graphics::api::GraphicsApi;
 use fluxemu_program::{AtariSystem, SystemId};
 use fluxemu_runtime::{
-    machine::builder::{MachineBuilder, RomRequirement, SealedMachineBuilder},
-    memory::{Address, AddressSpaceId, MemoryMapCommand, Permissions},
+    ComponentPath,
+    machine::{
+        RuntimeGuard,
+        builder::{MachineBuilder, RomRequirement, SealedMachineBuilder},
+    },
+    memory::{Address, AddressSpaceId, MemoryMapCommand, OpenBusDecay, Permissions},
     platform::Platform,
 };
 use fluxemu_system::System;
 use gamepad::joystick::JoystickConfig;
+use num::rational::Ratio;
+use serde::{Deserialize, Serialize};
 use strum::Display;
 use tia::{
     config::TiaConfig,
@@ -25,29 +32,41 @@ mod cartridge;
 mod gamepad;
 mod tia;
 
-#[derive(Debug, Clone, Copy, Display, PartialEq, Eq, PartialOrd, Ord)]
-enum RegionSelection {
+pub use gamepad::paddles::{ControllerPort, PaddlesConfig};
+
+#[derive(Debug, Clone, Copy, Display, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum RegionSelection {
     Ntsc,
     Pal,
     Secam,
 }
 
+/// Per program overrides for what cannot be detected from the ROM
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Atari2600Quirks {
+    /// The video standard the program was made for, assumed to be NTSC if unset
+    pub region: Option<RegionSelection>,
+}
+
 #[derive(Debug, Default)]
 pub struct Atari2600;
 
 impl<P: Platform<GraphicsApi: SupportedGraphicsApiTia>> System<P> for Atari2600 {
-    type Quirks = ();
+    type Quirks = Atari2600Quirks;
 
     const ID: SystemId = SystemId::Atari(AtariSystem::_2600);
 
     fn build(
         &self,
-        _quirks: Self::Quirks,
+        quirks: Self::Quirks,
         machine_builder: MachineBuilder<P>,
-    ) -> SealedMachineBuilder<P> {
+    ) -> Result<SealedMachineBuilder<P>, Box<dyn std::error::Error + Send + Sync>> {
         let (machine_builder, cpu_address_space) = machine_builder.address_space(13);
-        // For now, assume all games are ntsc
-        let region = RegionSelection::Ntsc;
+        // Unmapped reads see whatever the last access left on the bus
+        let machine_builder = machine_builder.open_bus(cpu_address_space, OpenBusDecay::Hold);
+        // Nothing in the ROM tells the regions apart, so assume NTSC unless told otherwise
+        let region = quirks.region.unwrap_or(RegionSelection::Ntsc);
 
         let program_specification = machine_builder.program_specification().unwrap();
         let filesystem = program_specification.info.filesystem();
@@ -114,12 +133,35 @@ impl<P: Platform<GraphicsApi: SupportedGraphicsApiTia>> System<P> for Atari2600
             ),
         );
 
-        match region {
+        let machine = match region {
             RegionSelection::Ntsc => common::<Ntsc, _>(cpu_address_space, machine),
             RegionSelection::Pal => common::<Pal, _>(cpu_address_space, machine),
             RegionSelection::Secam => common::<Secam, _>(cpu_address_space, machine),
-        }
-        .seal()
+        };
+
+        Ok(machine.seal())
+    }
+
+    /// Takes `"left-paddles"` and `"right-paddles"`, for a pair of paddles in that controller port
+    fn plug_peripheral(
+        &self,
+        runtime_guard: &RuntimeGuard<'_>,
+        peripheral: &str,
+        graphics_initialization_data: <P::GraphicsApi as GraphicsApi>::InitializationData,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let port = match peripheral {
+            "left-paddles" => ControllerPort::Left,
+            "right-paddles" => ControllerPort::Right,
+            _ => return Err(format!("No peripheral named {peripheral:?}").into()),
+        };
+
+        runtime_guard.plug_component::<P, _>(
+            ComponentPath::new(peripheral.to_owned())?,
+            PaddlesConfig { port },
+            graphics_initialization_data,
+        )?;
+
+        Ok(())
     }
 }
 
@@ -127,11 +169,15 @@ fn common<R: Region, P: Platform<GraphicsApi: SupportedGraphicsApiTia>>(
     cpu_address_space: AddressSpaceId,
     machine_builder: MachineBuilder<P>,
 ) -> MachineBuilder<P> {
+    let (machine_builder, color_clock) = machine_builder.master_clock("color", R::frequency());
+    let (machine_builder, cpu_clock) =
+        machine_builder.derived_clock("cpu", color_clock, Ratio::new(1, 3));
+
     let (machine_builder, joystick) = machine_builder.component("joystick", JoystickConfig);
 
     let (machine_builder, cpu) = machine_builder.component(
         "mos6502",
-        fluxemu_definition_mos6502::Config::<Mos6507>::new(R::frequency() / 3, cpu_address_space),
+        fluxemu_definition_mos6502::Config::<Mos6507>::new(cpu_clock, cpu_address_space),
     );
 
     let (machine_builder, _) = machine_builder.component(
@@ -139,7 +185,7 @@ fn common<R: Region, P: Platform<GraphicsApi: SupportedGraphicsApiTia>>(
         Mos6532RiotConfig {
             swcha: Some(joystick),
             swchb: None,
-            frequency: R::frequency() / 3,
+            clock: cpu_clock,
             registers_assigned_address: 0x280,
             ram_assigned_address: 0x80,
             assigned_address_space: cpu_address_space,
@@ -151,6 +197,7 @@ fn common<R: Region, P: Platform<GraphicsApi: SupportedGraphicsApiTia>>(
         TiaConfig::<R> {
             cpu,
             cpu_address_space,
+            clock: color_clock,
             _phantom: PhantomData,
         },
     );
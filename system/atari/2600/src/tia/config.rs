@@ -1,21 +1,20 @@
-use std::{collections::HashMap, marker::PhantomData, ops::RangeInclusive};
+use std::{marker::PhantomData, ops::RangeInclusive};
 
-use fluxemu_graphics::api::software::texture::Texture;
 use fluxemu_math::range::ContiguousRange;
 use fluxemu_runtime::{
+    clock::Clock,
     component::config::{ComponentConfig, LateContext},
     machine::builder::{ComponentBuilder, SchedulerParticipation},
     memory::{AddressSpaceId, MemoryMapCommand, Permissions},
     path::ComponentPath,
     platform::Platform,
+    signal::Wiring,
 };
-use nalgebra::Point2;
-use palette::named::BLACK;
 use strum::IntoEnumIterator;
 
 use super::{Tia, region::Region};
 use crate::tia::{
-    InputControl, State, VISIBLE_SCANLINE_LENGTH,
+    SCANLINE_LENGTH, State,
     backend::{SupportedGraphicsApiTia, TiaDisplayBackend},
     memory::{ReadRegisters, WriteRegisters},
 };
@@ -24,6 +23,7 @@ use crate::tia::{
 pub(crate) struct TiaConfig<R: Region> {
     pub cpu: ComponentPath,
     pub cpu_address_space: AddressSpaceId,
+    pub clock: Clock,
     pub _phantom: PhantomData<R>,
 }
 
@@ -43,9 +43,29 @@ impl<R: Region, P: Platform<GraphicsApi: SupportedGraphicsApiTia>> ComponentConf
         self,
         component_builder: ComponentBuilder<P, Self::Component>,
     ) -> Result<Self::Component, Box<dyn std::error::Error>> {
-        let (mut component_builder, _) = component_builder
+        let (component_builder, _) = component_builder
             .scheduler_participation(Some(SchedulerParticipation::OnAccess))
             .framebuffer("framebuffer");
+        // Programs decide when vsync happens, but they aim for this
+        let mut component_builder = component_builder.refresh_rate(
+            self.clock.frequency() / (u128::from(SCANLINE_LENGTH) * u128::from(R::TOTAL_SCANLINES)),
+        );
+
+        let (dump_pots, rdy);
+        (component_builder, dump_pots) = component_builder.signal_output("dump-pots", false);
+        (component_builder, rdy) = component_builder.signal_output("rdy", true);
+        component_builder =
+            component_builder.connect_signal(rdy.clone(), self.cpu.into_resource("rdy")?);
+
+        // Nothing charges the capacitor of a empty port, so they read low
+        let mut pots = Vec::with_capacity(4);
+        for index in 0..4 {
+            let pot;
+            (component_builder, pot) =
+                component_builder.signal_input(format!("inpt{index}"), Wiring::WiredOr);
+
+            pots.push(pot);
+        }
 
         let my_path = component_builder.path().clone();
 
@@ -74,31 +94,14 @@ impl<R: Region, P: Platform<GraphicsApi: SupportedGraphicsApiTia>> ComponentConf
             ),
         );
 
-        let staging_buffer = Texture::from_value(
-            VISIBLE_SCANLINE_LENGTH as usize,
-            R::TOTAL_SCANLINES as usize,
-            BLACK.into(),
-        );
-
         Ok(Tia {
             backend: None,
-            cpu_path: self.cpu,
-            state: State {
-                collision_matrix: HashMap::default(),
-                vblank_active: false,
-                in_vsync: false,
-                input_control: [InputControl::default(); 6],
-                electron_beam: Point2::default(),
-                missiles: Default::default(),
-                ball: Default::default(),
-                players: Default::default(),
-                playfield: Default::default(),
-                high_playfield_ball_priority: false,
-                background_color: Default::default(),
-                staging_buffer,
-                hmove_pending: false,
-            },
+            state: State::power_on::<R>(),
             path: component_builder.path().clone(),
+            clock: self.clock,
+            dump_pots,
+            pots: pots.try_into().unwrap(),
+            rdy,
         })
     }
 }
@@ -1,5 +1,7 @@
+use fluxemu_runtime::RuntimeHandle;
+
 use super::ReadRegisters;
-use crate::tia::{ObjectId, SupportedGraphicsApiTia, Tia, region::Region};
+use crate::tia::{InputControl, ObjectId, SupportedGraphicsApiTia, Tia, region::Region};
 
 impl<R: Region, G: SupportedGraphicsApiTia> Tia<R, G> {
     pub(crate) fn handle_read_register(&self, data: &mut u8, address: ReadRegisters) {
@@ -62,15 +64,24 @@ impl<R: Region, G: SupportedGraphicsApiTia> Tia<R, G> {
                     [ObjectId::Missile0, ObjectId::Missile1],
                 );
             }
-            ReadRegisters::Inpt0 => {}
-            ReadRegisters::Inpt1 => {}
-            ReadRegisters::Inpt2 => {}
-            ReadRegisters::Inpt3 => {}
+            ReadRegisters::Inpt0 => self.read_pot(data, 0),
+            ReadRegisters::Inpt1 => self.read_pot(data, 1),
+            ReadRegisters::Inpt2 => self.read_pot(data, 2),
+            ReadRegisters::Inpt3 => self.read_pot(data, 3),
             ReadRegisters::Inpt4 => {}
             ReadRegisters::Inpt5 => {}
         }
     }
 
+    #[inline]
+    fn read_pot(&self, data: &mut u8, index: usize) {
+        let charged = self.state.input_control[index] == InputControl::Normal
+            && RuntimeHandle::with_current(|runtime| runtime.signal_level(&self.pots[index]))
+                == Some(true);
+
+        *data = (*data & 0b0111_1111) | (charged as u8) << 7;
+    }
+
     #[inline]
     fn read_collision_register(&self, data: &mut u8, pair1: [ObjectId; 2], pair2: [ObjectId; 2]) {
         let collision1 = self
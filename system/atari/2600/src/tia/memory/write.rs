@@ -1,11 +1,11 @@
-use fluxemu_definition_mos6502::{Mos6502, Mos6502Event, Pin, variant::Mos6507};
-use fluxemu_runtime::{RuntimeHandle, event::EventMode, scheduler::Period};
+use fluxemu_runtime::{RuntimeHandle, event::EventMode};
 use nalgebra::Point2;
 
 use super::WriteRegisters;
 use crate::tia::{
     DelayChangeGraphicPlayer, DelayEnableChangeBall, InputControl, SCANLINE_LENGTH,
-    SupportedGraphicsApiTia, Tia, backend::TiaDisplayBackend, color::TiaColor, region::Region,
+    SupportedGraphicsApiTia, Tia, TiaEvent, backend::TiaDisplayBackend, color::TiaColor,
+    region::Region,
 };
 
 impl<R: Region, G: SupportedGraphicsApiTia> Tia<R, G> {
@@ -40,6 +40,16 @@ impl<R: Region, G: SupportedGraphicsApiTia> Tia<R, G> {
                 self.state.input_control[2] = bit;
                 self.state.input_control[3] = bit;
 
+                RuntimeHandle::with_current(|runtime| {
+                    let timestamp = runtime.current_timestamp(&self.path);
+
+                    runtime.drive_signal(
+                        &self.dump_pots,
+                        timestamp,
+                        bit == InputControl::LatchedOrDumped,
+                    );
+                });
+
                 let bit = if data & 0b0100_0000 != 0 {
                     InputControl::LatchedOrDumped
                 } else {
@@ -53,26 +63,16 @@ impl<R: Region, G: SupportedGraphicsApiTia> Tia<R, G> {
                 RuntimeHandle::with_current(|runtime| {
                     let timestamp = runtime.current_timestamp(&self.path);
 
-                    let until = Period::from_num(SCANLINE_LENGTH - self.state.electron_beam.x)
-                        / R::frequency();
+                    let until = self
+                        .clock
+                        .cycles((SCANLINE_LENGTH - self.state.electron_beam.x).into());
 
-                    runtime.schedule_event::<Mos6502<Mos6507>>(
-                        &self.cpu_path,
-                        EventMode::Once,
-                        timestamp,
-                        Mos6502Event::FlagChange {
-                            pin: Pin::Rdy,
-                            value: false,
-                        },
-                    );
-                    runtime.schedule_event::<Mos6502<Mos6507>>(
-                        &self.cpu_path,
+                    runtime.drive_signal(&self.rdy, timestamp, false);
+                    runtime.schedule_event::<Self>(
+                        &self.path,
                         EventMode::Once,
                         timestamp + until,
-                        Mos6502Event::FlagChange {
-                            pin: Pin::Rdy,
-                            value: true,
-                        },
+                        TiaEvent::ReleaseRdy,
                     );
                 });
             }
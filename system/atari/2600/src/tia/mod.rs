@@ -6,16 +6,21 @@ use std::{
 
 pub(crate) use backend::SupportedGraphicsApiTia;
 use color::TiaColor;
-use fluxemu_graphics::api::software::texture::OwnedTexture;
+use fluxemu_graphics::api::software::texture::{OwnedTexture, Texture};
 use fluxemu_runtime::{
-    ComponentPath,
-    component::Component,
+    ComponentPath, RuntimeHandle,
+    clock::Clock,
+    component::{Component, ResetKind},
+    event::{Event, downcast_event},
     memory::{Address, AddressSpaceId, MemoryError},
+    path::ResourcePath,
     scheduler::{Period, SynchronizationContext},
+    signal::SignalChange,
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
 use itertools::Itertools;
 use nalgebra::Point2;
-use palette::Srgba;
+use palette::{Srgba, named::BLACK};
 use region::Region;
 use serde::{Deserialize, Serialize};
 
@@ -121,16 +126,60 @@ struct State {
     hmove_pending: bool,
 }
 
+impl State {
+    fn power_on<R: Region>() -> Self {
+        Self {
+            collision_matrix: HashMap::default(),
+            vblank_active: false,
+            in_vsync: false,
+            input_control: [InputControl::default(); 6],
+            electron_beam: Point2::default(),
+            missiles: Default::default(),
+            ball: Default::default(),
+            players: Default::default(),
+            playfield: Default::default(),
+            high_playfield_ball_priority: false,
+            background_color: Default::default(),
+            staging_buffer: Texture::from_value(
+                VISIBLE_SCANLINE_LENGTH as usize,
+                R::TOTAL_SCANLINES as usize,
+                BLACK.into(),
+            ),
+            hmove_pending: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum TiaEvent {
+    /// Pot lines are sampled when read, so changes to them need no handling
+    Signal(SignalChange),
+    /// The scanline a WSYNC waited for ended
+    ReleaseRdy,
+}
+
+impl From<SignalChange> for TiaEvent {
+    fn from(change: SignalChange) -> Self {
+        Self::Signal(change)
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Tia<R: Region, G: SupportedGraphicsApiTia> {
     state: State,
     backend: Option<G::Backend<R>>,
-    cpu_path: ComponentPath,
     path: ComponentPath,
+    clock: Clock,
+    /// Grounds the paddle capacitors while high, driven by bit 7 of VBLANK
+    dump_pots: ResourcePath,
+    /// Lines read through INPT0 to INPT3, high once a paddle has charged its capacitor
+    pots: [ResourcePath; 4],
+    /// Held low from a write to WSYNC until the end of the scanline, halting the CPU
+    rdy: ResourcePath,
 }
 
 impl<R: Region, G: SupportedGraphicsApiTia> Component for Tia<R, G> {
-    type Event = ();
+    type Event = TiaEvent;
 
     fn memory_read(
         &mut self,
@@ -172,8 +221,8 @@ impl<R: Region, G: SupportedGraphicsApiTia> Component for Tia<R, G> {
     }
 
     fn synchronize(&mut self, mut context: SynchronizationContext) {
-        let mut quanta_iterator = context.quanta_allocator(R::frequency().recip());
-        while quanta_iterator.allocate().is_some() {
+        let mut cycle_allocator = context.cycle_allocator(self.clock);
+        while cycle_allocator.allocate().is_some() {
             if !(self.state.in_vsync || self.state.vblank_active)
                 && (HBLANK_LENGTH..(VISIBLE_SCANLINE_LENGTH + HBLANK_LENGTH))
                     .contains(&self.state.electron_beam.x)
@@ -231,12 +280,39 @@ impl<R: Region, G: SupportedGraphicsApiTia> Component for Tia<R, G> {
     }
 
     fn needs_work(&self, _timestamp: &Period, delta: &Period) -> bool {
-        *delta >= R::frequency().recip()
+        *delta >= self.clock.period()
+    }
+
+    fn handle_event(&mut self, event: Box<dyn Event>) {
+        if let TiaEvent::ReleaseRdy = downcast_event::<Self>(event) {
+            RuntimeHandle::with_current(|runtime| {
+                let timestamp = runtime.current_timestamp(&self.path);
+
+                runtime.drive_signal(&self.rdy, timestamp, true);
+            });
+        }
     }
 
     fn get_framebuffer(&mut self, _name: &str) -> &dyn Any {
         self.backend.as_mut().unwrap().framebuffer()
     }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.state))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        self.state = snapshot.decode()?;
+
+        Ok(())
+    }
+
+    fn reset(&mut self, kind: ResetKind) {
+        // The TIA has no reset line, the reset switch is only a hint read by the program through the RIOT
+        if kind == ResetKind::Hard {
+            self.state = State::power_on::<R>();
+        }
+    }
 }
 
 impl<R: Region, G: SupportedGraphicsApiTia> Tia<R, G> {
@@ -1,6 +1,6 @@
 use std::fmt::Debug;
 
-use fluxemu_runtime::scheduler::Frequency;
+use num::rational::Ratio;
 use palette::Srgb;
 
 use super::color::TiaColor;
@@ -12,7 +12,8 @@ pub mod secam;
 pub trait Region: Send + Sync + Debug + 'static {
     const TOTAL_SCANLINES: u16;
 
-    fn frequency() -> Frequency;
+    /// Frequency of the color clock in hertz, exactly
+    fn frequency() -> Ratio<u64>;
 
     fn color_to_srgb(color: TiaColor) -> Srgb<u8>;
 }
@@ -1,7 +1,7 @@
 use std::sync::LazyLock;
 
-use fluxemu_runtime::scheduler::Frequency;
 use nalgebra::SMatrix;
+use num::rational::Ratio;
 use palette::{FromColor, Hsl, Srgb};
 
 use super::Region;
@@ -57,8 +57,8 @@ impl Region for Ntsc {
     const TOTAL_SCANLINES: u16 = 262;
 
     #[inline]
-    fn frequency() -> Frequency {
-        Frequency::from_num(3579545)
+    fn frequency() -> Ratio<u64> {
+        Ratio::from_integer(3579545)
     }
 
     fn color_to_srgb(color: TiaColor) -> Srgb<u8> {
@@ -1,4 +1,4 @@
-use fluxemu_runtime::scheduler::Frequency;
+use num::rational::Ratio;
 
 use super::Region;
 use crate::tia::color::TiaColor;
@@ -9,8 +9,8 @@ pub struct Pal;
 impl Region for Pal {
     const TOTAL_SCANLINES: u16 = 312;
 
-    fn frequency() -> Frequency {
-        Frequency::from_num(17734475) / 4
+    fn frequency() -> Ratio<u64> {
+        Ratio::new(17734475, 4)
     }
 
     fn color_to_srgb(_color: TiaColor) -> palette::Srgb<u8> {
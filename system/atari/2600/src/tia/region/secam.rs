@@ -1,4 +1,4 @@
-use fluxemu_runtime::scheduler::Frequency;
+use num::rational::Ratio;
 
 use super::Region;
 use crate::tia::color::TiaColor;
@@ -9,7 +9,7 @@ pub struct Secam;
 impl Region for Secam {
     const TOTAL_SCANLINES: u16 = 312;
 
-    fn frequency() -> Frequency {
+    fn frequency() -> Ratio<u64> {
         todo!()
     }
 
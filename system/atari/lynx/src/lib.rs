@@ -4,7 +4,7 @@ use fluxemu_math::range::ContiguousRange;
 use fluxemu_program::{AtariSystem, RomId, SystemId};
 use fluxemu_runtime::{
     machine::builder::{MachineBuilder, SealedMachineBuilder},
-    memory::{Address, MapTarget, MemoryMapCommand, Permissions},
+    memory::{Address, MapTarget, MemoryMapCommand, OpenBusDecay, Permissions},
     platform::Platform,
 };
 use fluxemu_system::System;
@@ -35,10 +35,11 @@ impl<P: Platform> System<P> for AtariLynx {
         &self,
         _quirks: Self::Quirks,
         machine_builder: MachineBuilder<P>,
-    ) -> SealedMachineBuilder<P> {
+    ) -> Result<SealedMachineBuilder<P>, Box<dyn std::error::Error + Send + Sync>> {
         // 16 Mhz
         let _base_clock = Ratio::from_integer(16000000);
         let (machine_builder, cpu_address_space) = machine_builder.address_space(16);
+        let machine_builder = machine_builder.open_bus(cpu_address_space, OpenBusDecay::Hold);
 
         // A good portion of this will be initially shadowed
         let (machine_builder, ram_path) = machine_builder.memory("ram", 0x10000, []);
@@ -93,6 +94,6 @@ impl<P: Platform> System<P> for AtariLynx {
             },
         );
 
-        machine_builder.seal()
+        Ok(machine_builder.seal())
     }
 }
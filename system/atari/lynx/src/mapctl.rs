@@ -12,7 +12,7 @@ use fluxemu_runtime::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    MAPCTL_ADDRESS, MIKEY_ADDRESSES, RESERVED_MEMORY_ADDRESS, SUZY_ADDRESSES, VECTOR_ADDRESSES,
+    MAPCTL_ADDRESS, MIKEY_ADDRESSES, RESERVED_MEMORY_ADDRESS, VECTOR_ADDRESSES, suzy::suzy_mapping,
 };
 
 #[derive(Debug)]
@@ -60,11 +60,7 @@ impl Component for Mapctl {
             });
 
             if self.status.suzy {
-                remapping_commands.push(MemoryMapCommand::Map {
-                    range: SUZY_ADDRESSES,
-                    target: MapTarget::Component(self.config.suzy.clone()),
-                    permissions: Permissions::ALL,
-                });
+                remapping_commands.extend(suzy_mapping(self.config.suzy.clone()));
             }
 
             if self.status.mikey {
@@ -4,6 +4,7 @@ use fluxemu_runtime::{
     component::{Component, config::ComponentConfig},
     machine::builder::ComponentBuilder,
     memory::{Address, AddressSpaceId, MapTarget, MemoryError, MemoryMapCommand, Permissions},
+    path::ComponentPath,
     platform::Platform,
 };
 
@@ -49,6 +50,24 @@ const LEDS: RangeInclusive<Address> = 0xfcc0..=0xfcc0;
 const PPT: RangeInclusive<Address> = 0xfcc2..=0xfcc2;
 const PPTDATA: RangeInclusive<Address> = 0xfcc3..=0xfcc3;
 const HOWIE: RangeInclusive<Address> = 0xfcc4..=0xfcc4;
+/// Suzy shares the bus with the processor, so reaching its registers takes longer than reaching RAM
+const WAIT_STATES: u32 = 1;
+
+/// Map Suzy into the processor's address space, along with the cost of reaching it
+pub fn suzy_mapping(path: ComponentPath) -> [MemoryMapCommand; 2] {
+    [
+        MemoryMapCommand::Map {
+            range: SUZY_ADDRESSES,
+            permissions: Permissions::ALL,
+            target: MapTarget::Component(path),
+        },
+        MemoryMapCommand::WaitStates {
+            range: SUZY_ADDRESSES,
+            permissions: Permissions::ALL,
+            cycles: WAIT_STATES,
+        },
+    ]
+}
 
 #[derive(Debug)]
 pub struct Suzy {}
@@ -180,14 +199,7 @@ impl<P: Platform> ComponentConfig<P> for SuzyConfig {
         component_builder: ComponentBuilder<P, Self::Component>,
     ) -> Result<Self::Component, Box<dyn std::error::Error>> {
         let my_path = component_builder.path().clone();
-        component_builder.map_memory(
-            self.cpu_address_space,
-            [MemoryMapCommand::Map {
-                range: SUZY_ADDRESSES,
-                permissions: Permissions::ALL,
-                target: MapTarget::Component(my_path),
-            }],
-        );
+        component_builder.map_memory(self.cpu_address_space, suzy_mapping(my_path));
 
         Ok(Suzy {})
     }
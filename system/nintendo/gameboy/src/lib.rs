@@ -5,6 +5,10 @@ use fluxemu_runtime::{
 };
 use fluxemu_system::System;
 
+pub mod serial;
+#[cfg(test)]
+mod tests;
+
 #[derive(Debug, Default)]
 pub struct Gameboy;
 
@@ -16,7 +20,7 @@ impl<P: Platform> System<P> for Gameboy {
         &self,
         _quirks: Self::Quirks,
         _machine_builder: MachineBuilder<P>,
-    ) -> SealedMachineBuilder<P> {
+    ) -> Result<SealedMachineBuilder<P>, Box<dyn std::error::Error + Send + Sync>> {
         todo!()
     }
 }
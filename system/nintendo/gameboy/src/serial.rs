@@ -0,0 +1,219 @@
+//! The link port, shifting a byte out to another Game Boy while shifting its byte in
+
+use fluxemu_runtime::{
+    RuntimeHandle,
+    component::{Component, ResetKind, config::ComponentConfig},
+    event::{Event, EventMode, downcast_event},
+    link::LinkMessage,
+    machine::builder::ComponentBuilder,
+    memory::{Address, AddressSpaceId, MemoryError, MemoryMapCommand, Permissions},
+    path::{ComponentPath, ResourcePath},
+    platform::Platform,
+    scheduler::Period,
+    snapshot::{ComponentSnapshot, SnapshotError},
+};
+use serde::{Deserialize, Serialize};
+
+const SB: Address = 0xff01;
+const SC: Address = 0xff02;
+
+/// Eight bits on the 8192 Hz internal clock
+///
+/// Replies only make it back in time if the link latency is under half of this
+const TRANSFER_TIME: Period = Period::lit("0.0009765625");
+
+/// The side driving the clock sends its byte with this
+const SHIFT: u8 = 0;
+/// The other side answers with the byte it shifted out
+const REPLY: u8 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SerialEvent {
+    Link(LinkMessage),
+    /// The internal clock shifted all eight bits of transfer number `transfer`
+    Complete { transfer: u64 },
+}
+
+impl From<LinkMessage> for SerialEvent {
+    fn from(message: LinkMessage) -> Self {
+        Self::Link(message)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    data: u8,
+    control: u8,
+    /// Byte the other side shifted back during the running transfer
+    incoming: Option<u8>,
+    /// Counts transfers started on the internal clock, so completions of aborted ones are ignored
+    transfer: u64,
+}
+
+impl State {
+    fn transfer_requested(&self) -> bool {
+        self.control & 0b1000_0000 != 0
+    }
+
+    fn internal_clock(&self) -> bool {
+        self.control & 0b0000_0001 != 0
+    }
+}
+
+#[derive(Debug)]
+pub struct Serial {
+    path: ComponentPath,
+    state: State,
+    port: ResourcePath,
+    interrupt: ResourcePath,
+}
+
+impl Serial {
+    fn finish(&mut self, runtime: &RuntimeHandle, timestamp: Period, data: u8) {
+        self.state.data = data;
+        self.state.control &= !0b1000_0000;
+
+        // Pulsed, for the interrupt controller to latch
+        runtime.drive_signal(&self.interrupt, timestamp, true);
+        runtime.drive_signal(&self.interrupt, timestamp, false);
+    }
+}
+
+impl Component for Serial {
+    type Event = SerialEvent;
+
+    fn memory_read(
+        &mut self,
+        address: Address,
+        _address_space: AddressSpaceId,
+        _avoid_side_effects: bool,
+        buffer: &mut [u8],
+    ) -> Result<(), MemoryError> {
+        buffer[0] = match address {
+            SB => self.state.data,
+            // Unused bits read high
+            SC => self.state.control | 0b0111_1110,
+            _ => unreachable!("{:x}", address),
+        };
+
+        Ok(())
+    }
+
+    fn memory_write(
+        &mut self,
+        address: Address,
+        _address_space: AddressSpaceId,
+        buffer: &[u8],
+    ) -> Result<(), MemoryError> {
+        match address {
+            SB => self.state.data = buffer[0],
+            SC => {
+                self.state.control = buffer[0] & 0b1000_0001;
+
+                if self.state.transfer_requested() && self.state.internal_clock() {
+                    self.state.transfer = self.state.transfer.wrapping_add(1);
+                    self.state.incoming = None;
+
+                    RuntimeHandle::with_current(|runtime| {
+                        let timestamp = runtime.current_timestamp(&self.path);
+
+                        runtime.send_link_message(&self.port, timestamp, [SHIFT, self.state.data]);
+                        runtime.schedule_event::<Self>(
+                            &self.path,
+                            EventMode::Once,
+                            timestamp + TRANSFER_TIME,
+                            SerialEvent::Complete {
+                                transfer: self.state.transfer,
+                            },
+                        );
+                    });
+                }
+            }
+            _ => unreachable!("{:x}", address),
+        }
+
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: Box<dyn Event>) {
+        let event = downcast_event::<Self>(event);
+
+        RuntimeHandle::with_current(|runtime| {
+            let timestamp = runtime.current_timestamp(&self.path);
+
+            match event {
+                SerialEvent::Link(LinkMessage { data, .. }) => match data[..] {
+                    [SHIFT, incoming] => {
+                        // Without a transfer waiting for the external clock nothing shifts, and the line idles high
+                        if self.state.transfer_requested() && !self.state.internal_clock() {
+                            runtime.send_link_message(&self.port, timestamp, [REPLY, self.state.data]);
+                            self.finish(runtime, timestamp, incoming);
+                        } else {
+                            runtime.send_link_message(&self.port, timestamp, [REPLY, 0xff]);
+                        }
+                    }
+                    [REPLY, incoming] => {
+                        if self.state.transfer_requested() && self.state.internal_clock() {
+                            self.state.incoming = Some(incoming);
+                        }
+                    }
+                    _ => tracing::warn!("Malformed serial link message {:x?}", data),
+                },
+                SerialEvent::Complete { transfer } => {
+                    if transfer == self.state.transfer && self.state.transfer_requested() {
+                        // Nothing on the other end reads as the line idling high
+                        let incoming = self.state.incoming.take().unwrap_or(0xff);
+                        self.finish(runtime, timestamp, incoming);
+                    }
+                }
+            }
+        });
+    }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.state))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        self.state = snapshot.decode()?;
+
+        Ok(())
+    }
+
+    fn reset(&mut self, kind: ResetKind) {
+        match kind {
+            // Aborts the running transfer
+            ResetKind::Soft => self.state.control = 0,
+            ResetKind::Hard => self.state = State::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SerialConfig {
+    pub cpu_address_space: AddressSpaceId,
+}
+
+impl<P: Platform> ComponentConfig<P> for SerialConfig {
+    type Component = Serial;
+
+    fn build_component(
+        self,
+        component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn std::error::Error>> {
+        let path = component_builder.path().clone();
+        let (component_builder, port) = component_builder.link_port("link");
+        let (component_builder, interrupt) = component_builder.signal_output("interrupt", false);
+        component_builder.map_memory(
+            self.cpu_address_space,
+            MemoryMapCommand::with_component(path.clone(), [(SB..=SC, Permissions::ALL)]),
+        );
+
+        Ok(Serial {
+            path,
+            state: State::default(),
+            port,
+            interrupt,
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use fluxemu_runtime::{
+    link::{LinkEndpoint, MachineLink},
+    machine::Machine,
+    memory::AddressSpaceId,
+    path::ResourcePath,
+    scheduler::Period,
+};
+
+use crate::serial::SerialConfig;
+
+fn serial_machine() -> (Arc<Machine>, AddressSpaceId, ResourcePath) {
+    let (machine, cpu_address_space) = Machine::build_test_minimal().address_space(16);
+    let (machine, serial) = machine.component("serial", SerialConfig { cpu_address_space });
+
+    (
+        machine.seal().build(()),
+        cpu_address_space,
+        serial.into_resource("link").unwrap(),
+    )
+}
+
+fn write(machine: &Arc<Machine>, address_space: AddressSpaceId, writes: [(usize, u8); 2]) {
+    let runtime_guard = machine.enter_runtime();
+    let timestamp = runtime_guard.safe_advance_timestamp();
+    let mut memory = runtime_guard.address_space(address_space).unwrap();
+
+    for (address, value) in writes {
+        memory.write_le_value(address, &timestamp, value).unwrap();
+    }
+}
+
+fn read(machine: &Arc<Machine>, address_space: AddressSpaceId) -> [u8; 2] {
+    let runtime_guard = machine.enter_runtime();
+    let timestamp = runtime_guard.safe_advance_timestamp();
+    let mut memory = runtime_guard.address_space(address_space).unwrap();
+
+    [0xff01, 0xff02].map(|address| {
+        memory
+            .read_le_value::<u8, false>(address, &timestamp)
+            .unwrap()
+    })
+}
+
+#[test]
+fn bytes_swap_over_the_link() {
+    let (master, master_memory, master_port) = serial_machine();
+    let (slave, slave_memory, slave_port) = serial_machine();
+
+    let mut link = MachineLink::new(Period::lit("0.0001"));
+    let master_id = link.insert(master.clone());
+    let slave_id = link.insert(slave.clone());
+    link.connect(
+        LinkEndpoint {
+            machine: master_id,
+            port: master_port,
+        },
+        LinkEndpoint {
+            machine: slave_id,
+            port: slave_port,
+        },
+    )
+    .unwrap();
+
+    write(&slave, slave_memory, [(0xff01, 0x42), (0xff02, 0x80)]);
+    write(&master, master_memory, [(0xff01, 0x17), (0xff02, 0x81)]);
+
+    link.run(Period::lit("0.01"));
+
+    assert_eq!(read(&master, master_memory), [0x42, 0x7f]);
+    assert_eq!(read(&slave, slave_memory), [0x17, 0x7e]);
+}
+
+#[test]
+fn nothing_on_the_other_end_reads_high() {
+    let (master, master_memory, _) = serial_machine();
+
+    write(&master, master_memory, [(0xff01, 0x17), (0xff02, 0x81)]);
+    master.enter_runtime().run(Period::lit("0.01"));
+
+    assert_eq!(read(&master, master_memory), [0xff, 0x7f]);
+}
@@ -5,7 +5,7 @@ use fluxemu_environment::load_environment;
 use fluxemu_program::ProgramManager;
 use fluxemu_runtime::machine::Machine;
 use fluxemu_system::System;
-use fluxemu_system_nintendo_nes::Nes;
+use fluxemu_system_nintendo_nes::{Nes, NesQuirks};
 use redb::Database;
 
 fn emulation_performance(c: &mut Criterion) {
@@ -65,7 +65,7 @@ fn emulation_performance(c: &mut Criterion) {
             && program_manager.load(rom_id).unwrap().is_some()
         {
             let machine = Machine::build_test(Some(specification), program_manager.clone());
-            let machine = Nes.build((), machine).build(());
+            let machine = Nes.build(NesQuirks::default(), machine).unwrap().build(());
 
             group.bench_function(program_name, |b| {
                 b.iter(|| {
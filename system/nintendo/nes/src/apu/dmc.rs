@@ -0,0 +1,103 @@
+use fluxemu_runtime::{RuntimeHandle, event::EventMode, memory::Address, scheduler::Period};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    apu::{Apu, ApuEvent},
+    ppu::region::Region,
+};
+
+/// Only how long samples take to play is emulated, for the interrupt at their end
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DmcChannel {
+    pub irq_enabled: bool,
+    pub looping: bool,
+    pub rate: u8,
+    /// Length of a sample in bytes
+    pub sample_length: u16,
+    pub playing: bool,
+    pub interrupt: bool,
+    /// Counts started samples, so the ends of stopped ones are ignored
+    pub sample: u64,
+}
+
+impl Default for DmcChannel {
+    fn default() -> Self {
+        Self {
+            irq_enabled: false,
+            looping: false,
+            rate: 0,
+            sample_length: 1,
+            playing: false,
+            interrupt: false,
+            sample: 0,
+        }
+    }
+}
+
+impl<R: Region> Apu<R> {
+    pub(super) fn dmc_write(&mut self, position: Address, byte: u8) {
+        let dmc = &mut self.state.dmc;
+
+        match position {
+            0 => {
+                dmc.irq_enabled = (byte & 0b1000_0000) != 0;
+                dmc.looping = (byte & 0b0100_0000) != 0;
+                dmc.rate = byte & 0b0000_1111;
+
+                if !dmc.irq_enabled {
+                    dmc.interrupt = false;
+                }
+            }
+            // Output level and sample address only matter for the sound
+            1 | 2 => {}
+            3 => dmc.sample_length = u16::from(byte) * 16 + 1,
+            _ => {
+                unreachable!()
+            }
+        }
+    }
+
+    /// Handle the DMC bit of $4015, which also acknowledges its interrupt
+    pub(super) fn dmc_enable(&mut self, runtime: &RuntimeHandle, timestamp: Period, enabled: bool) {
+        self.state.dmc.interrupt = false;
+
+        if !enabled {
+            self.state.dmc.playing = false;
+        } else if !self.state.dmc.playing {
+            self.start_sample(runtime, timestamp);
+        }
+    }
+
+    pub(super) fn sample_end(&mut self, runtime: &RuntimeHandle, timestamp: Period, sample: u64) {
+        let dmc = &mut self.state.dmc;
+
+        if !dmc.playing || sample != dmc.sample {
+            return;
+        }
+
+        if dmc.looping {
+            self.start_sample(runtime, timestamp);
+        } else {
+            dmc.playing = false;
+            dmc.interrupt = dmc.irq_enabled;
+        }
+    }
+
+    /// Changing the rate or length while a sample plays only affects the next one
+    fn start_sample(&mut self, runtime: &RuntimeHandle, timestamp: Period) {
+        let dmc = &mut self.state.dmc;
+
+        dmc.sample = dmc.sample.wrapping_add(1);
+        dmc.playing = true;
+
+        let bits = u64::from(dmc.sample_length) * 8;
+        let cycles = bits * u64::from(R::DMC_RATES[usize::from(dmc.rate)]);
+
+        runtime.schedule_event::<Self>(
+            &self.path,
+            EventMode::Once,
+            timestamp + self.clock.cycles(cycles),
+            ApuEvent::SampleEnd { sample: dmc.sample },
+        );
+    }
+}
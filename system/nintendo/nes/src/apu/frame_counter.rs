@@ -0,0 +1,80 @@
+use fluxemu_runtime::{
+    RuntimeHandle,
+    event::{EventHandle, EventMode},
+    scheduler::Period,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    apu::{Apu, ApuEvent},
+    ppu::region::Region,
+};
+
+/// Only the interrupt at the end of the four step sequence is emulated, not the clocking of the channels
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct FrameCounter {
+    pub five_step: bool,
+    pub irq_inhibit: bool,
+    pub interrupt: bool,
+    /// Pending end of the four step sequence, moved whenever the sequence restarts
+    pub frame_end: Option<EventHandle>,
+}
+
+impl<R: Region> Apu<R> {
+    pub(super) fn frame_counter_write(
+        &mut self,
+        runtime: &RuntimeHandle,
+        timestamp: Period,
+        byte: u8,
+    ) {
+        let frame_counter = &mut self.state.frame_counter;
+
+        frame_counter.five_step = (byte & 0b1000_0000) != 0;
+        frame_counter.irq_inhibit = (byte & 0b0100_0000) != 0;
+
+        if frame_counter.irq_inhibit {
+            frame_counter.interrupt = false;
+        }
+
+        self.restart_frame_counter(runtime, timestamp);
+    }
+
+    pub(super) fn restart_frame_counter(&mut self, runtime: &RuntimeHandle, timestamp: Period) {
+        let frame_counter = &mut self.state.frame_counter;
+
+        // The five step sequence never interrupts
+        if frame_counter.five_step {
+            if let Some(frame_end) = frame_counter.frame_end.take() {
+                frame_end.cancel(runtime);
+            }
+
+            return;
+        }
+
+        let time = timestamp + self.clock.cycles(R::APU_FRAME_LENGTH.into());
+
+        if let Some(frame_end) = frame_counter.frame_end
+            && frame_end.reschedule(runtime, time)
+        {
+            return;
+        }
+
+        frame_counter.frame_end = Some(runtime.schedule_event::<Self>(
+            &self.path,
+            EventMode::Once,
+            time,
+            ApuEvent::FrameEnd,
+        ));
+    }
+
+    pub(super) fn frame_end(&mut self, runtime: &RuntimeHandle, timestamp: Period) {
+        // Once events are gone from the queue when they fire
+        self.state.frame_counter.frame_end = None;
+
+        if !self.state.frame_counter.irq_inhibit {
+            self.state.frame_counter.interrupt = true;
+        }
+
+        self.restart_frame_counter(runtime, timestamp);
+    }
+}
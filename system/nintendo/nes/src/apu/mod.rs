@@ -1,15 +1,27 @@
-use std::ops::RangeInclusive;
+use std::{marker::PhantomData, ops::RangeInclusive};
 
 use fluxemu_math::range::ContiguousRange;
 use fluxemu_runtime::{
-    component::{Component, config::ComponentConfig},
+    RuntimeHandle,
+    clock::Clock,
+    component::{Component, ResetKind, config::ComponentConfig},
+    event::{Event, EventHandle, EventMode, downcast_event},
     machine::builder::ComponentBuilder,
     memory::{Address, AddressSpaceId, MemoryError, MemoryMapCommand, Permissions},
+    path::{ComponentPath, ResourcePath},
     platform::Platform,
+    scheduler::Period,
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
+use serde::{Deserialize, Serialize};
 
-use crate::apu::pulse::PulseChannel;
+use crate::{
+    apu::{dmc::DmcChannel, frame_counter::FrameCounter, pulse::PulseChannel},
+    ppu::region::Region,
+};
 
+mod dmc;
+mod frame_counter;
 mod pulse;
 
 const PULSE_1: RangeInclusive<Address> = 0x4000..=0x4003;
@@ -21,23 +33,81 @@ const CONTROL: Address = 0x4015;
 const STATUS: Address = 0x4015;
 const FRAME_COUNTER: Address = 0x4017;
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ApuEvent {
+    /// The frame counter reached the last step of the four step sequence
+    FrameEnd,
+    /// The DMC finished sample number `sample`
+    SampleEnd { sample: u64 },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct State {
+    pulse_channels: [PulseChannel; 2],
+    frame_counter: FrameCounter,
+    dmc: DmcChannel,
+}
+
+impl State {
+    fn power_on(frame_end: EventHandle) -> Self {
+        Self {
+            pulse_channels: Default::default(),
+            frame_counter: FrameCounter {
+                frame_end: Some(frame_end),
+                ..Default::default()
+            },
+            dmc: Default::default(),
+        }
+    }
+}
+
 #[derive(Debug)]
-pub struct Apu {
-    pub pulse_channels: [PulseChannel; 2],
+pub struct Apu<R: Region> {
+    state: State,
+    path: ComponentPath,
+    clock: Clock,
+    /// Held low while the frame counter or the DMC has a interrupt pending
+    irq: ResourcePath,
+    /// Frame end scheduled at build time, which a hard reset puts back in the queue
+    initial_frame_end: EventHandle,
+    _phantom: PhantomData<R>,
 }
 
-impl Component for Apu {
-    type Event = ();
+impl<R: Region> Apu<R> {
+    fn update_irq(&self, runtime: &RuntimeHandle, timestamp: Period) {
+        let pending = self.state.frame_counter.interrupt || self.state.dmc.interrupt;
+
+        runtime.drive_signal(&self.irq, timestamp, !pending);
+    }
+}
+
+impl<R: Region> Component for Apu<R> {
+    type Event = ApuEvent;
 
     fn memory_read(
         &mut self,
         address: Address,
         _address_space: AddressSpaceId,
-        _avoid_side_effects: bool,
-        _buffer: &mut [u8],
+        avoid_side_effects: bool,
+        buffer: &mut [u8],
     ) -> Result<(), MemoryError> {
         match address {
-            STATUS => {}
+            STATUS => {
+                // Length counters are not emulated yet, so the channels always read as silent
+                buffer[0] = (u8::from(self.state.dmc.interrupt) << 7)
+                    | (u8::from(self.state.frame_counter.interrupt) << 6)
+                    | (u8::from(self.state.dmc.playing) << 4);
+
+                if !avoid_side_effects && self.state.frame_counter.interrupt {
+                    self.state.frame_counter.interrupt = false;
+
+                    RuntimeHandle::with_current(|runtime| {
+                        let timestamp = runtime.current_timestamp(&self.path);
+
+                        self.update_irq(runtime, timestamp);
+                    });
+                }
+            }
             _ => {
                 unreachable!()
             }
@@ -52,60 +122,137 @@ impl Component for Apu {
         _address_space: AddressSpaceId,
         buffer: &[u8],
     ) -> Result<(), MemoryError> {
-        for (address, byte) in
-            RangeInclusive::from_start_and_length(address, buffer.len()).zip(buffer.iter().copied())
-        {
-            if PULSE_1.contains(&address) {
-                self.pulse_write(0, address - PULSE_1.start(), byte);
-            }
+        RuntimeHandle::with_current(|runtime| {
+            let timestamp = runtime.current_timestamp(&self.path);
+
+            for (address, byte) in RangeInclusive::from_start_and_length(address, buffer.len())
+                .zip(buffer.iter().copied())
+            {
+                if PULSE_1.contains(&address) {
+                    self.pulse_write(0, address - PULSE_1.start(), byte);
+                }
+
+                if PULSE_2.contains(&address) {
+                    self.pulse_write(1, address - PULSE_2.start(), byte);
+                }
 
-            if PULSE_2.contains(&address) {
-                self.pulse_write(1, address - PULSE_2.start(), byte);
+                if DMC.contains(&address) {
+                    self.dmc_write(address - DMC.start(), byte);
+                }
+
+                if CONTROL == address {
+                    self.state.pulse_channels[1].enabled = (byte & 0b0000_0010) != 0;
+                    self.state.pulse_channels[0].enabled = (byte & 0b0000_0001) != 0;
+                    self.dmc_enable(runtime, timestamp, (byte & 0b0001_0000) != 0);
+                }
+
+                if FRAME_COUNTER == address {
+                    self.frame_counter_write(runtime, timestamp, byte);
+                }
             }
 
-            if CONTROL == address {
-                self.pulse_channels[1].enabled = (byte & 0b0000_0010) != 0;
-                self.pulse_channels[0].enabled = (byte & 0b0000_0001) != 0;
+            self.update_irq(runtime, timestamp);
+        });
+
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: Box<dyn Event>) {
+        let event = downcast_event::<Self>(event);
+
+        RuntimeHandle::with_current(|runtime| {
+            let timestamp = runtime.current_timestamp(&self.path);
+
+            match event {
+                ApuEvent::FrameEnd => self.frame_end(runtime, timestamp),
+                ApuEvent::SampleEnd { sample } => self.sample_end(runtime, timestamp, sample),
             }
-        }
+
+            self.update_irq(runtime, timestamp);
+        });
+    }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.state))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        self.state = snapshot.decode()?;
 
         Ok(())
     }
+
+    fn reset(&mut self, kind: ResetKind) {
+        match kind {
+            // Reset silences every channel as if $4015 was written with zero, and restarts the frame counter
+            ResetKind::Soft => RuntimeHandle::with_current(|runtime| {
+                let timestamp = runtime.current_timestamp(&self.path);
+
+                for pulse_channel in &mut self.state.pulse_channels {
+                    pulse_channel.enabled = false;
+                }
+
+                self.dmc_enable(runtime, timestamp, false);
+                self.state.frame_counter.interrupt = false;
+                self.restart_frame_counter(runtime, timestamp);
+                self.update_irq(runtime, timestamp);
+            }),
+            // The runtime puts the IRQ line and the first frame end back where they were at power on
+            ResetKind::Hard => self.state = State::power_on(self.initial_frame_end),
+        }
+    }
 }
 
 #[derive(Debug)]
-pub struct ApuConfig {
+pub struct ApuConfig<R: Region> {
     pub cpu_address_space: AddressSpaceId,
+    /// Clock of the processor, which the frame counter and the DMC count in
+    pub processor_clock: Clock,
+    pub _phantom: PhantomData<R>,
 }
 
-impl<P: Platform> ComponentConfig<P> for ApuConfig {
-    type Component = Apu;
+impl<R: Region, P: Platform> ComponentConfig<P> for ApuConfig<R> {
+    type Component = Apu<R>;
 
     fn build_component(
         self,
         component_builder: ComponentBuilder<P, Self::Component>,
     ) -> Result<Self::Component, Box<dyn std::error::Error>> {
         let my_path = component_builder.path().clone();
+        let (component_builder, irq) = component_builder.signal_output("irq", true);
 
-        component_builder.map_memory(
-            self.cpu_address_space,
-            MemoryMapCommand::with_component(
-                my_path,
-                [
-                    (PULSE_1, Permissions::WRITE),
-                    (PULSE_2, Permissions::WRITE),
-                    (TRIANGLE, Permissions::WRITE),
-                    (NOISE, Permissions::WRITE),
-                    (DMC, Permissions::WRITE),
-                    (CONTROL..=CONTROL, Permissions::WRITE),
-                    (STATUS..=STATUS, Permissions::READ),
-                    (FRAME_COUNTER..=FRAME_COUNTER, Permissions::WRITE),
-                ],
-            ),
-        );
+        let (component_builder, frame_end) = component_builder
+            .map_memory(
+                self.cpu_address_space,
+                MemoryMapCommand::with_component(
+                    my_path.clone(),
+                    [
+                        (PULSE_1, Permissions::WRITE),
+                        (PULSE_2, Permissions::WRITE),
+                        (TRIANGLE, Permissions::WRITE),
+                        (NOISE, Permissions::WRITE),
+                        (DMC, Permissions::WRITE),
+                        (CONTROL..=CONTROL, Permissions::WRITE),
+                        (STATUS..=STATUS, Permissions::READ),
+                        (FRAME_COUNTER..=FRAME_COUNTER, Permissions::WRITE),
+                    ],
+                ),
+            )
+            // The frame counter powers up in four step mode with its interrupt enabled
+            .schedule_event::<Self::Component>(
+                &my_path,
+                self.processor_clock.cycles(R::APU_FRAME_LENGTH.into()),
+                EventMode::Once,
+                ApuEvent::FrameEnd,
+            );
 
         Ok(Apu {
-            pulse_channels: Default::default(),
+            state: State::power_on(frame_end),
+            initial_frame_end: frame_end,
+            path: component_builder.path().clone(),
+            clock: self.processor_clock,
+            irq,
+            _phantom: PhantomData,
         })
     }
 }
@@ -4,6 +4,7 @@ use bitstream_io::{BigEndian, BitRead2, BitReader};
 use bytes::Bytes;
 use expansion_device::DefaultExpansionDevice;
 use fluxemu_math::range::ContiguousRange;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 pub mod expansion_device;
@@ -29,7 +30,7 @@ pub enum ParsingError {
     Io(#[from] std::io::Error),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum TimingMode {
     Ntsc,
     Pal,
@@ -1,16 +1,22 @@
 use std::ops::RangeInclusive;
 
+use bytes::Bytes;
 use fluxemu_math::range::ContiguousRange;
 use fluxemu_runtime::{
     ComponentPath, RuntimeHandle,
     component::{
-        Component,
+        Component, ResetKind,
         config::{ComponentConfig, LateContext},
     },
     machine::builder::ComponentBuilder,
-    memory::{Address, AddressSpaceId, MapTarget, MemoryError, MemoryMapCommand, Permissions},
+    memory::{
+        Address, AddressSpaceId, MapTarget, MappingPresetId, MemoryError, MemoryMapCommand,
+        Permissions,
+    },
     platform::Platform,
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
     cartridge::{CartParams, mapper::mmc1::shift::ShiftRegister},
@@ -22,20 +28,20 @@ mod shift;
 const PRG_BANK_SIZE: usize = 16 * 1024;
 const CHR_BANK_SIZE: usize = 4 * 1024;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 enum PrgRomBankMode {
     Unified32k,
     LockFirstBank,
     LockLastBank,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 enum ChrRomBankMode {
     Unified8k,
     Split4k,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 enum Mirroring {
     OneScreenLower,
     OneScreenUpper,
@@ -43,16 +49,38 @@ enum Mirroring {
     Horizontal,
 }
 
-#[derive(Debug)]
-pub struct Mmc1 {
+#[derive(Debug, Serialize, Deserialize)]
+struct State {
     shift_register: ShiftRegister,
     chr_rom_bank_mode: ChrRomBankMode,
     chr_rom_bank_indexes: [u8; 2],
     prg_rom_bank_mode: PrgRomBankMode,
     prg_rom_bank_index: u8,
     mirroring: Mirroring,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            shift_register: ShiftRegister::default(),
+            chr_rom_bank_mode: ChrRomBankMode::Unified8k,
+            chr_rom_bank_indexes: [0, 0],
+            prg_rom_bank_mode: PrgRomBankMode::LockLastBank,
+            prg_rom_bank_index: 0,
+            mirroring: Mirroring::Horizontal,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Mmc1 {
+    state: State,
     config: Mmc1Config,
     path: ComponentPath,
+    /// Mapping presets for every PRG-ROM bank, in the lower and upper window
+    prg_presets: [Vec<MappingPresetId>; 2],
+    /// Mapping presets for every CHR-ROM bank, in the lower and upper window
+    chr_presets: [Vec<MappingPresetId>; 2],
 }
 
 impl Mmc1 {
@@ -64,94 +92,50 @@ impl Mmc1 {
         //
         // Therefore the ppu can never observe stale mappings
 
-        let timestamp = runtime.current_timestamp(&self.path);
-
-        let mut cpu_commands = Vec::new();
-
-        let (prg_low_bank, prg_high_bank) = match self.prg_rom_bank_mode {
+        let (prg_low_bank, prg_high_bank) = match self.state.prg_rom_bank_mode {
             PrgRomBankMode::Unified32k => {
-                let bank = (self.prg_rom_bank_index & 0b1111_1110) as usize;
+                let bank = (self.state.prg_rom_bank_index & 0b1111_1110) as usize;
 
                 (bank, bank + 1)
             }
-            PrgRomBankMode::LockFirstBank => (0, self.prg_rom_bank_index as usize),
+            PrgRomBankMode::LockFirstBank => (0, self.state.prg_rom_bank_index as usize),
             PrgRomBankMode::LockLastBank => {
                 let last = (self.config.params.prg_rom.len() / PRG_BANK_SIZE) - 1;
 
-                (self.prg_rom_bank_index as usize, last)
+                (self.state.prg_rom_bank_index as usize, last)
             }
         };
 
-        cpu_commands.push(MemoryMapCommand::Map {
-            range: 0x8000..=0xbfff,
-            target: MapTarget::ImmutableMemory(self.config.params.prg_rom.slice(
-                RangeInclusive::from_start_and_length(prg_low_bank * PRG_BANK_SIZE, PRG_BANK_SIZE),
-            )),
-            permissions: Permissions::READ,
-        });
-
-        cpu_commands.push(MemoryMapCommand::Map {
-            range: 0xc000..=0xffff,
-            target: MapTarget::ImmutableMemory(self.config.params.prg_rom.slice(
-                RangeInclusive::from_start_and_length(prg_high_bank * PRG_BANK_SIZE, PRG_BANK_SIZE),
-            )),
-            permissions: Permissions::READ,
-        });
-
-        runtime
+        let mut cpu_address_space = runtime
             .address_space(self.config.params.cpu_address_space)
-            .unwrap()
-            .remap(&timestamp, cpu_commands);
-
-        if let Some(chr_rom) = self.config.params.chr_rom.as_ref() {
-            let mut ppu_commands = Vec::new();
-
-            match self.chr_rom_bank_mode {
+            .unwrap();
+        cpu_address_space
+            .switch_presets([
+                self.prg_presets[0][prg_low_bank],
+                self.prg_presets[1][prg_high_bank],
+            ])
+            .unwrap();
+
+        if self.config.params.chr_rom.is_some() {
+            let chr_banks = match self.state.chr_rom_bank_mode {
                 ChrRomBankMode::Unified8k => {
-                    let bank = (self.chr_rom_bank_indexes[0] & !1) as usize;
-
-                    ppu_commands.push(MemoryMapCommand::Map {
-                        range: 0x0000..=0x0fff,
-                        target: MapTarget::ImmutableMemory(chr_rom.slice(
-                            RangeInclusive::from_start_and_length(
-                                bank * CHR_BANK_SIZE,
-                                CHR_BANK_SIZE,
-                            ),
-                        )),
-                        permissions: Permissions::READ,
-                    });
-
-                    ppu_commands.push(MemoryMapCommand::Map {
-                        range: 0x1000..=0x1fff,
-                        target: MapTarget::ImmutableMemory(chr_rom.slice(
-                            RangeInclusive::from_start_and_length(
-                                (bank + 1) * CHR_BANK_SIZE,
-                                CHR_BANK_SIZE,
-                            ),
-                        )),
-                        permissions: Permissions::READ,
-                    });
-                }
-                ChrRomBankMode::Split4k => {
-                    for (i, &bank_index) in self.chr_rom_bank_indexes.iter().enumerate() {
-                        let ppu_base = i * 0x1000;
-                        let rom_offset = bank_index as usize * CHR_BANK_SIZE;
-
-                        ppu_commands.push(MemoryMapCommand::Map {
-                            range: RangeInclusive::from_start_and_length(ppu_base, 0x1000),
-                            target: MapTarget::ImmutableMemory(chr_rom.slice(
-                                RangeInclusive::from_start_and_length(rom_offset, CHR_BANK_SIZE),
-                            )),
-                            permissions: Permissions::READ,
-                        });
-                    }
+                    let bank = (self.state.chr_rom_bank_indexes[0] & !1) as usize;
+
+                    [bank, bank + 1]
                 }
-            }
+                ChrRomBankMode::Split4k => self.state.chr_rom_bank_indexes.map(usize::from),
+            };
 
-            runtime
+            let mut ppu_address_space = runtime
                 .address_space(self.config.params.ppu_address_space)
-                .unwrap()
-                .remap(&timestamp, ppu_commands);
+                .unwrap();
+
+            ppu_address_space
+                .switch_presets([
+                    self.chr_presets[0][chr_banks[0]],
+                    self.chr_presets[1][chr_banks[1]],
+                ])
+                .unwrap();
         }
     }
 
@@ -160,7 +144,7 @@ impl Mmc1 {
 
         let [nametable_0, nametable_1] = &self.config.params.nametables;
 
-        let commands = match self.mirroring {
+        let commands = match self.state.mirroring {
             Mirroring::OneScreenLower => vec![
                 MemoryMapCommand::Map {
                     range: NAMETABLE_ADDRESSES[0].clone(),
@@ -314,14 +298,14 @@ impl Component for Mmc1 {
                     let reset = byte & 0b1000_0000 != 0;
 
                     if reset {
-                        self.shift_register = ShiftRegister::default();
-                        self.prg_rom_bank_mode = PrgRomBankMode::LockLastBank;
+                        self.state.shift_register = ShiftRegister::default();
+                        self.state.prg_rom_bank_mode = PrgRomBankMode::LockLastBank;
 
                         self.update_banking(runtime);
                         continue;
                     }
 
-                    if let Some(value) = self.shift_register.shift(shift_in_bit) {
+                    if let Some(value) = self.state.shift_register.shift(shift_in_bit) {
                         let remap;
 
                         match address {
@@ -347,34 +331,34 @@ impl Component for Mmc1 {
                                     _ => unreachable!(),
                                 };
 
-                                remap = chr_rom_bank_mode != self.chr_rom_bank_mode
-                                    || prg_rom_bank_mode != self.prg_rom_bank_mode
-                                    || mirroring != self.mirroring;
+                                remap = chr_rom_bank_mode != self.state.chr_rom_bank_mode
+                                    || prg_rom_bank_mode != self.state.prg_rom_bank_mode
+                                    || mirroring != self.state.mirroring;
 
-                                self.chr_rom_bank_mode = chr_rom_bank_mode;
-                                self.prg_rom_bank_mode = prg_rom_bank_mode;
-                                self.mirroring = mirroring;
+                                self.state.chr_rom_bank_mode = chr_rom_bank_mode;
+                                self.state.prg_rom_bank_mode = prg_rom_bank_mode;
+                                self.state.mirroring = mirroring;
                             }
                             0xa000..=0xbfff => {
                                 let index = value & 0b0001_1111;
 
-                                remap = index != self.chr_rom_bank_indexes[0];
+                                remap = index != self.state.chr_rom_bank_indexes[0];
 
-                                self.chr_rom_bank_indexes[0] = index;
+                                self.state.chr_rom_bank_indexes[0] = index;
                             }
                             0xc000..=0xdfff => {
                                 let index = value & 0b0001_1111;
 
-                                remap = index != self.chr_rom_bank_indexes[1];
+                                remap = index != self.state.chr_rom_bank_indexes[1];
 
-                                self.chr_rom_bank_indexes[1] = index;
+                                self.state.chr_rom_bank_indexes[1] = index;
                             }
                             0xe000..=0xffff => {
                                 let index = value & 0b0000_1111;
 
-                                remap = index != self.prg_rom_bank_index;
+                                remap = index != self.state.prg_rom_bank_index;
 
-                                self.prg_rom_bank_index = index;
+                                self.state.prg_rom_bank_index = index;
                             }
                             _ => unreachable!(),
                         }
@@ -390,6 +374,32 @@ impl Component for Mmc1 {
 
         Ok(())
     }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.state))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        self.state = snapshot.decode()?;
+
+        RuntimeHandle::with_current(|runtime| {
+            self.update_banking(runtime);
+            self.update_nametables(runtime);
+        });
+
+        Ok(())
+    }
+
+    fn reset(&mut self, kind: ResetKind) {
+        // The cartridge connector has no reset line, so only a power cycle reaches the mapper
+        if kind == ResetKind::Hard {
+            self.state = State::default();
+
+            RuntimeHandle::with_current(|runtime| {
+                self.update_banking(runtime);
+            });
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -460,7 +470,11 @@ impl<P: Platform> ComponentConfig<P> for Mmc1Config {
                 return Err("PRG-RAM size is invalid for MMC1".into());
             }
 
-            let (cb, prg_ram_path) = component_builder.memory("prg-ram", 0x2000, []);
+            let (cb, prg_ram_path) = if self.params.prg_ram_battery_backed {
+                component_builder.save_memory("prg-ram", 0x2000, [])
+            } else {
+                component_builder.memory("prg-ram", 0x2000, [])
+            };
             let cb = cb.map_memory(
                 self.params.cpu_address_space,
                 [MemoryMapCommand::Map {
@@ -476,6 +490,41 @@ impl<P: Platform> ComponentConfig<P> for Mmc1Config {
             component_builder = cb;
         }
 
+        let mut prg_presets = [Vec::new(), Vec::new()];
+        for (window, presets) in [0x8000, 0xc000].into_iter().zip(&mut prg_presets) {
+            for bank in 0..self.params.prg_rom.len() / PRG_BANK_SIZE {
+                let (cb, preset) = component_builder.mapping_preset(
+                    self.params.cpu_address_space,
+                    format!("prg-{window:04x}-{bank}"),
+                    [rom_bank_command(
+                        &self.params.prg_rom,
+                        window,
+                        bank,
+                        PRG_BANK_SIZE,
+                    )],
+                );
+
+                component_builder = cb;
+                presets.push(preset);
+            }
+        }
+
+        let mut chr_presets = [Vec::new(), Vec::new()];
+        if let Some(chr_rom) = &self.params.chr_rom {
+            for (window, presets) in [0x0000, 0x1000].into_iter().zip(&mut chr_presets) {
+                for bank in 0..chr_rom.len() / CHR_BANK_SIZE {
+                    let (cb, preset) = component_builder.mapping_preset(
+                        self.params.ppu_address_space,
+                        format!("chr-{window:04x}-{bank}"),
+                        [rom_bank_command(chr_rom, window, bank, CHR_BANK_SIZE)],
+                    );
+
+                    component_builder = cb;
+                    presets.push(preset);
+                }
+            }
+        }
+
         let my_path = component_builder.path().clone();
 
         // Control register
@@ -489,14 +538,27 @@ impl<P: Platform> ComponentConfig<P> for Mmc1Config {
         );
 
         Ok(Mmc1 {
-            shift_register: ShiftRegister::default(),
+            state: State::default(),
             config: self,
-            chr_rom_bank_mode: ChrRomBankMode::Unified8k,
-            chr_rom_bank_indexes: [0, 0],
-            prg_rom_bank_mode: PrgRomBankMode::LockLastBank,
-            prg_rom_bank_index: 0,
-            mirroring: Mirroring::Horizontal,
             path: my_path,
+            prg_presets,
+            chr_presets,
         })
     }
 }
+
+fn rom_bank_command(
+    rom: &Bytes,
+    window: Address,
+    bank: usize,
+    bank_size: usize,
+) -> MemoryMapCommand {
+    MemoryMapCommand::Map {
+        range: RangeInclusive::from_start_and_length(window, bank_size),
+        target: MapTarget::ImmutableMemory(rom.slice(RangeInclusive::from_start_and_length(
+            bank * bank_size,
+            bank_size,
+        ))),
+        permissions: Permissions::READ,
+    }
+}
@@ -12,6 +12,8 @@ pub struct CartParams {
     pub chr_rom: Option<Bytes>,
     pub prg_rom: Bytes,
     pub prg_ram_size: usize,
+    /// PRG-RAM is battery backed and should persist between sessions
+    pub prg_ram_battery_backed: bool,
     pub chr_ram_size: usize,
     pub chr_nvram_size: usize,
 }
@@ -1 +1,2 @@
 pub mod standard_controllers;
+pub mod zapper;
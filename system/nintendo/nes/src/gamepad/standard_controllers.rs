@@ -8,7 +8,9 @@ use fluxemu_runtime::{
     machine::builder::ComponentBuilder,
     memory::{Address, AddressSpaceId, MemoryError, MemoryMapCommand, Permissions},
     platform::Platform,
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
+use serde::{Deserialize, Serialize};
 
 const CONTROLLER_0: Address = 0x4016;
 
@@ -23,7 +25,7 @@ const READ_ORDER: [InputId; 8] = [
     InputId::Gamepad(GamepadInputId::DPadRight),
 ];
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct ControllerState {
     current_reads: [u8; 2],
     strobe: bool,
@@ -79,6 +81,16 @@ impl Component for StandardNesControllers {
 
         Ok(())
     }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.state))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        self.state = snapshot.decode()?;
+
+        Ok(())
+    }
 }
 
 impl<P: Platform> ComponentConfig<P> for NesControllerConfig {
@@ -101,7 +113,7 @@ impl<P: Platform> ComponentConfig<P> for NesControllerConfig {
 
         let mut logical_input_devices = heapless::Vec::default();
 
-        for controller_index in 0..2 {
+        for controller_index in 0..self.controller_count {
             let (cb, input_device) = component_builder.input(
                 get_controller_name(controller_index),
                 present_inputs,
@@ -149,7 +161,7 @@ impl<P: Platform> ComponentConfig<P> for NesControllerConfig {
             component_builder = cb;
         }
 
-        // Grab both controllers
+        // Grab the ports we have controllers in
         let my_path = component_builder.path().clone();
         component_builder.map_memory(
             self.cpu_address_space,
@@ -157,7 +169,10 @@ impl<P: Platform> ComponentConfig<P> for NesControllerConfig {
                 my_path,
                 [
                     (
-                        RangeInclusive::from_start_and_length(CONTROLLER_0, 2),
+                        RangeInclusive::from_start_and_length(
+                            CONTROLLER_0,
+                            usize::from(self.controller_count),
+                        ),
                         Permissions::READ,
                     ),
                     (
@@ -178,6 +193,8 @@ impl<P: Platform> ComponentConfig<P> for NesControllerConfig {
 #[derive(Debug)]
 pub struct NesControllerConfig {
     pub cpu_address_space: AddressSpaceId,
+    /// Controllers plugged in from the first port on, at most two
+    pub controller_count: u8,
 }
 
 fn get_controller_name(index: u8) -> String {
@@ -0,0 +1,181 @@
+use std::{marker::PhantomData, ops::RangeInclusive, sync::Arc};
+
+use fluxemu_input::{GamepadInputId, InputId, KeyboardInputId};
+use fluxemu_math::range::ContiguousRange;
+use fluxemu_runtime::{
+    RuntimeHandle,
+    component::{Component, config::ComponentConfig},
+    input::LogicalInputDevice,
+    machine::builder::ComponentBuilder,
+    memory::{Address, AddressSpaceId, MemoryError, MemoryMapCommand, Permissions},
+    path::ComponentPath,
+    platform::Platform,
+};
+use nalgebra::Point2;
+
+use crate::ppu::{Ppu, VISIBLE_SCANLINE_LENGTH, backend::SupportedGraphicsApiPpu, region::Region};
+
+const CONTROLLER_1: Address = 0x4017;
+
+/// The Zapper light gun, in the second controller port
+#[derive(Debug)]
+pub struct Zapper<R: Region, G: SupportedGraphicsApiPpu> {
+    path: ComponentPath,
+    ppu: ComponentPath,
+    input_device: Arc<LogicalInputDevice>,
+    _phantom: PhantomData<fn() -> (R, G)>,
+}
+
+impl<R: Region, G: SupportedGraphicsApiPpu> Zapper<R, G> {
+    /// Point on the picture the gun is aimed at, the stick spanning the whole of it
+    fn aim(&self) -> Point2<u16> {
+        let axis = |negative, positive, length: u16| {
+            let negative = self
+                .input_device
+                .get_state(InputId::Gamepad(negative))
+                .as_analog();
+            let positive = self
+                .input_device
+                .get_state(InputId::Gamepad(positive))
+                .as_analog();
+
+            (((1.0 + positive - negative) / 2.0).clamp(0.0, 1.0) * f32::from(length - 1)) as u16
+        };
+
+        Point2::new(
+            axis(
+                GamepadInputId::LeftStickLeft,
+                GamepadInputId::LeftStickRight,
+                VISIBLE_SCANLINE_LENGTH,
+            ),
+            axis(
+                GamepadInputId::LeftStickUp,
+                GamepadInputId::LeftStickDown,
+                R::VISIBLE_SCANLINES,
+            ),
+        )
+    }
+}
+
+impl<R: Region, G: SupportedGraphicsApiPpu> Component for Zapper<R, G> {
+    type Event = ();
+
+    fn memory_read(
+        &mut self,
+        _address: Address,
+        _address_space: AddressSpaceId,
+        _avoid_side_effects: bool,
+        buffer: &mut [u8],
+    ) -> Result<(), MemoryError> {
+        let trigger = self
+            .input_device
+            .get_state(InputId::Gamepad(GamepadInputId::FPadDown))
+            .as_digital(None);
+        let aim = self.aim();
+
+        let lit = RuntimeHandle::with_current(|runtime| {
+            let timestamp = runtime.current_timestamp(&self.path);
+
+            runtime
+                .component_registry()
+                .interact::<Ppu<R, G>, _>(&self.ppu, &timestamp, |ppu| ppu.is_lit(aim))
+                .unwrap_or(false)
+        });
+
+        // The light sensor reads low when it sees light
+        buffer[0] = (buffer[0] & 0b1110_0111) | ((trigger as u8) << 4) | ((!lit as u8) << 3);
+
+        Ok(())
+    }
+}
+
+/// Configuration for a Zapper in the second controller port, watching the picture of `ppu`
+///
+/// Take any controller out of that port first, the Zapper answers all reads of it
+#[derive(Debug)]
+pub struct ZapperConfig<R: Region> {
+    pub cpu_address_space: AddressSpaceId,
+    pub ppu: ComponentPath,
+    pub _phantom: PhantomData<R>,
+}
+
+impl<R: Region, P: Platform<GraphicsApi: SupportedGraphicsApiPpu>> ComponentConfig<P>
+    for ZapperConfig<R>
+{
+    type Component = Zapper<R, P::GraphicsApi>;
+
+    fn build_component(
+        self,
+        component_builder: ComponentBuilder<P, Self::Component>,
+    ) -> Result<Self::Component, Box<dyn std::error::Error>> {
+        let path = component_builder.path().clone();
+        let (component_builder, input_device) =
+            component_builder.input("zapper", PRESENT_INPUTS, DEFAULT_MAPPINGS);
+
+        component_builder.map_memory(
+            self.cpu_address_space,
+            MemoryMapCommand::with_component(
+                path.clone(),
+                [(RangeInclusive::from_single(CONTROLLER_1), Permissions::READ)],
+            ),
+        );
+
+        Ok(Zapper {
+            path,
+            ppu: self.ppu,
+            input_device,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+const PRESENT_INPUTS: [InputId; 5] = [
+    InputId::Gamepad(GamepadInputId::LeftStickLeft),
+    InputId::Gamepad(GamepadInputId::LeftStickRight),
+    InputId::Gamepad(GamepadInputId::LeftStickUp),
+    InputId::Gamepad(GamepadInputId::LeftStickDown),
+    InputId::Gamepad(GamepadInputId::FPadDown),
+];
+
+const DEFAULT_MAPPINGS: [(InputId, InputId); 10] = [
+    (
+        InputId::Gamepad(GamepadInputId::LeftStickLeft),
+        InputId::Gamepad(GamepadInputId::LeftStickLeft),
+    ),
+    (
+        InputId::Gamepad(GamepadInputId::LeftStickRight),
+        InputId::Gamepad(GamepadInputId::LeftStickRight),
+    ),
+    (
+        InputId::Gamepad(GamepadInputId::LeftStickUp),
+        InputId::Gamepad(GamepadInputId::LeftStickUp),
+    ),
+    (
+        InputId::Gamepad(GamepadInputId::LeftStickDown),
+        InputId::Gamepad(GamepadInputId::LeftStickDown),
+    ),
+    (
+        InputId::Gamepad(GamepadInputId::FPadDown),
+        InputId::Gamepad(GamepadInputId::FPadDown),
+    ),
+    (
+        InputId::Keyboard(KeyboardInputId::ArrowLeft),
+        InputId::Gamepad(GamepadInputId::LeftStickLeft),
+    ),
+    (
+        InputId::Keyboard(KeyboardInputId::ArrowRight),
+        InputId::Gamepad(GamepadInputId::LeftStickRight),
+    ),
+    (
+        InputId::Keyboard(KeyboardInputId::ArrowUp),
+        InputId::Gamepad(GamepadInputId::LeftStickUp),
+    ),
+    (
+        InputId::Keyboard(KeyboardInputId::ArrowDown),
+        InputId::Gamepad(GamepadInputId::LeftStickDown),
+    ),
+    (
+        InputId::Keyboard(KeyboardInputId::KeyZ),
+        InputId::Gamepad(GamepadInputId::FPadDown),
+    ),
+];
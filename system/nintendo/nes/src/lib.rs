@@ -1,18 +1,25 @@
-use std::{marker::PhantomData, ops::RangeInclusive};
+use std::{any::Any, marker::PhantomData, ops::RangeInclusive};
 
-pub use cartridge::ines::INes;
-use cartridge::{CartParams, ines::TimingMode};
+use cartridge::CartParams;
+pub use cartridge::ines::{INes, TimingMode};
 use fluxemu_definition_mos6502::variant::Ricoh2A0x;
+use fluxemu_graphics::api::GraphicsApi;
 use fluxemu_math::range::ContiguousRange;
 use fluxemu_program::{NintendoSystem, SystemId};
 use fluxemu_runtime::{
-    ResourcePath,
-    machine::builder::{MachineBuilder, RomRequirement, SealedMachineBuilder},
-    memory::{AddressSpaceId, MapTarget, MemoryMapCommand, Permissions},
+    ComponentPath, ResourcePath,
+    machine::{
+        RuntimeGuard,
+        builder::{MachineBuilder, RomRequirement, SealedMachineBuilder},
+    },
+    memory::{AddressSpaceId, MapTarget, MemoryMapCommand, OpenBusDecay, Permissions},
     platform::Platform,
 };
 use fluxemu_system::System;
+use num::rational::Ratio;
 use ppu::PpuConfig;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::{
     apu::ApuConfig,
@@ -20,9 +27,9 @@ use crate::{
         ines::{INesVersion, NametableMirroring, expansion_device::DefaultExpansionDevice},
         mapper::{mmc1::Mmc1Config, nrom::NRomConfig},
     },
-    gamepad::standard_controllers::NesControllerConfig,
+    gamepad::{standard_controllers::NesControllerConfig, zapper::ZapperConfig},
     ppu::{
-        BACKGROUND_PALETTE_BASE_ADDRESS, NAMETABLE_ADDRESSES, PALETTE_RAM_ADDRESSES,
+        BACKGROUND_PALETTE_BASE_ADDRESS, NAMETABLE_ADDRESSES, PALETTE_RAM_ADDRESSES, Ppu,
         backend::SupportedGraphicsApiPpu,
         region::{Region, ntsc::Ntsc, pal::Pal},
     },
@@ -33,21 +40,40 @@ mod cartridge;
 mod gamepad;
 mod ppu;
 
+const CONTROLLERS: &str = "standard-nes-controllers";
+const ZAPPER: &str = "zapper";
+
 #[derive(Debug, Default)]
 pub struct Nes;
 
+#[derive(Debug, Error)]
+pub enum NesError {
+    #[error("{0:?} timing is not emulated")]
+    UnsupportedTiming(TimingMode),
+}
+
+/// Per program overrides for what the iNES header says
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct NesQuirks {
+    /// Run at this timing regardless of the header, for dumps with a wrong or missing region
+    pub region: Option<TimingMode>,
+}
+
 impl<G: SupportedGraphicsApiPpu, P: Platform<GraphicsApi = G>> System<P> for Nes {
-    type Quirks = ();
+    type Quirks = NesQuirks;
 
     const ID: SystemId = SystemId::Nintendo(NintendoSystem::NintendoEntertainmentSystem);
 
     fn build(
         &self,
-        _quirks: Self::Quirks,
+        quirks: Self::Quirks,
         machine_builder: MachineBuilder<P>,
-    ) -> SealedMachineBuilder<P> {
+    ) -> Result<SealedMachineBuilder<P>, Box<dyn std::error::Error + Send + Sync>> {
         let (machine_builder, cpu_address_space) = machine_builder.address_space(16);
         let (machine_builder, ppu_address_space) = machine_builder.address_space(14);
+        // Unmapped reads see whatever the last access left on the bus
+        let machine_builder = machine_builder.open_bus(cpu_address_space, OpenBusDecay::Hold);
 
         let program_specification = machine_builder.program_specification().unwrap();
         let filesystem = program_specification.info.filesystem();
@@ -197,6 +223,7 @@ impl<G: SupportedGraphicsApiPpu, P: Platform<GraphicsApi = G>> System<P> for Nes
             chr_ram_size: header.chr_ram_size,
             chr_nvram_size: header.chr_nvram_size,
             prg_ram_size: header.prg_ram_size,
+            prg_ram_battery_backed: header.non_volatile_memory,
             nametables,
         };
 
@@ -236,20 +263,33 @@ impl<G: SupportedGraphicsApiPpu, P: Platform<GraphicsApi = G>> System<P> for Nes
         }
         .unwrap_or(DefaultExpansionDevice::StandardControllers { swapped: false });
 
-        let machine_builder = match default_expansion_device {
+        let (machine_builder, zapper) = match default_expansion_device {
             DefaultExpansionDevice::StandardControllers { .. } => {
                 let (machine_builder, _) = machine_builder.component(
-                    "standard-nes-controllers",
-                    NesControllerConfig { cpu_address_space },
+                    CONTROLLERS,
+                    NesControllerConfig {
+                        cpu_address_space,
+                        controller_count: 2,
+                    },
+                );
+
+                (machine_builder, false)
+            }
+            DefaultExpansionDevice::Zapper => {
+                let (machine_builder, _) = machine_builder.component(
+                    CONTROLLERS,
+                    NesControllerConfig {
+                        cpu_address_space,
+                        controller_count: 1,
+                    },
                 );
 
-                machine_builder
+                (machine_builder, true)
             }
             DefaultExpansionDevice::FourScore => todo!(),
             DefaultExpansionDevice::SimpleFamiconFourPlayerAdaptor => todo!(),
             DefaultExpansionDevice::VsSystem { address: _ } => todo!(),
             DefaultExpansionDevice::VsZapper => todo!(),
-            DefaultExpansionDevice::Zapper => todo!(),
             DefaultExpansionDevice::DualZapper => todo!(),
             DefaultExpansionDevice::BandaiHyperShotLightgun => todo!(),
             DefaultExpansionDevice::PowerPad { upside: _ } => todo!(),
@@ -299,62 +339,241 @@ impl<G: SupportedGraphicsApiPpu, P: Platform<GraphicsApi = G>> System<P> for Nes
             DefaultExpansionDevice::泽诚Keyboard => todo!(),
         };
 
-        match header.timing_mode {
+        let machine_builder = match quirks.region.unwrap_or(header.timing_mode) {
             // FIXME: Implementing Multi as NTSC for now
             TimingMode::Ntsc | TimingMode::Multi => {
+                let (machine_builder, master_clock) =
+                    machine_builder.master_clock("master", Ntsc::master_clock());
+                let (machine_builder, cpu_clock) =
+                    machine_builder.derived_clock("cpu", master_clock, Ratio::new(1, 12));
+                let (machine_builder, ppu_clock) = machine_builder.derived_clock(
+                    "ppu",
+                    master_clock,
+                    Ratio::new(1, Ntsc::PPU_CLOCK_DIVISOR.into()),
+                );
+
                 let (machine_builder, processor) = machine_builder.component(
                     "cpu",
                     fluxemu_definition_mos6502::Config::<Ricoh2A0x>::new(
-                        Ntsc::master_clock() / 12,
+                        cpu_clock,
                         cpu_address_space,
                     ),
                 );
 
-                let (machine_builder, _) = machine_builder.component(
+                let (machine_builder, ppu) = machine_builder.component(
                     "ppu",
                     PpuConfig::<Ntsc> {
                         ppu_address_space,
                         cpu_address_space,
-                        processor,
+                        clock: ppu_clock,
+                        processor_clock: cpu_clock,
+                        _phantom: PhantomData,
+                    },
+                );
+                let (machine_builder, apu) = machine_builder.component(
+                    "apu",
+                    ApuConfig::<Ntsc> {
+                        cpu_address_space,
+                        processor_clock: cpu_clock,
                         _phantom: PhantomData,
                     },
                 );
 
-                let (machine_builder, _) =
-                    machine_builder.component("apu", ApuConfig { cpu_address_space });
-
-                machine_builder
+                let machine_builder = if zapper {
+                    machine_builder
+                        .component(
+                            ZAPPER,
+                            ZapperConfig::<Ntsc> {
+                                cpu_address_space,
+                                ppu: ppu.clone(),
+                                _phantom: PhantomData,
+                            },
+                        )
+                        .0
+                } else {
+                    machine_builder
+                };
+
+                connect_processor_lines(machine_builder, &processor, &ppu, &apu)
             }
             TimingMode::Pal => {
+                let (machine_builder, master_clock) =
+                    machine_builder.master_clock("master", Pal::master_clock());
+                let (machine_builder, cpu_clock) =
+                    machine_builder.derived_clock("cpu", master_clock, Ratio::new(1, 16));
+                let (machine_builder, ppu_clock) = machine_builder.derived_clock(
+                    "ppu",
+                    master_clock,
+                    Ratio::new(1, Pal::PPU_CLOCK_DIVISOR.into()),
+                );
+
                 let (machine_builder, processor) = machine_builder.component(
                     "cpu",
                     fluxemu_definition_mos6502::Config::<Ricoh2A0x>::new(
-                        Pal::master_clock() / 16,
+                        cpu_clock,
                         cpu_address_space,
                     ),
                 );
 
-                let (machine_builder, _) = machine_builder.component(
+                let (machine_builder, ppu) = machine_builder.component(
                     "ppu",
                     PpuConfig::<Pal> {
                         ppu_address_space,
                         cpu_address_space,
-                        processor,
+                        clock: ppu_clock,
+                        processor_clock: cpu_clock,
+                        _phantom: PhantomData,
+                    },
+                );
+                let (machine_builder, apu) = machine_builder.component(
+                    "apu",
+                    ApuConfig::<Pal> {
+                        cpu_address_space,
+                        processor_clock: cpu_clock,
                         _phantom: PhantomData,
                     },
                 );
 
-                let (machine_builder, _) =
-                    machine_builder.component("apu", ApuConfig { cpu_address_space });
-
-                machine_builder
+                let machine_builder = if zapper {
+                    machine_builder
+                        .component(
+                            ZAPPER,
+                            ZapperConfig::<Pal> {
+                                cpu_address_space,
+                                ppu: ppu.clone(),
+                                _phantom: PhantomData,
+                            },
+                        )
+                        .0
+                } else {
+                    machine_builder
+                };
+
+                connect_processor_lines(machine_builder, &processor, &ppu, &apu)
+            }
+            timing_mode @ TimingMode::Dendy => {
+                return Err(NesError::UnsupportedTiming(timing_mode).into());
             }
-            TimingMode::Dendy => todo!(),
+        };
+
+        Ok(machine_builder.seal())
+    }
+
+    /// Takes `"zapper"` to put a Zapper in the second controller port, and `"standard-controller"` to put a controller
+    /// back
+    fn plug_peripheral(
+        &self,
+        runtime_guard: &RuntimeGuard<'_>,
+        peripheral: &str,
+        graphics_initialization_data: <P::GraphicsApi as GraphicsApi>::InitializationData,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let zapper = match peripheral {
+            "zapper" => true,
+            "standard-controller" => false,
+            _ => return Err(format!("No peripheral named {peripheral:?}").into()),
+        };
+
+        let ppu = ComponentPath::new("ppu")?;
+
+        // The PPU knows the region the Zapper has to watch it in, and the processor's address space
+        let (pal, cpu_address_space) = runtime_guard
+            .component_registry()
+            .interact_dyn(&ppu, &runtime_guard.safe_advance_timestamp(), |ppu| {
+                let ppu = ppu as &mut dyn Any;
+
+                if let Some(ppu) = ppu.downcast_ref::<Ppu<Pal, G>>() {
+                    Some((true, ppu.cpu_address_space()))
+                } else {
+                    ppu.downcast_ref::<Ppu<Ntsc, G>>()
+                        .map(|ppu| (false, ppu.cpu_address_space()))
+                }
+            })
+            .flatten()
+            .ok_or("Machine has no PPU")?;
+
+        if pal {
+            swap_peripherals::<Pal, P>(
+                runtime_guard,
+                ppu,
+                cpu_address_space,
+                zapper,
+                graphics_initialization_data,
+            )
+        } else {
+            swap_peripherals::<Ntsc, P>(
+                runtime_guard,
+                ppu,
+                cpu_address_space,
+                zapper,
+                graphics_initialization_data,
+            )
         }
-        .seal()
     }
 }
 
+/// Put either a Zapper or a second controller into the second controller port, with a controller in the first
+///
+/// Everything is worked out before anything is unplugged, so only plugging the new components in could fail
+fn swap_peripherals<R: Region, P: Platform<GraphicsApi: SupportedGraphicsApiPpu>>(
+    runtime_guard: &RuntimeGuard<'_>,
+    ppu: ComponentPath,
+    cpu_address_space: AddressSpaceId,
+    zapper: bool,
+    graphics_initialization_data: <P::GraphicsApi as GraphicsApi>::InitializationData,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let controllers = ComponentPath::new(CONTROLLERS)?;
+    let zapper_path = ComponentPath::new(ZAPPER)?;
+    let controller_config = NesControllerConfig {
+        cpu_address_space,
+        controller_count: if zapper { 1 } else { 2 },
+    };
+    let zapper_config = zapper.then_some(ZapperConfig::<R> {
+        cpu_address_space,
+        ppu,
+        _phantom: PhantomData,
+    });
+
+    // Either may be missing, depending on what was plugged in before, and both paths are free afterwards
+    runtime_guard.unplug_component(&controllers);
+    runtime_guard.unplug_component(&zapper_path);
+
+    runtime_guard.plug_component::<P, _>(
+        controllers,
+        controller_config,
+        graphics_initialization_data.clone(),
+    )?;
+
+    if let Some(zapper_config) = zapper_config {
+        runtime_guard.plug_component::<P, _>(
+            zapper_path,
+            zapper_config,
+            graphics_initialization_data,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Wire the PPU and APU to the processor pins they pull on
+///
+/// None of the emulated mappers has a IRQ of its own, ones that do would join the APU on the IRQ line
+fn connect_processor_lines<P: Platform>(
+    machine: MachineBuilder<P>,
+    processor: &ComponentPath,
+    ppu: &ComponentPath,
+    apu: &ComponentPath,
+) -> MachineBuilder<P> {
+    [(ppu, "nmi"), (ppu, "rdy"), (apu, "irq")].into_iter().fold(
+        machine,
+        |machine, (source, pin)| {
+            machine.connect_signal(
+                source.clone().into_resource(pin).unwrap(),
+                processor.clone().into_resource(pin).unwrap(),
+            )
+        },
+    )
+}
+
 // Note that these are the *default* mapping for this particular cart
 //
 // The actual cart hardware is free to and often will immediately overwrite this
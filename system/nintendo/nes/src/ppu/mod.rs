@@ -1,20 +1,21 @@
 use std::{any::Any, marker::PhantomData, ops::RangeInclusive};
 
-use fluxemu_definition_mos6502::{Mos6502, Mos6502Event, Pin, variant::Ricoh2A0x};
 use fluxemu_graphics::api::software::texture::{AsViewTexture, OwnedTexture, Texture};
 use fluxemu_math::range::ContiguousRange;
 use fluxemu_runtime::{
     RuntimeHandle,
+    clock::Clock,
     component::{
-        Component,
+        Component, ResetKind,
         config::{ComponentConfig, LateContext},
     },
     event::{Event, EventMode, downcast_event},
     machine::builder::{ComponentBuilder, SchedulerParticipation},
     memory::{Address, AddressSpaceId, MemoryError, MemoryMapCommand, Permissions},
-    path::ComponentPath,
+    path::{ComponentPath, ResourcePath},
     platform::Platform,
     scheduler::{Period, SynchronizationContext},
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
 use nalgebra::Point2;
 use palette::Srgb;
@@ -23,9 +24,7 @@ use strum::FromRepr;
 
 use crate::ppu::{
     backend::{PpuDisplayBackend, SupportedGraphicsApiPpu},
-    background::{BackgroundPipelineState, BackgroundState, SpritePipelineState},
     color::{PPU_BLACK_INDEX, PpuColorIndex},
-    oam::{OamState, SpriteEvaluationState},
     region::Region,
     state::{State, VramAddressPointerContents},
 };
@@ -64,9 +63,13 @@ pub const SPRITE_PALETTE_BASE_ADDRESS: Address = 0x3f10;
 pub const PALETTE_RAM_ADDRESSES: RangeInclusive<Address> = BACKGROUND_PALETTE_BASE_ADDRESS..=0x3f1f;
 pub const ATTRIBUTE_BASE_ADDRESS: Address = NAMETABLE_BASE_ADDRESS + 0x3c0;
 const DUMMY_SCANLINE_COUNT: u16 = 2;
-const VISIBLE_SCANLINE_LENGTH: u16 = 256;
+pub const VISIBLE_SCANLINE_LENGTH: u16 = 256;
 const HBLANK_LENGTH: u16 = 85;
 const TOTAL_SCANLINE_LENGTH: u16 = VISIBLE_SCANLINE_LENGTH + HBLANK_LENGTH;
+/// Scanlines a drawn pixel keeps glowing for, as far as a light gun can tell
+const LIGHT_PERSISTENCE: u16 = 20;
+/// Luma, out of 255, a pixel needs for a light gun to see it
+const LIGHT_THRESHOLD: u32 = 128;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct ColorEmphasis {
@@ -81,7 +84,9 @@ pub struct ColorEmphasis {
 pub struct PpuConfig<R: Region> {
     pub cpu_address_space: AddressSpaceId,
     pub ppu_address_space: AddressSpaceId,
-    pub processor: ComponentPath,
+    pub clock: Clock,
+    /// Clock of the processor, which OAM DMA holds up for a number of its cycles
+    pub processor_clock: Clock,
     pub _phantom: PhantomData<R>,
 }
 
@@ -91,11 +96,15 @@ pub struct Ppu<R: Region, G: SupportedGraphicsApiPpu> {
     backend: Option<G::Backend<R>>,
     cpu_address_space: AddressSpaceId,
     ppu_address_space: AddressSpaceId,
-    processor_path: ComponentPath,
+    /// Drives the NMI line of the processor
+    nmi: ResourcePath,
+    /// Holds the processor during OAM DMA
+    rdy: ResourcePath,
     staging_buffer: OwnedTexture<PpuColorIndex>,
     palette: [Srgb<u8>; 64],
     path: ComponentPath,
-    period: Period,
+    clock: Clock,
+    processor_clock: Clock,
 }
 
 impl<R: Region, P: Platform<GraphicsApi: SupportedGraphicsApiPpu>> ComponentConfig<P>
@@ -114,11 +123,15 @@ impl<R: Region, P: Platform<GraphicsApi: SupportedGraphicsApiPpu>> ComponentConf
         self,
         component_builder: ComponentBuilder<P, Self::Component>,
     ) -> Result<Self::Component, Box<dyn std::error::Error>> {
-        let frequency = R::master_clock() / R::PPU_CLOCK_DIVISOR as u128;
-
         let (component_builder, _) = component_builder
             .scheduler_participation(Some(SchedulerParticipation::OnAccess))
             .framebuffer("framebuffer");
+        let component_builder = component_builder.refresh_rate(
+            self.clock.frequency()
+                / (u128::from(TOTAL_SCANLINE_LENGTH) * u128::from(R::TOTAL_SCANLINES)),
+        );
+        let (component_builder, nmi) = component_builder.signal_output("nmi", true);
+        let (component_builder, rdy) = component_builder.signal_output("rdy", true);
 
         let my_path = component_builder.path().clone();
 
@@ -165,12 +178,12 @@ impl<R: Region, P: Platform<GraphicsApi: SupportedGraphicsApiPpu>> ComponentConf
         );
 
         let time = TOTAL_SCANLINE_LENGTH as u32 * (R::VISIBLE_SCANLINES as u32 + 2);
-        let component_builder = component_builder
+        let (component_builder, _) = component_builder
             .map_memory(self.cpu_address_space, register_mappings)
             .schedule_event::<Self::Component>(
                 // x: 1, y: 241
                 &my_path,
-                Period::from_num(time) / frequency,
+                self.clock.cycles(time.into()),
                 EventMode::Once,
                 PpuEvent::VblankStart,
             );
@@ -182,63 +195,54 @@ impl<R: Region, P: Platform<GraphicsApi: SupportedGraphicsApiPpu>> ComponentConf
         );
 
         Ok(Ppu {
-            state: State {
-                vblank_nmi_enabled: false,
-                greyscale: false,
-                entered_vblank: false,
-                vram_address_pointer_write_phase: false,
-                vram_address_pointer_increment_amount: 1,
-                vram_read_buffer: 0,
-                color_emphasis: ColorEmphasis {
-                    red: false,
-                    green: false,
-                    blue: false,
-                },
-                cycle_counter: Point2::new(1, R::PRERENDER_SCANLINE),
-                background_pipeline_state: BackgroundPipelineState::FetchingNametable,
-                sprite_pipeline_state: SpritePipelineState::FetchingNametableGarbage0,
-                oam: OamState {
-                    data: rand::random(),
-                    oam_addr: 0x00,
-                    sprite_evaluation_state: SpriteEvaluationState::InspectingY,
-                    secondary_data: heapless::Vec::new(),
-                    sprite_zero_in_secondary: false,
-                    currently_rendering_sprites: heapless::Vec::new(),
-                    show_leftmost_pixels: true,
-                    sprite_8x8_pattern_table_index: 0x0000,
-                    rendering_enabled: false,
-                    awaiting_memory_access: true,
-                    sprite_zero_hit: false,
-                    sprite_8x16_mode: false,
-                    overflow: false,
-                },
-                background: BackgroundState {
-                    pattern_table_index: 0x0000,
-                    pattern_low_shift: 0,
-                    pattern_high_shift: 0,
-                    attribute_shift: 0,
-                    fine_x_scroll: 0,
-                    rendering_enabled: false,
-                    awaiting_memory_access: true,
-                    tile_pixel: 0,
-                    show_leftmost_pixels: true,
-                },
-                vram_address_pointer: 0,
-                shadow_vram_address_pointer: 0,
-                odd_frame: false,
-            },
+            state: State::power_on::<R>(),
             backend: None,
             staging_buffer,
             cpu_address_space: self.cpu_address_space,
-            processor_path: self.processor.clone(),
+            nmi,
+            rdy,
             ppu_address_space: self.ppu_address_space,
             palette: R::generate_palette(),
             path: component_builder.path().clone(),
-            period: frequency.recip(),
+            clock: self.clock,
+            processor_clock: self.processor_clock,
         })
     }
 }
 
+impl<R: Region, G: SupportedGraphicsApiPpu> Ppu<R, G> {
+    /// Address space of the processor the PPU maps its registers into
+    pub fn cpu_address_space(&self) -> AddressSpaceId {
+        self.cpu_address_space
+    }
+
+    /// If the pixel at `position` is bright, and the beam passed it recently enough, for a light gun aimed there to see
+    pub fn is_lit(&self, position: Point2<u16>) -> bool {
+        let beam = self.state.cycle_counter;
+
+        if position.x >= VISIBLE_SCANLINE_LENGTH || position.y >= R::VISIBLE_SCANLINES {
+            return false;
+        }
+
+        // Whatever was there last frame has faded by the time the beam comes around again
+        let drawn = beam.y > position.y || (beam.y == position.y && beam.x > position.x);
+        if !drawn || beam.y - position.y >= LIGHT_PERSISTENCE {
+            return false;
+        }
+
+        let color_index =
+            self.staging_buffer[Point2::new(usize::from(position.x), usize::from(position.y))];
+        let color = self.palette[usize::from(color_index & 0b0011_1111)];
+
+        let luma = (299 * u32::from(color.red)
+            + 587 * u32::from(color.green)
+            + 114 * u32::from(color.blue))
+            / 1000;
+
+        luma >= LIGHT_THRESHOLD
+    }
+}
+
 impl<R: Region, G: SupportedGraphicsApiPpu> Component for Ppu<R, G> {
     type Event = PpuEvent;
 
@@ -454,31 +458,16 @@ impl<R: Region, G: SupportedGraphicsApiPpu> Component for Ppu<R, G> {
 
                         let page = u16::from(*buffer) << 8;
 
-                        runtime.schedule_event::<Mos6502<Ricoh2A0x>>(
-                            &self.processor_path,
-                            EventMode::Once,
-                            timestamp,
-                            Mos6502Event::FlagChange {
-                                pin: Pin::Rdy,
-                                value: false,
-                            },
-                        );
+                        runtime.drive_signal(&self.rdy, timestamp, false);
 
-                        // TODO: Extract to constant or extract from cpu directly within the config builder
-                        let processor_frequency = R::master_clock() / 12;
-
-                        let next_processor_rdy_high =
-                            timestamp + (processor_frequency.recip() * 514);
+                        let next_processor_rdy_high = timestamp + self.processor_clock.cycles(514);
 
                         // Make sure the cpu wakes up
-                        runtime.schedule_event::<Mos6502<Ricoh2A0x>>(
-                            &self.processor_path,
+                        runtime.schedule_event::<Self>(
+                            &self.path,
                             EventMode::Once,
                             next_processor_rdy_high,
-                            Mos6502Event::FlagChange {
-                                pin: Pin::Rdy,
-                                value: true,
-                            },
+                            PpuEvent::OamDmaEnd,
                         );
 
                         // Read off OAM data immediately, this is done for performance and should not
@@ -512,24 +501,15 @@ impl<R: Region, G: SupportedGraphicsApiPpu> Component for Ppu<R, G> {
                     self.state.entered_vblank = true;
 
                     if self.state.vblank_nmi_enabled {
-                        runtime.schedule_event::<Mos6502<Ricoh2A0x>>(
-                            &self.processor_path,
-                            EventMode::Once,
-                            timestamp,
-                            Mos6502Event::FlagChange {
-                                pin: Pin::Nmi,
-                                value: false,
-                            },
-                        );
+                        runtime.drive_signal(&self.nmi, timestamp, false);
                     }
 
-                    let vblank_len =
-                        Period::from_num(TOTAL_SCANLINE_LENGTH as u128 * R::VBLANK_LENGTH as u128);
+                    let vblank_len = u64::from(TOTAL_SCANLINE_LENGTH) * u64::from(R::VBLANK_LENGTH);
 
                     runtime.schedule_event::<Self>(
                         &self.path,
                         EventMode::Once,
-                        timestamp + self.period * vblank_len,
+                        timestamp + self.clock.cycles(vblank_len),
                         PpuEvent::VblankEnd,
                     );
                 }
@@ -537,15 +517,7 @@ impl<R: Region, G: SupportedGraphicsApiPpu> Component for Ppu<R, G> {
                     self.state.entered_vblank = false;
                     self.state.odd_frame = !self.state.odd_frame;
 
-                    runtime.schedule_event::<Mos6502<Ricoh2A0x>>(
-                        &self.processor_path,
-                        EventMode::Once,
-                        timestamp,
-                        Mos6502Event::FlagChange {
-                            pin: Pin::Nmi,
-                            value: true,
-                        },
-                    );
+                    runtime.drive_signal(&self.nmi, timestamp, true);
 
                     self.backend
                         .as_mut()
@@ -554,7 +526,7 @@ impl<R: Region, G: SupportedGraphicsApiPpu> Component for Ppu<R, G> {
 
                     let lines_until_next_vblank = R::TOTAL_SCANLINES - R::VBLANK_LENGTH;
                     let mut cycles_until_next_vblank =
-                        TOTAL_SCANLINE_LENGTH as u128 * lines_until_next_vblank as u128;
+                        u64::from(TOTAL_SCANLINE_LENGTH) * u64::from(lines_until_next_vblank);
 
                     // This only occurs on NTSC and Dendy
                     if R::SKIPS_DOT_ON_ODD_FRAME
@@ -565,7 +537,7 @@ impl<R: Region, G: SupportedGraphicsApiPpu> Component for Ppu<R, G> {
                         cycles_until_next_vblank -= 1;
                     }
 
-                    let next_timestamp = timestamp + self.period * cycles_until_next_vblank;
+                    let next_timestamp = timestamp + self.clock.cycles(cycles_until_next_vblank);
                     runtime.schedule_event::<Self>(
                         &self.path,
                         EventMode::Once,
@@ -573,6 +545,9 @@ impl<R: Region, G: SupportedGraphicsApiPpu> Component for Ppu<R, G> {
                         PpuEvent::VblankStart,
                     );
                 }
+                PpuEvent::OamDmaEnd => {
+                    runtime.drive_signal(&self.rdy, timestamp, true);
+                }
             }
         });
     }
@@ -581,8 +556,8 @@ impl<R: Region, G: SupportedGraphicsApiPpu> Component for Ppu<R, G> {
         let runtime = context.runtime();
         let mut ppu_address_space = runtime.address_space(self.ppu_address_space).unwrap();
 
-        let mut quanta_iterator = context.quanta_allocator(self.period);
-        while let Some(timestamp) = quanta_iterator.allocate() {
+        let mut cycle_allocator = context.cycle_allocator(self.clock);
+        while let Some(timestamp) = cycle_allocator.allocate() {
             if (0..R::VISIBLE_SCANLINES).contains(&self.state.cycle_counter.y) {
                 self.handle_visible_scanlines(&mut ppu_address_space, timestamp);
             } else if self.state.cycle_counter.y == R::PRERENDER_SCANLINE {
@@ -611,16 +586,58 @@ impl<R: Region, G: SupportedGraphicsApiPpu> Component for Ppu<R, G> {
     }
 
     fn needs_work(&self, _timestamp: &Period, delta: &Period) -> bool {
-        delta >= &self.period
+        *delta >= self.clock.period()
     }
 
     fn get_framebuffer(&mut self, _name: &str) -> &dyn Any {
         self.backend.as_mut().unwrap().framebuffer()
     }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.state))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        self.state = snapshot.decode()?;
+
+        Ok(())
+    }
+
+    fn reset(&mut self, kind: ResetKind) {
+        match kind {
+            // Like writing zero to PPUCTRL, PPUMASK and PPUSCROLL, while the frame carries on where it was
+            ResetKind::Soft => {
+                self.state.vblank_nmi_enabled = false;
+                self.state.greyscale = false;
+                self.state.vram_address_pointer_write_phase = false;
+                self.state.vram_address_pointer_increment_amount = 1;
+                self.state.vram_read_buffer = 0;
+                self.state.color_emphasis = ColorEmphasis {
+                    red: false,
+                    green: false,
+                    blue: false,
+                };
+                self.state.shadow_vram_address_pointer = 0;
+                self.state.odd_frame = false;
+
+                self.state.background.pattern_table_index = 0;
+                self.state.background.fine_x_scroll = 0;
+                self.state.background.rendering_enabled = false;
+                self.state.background.show_leftmost_pixels = false;
+
+                self.state.oam.sprite_8x8_pattern_table_index = 0;
+                self.state.oam.sprite_8x16_mode = false;
+                self.state.oam.rendering_enabled = false;
+                self.state.oam.show_leftmost_pixels = false;
+            }
+            ResetKind::Hard => self.state = State::power_on::<R>(),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PpuEvent {
     VblankStart,
     VblankEnd,
+    OamDmaEnd,
 }
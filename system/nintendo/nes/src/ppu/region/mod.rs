@@ -1,11 +1,10 @@
 use std::fmt::Debug;
 
-use fluxemu_runtime::scheduler::Frequency;
+use num::rational::Ratio;
 use palette::Srgb;
 
 use crate::ppu::DUMMY_SCANLINE_COUNT;
 
-pub mod dendy;
 pub mod ntsc;
 pub mod pal;
 
@@ -20,7 +19,12 @@ pub trait Region: Send + Sync + Debug + 'static {
     const PRERENDER_SCANLINE: u16 = Self::TOTAL_SCANLINES - 1;
     const SKIPS_DOT_ON_ODD_FRAME: bool;
     const PPU_CLOCK_DIVISOR: u8;
+    /// Processor cycles between frame interrupts of the APU frame counter in four step mode
+    const APU_FRAME_LENGTH: u16;
+    /// Processor cycles the DMC spends on each bit of a sample, for each rate index
+    const DMC_RATES: [u16; 16];
 
-    fn master_clock() -> Frequency;
+    /// Frequency of the master oscillator in hertz, exactly
+    fn master_clock() -> Ratio<u64>;
     fn generate_palette() -> [Srgb<u8>; 64];
 }
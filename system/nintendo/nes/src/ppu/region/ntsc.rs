@@ -1,8 +1,8 @@
 use std::f32::consts::TAU;
 
 use fluxemu_math::color::YIQ_TO_RGB_NTSC_1953;
-use fluxemu_runtime::scheduler::Frequency;
 use nalgebra::{Rotation, SMatrix};
+use num::rational::Ratio;
 use palette::Srgb;
 
 use crate::ppu::region::composite::{CompositeParams, build_palette};
@@ -18,11 +18,15 @@ impl Region for Ntsc {
     const VISIBLE_SCANLINES: u16 = 240;
     const SKIPS_DOT_ON_ODD_FRAME: bool = true;
     const PPU_CLOCK_DIVISOR: u8 = 4;
+    const APU_FRAME_LENGTH: u16 = 29830;
+    const DMC_RATES: [u16; 16] = [
+        428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+    ];
 
     #[inline]
-    fn master_clock() -> Frequency {
+    fn master_clock() -> Ratio<u64> {
         // 236.25 MHz / 11
-        Frequency::from_num(236250000) / 11
+        Ratio::new(236250000, 11)
     }
 
     #[inline]
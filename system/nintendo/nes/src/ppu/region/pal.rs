@@ -1,8 +1,8 @@
 use std::f32::consts::TAU;
 
 use fluxemu_math::color::YUV_TO_RGB_SDTV_WITH_BT470;
-use fluxemu_runtime::scheduler::Frequency;
 use nalgebra::Rotation;
+use num::rational::Ratio;
 use palette::Srgb;
 
 use crate::ppu::region::composite::{CompositeParams, build_palette};
@@ -18,10 +18,14 @@ impl Region for Pal {
     const VISIBLE_SCANLINES: u16 = 240;
     const SKIPS_DOT_ON_ODD_FRAME: bool = false;
     const PPU_CLOCK_DIVISOR: u8 = 5;
+    const APU_FRAME_LENGTH: u16 = 33254;
+    const DMC_RATES: [u16; 16] = [
+        398, 354, 316, 298, 276, 236, 210, 198, 176, 148, 132, 118, 98, 78, 66, 50,
+    ];
 
-    fn master_clock() -> Frequency {
+    fn master_clock() -> Ratio<u64> {
         // ~53.203425 MHZ / 2
-        Frequency::from_num(53203425) / 2
+        Ratio::new(53203425, 2)
     }
 
     fn generate_palette() -> [Srgb<u8>; 64] {
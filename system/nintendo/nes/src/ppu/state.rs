@@ -3,9 +3,10 @@ use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
 use crate::ppu::{
-    BackgroundPipelineState, ColorEmphasis,
-    background::{BackgroundState, SpritePipelineState},
-    oam::OamState,
+    ColorEmphasis,
+    background::{BackgroundPipelineState, BackgroundState, SpritePipelineState},
+    oam::{OamState, SpriteEvaluationState},
+    region::Region,
 };
 
 #[serde_as]
@@ -31,6 +32,57 @@ pub struct State {
     pub odd_frame: bool,
 }
 
+impl State {
+    /// State at power on, with OAM holding garbage
+    pub fn power_on<R: Region>() -> Self {
+        Self {
+            vblank_nmi_enabled: false,
+            greyscale: false,
+            entered_vblank: false,
+            vram_address_pointer_write_phase: false,
+            vram_address_pointer_increment_amount: 1,
+            vram_read_buffer: 0,
+            color_emphasis: ColorEmphasis {
+                red: false,
+                green: false,
+                blue: false,
+            },
+            cycle_counter: Point2::new(1, R::PRERENDER_SCANLINE),
+            background_pipeline_state: BackgroundPipelineState::FetchingNametable,
+            sprite_pipeline_state: SpritePipelineState::FetchingNametableGarbage0,
+            oam: OamState {
+                data: rand::random(),
+                oam_addr: 0x00,
+                sprite_evaluation_state: SpriteEvaluationState::InspectingY,
+                secondary_data: heapless::Vec::new(),
+                sprite_zero_in_secondary: false,
+                currently_rendering_sprites: heapless::Vec::new(),
+                show_leftmost_pixels: true,
+                sprite_8x8_pattern_table_index: 0x0000,
+                rendering_enabled: false,
+                awaiting_memory_access: true,
+                sprite_zero_hit: false,
+                sprite_8x16_mode: false,
+                overflow: false,
+            },
+            background: BackgroundState {
+                pattern_table_index: 0x0000,
+                pattern_low_shift: 0,
+                pattern_high_shift: 0,
+                attribute_shift: 0,
+                fine_x_scroll: 0,
+                rendering_enabled: false,
+                awaiting_memory_access: true,
+                tile_pixel: 0,
+                show_leftmost_pixels: true,
+            },
+            vram_address_pointer: 0,
+            shadow_vram_address_pointer: 0,
+            odd_frame: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct VramAddressPointerContents {
     pub fine_y: u8,
@@ -1,24 +1,35 @@
 use fluxemu_audio::{SampleFormat, SquareWave};
 use fluxemu_runtime::{
+    clock::Clock,
     component::{Component, SampleSource, config::ComponentConfig},
     machine::builder::{ComponentBuilder, SchedulerParticipation},
     platform::Platform,
-    scheduler::{Frequency, Period, SynchronizationContext},
+    scheduler::{Period, SynchronizationContext},
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
 use nalgebra::SVector;
 use ringbuffer::{AllocRingBuffer, RingBuffer};
+use serde::{Deserialize, Serialize};
 
 /// Imaginary chip8 hardware sample rate
 const INTERNAL_SAMPLE_RATE: f32 = 1760.0;
 
+/// Cycles of the audio clock per tick of the 60 Hz sound timer
+const TIMER_DIVIDER: u64 = 10;
+
 #[derive(Debug)]
 pub struct Chip8Audio {
     // The CPU will set this according to what the program wants
     timer: u8,
     buffer: AllocRingBuffer<SVector<f32, 1>>,
     wave_generator: SquareWave<f32, 1>,
-    processor_frequency: Frequency,
-    timer_accumulator: Period,
+    clock: Clock,
+    audio_accumulator: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    timer: u8,
     audio_accumulator: f32,
 }
 
@@ -39,11 +50,10 @@ impl Component for Chip8Audio {
     }
 
     fn synchronize(&mut self, mut context: SynchronizationContext) {
-        let timer_period = Period::from_num(60).recip();
-        let samples_per_tick = INTERNAL_SAMPLE_RATE / self.processor_frequency.to_num::<f32>();
+        let samples_per_tick = INTERNAL_SAMPLE_RATE / self.clock.frequency().to_num::<f32>();
 
-        let mut quanta_iterator = context.quanta_allocator(self.processor_frequency.recip());
-        while quanta_iterator.allocate().is_some() {
+        let mut cycle_allocator = context.cycle_allocator(self.clock);
+        while cycle_allocator.allocate().is_some() {
             self.audio_accumulator += samples_per_tick;
 
             while self.audio_accumulator >= 1.0 {
@@ -59,23 +69,37 @@ impl Component for Chip8Audio {
                 self.audio_accumulator -= 1.0;
             }
 
-            self.timer_accumulator += self.processor_frequency.recip();
-
-            while self.timer_accumulator >= timer_period {
+            if cycle_allocator.cycle() % TIMER_DIVIDER == 0 {
                 self.timer = self.timer.saturating_sub(1);
-                self.timer_accumulator -= timer_period;
             }
         }
     }
 
     fn needs_work(&self, _timestamp: &Period, delta: &Period) -> bool {
-        *delta >= self.processor_frequency.recip()
+        *delta >= self.clock.period()
+    }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&Snapshot {
+            timer: self.timer,
+            audio_accumulator: self.audio_accumulator,
+        }))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        let snapshot: Snapshot = snapshot.decode()?;
+
+        self.timer = snapshot.timer;
+        self.audio_accumulator = snapshot.audio_accumulator;
+
+        Ok(())
     }
 }
 
 #[derive(Debug)]
 pub struct Chip8AudioConfig {
-    pub processor_frequency: Frequency,
+    /// Ten times the rate of the sound timer
+    pub clock: Clock,
 }
 
 impl<P: Platform> ComponentConfig<P> for Chip8AudioConfig {
@@ -93,8 +117,7 @@ impl<P: Platform> ComponentConfig<P> for Chip8AudioConfig {
             timer: 0,
             buffer: AllocRingBuffer::new(INTERNAL_SAMPLE_RATE as _),
             wave_generator: SquareWave::new(440.0, INTERNAL_SAMPLE_RATE, 0.5),
-            processor_frequency: self.processor_frequency,
-            timer_accumulator: Period::ZERO,
+            clock: self.clock,
             audio_accumulator: 0.0,
         })
     }
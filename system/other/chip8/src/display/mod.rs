@@ -5,6 +5,7 @@ use fluxemu_graphics::api::{
     software::texture::{CopyMode, OwnedTexture, Texture},
 };
 use fluxemu_runtime::{
+    clock::Clock,
     component::{
         Component,
         config::{ComponentConfig, LateContext},
@@ -12,6 +13,7 @@ use fluxemu_runtime::{
     machine::builder::{ComponentBuilder, SchedulerParticipation},
     platform::Platform,
     scheduler::{Period, SynchronizationContext},
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
 use nalgebra::{Point2, Vector2};
 use palette::{
@@ -166,8 +168,8 @@ impl<G: SupportedGraphicsApiChip8Display> Component for Chip8Display<G> {
     fn synchronize(&mut self, mut context: SynchronizationContext) {
         let mut commit_staging_buffer = false;
 
-        let mut quanta_iterator = context.quanta_allocator(Period::ONE / 60);
-        while quanta_iterator.allocate().is_some() {
+        let mut cycle_allocator = context.cycle_allocator(self.config.clock);
+        while cycle_allocator.allocate().is_some() {
             self.vsync_occurred = true;
 
             commit_staging_buffer = true;
@@ -182,12 +184,30 @@ impl<G: SupportedGraphicsApiChip8Display> Component for Chip8Display<G> {
     }
 
     fn needs_work(&self, _timestamp: &Period, delta: &Period) -> bool {
-        *delta >= Period::ONE / 60
+        *delta >= self.config.clock.period()
     }
 
     fn get_framebuffer(&mut self, _name: &str) -> &dyn Any {
         self.backend.as_mut().unwrap().framebuffer()
     }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&Snapshot {
+            screen_buffer: self.staging_buffer.clone(),
+            vsync_occurred: self.vsync_occurred,
+            hires: self.hires,
+        }))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        let snapshot: Snapshot = snapshot.decode()?;
+
+        self.staging_buffer = snapshot.screen_buffer;
+        self.vsync_occurred = snapshot.vsync_occurred;
+        self.hires = snapshot.hires;
+
+        Ok(())
+    }
 }
 
 pub(crate) trait Chip8DisplayBackend: Send + Sync + Debug + 'static {
@@ -198,8 +218,10 @@ pub(crate) trait Chip8DisplayBackend: Send + Sync + Debug + 'static {
     fn commit_staging_buffer(&mut self, staging_buffer: &OwnedTexture<Srgba<u8>>);
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Chip8DisplayConfig {
+    /// Ticks once per vertical blank
+    pub clock: Clock,
     pub clear_on_resolution_change: bool,
 }
 
@@ -219,9 +241,10 @@ impl<P: Platform<GraphicsApi: SupportedGraphicsApiChip8Display>> ComponentConfig
         self,
         component_builder: ComponentBuilder<P, Self::Component>,
     ) -> Result<Self::Component, Box<dyn std::error::Error>> {
-        component_builder
+        let (component_builder, _) = component_builder
             .scheduler_participation(Some(SchedulerParticipation::OnAccess))
             .framebuffer("framebuffer");
+        component_builder.refresh_rate(self.clock.frequency());
 
         Ok(Chip8Display {
             backend: None,
@@ -7,12 +7,14 @@ use fluxemu_math::range::ContiguousRange;
 use fluxemu_program::{OtherSystem, SystemId};
 use fluxemu_runtime::{
     machine::builder::{MachineBuilder, RomRequirement, SealedMachineBuilder},
-    memory::{MapTarget, MemoryMapCommand, Permissions},
+    memory::{
+        BusWidth, DataBus, Endianness, MapTarget, MemoryMapCommand, MisalignedAccess, Permissions,
+    },
     platform::Platform,
-    scheduler::Frequency,
 };
 use fluxemu_system::System;
 use font::CHIP8_FONT;
+use num::rational::Ratio;
 use processor::Chip8ProcessorConfig;
 use serde::{Deserialize, Serialize};
 use timer::Chip8TimerConfig;
@@ -35,30 +37,72 @@ pub enum Chip8Mode {
     XoChip,
 }
 
+/// Behaviours that differ between CHIP-8 interpreters, which programs written for one of them tend to rely on
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[serde(default)]
+pub struct Chip8Quirks {
+    /// Start in this mode instead of the original CHIP-8
+    pub mode: Option<Chip8Mode>,
+    /// Whether 8XY6 and 8XYE shift VY into VX instead of shifting VX in place, following the mode if unset
+    pub shift_reads_vy: Option<bool>,
+    /// Whether FX55 and FX65 leave I pointing past the last register, following the mode if unset
+    pub load_store_increments_index: Option<bool>,
+    /// Hold the processor after a draw until the next vertical blank
+    pub stall_on_draw_until_vsync: bool,
+}
+
+impl Chip8Quirks {
+    pub(crate) fn reads_vy_on_shift(&self, mode: Chip8Mode) -> bool {
+        self.shift_reads_vy.unwrap_or(mode == Chip8Mode::Chip8)
+    }
+
+    pub(crate) fn advances_index(&self, mode: Chip8Mode) -> bool {
+        self.load_store_increments_index
+            .unwrap_or(mode == Chip8Mode::Chip8)
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Chip8;
 
 impl<P: Platform<GraphicsApi: SupportedGraphicsApiChip8Display>> System<P> for Chip8 {
-    type Quirks = ();
+    type Quirks = Chip8Quirks;
 
     const ID: SystemId = SystemId::Other(OtherSystem::Chip8);
 
     fn build(
         &self,
-        _quirks: Self::Quirks,
+        quirks: Self::Quirks,
         machine_builder: MachineBuilder<P>,
-    ) -> SealedMachineBuilder<P> {
+    ) -> Result<SealedMachineBuilder<P>, Box<dyn std::error::Error + Send + Sync>> {
         let (machine_builder, cpu_address_space) = machine_builder.address_space(12);
+        // Instructions are stored big endian
+        let machine_builder = machine_builder.data_bus(
+            cpu_address_space,
+            DataBus {
+                width: BusWidth::Bits8,
+                endianness: Endianness::Big,
+                misaligned: MisalignedAccess::Split,
+            },
+        );
+        // The delay and sound timers count down at 60 Hz, which is also when the display refreshes
+        let (machine_builder, timer_clock) =
+            machine_builder.master_clock("timer", Ratio::from_integer(60));
+        let (machine_builder, audio_clock) =
+            machine_builder.derived_clock("audio", timer_clock, Ratio::from_integer(10));
+        let (machine_builder, processor_clock) =
+            machine_builder.master_clock("cpu", Ratio::from_integer(700));
         let (machine_builder, timer) =
-            machine_builder.default_component::<Chip8TimerConfig>("timer");
-        let (machine_builder, audio) = machine_builder.component(
-            "audio",
-            Chip8AudioConfig {
-                processor_frequency: Frequency::from_num(600),
+            machine_builder.component("timer", Chip8TimerConfig { clock: timer_clock });
+        let (machine_builder, audio) =
+            machine_builder.component("audio", Chip8AudioConfig { clock: audio_clock });
+        let (machine_builder, display) = machine_builder.component(
+            "display",
+            Chip8DisplayConfig {
+                clock: timer_clock,
+                clear_on_resolution_change: false,
             },
         );
-        let (machine_builder, display) =
-            machine_builder.default_component::<Chip8DisplayConfig>("display");
         let (machine_builder, _) = machine_builder.component(
             "cpu",
             Chip8ProcessorConfig {
@@ -66,10 +110,8 @@ impl<P: Platform<GraphicsApi: SupportedGraphicsApiChip8Display>> System<P> for C
                 timer,
                 audio,
                 display,
-                frequency: Frequency::from_num(700),
-                force_mode: None,
-                always_shr_in_place: false,
-                stall_on_draw_until_vsync: false,
+                clock: processor_clock,
+                quirks,
                 _phantom: PhantomData,
             },
         );
@@ -108,18 +150,18 @@ impl<P: Platform<GraphicsApi: SupportedGraphicsApiChip8Display>> System<P> for C
             ],
         );
 
-        machine_builder
-            .map_memory(
-                cpu_address_space,
-                [MemoryMapCommand::Map {
-                    range: 0x000..=0xfff,
-                    permissions: Permissions::ALL,
-                    target: MapTarget::Memory {
-                        path: ram_path,
-                        subrange: None,
-                    },
-                }],
-            )
-            .seal()
+        let machine_builder = machine_builder.map_memory(
+            cpu_address_space,
+            [MemoryMapCommand::Map {
+                range: 0x000..=0xfff,
+                permissions: Permissions::ALL,
+                target: MapTarget::Memory {
+                    path: ram_path,
+                    subrange: None,
+                },
+            }],
+        );
+
+        Ok(machine_builder.seal())
     }
 }
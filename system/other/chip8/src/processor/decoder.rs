@@ -4,9 +4,7 @@ use super::instruction::{
     Chip8InstructionSet, InstructionSetChip8, InstructionSetSuperChip8, Register, ScrollDirection,
 };
 
-pub(super) fn decode_instruction(instruction: [u8; 2]) -> Option<Chip8InstructionSet> {
-    let instruction = u16::from_be_bytes(instruction);
-
+pub(super) fn decode_instruction(instruction: u16) -> Option<Chip8InstructionSet> {
     let get_nibble = |n: u8| -> u8 { ((instruction >> (12 - n * 4)) & 0xf) as u8 };
 
     let nnn = instruction & 0x0fff;
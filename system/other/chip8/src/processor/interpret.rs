@@ -160,7 +160,7 @@ impl<G: SupportedGraphicsApiChip8Display> Chip8Processor<G> {
             Chip8InstructionSet::Chip8(InstructionSetChip8::Shr { register, value }) => {
                 let mut destination_value = self.state.registers.work_registers[register as usize];
 
-                if *mode_guard == Chip8Mode::Chip8 || self.config.always_shr_in_place {
+                if self.config.quirks.reads_vy_on_shift(*mode_guard) {
                     destination_value = self.state.registers.work_registers[value as usize];
                 }
                 let overflow = destination_value & 0b0000_0001;
@@ -183,7 +183,7 @@ impl<G: SupportedGraphicsApiChip8Display> Chip8Processor<G> {
             Chip8InstructionSet::Chip8(InstructionSetChip8::Shl { register, value }) => {
                 let mut destination_value = self.state.registers.work_registers[register as usize];
 
-                if *mode_guard == Chip8Mode::Chip8 {
+                if self.config.quirks.reads_vy_on_shift(*mode_guard) {
                     destination_value = self.state.registers.work_registers[value as usize];
                 }
 
@@ -283,7 +283,7 @@ impl<G: SupportedGraphicsApiChip8Display> Chip8Processor<G> {
                         .unwrap();
                 }
 
-                if self.config.stall_on_draw_until_vsync {
+                if self.config.quirks.stall_on_draw_until_vsync {
                     self.state.execution_state = ExecutionState::AwaitingVsync;
                 }
             }
@@ -375,8 +375,8 @@ impl<G: SupportedGraphicsApiChip8Display> Chip8Processor<G> {
                         .unwrap();
                 }
 
-                // Only the original chip8 modifies the index register for this operation
-                if *mode_guard == Chip8Mode::Chip8 {
+                // By default only the original chip8 modifies the index register for this operation
+                if self.config.quirks.advances_index(*mode_guard) {
                     self.state.registers.index = self
                         .state
                         .registers
@@ -395,8 +395,8 @@ impl<G: SupportedGraphicsApiChip8Display> Chip8Processor<G> {
                         .unwrap();
                 }
 
-                // Only the original chip8 modifies the index register for this operation
-                if *mode_guard == Chip8Mode::Chip8 {
+                // By default only the original chip8 modifies the index register for this operation
+                if self.config.quirks.advances_index(*mode_guard) {
                     self.state.registers.index = self
                         .state
                         .registers
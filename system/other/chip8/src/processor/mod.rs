@@ -4,19 +4,21 @@ use std::{
 };
 
 use fluxemu_runtime::{
+    clock::Clock,
     component::{Component, config::ComponentConfig},
     input::LogicalInputDevice,
     machine::builder::{ComponentBuilder, SchedulerParticipation},
     memory::AddressSpaceId,
     path::ComponentPath,
     platform::Platform,
-    scheduler::{Frequency, Period, SynchronizationContext},
+    scheduler::{Period, SynchronizationContext},
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
 use input::Chip8KeyCode;
 use instruction::Register;
 use serde::{Deserialize, Serialize};
 
-use super::Chip8Mode;
+use super::{Chip8Mode, Chip8Quirks};
 use crate::{
     display::{Chip8Display, SupportedGraphicsApiChip8Display},
     processor::{
@@ -94,6 +96,7 @@ pub struct Chip8ProcessorSnapshot {
     registers: Chip8ProcessorRegisters,
     stack: heapless::Vec<u16, 16>,
     execution_state: ExecutionState,
+    mode: Chip8Mode,
 }
 
 impl<G: SupportedGraphicsApiChip8Display> Component for Chip8Processor<G> {
@@ -106,19 +109,13 @@ impl<G: SupportedGraphicsApiChip8Display> Component for Chip8Processor<G> {
             .address_space(self.config.cpu_address_space)
             .unwrap();
 
-        let mut quanta_iterator = context.quanta_allocator(self.config.frequency.recip());
-        while let Some(timestamp) = quanta_iterator.allocate() {
+        let mut cycle_allocator = context.cycle_allocator(self.config.clock);
+        while let Some(timestamp) = cycle_allocator.allocate() {
             'main: {
                 match &self.state.execution_state {
                     ExecutionState::Normal => {
-                        let mut instruction = [0; 2];
-
-                        address_space
-                            .read::<_, false>(
-                                self.state.registers.program as usize,
-                                timestamp,
-                                &mut instruction,
-                            )
+                        let instruction = address_space
+                            .fetch_value(self.state.registers.program as usize, timestamp)
                             .unwrap();
 
                         let instruction =
@@ -196,7 +193,29 @@ impl<G: SupportedGraphicsApiChip8Display> Component for Chip8Processor<G> {
     }
 
     fn needs_work(&self, _timestamp: &Period, delta: &Period) -> bool {
-        *delta >= self.config.frequency.recip()
+        *delta >= self.config.clock.period()
+    }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&Chip8ProcessorSnapshot {
+            registers: self.state.registers.clone(),
+            stack: self.state.stack.clone(),
+            execution_state: self.state.execution_state.clone(),
+            mode: *self.mode.lock().unwrap(),
+        }))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        let snapshot: Chip8ProcessorSnapshot = snapshot.decode()?;
+
+        self.state = ProcessorState {
+            registers: snapshot.registers,
+            stack: snapshot.stack,
+            execution_state: snapshot.execution_state,
+        };
+        *self.mode.lock().unwrap() = snapshot.mode;
+
+        Ok(())
     }
 }
 
@@ -206,10 +225,8 @@ pub struct Chip8ProcessorConfig<G: SupportedGraphicsApiChip8Display> {
     pub display: ComponentPath,
     pub audio: ComponentPath,
     pub timer: ComponentPath,
-    pub frequency: Frequency,
-    pub force_mode: Option<Chip8Mode>,
-    pub always_shr_in_place: bool,
-    pub stall_on_draw_until_vsync: bool,
+    pub clock: Clock,
+    pub quirks: Chip8Quirks,
     pub _phantom: PhantomData<fn() -> G>,
 }
 
@@ -222,11 +239,12 @@ impl<P: Platform<GraphicsApi: SupportedGraphicsApiChip8Display>> ComponentConfig
         self,
         component_builder: ComponentBuilder<P, Self::Component>,
     ) -> Result<Self::Component, Box<dyn std::error::Error>> {
-        let mode = Arc::new(Mutex::new(self.force_mode.unwrap_or(Chip8Mode::Chip8)));
+        let mode = Arc::new(Mutex::new(self.quirks.mode.unwrap_or(Chip8Mode::Chip8)));
         let state = ProcessorState::default();
 
         let (_component_builder, keypad) = component_builder
             .scheduler_participation(Some(SchedulerParticipation::SchedulerDriven))
+            .isolated_to([self.cpu_address_space])
             .input("keypad", PRESENT_INPUTS, DEFAULT_MAPPINGS);
 
         Ok(Chip8Processor {
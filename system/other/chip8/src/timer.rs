@@ -1,14 +1,17 @@
 use fluxemu_runtime::{
+    clock::Clock,
     component::{Component, config::ComponentConfig},
     machine::builder::{ComponentBuilder, SchedulerParticipation},
     platform::Platform,
     scheduler::{Period, SynchronizationContext},
+    snapshot::{ComponentSnapshot, SnapshotError},
 };
 
 #[derive(Debug)]
 pub struct Chip8Timer {
     // The CPU will set this according to what the program wants
     timer: u8,
+    clock: Clock,
 }
 
 impl Chip8Timer {
@@ -25,19 +28,31 @@ impl Component for Chip8Timer {
     type Event = ();
 
     fn synchronize(&mut self, mut context: SynchronizationContext) {
-        let mut quanta_iterator = context.quanta_allocator(Period::ONE / 60);
-        while quanta_iterator.allocate().is_some() {
+        let mut cycle_allocator = context.cycle_allocator(self.clock);
+        while cycle_allocator.allocate().is_some() {
             self.timer = self.timer.saturating_sub(1);
         }
     }
 
     fn needs_work(&self, _timestamp: &Period, delta: &Period) -> bool {
-        *delta >= Period::ONE / 60
+        *delta >= self.clock.period()
+    }
+
+    fn snapshot(&self) -> Option<ComponentSnapshot> {
+        Some(ComponentSnapshot::new(&self.timer))
+    }
+
+    fn restore(&mut self, snapshot: &ComponentSnapshot) -> Result<(), SnapshotError> {
+        self.timer = snapshot.decode()?;
+
+        Ok(())
     }
 }
 
-#[derive(Debug, Default)]
-pub struct Chip8TimerConfig;
+#[derive(Debug)]
+pub struct Chip8TimerConfig {
+    pub clock: Clock,
+}
 
 impl<P: Platform> ComponentConfig<P> for Chip8TimerConfig {
     type Component = Chip8Timer;
@@ -48,6 +63,9 @@ impl<P: Platform> ComponentConfig<P> for Chip8TimerConfig {
     ) -> Result<Self::Component, Box<dyn std::error::Error>> {
         component_builder.scheduler_participation(Some(SchedulerParticipation::OnAccess));
 
-        Ok(Chip8Timer { timer: 0 })
+        Ok(Chip8Timer {
+            timer: 0,
+            clock: self.clock,
+        })
     }
 }
@@ -148,11 +148,12 @@ pub fn import(
             let name = first_rom_path[0].clone();
             let name_metadata_extractor = NameMetadataExtractor::from_str(&name)?;
 
-            let info = ProgramInfo::V0 {
+            let info = ProgramInfo::V1 {
                 names: BTreeSet::from([name]),
                 filesystem,
                 languages: name_metadata_extractor.languages,
                 version: None,
+                quirks: None,
             };
 
             program_information.insert(program_id.clone(), info)?;